@@ -0,0 +1,103 @@
+# bends the straight belt models in assets/models into quarter
+# circles for belts that get fed from the side
+# run from the repository root: python3 scripts/generate_belt_curves.py
+
+import math
+import struct
+
+TIERS = ["belt", "fast_belt", "express_belt"]
+
+# how long a quad gets along the belt before it's split up, so
+# the long faces of the straight model follow the curve smoothly
+SEGMENT = 1 / 16
+
+HEADER = 33
+VERTEX = struct.Struct("<fffI")
+
+
+def read(path):
+    data = open(path, "rb").read()
+
+    count = struct.unpack_from("<I", data, HEADER)[0]
+    offset = HEADER + 4
+    vertices = [VERTEX.unpack_from(data, offset + i * VERTEX.size) for i in range(count)]
+
+    offset += count * VERTEX.size
+    count = struct.unpack_from("<I", data, offset)[0]
+    indices = struct.unpack_from(f"<{count}I", data, offset + 4)
+
+    return data[:HEADER], vertices, indices
+
+
+def write(path, header, vertices, indices):
+    out = bytearray(header)
+    out += struct.pack("<I", len(vertices))
+    for vertex in vertices:
+        out += VERTEX.pack(*vertex)
+
+    out += struct.pack("<I", len(indices))
+    out += struct.pack(f"<{len(indices)}I", *indices)
+    open(path, "wb").write(out)
+
+
+def lerp(a, b, t):
+    return tuple(x + (y - x) * t for x, y in zip(a, b))
+
+
+# the models run along +z with +x on their right, the same frame
+# as `rotate_block_vector`. `curve` is the side the belt gets fed
+# from and the items go round the corner between it and the front
+def bend(x, z, curve):
+    radius = 0.5 - curve * x
+    angle = (z + 0.5) * math.pi / 2
+    return curve * 0.5 - curve * radius * math.sin(angle), 0.5 - radius * math.cos(angle)
+
+
+def flips_faces(curve):
+    e = 1e-3
+    x0, z0 = bend(0.0, 0.0, curve)
+    x1, z1 = bend(e, 0.0, curve)
+    x2, z2 = bend(0.0, e, curve)
+    return (x1 - x0) * (z2 - z0) - (z1 - z0) * (x2 - x0) < 0
+
+
+def curve_model(vertices, indices, curve):
+    # every quad is two triangles, 0 1 2 and 2 3 0
+    assert len(indices) == len(vertices) // 4 * 6
+    for quad in range(len(vertices) // 4):
+        assert indices[quad * 6:quad * 6 + 6] == tuple(quad * 4 + i for i in (0, 1, 2, 2, 3, 0))
+
+    out_vertices = []
+    out_indices = []
+    flip = flips_faces(curve)
+
+    for quad in range(len(vertices) // 4):
+        corners = vertices[quad * 4:quad * 4 + 4]
+        colour = corners[0][3]
+        p = [c[:3] for c in corners]
+
+        nu = max(1, math.ceil(abs(p[1][2] - p[0][2]) / SEGMENT))
+        nv = max(1, math.ceil(abs(p[3][2] - p[0][2]) / SEGMENT))
+
+        base = len(out_vertices)
+        for j in range(nv + 1):
+            for i in range(nu + 1):
+                x, y, z = lerp(lerp(p[0], p[1], i / nu), lerp(p[3], p[2], i / nu), j / nv)
+                x, z = bend(x, z, curve)
+                out_vertices.append((x, y, z, colour))
+
+        for j in range(nv):
+            for i in range(nu):
+                a = base + j * (nu + 1) + i
+                b, c, d = a + 1, a + nu + 2, a + nu + 1
+                tris = (a, b, c, c, d, a) if not flip else (c, b, a, a, d, c)
+                out_indices.extend(tris)
+
+    return out_vertices, out_indices
+
+
+for tier in TIERS:
+    header, vertices, indices = read(f"assets/models/{tier}.vmf")
+    for side, curve in (("left", -1), ("right", 1)):
+        out_vertices, out_indices = curve_model(vertices, indices, curve)
+        write(f"assets/models/{tier}_curve_{side}.vmf", header, out_vertices, out_indices)
@@ -133,16 +133,102 @@ recipes = {
         "amount": 1,
         "time": 2,
     },
-
     "Radar": {
         "requirements": [
-            ("SteelPlate", 160),
-            ("CircuitBoard", 200),
+            ("SteelPlate", 90),
+            ("CircuitBoard", 120),
             ("Brick", 500),
         ],
         "amount": 1,
         "time": 0.1,
     },
+
+    # saves store assembler recipes by their index in `RECIPES`,
+    # so new recipes always go at the end
+    "SpeedModule": {
+        "requirements": [
+            ("CircuitBoard", 2),
+            ("MechanicalComponent", 4),
+        ],
+        "amount": 1,
+        "time": 15,
+    },
+    "ProductivityModule": {
+        "requirements": [
+            ("CircuitBoard", 2),
+            ("SteelPlate", 4),
+        ],
+        "amount": 1,
+        "time": 15,
+    },
+    "Structure(StructureKind::FastBelt)": {
+        "requirements": [
+            ("Structure(StructureKind::Belt)", 1),
+            ("IronGearWheel", 5),
+        ],
+        "amount": 1,
+        "time": 1,
+    },
+    "Structure(StructureKind::ExpressBelt)": {
+        "requirements": [
+            ("Structure(StructureKind::FastBelt)", 1),
+            ("MechanicalComponent", 2),
+            ("SteelPlate", 1),
+        ],
+        "amount": 1,
+        "time": 2,
+    },
+    "Structure(StructureKind::Pipe)": {
+        "requirements": [
+            ("IronPlate", 1),
+        ],
+        "amount": 2,
+        "time": 0.5,
+    },
+    "Structure(StructureKind::Pump)": {
+        "requirements": [
+            ("MechanicalComponent", 2),
+            ("Structure(StructureKind::Pipe)", 1),
+            ("IronPlate", 4),
+        ],
+        "amount": 1,
+        "time": 2,
+    },
+    "Structure(StructureKind::ProviderChest)": {
+        "requirements": [
+            ("Structure(StructureKind::Chest)", 1),
+            ("CircuitBoard", 2),
+        ],
+        "amount": 1,
+        "time": 2,
+    },
+    "Structure(StructureKind::RequesterChest)": {
+        "requirements": [
+            ("Structure(StructureKind::Chest)", 1),
+            ("CircuitBoard", 2),
+            ("ElectronicsKit", 1),
+        ],
+        "amount": 1,
+        "time": 2,
+    },
+    "Structure(StructureKind::LargeQuarry)": {
+        "requirements": [
+            ("Structure(StructureKind::Quarry)", 1),
+            ("MechanicalComponent", 8),
+            ("SteelPlate", 10),
+        ],
+        "amount": 1,
+        "time": 4,
+    },
+    "Structure(StructureKind::LongInserter)": {
+        "requirements": [
+            ("Structure(StructureKind::Inserter)", 1),
+            ("MechanicalComponent", 1),
+            ("IronRod", 2),
+        ],
+        "amount": 1,
+        "time": 2,
+    },
 }
 
 
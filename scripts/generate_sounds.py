@@ -0,0 +1,72 @@
+# generates the looping ambient machine sounds in assets/sounds
+# run from the repository root: python3 scripts/generate_sounds.py
+
+import math
+import random
+import struct
+import wave
+
+SAMPLE_RATE = 22050
+DURATION = 2.0
+SAMPLES = int(SAMPLE_RATE * DURATION)
+
+random.seed(616)
+
+
+def write(name, samples):
+    peak = max(abs(s) for s in samples) or 1.0
+    with wave.open(f"assets/sounds/{name}.wav", "wb") as f:
+        f.setnchannels(1)
+        f.setsampwidth(2)
+        f.setframerate(SAMPLE_RATE)
+        f.writeframes(b"".join(struct.pack("<h", int(s / peak * 0.8 * 32767)) for s in samples))
+
+
+def low_pass(samples, strength):
+    out = []
+    prev = samples[-1]
+    for s in samples:
+        prev += (s - prev) * strength
+        out.append(prev)
+    return out
+
+
+# a soft bed of noise with random pops on top
+def furnace():
+    bed = low_pass([random.uniform(-1, 1) for _ in range(SAMPLES)], 0.05)
+    samples = [s * 0.6 for s in bed]
+
+    for _ in range(40):
+        start = random.randrange(SAMPLES)
+        length = random.randrange(40, 200)
+        amp = random.uniform(0.3, 1.0)
+        for i in range(length):
+            decay = math.exp(-i / (length * 0.2))
+            samples[(start + i) % SAMPLES] += random.uniform(-1, 1) * amp * decay
+
+    return samples
+
+
+# a low hum, every frequency fits a whole number of times into the loop
+def assembler():
+    samples = []
+    for i in range(SAMPLES):
+        t = i / SAMPLE_RATE
+        s = math.sin(2 * math.pi * 110 * t)
+        s += 0.5 * math.sin(2 * math.pi * 220 * t)
+        s += 0.25 * math.sin(2 * math.pi * 330 * t)
+        s *= 0.8 + 0.2 * math.sin(2 * math.pi * 2 * t)
+        samples.append(s)
+
+    return samples
+
+
+# heavily filtered noise with a slow wobble
+def belt():
+    noise = low_pass([random.uniform(-1, 1) for _ in range(SAMPLES)], 0.02)
+    return [s * (0.7 + 0.3 * math.sin(2 * math.pi * 4 * i / SAMPLE_RATE)) for i, s in enumerate(noise)]
+
+
+write("furnace", furnace())
+write("assembler", assembler())
+write("belt", belt())
@@ -16,6 +16,7 @@ struct InstanceIn {
 struct VertexOut {
     @builtin(position) position: vec4<f32>,
     @location(0)       modulate: vec4<f32>,
+    @location(1)       view_pos: vec3<f32>,
 };
 
 
@@ -27,6 +28,9 @@ struct FragmentIn {
 struct Uniforms {
     view       : mat4x4<f32>,
     projection : mat4x4<f32>,
+    sun_direction: vec3<f32>,
+    sun_intensity: f32,
+    sun_ambient: f32,
 };
 
 
@@ -40,16 +44,28 @@ fn vs_main(vertex: VertexIn, instance: InstanceIn) -> VertexOut {
     let colour   = unpack4x8unorm(vertex.colour).abgr;
     let model = mat4x4(instance.model0, instance.model1, instance.model2, instance.model3);
     out.modulate = instance.modulate * colour;
-    out.position = u.projection * u.view * model * vec4f(vertex.position, 1.0);
+
+    let view_pos = u.view * model * vec4f(vertex.position, 1.0);
+    out.view_pos = view_pos.xyz;
+    out.position = u.projection * view_pos;
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    // the meshes don't carry normals, the face's gets
+    // rebuilt from how the position changes across it
+    var normal = normalize(cross(dpdx(in.view_pos), dpdy(in.view_pos)));
+    if dot(normal, in.view_pos) > 0.0 { normal = -normal; }
+
+    let sun = (u.view * vec4(u.sun_direction, 0.0)).xyz;
+    let diffuse = max(dot(normal, sun), 0.0);
+    let light = min(u.sun_ambient + diffuse * u.sun_intensity, 1.0);
+
     return vec4(
-        pow(in.modulate.x, 2.2),
-        pow(in.modulate.y, 2.2),
-        pow(in.modulate.z, 2.2),
+        pow(in.modulate.x, 2.2) * light,
+        pow(in.modulate.y, 2.2) * light,
+        pow(in.modulate.z, 2.2) * light,
         pow(in.modulate.w, 2.2),
     );
 }
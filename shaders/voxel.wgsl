@@ -19,6 +19,7 @@ struct VertexOut {
     @location(4)       tex_coords: vec2<f32>,
     @location(5)       id        : u32,
     @location(6)       colour    : vec3<f32>,
+    @location(7)       light     : f32,
 };
 
 
@@ -33,7 +34,7 @@ struct Uniforms {
     projection : mat4x4<f32>,
     modulate   : vec4<f32>,
     camera_block: vec3<i32>,
-    pad_00     : f32,
+    time       : f32,
     camera_offset: vec3<f32>,
     pad_01     : f32,
     fog_color  : vec3<f32>,
@@ -41,7 +42,10 @@ struct Uniforms {
     fog_density: f32,
     fog_start  : f32,
     fog_end    : f32,
-    pad_03     : f32,
+    debug_normals: u32,
+    sun_direction: vec3<f32>,
+    sun_intensity: f32,
+    sun_ambient: f32,
 };
 
 @group(0) @binding(0)
@@ -155,11 +159,12 @@ fn vs_main(vertex: VertexIn, input: InstanceIn) -> VertexOut {
 
     let world_pos = pos + model.xyz + vec3<f32>(o);
 
-    let light_dir = normalize(vec3<f32>(0.5, 1.0, 0.3));
-
-    let light = min(max(dot(normal, light_dir), 0.0) + 0.2, 1.0);
+    // the sun direction comes in normalized
+    let diffuse = max(dot(normal, u.sun_direction), 0.0);
+    output.light = min(u.sun_ambient + diffuse * u.sun_intensity, 1.0);
 
     output.position = u.projection * u.view * vec4<f32>(world_pos, 1.0);
+    output.normal = normal;
     output.v_distance = length(world_pos);
     output.frag_pos = world_pos;
     output.id = input.id & 0xFFu;
@@ -170,6 +175,8 @@ fn vs_main(vertex: VertexIn, input: InstanceIn) -> VertexOut {
 
 const TILE_SIZE: f32 = 1.0 / 256.0;
 const PIXEL_SIZE : f32 = TILE_SIZE / 32.0;
+// has to match `Voxel::texture_id` for water
+const WATER_TEXTURE : u32 = 7u;
 
 
 fn lerp(a: f32, b: f32, t: f32) -> f32 {
@@ -179,14 +186,25 @@ fn lerp(a: f32, b: f32, t: f32) -> f32 {
 
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    if u.debug_normals != 0u {
+        return vec4(in.normal * 0.5 + 0.5, 1.0);
+    }
+
     let fog_factor = clamp((u.fog_end - in.v_distance) / (u.fog_end - u.fog_start), 0.0, 1.0);
+    let fog_factor = 1.0 - clamp((1.0 - fog_factor) * u.fog_density, 0.0, 1.0);
 
     let base = f32(in.id) * TILE_SIZE;
     let max = base + TILE_SIZE;
 
-    var v = clamp(lerp(base, max, in.tex_coords.x % 1.0), base, max);
-    var colour = textureSample(t_diffuse, s_diffuse, vec2<f32>(v, in.tex_coords.y % 1.0));
+    // the water surface slowly drifts along
+    var tex_coords = in.tex_coords;
+    if in.id == WATER_TEXTURE {
+        tex_coords += vec2<f32>(0.05, 0.03) * u.time;
+    }
+
+    var v = clamp(lerp(base, max, tex_coords.x % 1.0), base, max);
+    var colour = textureSample(t_diffuse, s_diffuse, vec2<f32>(v, tex_coords.y % 1.0));
 
 
-    return vec4(mix(u.fog_color, colour.xyz * in.colour, fog_factor), colour.w);
+    return vec4(mix(u.fog_color, colour.xyz * in.colour * in.light, fog_factor), colour.w);
 }
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use glam::{DVec3, Mat4, Quat, Vec3};
+use kira::{listener::ListenerHandle, sound::static_sound::StaticSoundData, track::{SpatialTrackBuilder, SpatialTrackDistances, SpatialTrackHandle}, AudioManager, Decibels, DefaultBackend, Tween};
+use tracing::warn;
+
+use crate::{constants::{AMBIENCE_MAX_EMITTERS, AMBIENCE_MIN_DISTANCE, AMBIENCE_RADIUS, AMBIENCE_VOLUME}, structures::{strct::{Structure, StructureData}, StructureId, Structures}, Camera};
+
+
+///
+/// Positional machine sounds for the structures around the camera
+///
+pub struct Ambience {
+    listener: ListenerHandle,
+    /// indexed by `AmbientSound`, `None` if the file failed to load
+    sounds: [Option<StaticSoundData>; 3],
+    emitters: HashMap<StructureId, Emitter>,
+}
+
+
+struct Emitter {
+    kind: AmbientSound,
+    // dropping the track stops the sound playing on it
+    track: SpatialTrackHandle,
+}
+
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum AmbientSound {
+    Crackle,
+    Hum,
+    Rumble,
+}
+
+
+impl AmbientSound {
+    const ALL : [AmbientSound; 3] = [AmbientSound::Crackle, AmbientSound::Hum, AmbientSound::Rumble];
+
+
+    fn path(self) -> &'static str {
+        match self {
+            AmbientSound::Crackle => "assets/sounds/furnace.wav",
+            AmbientSound::Hum => "assets/sounds/assembler.wav",
+            AmbientSound::Rumble => "assets/sounds/belt.wav",
+        }
+    }
+
+
+    ///
+    /// The sound a structure makes while it's working, if any
+    ///
+    fn of(structure: &Structure) -> Option<AmbientSound> {
+        if structure.is_asleep { return None }
+
+        match &structure.data {
+            StructureData::Furnace(_) => Some(AmbientSound::Crackle),
+            StructureData::Assembler { .. } => Some(AmbientSound::Hum),
+
+            // belts never sleep so only the ones carrying something count
+              StructureData::Belt { .. }
+            | StructureData::Splitter { .. } => {
+                let slots = &structure.inventory.as_ref().unwrap().slots;
+                slots.iter().any(|x| x.is_some()).then_some(AmbientSound::Rumble)
+            },
+
+            _ => None,
+        }
+    }
+}
+
+
+impl Ambience {
+    pub fn new(audio: &mut AudioManager<DefaultBackend>) -> Option<Self> {
+        let listener = audio.add_listener(Vec3::ZERO, Quat::IDENTITY)
+            .inspect_err(|e| warn!("ambience: unable to create a listener: {e}"))
+            .ok()?;
+
+        let sounds = AmbientSound::ALL.map(|kind| {
+            StaticSoundData::from_file(kind.path())
+                .inspect_err(|e| warn!("ambience: unable to load '{}': {e}", kind.path()))
+                .ok()
+                .map(|sound| sound.loop_region(..))
+        });
+
+        Some(Self { listener, sounds, emitters: HashMap::new() })
+    }
+
+
+    pub fn update_listener(&mut self, camera: &Camera) {
+        self.listener.set_position(camera.position.as_vec3(), Tween::default());
+
+        // the listener faces down -z by default
+        let view = Mat4::look_to_rh(Vec3::ZERO, camera.front, camera.up);
+        self.listener.set_orientation(Quat::from_mat4(&view).inverse(), Tween::default());
+    }
+
+
+    ///
+    /// Starts and stops emitters as structures come in and out
+    /// of earshot, only the closest few of each sound get one
+    ///
+    pub fn update_emitters(&mut self, audio: &mut AudioManager<DefaultBackend>, structures: &Structures, listener: DVec3) {
+        let mut nearby : [Vec<(f64, StructureId, DVec3)>; 3] = Default::default();
+        for (key, structure) in structures.structs.iter() {
+            let Some(kind) = AmbientSound::of(structure)
+            else { continue };
+
+            let position = structure.zero_zero().as_dvec3();
+            let distance = position.distance_squared(listener);
+            if distance > AMBIENCE_RADIUS * AMBIENCE_RADIUS { continue }
+
+            nearby[kind as usize].push((distance, StructureId(key), position));
+        }
+
+        for list in &mut nearby {
+            list.sort_by(|a, b| a.0.total_cmp(&b.0));
+            list.truncate(AMBIENCE_MAX_EMITTERS);
+        }
+
+        self.emitters.retain(|id, emitter| nearby[emitter.kind as usize].iter().any(|x| x.1 == *id));
+
+
+        for (kind, list) in AmbientSound::ALL.into_iter().zip(&nearby) {
+            let Some(sound) = &self.sounds[kind as usize]
+            else { continue };
+
+            // a cluster of the same machine should be about
+            // as loud as a single one, not a wall of noise
+            let volume = Decibels(AMBIENCE_VOLUME - 10.0 * (list.len() as f32).log10());
+
+            for &(_, id, position) in list {
+                if let Some(emitter) = self.emitters.get_mut(&id) {
+                    emitter.track.set_volume(volume, Tween::default());
+                    continue;
+                }
+
+                let builder = SpatialTrackBuilder::new()
+                    .distances(SpatialTrackDistances {
+                        min_distance: AMBIENCE_MIN_DISTANCE,
+                        max_distance: AMBIENCE_RADIUS as f32,
+                    })
+                    .volume(volume);
+
+                let Ok(mut track) = audio.add_spatial_sub_track(self.listener.id(), position.as_vec3(), builder)
+                else { warn!("ambience: ran out of tracks"); return };
+
+                if let Err(e) = track.play(sound.clone()) {
+                    warn!("ambience: unable to play a sound: {e}");
+                    continue;
+                }
+
+                self.emitters.insert(id, Emitter { kind, track });
+            }
+        }
+    }
+}
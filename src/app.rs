@@ -0,0 +1,282 @@
+use std::time::{Duration, Instant};
+
+use glam::{DVec2, Vec2};
+use tracing::error;
+use wgpu::TextureViewDescriptor;
+use winit::{dpi::LogicalSize, error::EventLoopError, event::{StartCause, WindowEvent}, event_loop::{ActiveEventLoop, ControlFlow, EventLoop}, window::{CursorGrabMode, Window, WindowId}};
+use winit::application::ApplicationHandler;
+
+use crate::{constants::MESH_BUDGET_TARGET_FRAME_TIME, game::Game, input::InputManager, renderer::{create_multisampled_framebuffer, DepthBuffer, RenderSettings, Renderer}, voxel_world::{mesh_job_budget, MeshBudget}};
+
+
+///
+/// The windowed game, owns the renderer and feeds
+/// winit's events through to the `Game`
+///
+pub struct App {
+    renderer: Option<Renderer>,
+    last_frame: Instant,
+    /// how long the last few frames took to build, smoothed, not
+    /// counting waiting on the surface, vsync or the frame limiter.
+    /// see `mesh_job_budget`
+    busy_time: f32,
+    time_since_last_simulation: f32,
+    game: Game,
+    input: InputManager,
+}
+
+impl ApplicationHandler for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window = event_loop.create_window(Window::default_attributes().with_inner_size(LogicalSize::new(960, 540))).unwrap();
+
+        window.set_cursor_visible(false);
+        window.set_cursor_grab(CursorGrabMode::Confined) // or Locked
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked))
+            .unwrap();
+
+        self.renderer = Some(pollster::block_on(Renderer::new(window)));
+    }
+
+
+    fn new_events(&mut self, _: &ActiveEventLoop, cause: StartCause) {
+        // the frame timer ran out, time to draw the next frame
+        if let StartCause::ResumeTimeReached { .. } = cause
+            && let Some(renderer) = &self.renderer {
+            renderer.window.request_redraw();
+        }
+    }
+
+
+    fn device_event(
+            &mut self,
+            _: &ActiveEventLoop,
+            _: winit::event::DeviceId,
+            event: winit::event::DeviceEvent,
+        ) {
+        match event {
+            winit::event::DeviceEvent::MouseMotion { delta } => {
+                self.input.set_delta(Vec2::new(delta.0 as f32, delta.1 as f32));
+            },
+            _ => (),
+        }
+    }
+
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => {
+                println!("closing");
+                self.game.save();
+                event_loop.exit();
+            },
+
+
+            WindowEvent::MouseWheel { delta, .. } => {
+                let vec = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(x, y) => Vec2::new(x, y),
+                    winit::event::MouseScrollDelta::PixelDelta(pp) => DVec2::new(pp.x, pp.y).as_vec2(),
+                };
+
+                self.input.scroll(vec);
+            }
+
+
+            WindowEvent::MouseInput { state, button, .. } => {
+                match state {
+                    winit::event::ElementState::Pressed => self.input.set_pressed_button(button),
+                    winit::event::ElementState::Released => self.input.set_unpressed_button(button),
+                };
+
+                self.request_redraw_on_input();
+
+            }
+
+            WindowEvent::CursorMoved { position: pos, .. } => {
+                self.input.move_cursor(DVec2::new(pos.x, pos.y).as_vec2());
+            }
+
+
+            WindowEvent::KeyboardInput { event, .. } => {
+                match event.state {
+                    winit::event::ElementState::Pressed => {
+                        self.input.set_pressed_key(event.physical_key);
+                        if let Some(txt) = event.text {
+                            for char in txt.chars() {
+                                if char.is_ascii_control() {
+                                    continue;
+                                }
+                                self.input.new_char(char);
+                            }
+                        }
+                    },
+                    winit::event::ElementState::Released => self.input.set_unpressed_key(event.physical_key),
+                };
+
+                self.request_redraw_on_input();
+
+
+                if self.input.is_key_pressed(winit::keyboard::KeyCode::ShiftLeft) 
+                    && self.input.is_key_just_pressed(winit::keyboard::KeyCode::Escape) {
+                    event_loop.exit();
+                }
+            }
+
+
+            WindowEvent::RedrawRequested => {
+
+                let game = &mut self.game;
+                let Some(renderer) = &mut self.renderer
+                else { error!("redraw-requested: no renderer found"); return; };
+
+                if renderer.config.present_mode != game.settings.present_mode {
+                    game.settings.present_mode = renderer.set_present_mode(game.settings.present_mode);
+                }
+
+                if renderer.sample_count != game.settings.msaa {
+                    game.settings.msaa = renderer.set_sample_count(game.settings.msaa);
+                }
+
+
+                let now = Instant::now();
+                let dt = now.duration_since(self.last_frame).as_secs_f32();
+                self.last_frame = now;
+
+                self.time_since_last_simulation += dt;
+
+                self.input.poll_gamepad(dt, game.settings.stick_deadzone);
+                game.handle_input(dt, &mut self.input);
+                
+                if !game.camera.front.is_normalized() { panic!("{:?}", self.game.camera.front); }
+
+                game.catch_up(&mut self.time_since_last_simulation);
+
+                let target_frame_time = if game.settings.min_frame_time > 0.0 { game.settings.min_frame_time }
+                                        else { MESH_BUDGET_TARGET_FRAME_TIME };
+                let mut mesh_budget = MeshBudget::new(mesh_job_budget(self.busy_time, target_frame_time));
+
+                game.world.process(&mut mesh_budget, &mut renderer.voxel_pipeline.chunk_offsets, &mut renderer.voxel_pipeline.instances);
+
+
+                let mut encoder = renderer.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("command-encoder"),
+                });
+
+
+                game.world.chunker.process_mesh_jobs(
+                    mesh_budget.next_share(),
+                    &renderer.device,
+                    &mut encoder,
+                    &mut renderer.staging_buffer,
+                    &mut renderer.voxel_pipeline.instances,
+                    &mut renderer.voxel_pipeline.chunk_offsets,
+                    &mut renderer.voxel_pipeline.model_uniform,
+                );
+
+
+                //
+                // render
+                //
+                
+                game.render(renderer, &mut self.input, dt);
+                self.input.update();
+
+                // acquiring the texture can block on vsync
+                let busy_time = now.elapsed();
+                let output = renderer.surface.get_current_texture().unwrap();
+                let view = output.texture.create_view(&TextureViewDescriptor::default());
+
+                let render_world_start = Instant::now();
+                renderer.end(encoder, &mut self.game.world, &view, RenderSettings {
+                    camera: &self.game.camera,
+                    skybox: self.game.current_sky_colour(),
+                    render_distance: self.game.render_distance as u32,
+                    lod_distance: self.game.settings.lod_distance as u32,
+                    frustum: self.game.lock_frustum.clone(),
+                    debug_draw: self.game.settings.debug_draw,
+                    fog: self.game.settings.fog,
+                    sun: self.game.settings.sun,
+                    thumbnail: self.game.thumbnail_request.take().map(|path| (&output.texture, path)),
+                });
+                let render_world_time = render_world_start.elapsed();
+                self.game.render_world_time = render_world_time.as_micros() as u32;
+
+                // a single hitch shouldn't throw the budget around
+                let busy_time = (busy_time + render_world_time).as_secs_f32();
+                self.busy_time += (busy_time - self.busy_time) * 0.1;
+
+
+                output.present();
+
+                // when throttled we sleep until the next frame is due
+                // instead of spinning, the simulation catches up through
+                // `time_since_last_simulation` so the tick rate is unaffected
+                let min_frame_time = self.game.settings.min_frame_time;
+                if min_frame_time > 0.0 {
+                    let next_frame = now + Duration::from_secs_f32(min_frame_time);
+                    event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame));
+                } else {
+                    event_loop.set_control_flow(ControlFlow::Poll);
+                    renderer.window.request_redraw();
+                }
+                println!("frame");
+            }
+
+
+            WindowEvent::Resized(size) => {
+                let Some(renderer) = &mut self.renderer
+                else { error!("resized: no renderer found"); return; };
+
+                renderer.config.width = size.width;
+                renderer.config.height = size.height;
+                renderer.surface.configure(&renderer.device, &renderer.config);
+                renderer.framebuffer = create_multisampled_framebuffer(&renderer.device, &renderer.config, renderer.sample_count);
+                renderer.voxel_pipeline.depth_buffer = DepthBuffer::new(&renderer.device, renderer.config.width, renderer.config.height, renderer.sample_count);
+                renderer.ui_depth_texture = DepthBuffer::new(&renderer.device, renderer.config.width, renderer.config.height, 1);
+
+            }
+            _ => (),
+        }
+    }
+}
+
+
+impl App {
+    pub fn new() -> Self {
+        let mut app = App {
+            last_frame: Instant::now(),
+            busy_time: MESH_BUDGET_TARGET_FRAME_TIME,
+            time_since_last_simulation: 0.0,
+            game: Game::new(),
+            renderer: None,
+            input: InputManager::new(),
+        };
+
+        app.game.open_main_menu();
+        app
+    }
+
+
+    ///
+    /// Runs the game until the window gets closed,
+    /// saving the world on the way out
+    ///
+    pub fn run(mut self, event_loop: EventLoop<()>) -> Result<(), EventLoopError> {
+        let result = event_loop.run_app(&mut self);
+        self.game.save();
+        self.game.finish_saves();
+        result
+    }
+
+
+    fn request_redraw_on_input(&self) {
+        // when uncapped we're redrawing every frame anyways
+        if self.game.settings.min_frame_time == 0.0 { return }
+
+        let Some(renderer) = &self.renderer
+        else { return };
+
+        // don't make the player wait for the timer to see
+        // the result of a key press or a click
+        renderer.window.request_redraw();
+    }
+}
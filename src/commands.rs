@@ -1,10 +1,15 @@
 use std::collections::HashMap;
 
-use crate::game::Game;
+use tracing::warn;
+
+use crate::{constants::{COMMAND_HISTORY_CAP, SAVES_DIR}, game::Game};
 
 pub struct CommandRegistry {
     commands: HashMap<String, fn(&mut Game, &Command) -> Option<()>>,
+    /// oldest first, use `push_history` to add to it
     pub previous_commands: Vec<Command>,
+    /// how many commands the history keeps around
+    pub history_cap: usize,
 }
 
 
@@ -23,6 +28,59 @@ impl CommandRegistry {
         Self {
             commands: HashMap::new(),
             previous_commands: vec![],
+            history_cap: COMMAND_HISTORY_CAP,
+        }
+    }
+
+
+    ///
+    /// Adds `command` to the history, running the same command
+    /// twice in a row only keeps it once and the oldest commands
+    /// get dropped once there's more than `history_cap`
+    ///
+    pub fn push_history(&mut self, command: Command) {
+        if self.previous_commands.last().is_some_and(|x| x.as_str() == command.as_str()) {
+            return;
+        }
+
+        self.previous_commands.push(command);
+        self.trim_history();
+    }
+
+
+    pub fn trim_history(&mut self) {
+        let len = self.previous_commands.len();
+        if len > self.history_cap {
+            self.previous_commands.drain(..len - self.history_cap);
+        }
+    }
+
+
+    ///
+    /// Reads the history written by `save_history`, it's shared
+    /// between worlds so it lives outside of them
+    ///
+    pub fn load_history(&mut self) {
+        let Ok(file) = std::fs::read_to_string(format!("{SAVES_DIR}/command_history"))
+        else { return };
+
+        for line in file.lines() {
+            if line.trim().is_empty() { continue }
+            self.push_history(Command::parse(line.to_string()));
+        }
+    }
+
+
+    pub fn save_history(&self) {
+        let mut file = String::new();
+        for command in &self.previous_commands {
+            file.push_str(command.as_str());
+            file.push('\n');
+        }
+
+        let _ = std::fs::create_dir_all(SAVES_DIR);
+        if let Err(e) = std::fs::write(format!("{SAVES_DIR}/command_history"), file) {
+            warn!("commands: failed to save the command history: {e}");
         }
     }
 
@@ -35,6 +93,11 @@ impl CommandRegistry {
     pub fn find(&self, command: &str) -> Option<fn(&mut Game, &Command) -> Option<()>> {
         self.commands.get(command).copied()
     }
+
+
+    pub fn names(&self) -> impl Iterator<Item=&str> {
+        self.commands.keys().map(|x| x.as_str())
+    }
 }
 
 
@@ -1,4 +1,6 @@
-use glam::{Vec2, Vec4};
+use glam::{DVec3, IVec3, Vec2, Vec3, Vec4};
+
+use crate::structures::transfer::TransferRate;
 
 pub const COLOUR_WHITE: Vec4 = Vec4::new(1.0, 1.0, 1.0, 1.0);
 pub const COLOUR_PASS : Vec4 = Vec4::new(0.2, 0.8, 0.2, 1.0);
@@ -10,13 +12,19 @@ pub const COLOUR_SCREEN_DIM : Vec4 = Vec4::new(0.1, 0.1, 0.1, 0.6);
 pub const COLOUR_PLAYER_ACTIVE_HOTBAR : Vec4 = Vec4::new(0.4, 0.6, 0.4, 1.0);
 
 pub const COLOUR_ADDITIVE_HIGHLIGHT: Vec4 = Vec4::splat(0.4);
+pub const COLOUR_VOID : Vec4 = Vec4::new(0.02, 0.0, 0.05, 1.0);
 
+/// the msaa sample count until the settings say otherwise, see `Renderer::set_sample_count`
 pub const MSAA_SAMPLE_COUNT : u32 = 4;
 pub const VOXEL_TEXTURE_ATLAS_TILE_SIZE : u32 = 32;
 pub const VOXEL_TEXTURE_ATLAS_TILE_CAP : u32 = 256;
 
 pub const UI_CROSSAIR_SIZE        : f32  = 8.0;
 pub const UI_CROSSAIR_COLOUR      : Vec4 = Vec4::ONE;
+/// the crossair when nothing is in reach
+pub const UI_CROSSAIR_IDLE_COLOUR : Vec4 = Vec4::new(1.0, 1.0, 1.0, 0.4);
+/// the crossair's outline when looking at something you can press E on
+pub const UI_CROSSAIR_INTERACT_COLOUR : Vec4 = Vec4::new(1.0, 0.8, 0.2, 1.0);
 pub const UI_HOTBAR_UNSELECTED_BG : Vec4 = Vec4::new(0.2, 0.2, 0.2, 1.0);
 pub const UI_HOTBAR_SELECTED_BG   : Vec4 = Vec4::new(1.0, 0.0, 0.0, 1.0);
 pub const UI_SLOT_SIZE            : f32  = 60.0;
@@ -25,9 +33,20 @@ pub const UI_ITEM_OFFSET          : f32  = UI_SLOT_SIZE * 0.05;
 pub const UI_ITEM_SIZE            : f32  = UI_SLOT_SIZE * 0.9;
 pub const UI_ITEM_AMOUNT_SCALE    : f32  = 0.5;
 pub const UI_SLOT_PADDING         : f32  = 16.0;
+pub const UI_HEALTH_BAR_HEIGHT    : f32  = 8.0;
+pub const UI_HEALTH_BAR_COLOUR    : Vec4 = Vec4::new(0.8, 0.2, 0.2, 1.0);
+pub const UI_TEXT_SHADOW_OFFSET   : Vec2 = Vec2::new(1.5, 1.5);
+pub const UI_TEXT_SHADOW_COLOUR   : Vec4 = Vec4::new(0.05, 0.05, 0.05, 0.8);
 pub const UI_DELTA_Z              : f32  = 0.0001;
 pub const UI_Z_MAX                : f32  = 1.0;
 pub const UI_Z_MIN                : f32  = 0.0;
+pub const UI_MINIMAP_SIZE         : f32  = 200.0;
+pub const UI_MINIMAP_PADDING      : f32  = 16.0;
+pub const UI_THUMBNAIL_SCALE      : f32  = 2.0;
+/// how many worlds in the world list can show a thumbnail
+pub const UI_THUMBNAIL_SLOTS      : usize = 16;
+pub const UI_MAP_PLAYER_COLOUR    : Vec4 = Vec4::new(1.0, 0.0, 0.0, 1.0);
+pub const UI_MAP_STRUCT_COLOUR    : Vec4 = Vec4::new(0.9, 0.6, 0.1, 1.0);
 
 pub const REGION_SIZE    : usize = 32;
 pub const REGION_SIZE_P3 : usize = REGION_SIZE*REGION_SIZE*REGION_SIZE;
@@ -37,29 +56,235 @@ pub const CHUNK_SIZE_PAD : usize = CHUNK_SIZE+2;
 pub const CHUNK_SIZE_P3  : usize = CHUNK_SIZE*CHUNK_SIZE*CHUNK_SIZE;
 pub const CHUNK_SIZE_I32 : i32 = CHUNK_SIZE as i32;
 
+pub const TERRAIN_SEED : u64 = 69696969;
+/// xor'd with a world's seed to get the seed of its biome map
+pub const BIOME_SEED : u64 = 1337;
+pub const BIOME_SCALE : f64 = 0.0025;
+/// how far away, in blocks, biomes start blending into each other
+pub const BIOME_BLEND_RADIUS : i32 = 4;
+
+/// how much of the underground caves carve out, 0.0 turns them off
+pub const CAVE_DENSITY : f64 = 0.012;
+pub const CAVE_SCALE : f64 = 0.02;
+/// how many blocks below the surface caves grow to their full size
+pub const CAVE_SURFACE_FADE : i32 = 12;
+
+pub const WORLD_MIN_Y : i32 = -256;
+pub const WORLD_MAX_Y : i32 = 512;
+/// a spot above the highest terrain the generator can make where tests build
+/// their structures, see `Structures::place_at_test_site`
+pub const TEST_SITE : IVec3 = IVec3::new(0, WORLD_MAX_Y - 64, 0);
+/// how many blocks above the world floor the sky starts fading into the void
+pub const VOID_FADE_DISTANCE : f32 = 64.0;
+
+/// how many console commands are remembered, see `CommandRegistry::history_cap`
+pub const COMMAND_HISTORY_CAP : usize = 100;
+/// every subdirectory of this is a world
+pub const SAVES_DIR : &str = "saves";
+/// the world older single-world saves get moved into
+pub const DEFAULT_WORLD_NAME : &str = "world";
+/// the default amount of seconds between autosaves
+pub const AUTOSAVE_INTERVAL : u32 = 120;
+/// the size thumbnails of a world get shrunk down to when it's saved
+pub const THUMBNAIL_WIDTH  : u32 = 48;
+pub const THUMBNAIL_HEIGHT : u32 = 27;
+
+/// bumped whenever the layout of `world.sft` changes, see `migrate_save`
+pub const SAVE_FORMAT_VERSION : u32 = 3;
+
+pub const CHUNK_FILE_MAGIC : [u8; 4] = *b"FGCZ";
+pub const CHUNK_FILE_VERSION : u8 = 1;
+pub const CHUNK_COMPRESSION_LEVEL : i32 = 3;
+
 pub const MOUSE_SENSITIVITY : f32 = 0.0016;
+/// how much the sensitivity grows per pixel per second the mouse moves,
+/// only used while mouse acceleration is turned on
+pub const MOUSE_ACCELERATION : f32 = 0.0005;
+/// the most mouse acceleration can multiply the sensitivity by
+pub const MOUSE_ACCELERATION_CAP : f32 = 3.0;
+/// how far, in mouse pixels per second, a fully tilted right stick turns the camera
+pub const GAMEPAD_LOOK_SPEED : f32 = 1200.0;
+/// stick tilts smaller than this are ignored
+pub const GAMEPAD_DEADZONE : f32 = 0.15;
 
+/// how far away, in blocks, the player can mine, place and interact by default
 pub const PLAYER_REACH : f32 = 5.0;
+/// the reach while `god_reach` is on in creative
+pub const PLAYER_GOD_REACH : f32 = 64.0;
+/// the furthest the `reach` command lets the normal reach go
+pub const PLAYER_MAX_REACH : f32 = 16.0;
 pub const PLAYER_SPEED : f32 = 10.0;
+pub const PLAYER_SPRINT_MULTIPLIER : f32 = 1.6;
+/// how quickly the player's speed eases towards where they're
+/// walking, per second, higher is snappier
+pub const PLAYER_ACCELERATION : f32 = 12.0;
+/// how quickly the player slows down with nothing held, per second
+pub const PLAYER_FRICTION : f32 = 16.0;
+/// the fov, in degrees, while walking around
+pub const PLAYER_FOV : f32 = 80.0;
+/// the fov, in degrees, while zooming in with C
+pub const PLAYER_ZOOM_FOV : f32 = 15.0;
+/// how quickly the fov eases towards its target, higher is snappier
+pub const PLAYER_FOV_EASING : f32 = 10.0;
+/// degrees added to the fov while sprinting
+pub const PLAYER_SPRINT_FOV : f32 = 8.0;
+pub const PLAYER_MAX_HEALTH : f32 = 20.0;
+/// where the player ends up after dying
+pub const PLAYER_SPAWN : DVec3 = DVec3::new(0.0, 10.0, 0.0);
+/// landing faster than this many blocks per second hurts
+pub const PLAYER_FALL_DAMAGE_SPEED : f32 = 10.0;
+pub const PLAYER_FALL_DAMAGE_PER_SPEED : f32 = 2.0;
+/// how many seconds after taking damage health starts coming back
+pub const PLAYER_REGEN_DELAY : f32 = 5.0;
+pub const PLAYER_REGEN_PER_SECOND : f32 = 0.5;
 pub const PLAYER_PULL_DISTANCE : f32 = 3.5;
+/// how many seconds "Inventory Full" stays up after
+/// the last item the player couldn't pick up
+pub const PLAYER_INVENTORY_FULL_TIME : f32 = 1.5;
 pub const PLAYER_INTERACT_DELAY : f32 = 0.125;
+/// where the held item sits relative to the camera, right, down and forward
+pub const PLAYER_HAND_OFFSET : Vec3 = Vec3::new(0.6, -0.5, -1.0);
+/// how far the held item bobs up and down while walking
+pub const PLAYER_HAND_BOB : f32 = 0.04;
+/// the fraction of gravity that still pulls on bodies in water
+pub const WATER_GRAVITY_MULTIPLIER : f32 = 0.2;
+/// how quickly water slows down a body's vertical speed
+pub const WATER_DRAG : f32 = 4.0;
+/// how much slower the player moves while swimming
+pub const PLAYER_SWIM_MULTIPLIER : f32 = 0.5;
+/// how fast holding space pulls the player up through water
+pub const PLAYER_SWIM_UP_SPEED : f32 = 3.0;
 pub const PLAYER_HOTBAR_SIZE : usize = 5;
 pub const PLAYER_ROW_SIZE : usize = 6;
 pub const PLAYER_INVENTORY_SIZE : usize = PLAYER_ROW_SIZE * PLAYER_HOTBAR_SIZE;
 
+/// the most entities that can exist at once, see `EntityMap::max_entities`
+pub const MAX_ENTITIES : usize = 2048;
+/// how far away, in blocks, an evicted dropped item can merge into another stack
+pub const ENTITY_MERGE_DISTANCE : f64 = 2.0;
+
 pub const RENDER_DISTANCE : i32 = 16;
+/// the render distance a freshly loaded world starts at, it
+/// grows from there one chunk at a time as the chunks come in
+pub const INITIAL_RENDER_DISTANCE : i32 = 2;
+/// the furthest the pause menu's slider lets the render distance go
+pub const MAX_RENDER_DISTANCE : i32 = 32;
 pub const LOAD_DISTANCE : i32 = 4;
+/// chunks further away than this get drawn with a coarser mesh
+pub const LOD_DISTANCE : i32 = 10;
+
+/// how far away, in blocks, machines can be heard
+pub const AMBIENCE_RADIUS : f64 = 32.0;
+/// machines closer than this are heard at full volume
+pub const AMBIENCE_MIN_DISTANCE : f32 = 2.0;
+/// the most machines of one kind that get their own sound
+pub const AMBIENCE_MAX_EMITTERS : usize = 8;
+pub const AMBIENCE_VOLUME : f32 = -12.0;
+pub const AMBIENCE_REFRESH_TICKS : u32 = 10;
+
+/// how many cells wide and tall the minimap is
+pub const MINIMAP_RESOLUTION : usize = 96;
+/// how far, in blocks, the corner minimap reaches from the player
+pub const MINIMAP_RADIUS : i32 = 48;
+/// how far, in blocks, the full screen map reaches from the player
+pub const MINIMAP_FULL_RADIUS : i32 = 192;
+pub const MINIMAP_REFRESH_TICKS : u32 = 20;
+
+/// the most voxels a single `fill` command is allowed to touch
+pub const FILL_MAX_VOLUME : i64 = 64 * 64 * 64;
 
 pub const FONT_SIZE : u32 = 48;
 
 pub const DROPPED_ITEM_SCALE : f32 = 0.5;
+/// how fast an item dropped with Q leaves the player
+pub const DROPPED_ITEM_THROW_SPEED : f32 = 8.0;
+/// how fast a single item dropped with alt-Q leaves the player
+pub const DROPPED_ITEM_TOSS_SPEED : f32 = 3.0;
+/// upwards bias added to the throw direction so items arc
+pub const DROPPED_ITEM_THROW_LIFT : f32 = 0.25;
+
+/// the most particles alive at once, the oldest ones make room for new ones
+pub const PARTICLE_MAX : usize = 256;
+/// how many seconds a particle lives before it's gone
+pub const PARTICLE_LIFETIME : f32 = 0.5;
+/// how big a particle is when it spawns, in blocks
+pub const PARTICLE_SIZE : f32 = 0.12;
+/// how fast particles burst out of a block
+pub const PARTICLE_SPEED : f32 = 3.0;
+/// how many particles a broken block bursts into
+pub const PARTICLE_BREAK_COUNT : usize = 16;
+/// how many particles puff out of a freshly placed block
+pub const PARTICLE_PLACE_COUNT : usize = 4;
 
+/// every tick simulates `DELTA_TICK` seconds of game time, timers
+/// count in ticks so they're all in terms of this
 pub const TICKS_PER_SECOND : u32 = 60;
+
+/// the most slots an item can move per belt update, see `BeltTier::speed`
+pub const BELT_MAX_SPEED : u32 = 3;
+/// how many ticks apart the belts move their items
+pub const BELT_UPDATE_INTERVAL : u32 = 5;
+/// how many belt updates a belt has to hold items without moving
+/// any of them before it counts as jammed, see `Structures::is_belt_jammed`
+pub const BELT_JAM_UPDATES : u32 = 12;
 pub const DELTA_TICK : f32 = 1.0 / TICKS_PER_SECOND as f32; 
+const _ : () = assert!((DELTA_TICK * TICKS_PER_SECOND as f32 - 1.0).abs() < 1e-6);
+const _ : () = assert!(TICKS_PER_SECOND % BELT_UPDATE_INTERVAL == 0);
+/// the most real time, in seconds, the simulation tries to catch up on
+/// in one frame. anything past it is dropped so a slow frame doesn't
+/// lead to even more ticks the next frame
+pub const MAX_SIMULATION_CATCH_UP : f32 = 0.25;
+/// the fastest the `timescale` command lets the simulation run
+pub const MAX_TIMESCALE : f32 = 64.0;
+/// the frame time, in seconds, the mesh job budget aims for when uncapped
+pub const MESH_BUDGET_TARGET_FRAME_TIME : f32 = 1.0 / 60.0;
+/// bounds, in milliseconds, on how long the chunk and mesh queues get per frame
+pub const MESH_BUDGET_MIN : u32 = 2;
+pub const MESH_BUDGET_MAX : u32 = 12;
 
 
 pub const COAL_ENERGY_PER_UNIT : u32 = 200;
 pub const FURNACE_COST_PER_SMELT : u32 = 50;
+/// steel furnaces get more smelts out of the same fuel
+pub const STEEL_FURNACE_COST_PER_SMELT : u32 = 30;
+pub const OIL_ENERGY_PER_UNIT : u32 = 5;
+
+/// how much fluid a single pipe holds
+pub const PIPE_CAPACITY : u32 = 100;
+/// how much fluid a pump adds to its network every pipe update
+pub const PUMP_RATE : u32 = 10;
+
+/// how many layers down a freshly placed quarry digs before stopping
+pub const QUARRY_MAX_DEPTH : u32 = 256;
+
+/// how close, in blocks, logistics chests have to be to share a network
+pub const LOGISTICS_RANGE : i32 = 16;
+/// how many items a logistics network moves every tick
+pub const LOGISTICS_TRANSFER_RATE : u32 = 4;
+
+/// how many items a furnace takes in and hands out per tick,
+/// see `StructureKind::transfer_rate`
+pub const FURNACE_TRANSFER_RATE : TransferRate = TransferRate { accept: 2, provide: 2 };
+/// how many items an assembler takes in and hands out per tick
+pub const ASSEMBLER_TRANSFER_RATE : TransferRate = TransferRate { accept: 4, provide: 4 };
+/// how many items a chest or silo takes in and hands out per tick,
+/// enough for a whole logistics run to go through one chest
+pub const CHEST_TRANSFER_RATE : TransferRate = TransferRate {
+    accept: LOGISTICS_TRANSFER_RATE * BELT_UPDATE_INTERVAL,
+    provide: LOGISTICS_TRANSFER_RATE * BELT_UPDATE_INTERVAL,
+};
+/// how many items a belt takes in and hands out per tick, one per lane
+pub const BELT_TRANSFER_RATE : TransferRate = TransferRate { accept: 2, provide: 2 };
+/// how many items an inserter picks up and puts down per tick
+pub const INSERTER_TRANSFER_RATE : TransferRate = TransferRate { accept: 1, provide: 1 };
+/// the size of the cells `Structures` buckets structures into for spatial queries
+pub const STRUCTURE_GRID_CELL : i32 = 16;
+
+pub const ASSEMBLER_MODULE_SLOTS : usize = 2;
+/// how much of a recipe's time each speed module takes off
+pub const SPEED_MODULE_BONUS : f32 = 0.25;
+/// the chance each productivity module adds for a bonus craft
+pub const PRODUCTIVITY_MODULE_BONUS : f32 = 0.1;
 
 
 pub const QUAD_VERTICES : &[i32] = &[
@@ -1,6 +1,6 @@
 pub mod data;
 
-use crate::items::Item;
+use crate::items::{Item, ItemKind};
 
 pub use data::RECIPES;
 pub use data::FURNACE_RECIPES;
@@ -15,6 +15,59 @@ pub struct Recipe {
 }
 
 
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum RecipeCategory {
+    Smelting,
+    Intermediate,
+    Components,
+    Structures,
+}
+
+
+impl RecipeCategory {
+    pub const ALL : &[RecipeCategory] = &[
+        RecipeCategory::Smelting,
+        RecipeCategory::Intermediate,
+        RecipeCategory::Components,
+        RecipeCategory::Structures,
+    ];
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RecipeCategory::Smelting => "Smelting",
+            RecipeCategory::Intermediate => "Intermediate",
+            RecipeCategory::Components => "Components",
+            RecipeCategory::Structures => "Structures",
+        }
+    }
+}
+
+
+impl Recipe {
+    ///
+    /// Which tab of the recipe list this recipe shows up under,
+    /// derived from what it makes
+    ///
+    pub fn category(self) -> RecipeCategory {
+        match self.result.kind {
+              ItemKind::IronPlate
+            | ItemKind::CopperPlate
+            | ItemKind::SteelPlate
+            | ItemKind::Brick => RecipeCategory::Smelting,
+
+              ItemKind::IronGearWheel
+            | ItemKind::IronRod
+            | ItemKind::CopperWire => RecipeCategory::Intermediate,
+
+            ItemKind::Structure(_) => RecipeCategory::Structures,
+
+            _ => RecipeCategory::Components,
+        }
+    }
+}
+
+
 
 pub fn crafting_recipe_index(recipe: Recipe) -> usize {
     RECIPES.iter().enumerate().find(|x| x.1 == &recipe).unwrap().0
@@ -110,6 +110,56 @@ pub const RECIPES : &'static [Recipe] = &[
         result: Item::new(ItemKind::Radar, 1),
         time: TICKS_PER_SECOND / 10,
     },
+    Recipe {
+        requirements: &[Item::new(ItemKind::CircuitBoard, 2), Item::new(ItemKind::MechanicalComponent, 4)],
+        result: Item::new(ItemKind::SpeedModule, 1),
+        time: TICKS_PER_SECOND * 15,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::CircuitBoard, 2), Item::new(ItemKind::SteelPlate, 4)],
+        result: Item::new(ItemKind::ProductivityModule, 1),
+        time: TICKS_PER_SECOND * 15,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::Structure(StructureKind::Belt), 1), Item::new(ItemKind::IronGearWheel, 5)],
+        result: Item::new(ItemKind::Structure(StructureKind::FastBelt), 1),
+        time: TICKS_PER_SECOND,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::Structure(StructureKind::FastBelt), 1), Item::new(ItemKind::MechanicalComponent, 2), Item::new(ItemKind::SteelPlate, 1)],
+        result: Item::new(ItemKind::Structure(StructureKind::ExpressBelt), 1),
+        time: TICKS_PER_SECOND * 2,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::IronPlate, 1)],
+        result: Item::new(ItemKind::Structure(StructureKind::Pipe), 2),
+        time: TICKS_PER_SECOND / 2,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::MechanicalComponent, 2), Item::new(ItemKind::Structure(StructureKind::Pipe), 1), Item::new(ItemKind::IronPlate, 4)],
+        result: Item::new(ItemKind::Structure(StructureKind::Pump), 1),
+        time: TICKS_PER_SECOND * 2,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::Structure(StructureKind::Chest), 1), Item::new(ItemKind::CircuitBoard, 2)],
+        result: Item::new(ItemKind::Structure(StructureKind::ProviderChest), 1),
+        time: TICKS_PER_SECOND * 2,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::Structure(StructureKind::Chest), 1), Item::new(ItemKind::CircuitBoard, 2), Item::new(ItemKind::ElectronicsKit, 1)],
+        result: Item::new(ItemKind::Structure(StructureKind::RequesterChest), 1),
+        time: TICKS_PER_SECOND * 2,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::Structure(StructureKind::Quarry), 1), Item::new(ItemKind::MechanicalComponent, 8), Item::new(ItemKind::SteelPlate, 10)],
+        result: Item::new(ItemKind::Structure(StructureKind::LargeQuarry), 1),
+        time: TICKS_PER_SECOND * 4,
+    },
+    Recipe {
+        requirements: &[Item::new(ItemKind::Structure(StructureKind::Inserter), 1), Item::new(ItemKind::MechanicalComponent, 1), Item::new(ItemKind::IronRod, 2)],
+        result: Item::new(ItemKind::Structure(StructureKind::LongInserter), 1),
+        time: TICKS_PER_SECOND * 2,
+    },
 ];
 pub fn crafting_recipe_inventory(index: usize) -> &'static [SlotMeta] {
     match index {
@@ -246,6 +296,90 @@ pub fn crafting_recipe_inventory(index: usize) -> &'static [SlotMeta] {
             ];
             SLOTS
         },
+        17 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::CircuitBoard) }),
+                SlotMeta::new(8, SlotKind::Input { filter: Filter::ItemKind(ItemKind::MechanicalComponent) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        18 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::CircuitBoard) }),
+                SlotMeta::new(8, SlotKind::Input { filter: Filter::ItemKind(ItemKind::SteelPlate) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        19 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::Belt)) }),
+                SlotMeta::new(10, SlotKind::Input { filter: Filter::ItemKind(ItemKind::IronGearWheel) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        20 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::FastBelt)) }),
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::MechanicalComponent) }),
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::SteelPlate) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        21 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::IronPlate) }),
+                SlotMeta::new(4, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        22 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::MechanicalComponent) }),
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::Pipe)) }),
+                SlotMeta::new(8, SlotKind::Input { filter: Filter::ItemKind(ItemKind::IronPlate) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        23 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::Chest)) }),
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::CircuitBoard) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        24 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::Chest)) }),
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::CircuitBoard) }),
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::ElectronicsKit) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        25 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::Quarry)) }),
+                SlotMeta::new(16, SlotKind::Input { filter: Filter::ItemKind(ItemKind::MechanicalComponent) }),
+                SlotMeta::new(20, SlotKind::Input { filter: Filter::ItemKind(ItemKind::SteelPlate) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
+        26 => {
+            const SLOTS : &[SlotMeta] = &[
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::Structure(StructureKind::Inserter)) }),
+                SlotMeta::new(2, SlotKind::Input { filter: Filter::ItemKind(ItemKind::MechanicalComponent) }),
+                SlotMeta::new(4, SlotKind::Input { filter: Filter::ItemKind(ItemKind::IronRod) }),
+                SlotMeta::new(2, SlotKind::Output),
+            ];
+            SLOTS
+        },
         _ => unreachable!(),
     }
 }
\ No newline at end of file
@@ -20,6 +20,17 @@ impl CardinalDirection {
     }
 
 
+    pub fn from_ivec3(dir: IVec3) -> Option<CardinalDirection> {
+        match dir {
+            IVec3 { x: 0, y: 0, z: 1 } => Some(CardinalDirection::South),
+            IVec3 { x: 0, y: 0, z: -1 } => Some(CardinalDirection::North),
+            IVec3 { x: 1, y: 0, z: 0 } => Some(CardinalDirection::East),
+            IVec3 { x: -1, y: 0, z: 0 } => Some(CardinalDirection::West),
+            _ => None,
+        }
+    }
+
+
     pub fn from_index(i: u8) -> CardinalDirection {
         match i {
             3 => CardinalDirection::North,
@@ -50,6 +61,53 @@ impl CardinalDirection {
 
 
 
+///
+/// Whether a structure lies flat or is tipped over so its front
+/// points straight up or down. The yaw stays a `CardinalDirection`
+/// so structures that only ever lie flat don't have to care
+///
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Orientation {
+    Flat,
+    Up,
+    Down,
+}
+
+
+impl Orientation {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Orientation::Flat => "flat",
+            Orientation::Up => "up",
+            Orientation::Down => "down",
+        }
+    }
+
+
+    pub fn from_str(str: &str) -> Option<Orientation> {
+        match str {
+            "flat" => Some(Orientation::Flat),
+            "up" => Some(Orientation::Up),
+            "down" => Some(Orientation::Down),
+            _ => None,
+        }
+    }
+
+
+    ///
+    /// The preview rotation goes through the four flat directions
+    /// first, anything past those tips the structure up and then down
+    ///
+    pub fn from_preview(facing: CardinalDirection, offset: u8) -> (CardinalDirection, Orientation) {
+        match offset {
+            0..4 => (facing.next_n(offset), Orientation::Flat),
+            4 => (facing, Orientation::Up),
+            _ => (facing, Orientation::Down),
+        }
+    }
+}
+
+
 #[derive(Copy, Clone, Debug)]
 pub enum Direction {
     Left,
@@ -2,7 +2,7 @@ use glam::{DVec3, Vec3};
 use rand::random;
 use sti::{define_key, vec::KVec};
 
-use crate::{constants::DROPPED_ITEM_SCALE, gen_map::KGenMap, items::Item, PhysicsBody, Tick};
+use crate::{constants::{DROPPED_ITEM_SCALE, DROPPED_ITEM_THROW_LIFT, ENTITY_MERGE_DISTANCE, MAX_ENTITIES}, gen_map::{KGenMap, KeyGen}, items::Item, PhysicsBody, Tick};
 
 define_key!(pub EntityId(u32));
 
@@ -10,6 +10,9 @@ define_key!(pub EntityId(u32));
 #[derive(Debug)]
 pub struct EntityMap {
     pub entities: KGenMap<u32, EntityId, Entity>,
+    /// once there's this many entities spawning a new
+    /// one evicts the oldest dropped item
+    pub max_entities: usize,
 }
 
 
@@ -37,23 +40,112 @@ impl EntityMap {
     pub fn new() -> Self {
         Self {
             entities: KGenMap::new(),
+            max_entities: MAX_ENTITIES,
         }
     }
 
 
+    pub fn count(&self) -> usize {
+        self.entities.count()
+    }
+
+
     pub fn spawn(&mut self, kind: EntityKind, position: DVec3) {
+        let velocity = (random::<Vec3>() - Vec3::ONE*0.5) * kind.splash();
+        self.spawn_with_velocity(kind, position, velocity);
+    }
+
+
+    ///
+    /// Spawns an entity that's launched from `position`
+    /// in `direction` at `speed`, slightly biased upwards
+    /// so it arcs away instead of sliding along the floor
+    ///
+    pub fn throw(&mut self, kind: EntityKind, position: DVec3, direction: Vec3, speed: f32) {
+        let direction = (direction.normalize_or_zero() + Vec3::Y * DROPPED_ITEM_THROW_LIFT).normalize_or_zero();
+        self.spawn_with_velocity(kind, position, direction * speed);
+    }
+
+
+    pub fn spawn_with_velocity(&mut self, kind: EntityKind, position: DVec3, velocity: Vec3) {
         let entity = Entity {
             spawn_tick: Tick::NEVER,
             body: PhysicsBody {
                 position,
-                velocity: (random::<Vec3>() - Vec3::ONE*0.5) * kind.splash(),
-                aabb_dims: kind.aabb()
+                velocity,
+                aabb_dims: kind.aabb(),
+                pass_through_walkable: false,
+                has_gravity: true,
+                on_ground: false,
+                in_water: false,
             },
             kind,
         };
 
+        while self.count() >= self.max_entities {
+            if !self.evict_oldest() { break }
+        }
+
         self.entities.insert(entity);
     }
+
+
+    ///
+    /// The closest entity whose aabb the ray from `start` along `dir`
+    /// hits within `max_dist`
+    ///
+    pub fn raycast(&self, start: DVec3, dir: Vec3, max_dist: f32) -> Option<KeyGen<u32, EntityId>> {
+        self.entities.iter()
+            .filter_map(|(id, entity)| {
+                let half = (entity.body.aabb_dims * 0.5).as_dvec3();
+                let dist = ray_aabb(start, dir, entity.body.position - half, entity.body.position + half)?;
+                (dist <= max_dist).then_some((id, dist))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+
+    ///
+    /// Removes the oldest dropped item that isn't being pulled
+    /// towards the player, merging it into a nearby stack of the
+    /// same kind if there's room for it
+    ///
+    /// Returns false if there was nothing to evict
+    ///
+    fn evict_oldest(&mut self) -> bool {
+        let oldest = self.entities.iter()
+            .filter(|(_, entity)| matches!(entity.kind, EntityKind::DroppedItem { is_attracted: false, .. }))
+            // entities that haven't seen a tick yet are the newest
+            .filter(|(_, entity)| entity.spawn_tick != Tick::NEVER)
+            .min_by_key(|(_, entity)| entity.spawn_tick.u32())
+            .map(|(id, _)| id);
+
+        let Some(oldest) = oldest
+        else { return false };
+
+        let evicted = self.entities.remove(oldest);
+        let EntityKind::DroppedItem { item: evicted_item, .. } = evicted.kind;
+
+
+        let len = self.entities.len();
+        for i in 0..len {
+            let Some(entity) = self.entities.entry_at(i)
+            else { continue };
+
+            let EntityKind::DroppedItem { item, .. } = &mut entity.kind;
+            if item.kind != evicted_item.kind { continue }
+            if evicted_item.amount > item.room_left() { continue }
+
+            let distance = entity.body.position.distance_squared(evicted.body.position);
+            if distance > ENTITY_MERGE_DISTANCE * ENTITY_MERGE_DISTANCE { continue }
+
+            item.amount += evicted_item.amount;
+            break;
+        }
+
+        true
+    }
 }
 
 
@@ -78,3 +170,21 @@ impl EntityKind {
         Self::DroppedItem { item, is_attracted: false }
     }
 }
+
+
+
+///
+/// How far along `dir` the ray from `start` enters the box
+/// between `min` and `max`, zero if it starts inside of it
+///
+pub fn ray_aabb(start: DVec3, dir: Vec3, min: DVec3, max: DVec3) -> Option<f32> {
+    let inv = 1.0 / dir.as_dvec3();
+    let t0 = (min - start) * inv;
+    let t1 = (max - start) * inv;
+
+    let near = t0.min(t1).max_element();
+    let far = t0.max(t1).min_element();
+    if near > far || far < 0.0 { return None }
+
+    Some(near.max(0.0) as f32)
+}
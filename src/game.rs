@@ -1,19 +1,21 @@
 pub mod save_system;
 
-use std::{collections::HashSet, time::Instant};
+use std::{collections::HashSet, thread::JoinHandle, time::Instant};
 
-use glam::{DVec3, IVec3, Mat4, Quat, Vec2, Vec3, Vec4, Vec4Swizzles};
-use kira::{sound::static_sound::{StaticSoundData, StaticSoundSettings}, AudioManager, AudioManagerSettings, DefaultBackend, Tween};
+use glam::{DVec2, DVec3, IVec2, IVec3, Mat4, Quat, Vec2, Vec3, Vec4, Vec4Swizzles};
+use kira::{sound::static_sound::{StaticSoundData, StaticSoundSettings}, AudioManager, AudioManagerSettings, Decibels, DefaultBackend, Tween};
 use sti::hash::fxhash::fxhash32;
 use tracing::{info, warn, Instrument};
+use wgpu::PresentMode;
 use winit::{dpi::LogicalPosition, event::MouseButton, keyboard::KeyCode, window::CursorGrabMode};
 
-use crate::{commands::{Command, CommandRegistry}, constants::{CHUNK_SIZE_I32, COLOUR_DENY, COLOUR_PASS, DELTA_TICK, DROPPED_ITEM_SCALE, LOAD_DISTANCE, MOUSE_SENSITIVITY, PLAYER_HOTBAR_SIZE, PLAYER_INTERACT_DELAY, PLAYER_INVENTORY_SIZE, PLAYER_PULL_DISTANCE, PLAYER_REACH, PLAYER_ROW_SIZE, PLAYER_SPEED, RENDER_DISTANCE, TICKS_PER_SECOND, UI_CROSSAIR_COLOUR, UI_CROSSAIR_SIZE, UI_HOTBAR_SELECTED_BG, UI_HOTBAR_UNSELECTED_BG, UI_ITEM_AMOUNT_SCALE, UI_ITEM_OFFSET, UI_ITEM_SIZE, UI_SLOT_PADDING, UI_SLOT_SIZE}, directions::CardinalDirection, entities::{EntityKind, EntityMap}, frustum::Frustum, input::InputManager, items::{Assets, Item, ItemKind, MeshIndex}, mesh::{Mesh, MeshInstance}, renderer::{Renderer, View}, structures::{strct::{Structure, StructureData, StructureKind}, Structures}, ui::{InventoryMode, UILayer, HOTBAR_KEYS}, voxel_world::{chunker::{ChunkEntry, ChunkPos, MeshEntry, WorldChunkPos}, split_world_pos, voxel::Voxel, VoxelWorld, SURROUNDING_OFFSETS}, Camera, PhysicsBody, Player, Tick};
+use crate::{ambience::Ambience, commands::{Command, CommandRegistry}, constants::{AMBIENCE_REFRESH_TICKS, AUTOSAVE_INTERVAL, CHUNK_SIZE_I32, COLOUR_DENY, COLOUR_PASS, COLOUR_VOID, COLOUR_WHITE, DELTA_TICK, DROPPED_ITEM_SCALE, DROPPED_ITEM_TOSS_SPEED, FILL_MAX_VOLUME, GAMEPAD_DEADZONE, INITIAL_RENDER_DISTANCE, LOAD_DISTANCE, LOD_DISTANCE, MAX_SIMULATION_CATCH_UP, MAX_TIMESCALE, MINIMAP_REFRESH_TICKS, MOUSE_ACCELERATION, MOUSE_ACCELERATION_CAP, MOUSE_SENSITIVITY, MSAA_SAMPLE_COUNT, PARTICLE_BREAK_COUNT, PARTICLE_PLACE_COUNT, PLAYER_ACCELERATION, PLAYER_FOV, PLAYER_FOV_EASING, PLAYER_HAND_BOB, PLAYER_HAND_OFFSET, PLAYER_HOTBAR_SIZE, PLAYER_INTERACT_DELAY, PLAYER_INVENTORY_FULL_TIME, PLAYER_FALL_DAMAGE_PER_SPEED, PLAYER_FALL_DAMAGE_SPEED, PLAYER_FRICTION, PLAYER_INVENTORY_SIZE, PLAYER_MAX_HEALTH, PLAYER_PULL_DISTANCE, PLAYER_GOD_REACH, PLAYER_MAX_REACH, PLAYER_REACH, PLAYER_ROW_SIZE, PLAYER_SPAWN, PLAYER_SPEED, PLAYER_SPRINT_FOV, PLAYER_SPRINT_MULTIPLIER, PLAYER_SWIM_MULTIPLIER, PLAYER_SWIM_UP_SPEED, PLAYER_ZOOM_FOV, RENDER_DISTANCE, TICKS_PER_SECOND, UI_CROSSAIR_COLOUR, UI_CROSSAIR_IDLE_COLOUR, UI_CROSSAIR_INTERACT_COLOUR, UI_CROSSAIR_SIZE, UI_HEALTH_BAR_COLOUR, UI_HEALTH_BAR_HEIGHT, UI_HOTBAR_SELECTED_BG, UI_HOTBAR_UNSELECTED_BG, UI_ITEM_AMOUNT_SCALE, UI_ITEM_OFFSET, UI_ITEM_SIZE, UI_SLOT_PADDING, UI_SLOT_SIZE, UI_TEXT_SHADOW_COLOUR, UI_TEXT_SHADOW_OFFSET, VOID_FADE_DISTANCE}, directions::{CardinalDirection, Orientation}, entities::{ray_aabb, EntityKind, EntityMap}, frustum::Frustum, input::{InputDevice, InputManager}, items::{Assets, Item, ItemKind, MeshIndex}, mesh::{Mesh, MeshInstance}, minimap::Minimap, particles::Particles, perlin::{LaneF64, LANES}, renderer::{DebugDraw, Fog, Renderer, Sun, TextAlign}, structures::{belts::GraphFormat, strct::{facing_towards, orientation_tilt, rotate_oriented_vector, Structure, StructureConfig, StructureData, StructureKind}, StructureId, Structures}, ui::{InventoryMode, UILayer, HOTBAR_KEYS}, voxel_world::{chunk::Chunk, chunker::{ChunkEntry, ChunkPos, MeshEntry, WorldChunkPos}, split_world_pos, voxel::Voxel, HeightLimits, VoxelWorld, SURROUNDING_OFFSETS}, Camera, PhysicsBody, Player, Tick};
 
 pub struct Game {
     pub world: VoxelWorld,
     pub player: Player,
     pub entities: EntityMap,
+    pub particles: Particles,
     pub command_registry: CommandRegistry,
     pub structures: Structures,
 
@@ -23,6 +25,7 @@ pub struct Game {
     pub craft_progress: u32,
     pub triangle_count: u32,
     pub draw_call_count: u32,
+    /// how long the last `Renderer::end` took in microseconds
     pub render_world_time: u32,
     pub total_rendered_chunks: u32,
     pub lock_frustum: Option<Frustum>,
@@ -31,10 +34,32 @@ pub struct Game {
     ui_layer: UILayer,
 
     pub settings: Settings,
+    /// how far out chunks are loaded right now, it ramps up to
+    /// `Settings::render_distance` while the world is loading
+    pub render_distance: i32,
     prev_player_chunk: Option<WorldChunkPos>,
 
 
-    audio: AudioManager<DefaultBackend>,
+    /// `None` if there's no audio device or the game is headless
+    audio: Option<AudioManager<DefaultBackend>>,
+    ambience: Option<Ambience>,
+    minimap: Minimap,
+
+    /// the autosave that's still being written to disk, if any
+    save_job: Option<JoinHandle<()>>,
+    /// the path and contents of a save made while an autosave was
+    /// still being written, it goes to disk once that one's done
+    queued_save: Option<(String, String)>,
+    /// where to write a thumbnail of the next frame,
+    /// the renderer takes it once it's done drawing
+    pub thumbnail_request: Option<String>,
+
+    /// settings copied off of a structure with Ctrl+C,
+    /// pasted onto another of the same kind with Ctrl+V
+    clipboard: Option<(StructureKind, StructureConfig)>,
+
+    /// where the player respawns, `PLAYER_SPAWN` if it was never set
+    pub world_spawn: Option<DVec3>,
 
 
 }
@@ -42,16 +67,89 @@ pub struct Game {
 #[derive(Clone, Copy)]
 pub struct Settings {
     pub ui_scale: f32,
-    pub delta_tick: f32,
+    /// how much faster than real time the simulation runs,
+    /// a tick always simulates `DELTA_TICK` seconds regardless
+    pub timescale: f32,
     pub player_speed: f32,
     pub render_distance: i32,
-    pub lines: bool,
+    pub debug_draw: DebugDraw,
     pub draw_hitboxes: bool,
+    /// the minimum amount of seconds between two frames,
+    /// 0.0 means the frame rate is uncapped
+    pub min_frame_time: f32,
+    /// outline every element laid out by a `View`
+    pub debug_layout: bool,
+    /// remesh the chunk the player edits on the main thread
+    /// so the change shows up without a delay
+    pub instant_mesh: bool,
+    /// whether scrolling past the end of the hotbar wraps
+    /// around to the other side or stops
+    pub hotbar_wrap: bool,
+    /// whether items the player can't hold still get pulled
+    /// in, they wait at the player's feet until there's room
+    pub pull_when_full: bool,
+    /// how far away, in blocks, the player can mine,
+    /// place and interact with things, see `Game::reach`
+    pub reach: f32,
+    /// lets a creative player build from `PLAYER_GOD_REACH`
+    /// blocks away instead of `reach`
+    pub god_reach: bool,
+    /// how much faster the player moves while sprinting
+    pub sprint_multiplier: f32,
+    /// how quickly the player speeds up towards where they're
+    /// walking, see `PLAYER_ACCELERATION`
+    pub acceleration: f32,
+    /// how quickly the player comes to a stop once they let go
+    pub friction: f32,
+    /// how frames get presented, `Immediate` turns vsync off
+    pub present_mode: PresentMode,
+    /// how many samples msaa takes per pixel, 1 turns it off
+    pub msaa: u32,
+    /// how many chunks away the world switches to coarser meshes
+    pub lod_distance: i32,
+    /// how far a gamepad stick has to be tilted before it counts
+    pub stick_deadzone: f32,
+    /// the fov, in degrees, while not zoomed in
+    pub fov: f32,
+    /// the fov, in degrees, while holding C
+    pub zoom_fov: f32,
+    /// how fast the camera turns horizontally and vertically,
+    /// as a multiple of `MOUSE_SENSITIVITY`
+    pub sensitivity: Vec2,
+    /// whether faster mouse movements turn the camera further
+    pub mouse_acceleration: bool,
+    /// how many seconds apart autosaves are, 0 turns them off
+    pub autosave_interval: u32,
+    pub fog: Fog,
+    pub sun: Sun,
+    /// how loud everything is from 0.0 to 1.0, see `Game::set_volume`
+    pub volume: f32,
 }
 
 
 impl Game {
     pub fn new() -> Game {
+        let audio = AudioManager::new(AudioManagerSettings::default())
+            .inspect_err(|e| warn!("unable to start the audio manager: {e}"))
+            .ok();
+
+        let mut this = Self::with_audio(audio);
+        this.command_registry.load_history();
+        this
+    }
+
+
+    ///
+    /// A game without audio that doesn't touch the disk until
+    /// it's told to, so the simulation can be driven from tests
+    /// with `simulation_tick` and no window or gpu around
+    ///
+    pub fn headless() -> Game {
+        Self::with_audio(None)
+    }
+
+
+    fn with_audio(audio: Option<AudioManager<DefaultBackend>>) -> Game {
         let mut this = Game {
             triangle_count: 0,
             total_rendered_chunks: 0,
@@ -63,6 +161,7 @@ impl Game {
             world: VoxelWorld::new(),
             structures: Structures::new(),
             entities: EntityMap::new(),
+            particles: Particles::new(),
 
             camera: Camera {
                 position: DVec3::ZERO,
@@ -70,7 +169,8 @@ impl Game {
                 up: Vec3::new(0.0, 1.0, 0.0),
                 pitch: 0.0,
                 yaw: 90.0f32.to_radians(),
-                fov: 80.069f32.to_radians(),
+                fov: PLAYER_FOV.to_radians(),
+                target_fov: PLAYER_FOV.to_radians(),
                 aspect_ratio: 16.0/9.0,
                 near: 0.01,
                 far: 5_000.0,
@@ -81,9 +181,13 @@ impl Game {
 
             player: Player {
                 body: PhysicsBody {
-                    position: DVec3::new(0.0, 10.0, 0.0),
+                    position: PLAYER_SPAWN,
                     velocity: Vec3::ZERO,
                     aabb_dims: Vec3::new(0.8, 1.8, 0.8),
+                    pass_through_walkable: true,
+                    has_gravity: true,
+                    on_ground: false,
+                    in_water: false,
                 },
 
                 inventory: [None; PLAYER_INVENTORY_SIZE],
@@ -91,7 +195,16 @@ impl Game {
                 hotbar: PLAYER_ROW_SIZE-1,
                 mining_progress: None,
                 interact_delay: 0.0,
+                health: PLAYER_MAX_HEALTH,
+                time_since_damage: 0.0,
+                creative: false,
+                pipette: None,
+                pinned_recipes: Vec::new(),
                 preview_rotation_offset: 0,
+                drag_from: None,
+                last_belt: None,
+                walk_cycle: 0.0,
+                inventory_full_time: 0.0,
 
             },
 
@@ -105,19 +218,51 @@ impl Game {
 
             settings: Settings {
                 ui_scale: 1.0,
-                delta_tick: DELTA_TICK,
+                timescale: 1.0,
                 player_speed: PLAYER_SPEED,
                 render_distance: RENDER_DISTANCE,
-                lines: false,
+                debug_draw: DebugDraw::Off,
                 draw_hitboxes: false,
+                min_frame_time: 0.0,
+                debug_layout: false,
+                instant_mesh: true,
+                hotbar_wrap: true,
+                pull_when_full: false,
+                reach: PLAYER_REACH,
+                god_reach: false,
+                sprint_multiplier: PLAYER_SPRINT_MULTIPLIER,
+                acceleration: PLAYER_ACCELERATION,
+                friction: PLAYER_FRICTION,
+                present_mode: PresentMode::Immediate,
+                msaa: MSAA_SAMPLE_COUNT,
+                lod_distance: LOD_DISTANCE,
+                stick_deadzone: GAMEPAD_DEADZONE,
+                fov: PLAYER_FOV,
+                zoom_fov: PLAYER_ZOOM_FOV,
+                sensitivity: Vec2::ONE,
+                mouse_acceleration: false,
+                autosave_interval: AUTOSAVE_INTERVAL,
+                fog: Fog::DEFAULT,
+                sun: Sun::DEFAULT,
+                volume: 1.0,
             },
 
+            render_distance: INITIAL_RENDER_DISTANCE,
             prev_player_chunk: Some(WorldChunkPos(IVec3::MAX)),
 
 
-            audio: AudioManager::new(AudioManagerSettings::default()).unwrap(),
+            audio,
+            ambience: None,
+            minimap: Minimap::new(),
+            save_job: None,
+            queued_save: None,
+            thumbnail_request: None,
+            clipboard: None,
+            world_spawn: None,
         };
 
+        this.ambience = this.audio.as_mut().and_then(|audio| Ambience::new(audio));
+
 
         this.command_registry.register("speed", |game, cmd| {
             let speed = cmd.arg(0)?.as_f32()?;
@@ -126,9 +271,153 @@ impl Game {
         });
 
 
+        this.command_registry.register("sprint", |game, cmd| {
+            let multiplier = cmd.arg(0)?.as_f32()?;
+            game.settings.sprint_multiplier = multiplier;
+            Some(())
+        });
+
+
+        this.command_registry.register("acceleration", |game, cmd| {
+            let acceleration = cmd.arg(0)?.as_f32()?;
+            game.settings.acceleration = acceleration.max(1.0);
+            Some(())
+        });
+
+
+        this.command_registry.register("friction", |game, cmd| {
+            let friction = cmd.arg(0)?.as_f32()?;
+            game.settings.friction = friction.max(1.0);
+            Some(())
+        });
+
+
+        this.command_registry.register("fov", |game, cmd| {
+            let fov = cmd.arg(0)?.as_f32()?;
+            game.settings.fov = fov.clamp(1.0, 179.0 - PLAYER_SPRINT_FOV);
+            Some(())
+        });
+
+
+        this.command_registry.register("zoom_fov", |game, cmd| {
+            let fov = cmd.arg(0)?.as_f32()?;
+            game.settings.zoom_fov = fov.clamp(1.0, 179.0);
+            Some(())
+        });
+
+
+        this.command_registry.register("lod", |game, cmd| {
+            let distance = cmd.arg(0)?.as_i32()?;
+            game.settings.lod_distance = distance.max(0);
+            Some(())
+        });
+
+
+        this.command_registry.register("sensitivity", |game, cmd| {
+            if let Some(x) = cmd.arg(0) {
+                let x = x.as_f32()?;
+                let y = match cmd.arg(1) {
+                    Some(y) => y.as_f32()?,
+                    None => x,
+                };
+
+                game.settings.sensitivity = Vec2::new(x, y).max(Vec2::ZERO);
+            }
+
+            let sensitivity = game.settings.sensitivity;
+            info!("sensitivity: horizontal {} vertical {}", sensitivity.x, sensitivity.y);
+            Some(())
+        });
+
+
+        this.command_registry.register("mouse_acceleration", |game, _| {
+            game.settings.mouse_acceleration = !game.settings.mouse_acceleration;
+            Some(())
+        });
+
+
+        this.command_registry.register("autosave", |game, cmd| {
+            if let Some(seconds) = cmd.arg(0) {
+                let seconds = seconds.as_u32()?;
+                // the interval gets counted in ticks
+                TICKS_PER_SECOND.checked_mul(seconds)?;
+                game.settings.autosave_interval = seconds;
+            }
+
+            match game.settings.autosave_interval {
+                0 => info!("autosave: off"),
+                seconds => info!("autosave: every {seconds} seconds"),
+            }
+            Some(())
+        });
+
+
+        this.command_registry.register("fog", |game, cmd| {
+            let fog = &mut game.settings.fog;
+            match cmd.arg(0).map(|x| x.as_str()) {
+                None => (),
+                Some("off") => fog.density = 0.0,
+                Some("reset") => *fog = Fog::DEFAULT,
+                Some("density") => fog.density = cmd.arg(1)?.as_f32()?.max(0.0),
+                Some("start") => fog.start = Some(cmd.arg(1)?.as_f32()?.max(0.0)),
+                Some("end") => fog.end = Some(cmd.arg(1)?.as_f32()?.max(0.0)),
+
+                // `fog colour sky` goes back to following the sky
+                Some("colour") if cmd.arg(1)?.as_str() == "sky" => fog.colour = None,
+                Some("colour") => {
+                    let r = cmd.arg(1)?.as_f32()?;
+                    let g = cmd.arg(2)?.as_f32()?;
+                    let b = cmd.arg(3)?.as_f32()?;
+                    fog.colour = Some(Vec3::new(r, g, b).clamp(Vec3::ZERO, Vec3::ONE));
+                }
+
+                _ => return None,
+            }
+
+            let distance = |x: Option<f32>| x.map(|x| format!("{x}")).unwrap_or("auto".to_string());
+            let colour = fog.colour.map(|x| format!("{} {} {}", x.x, x.y, x.z)).unwrap_or("sky".to_string());
+            info!("fog: start {} end {} density {} colour {colour}", distance(fog.start), distance(fog.end), fog.density);
+            Some(())
+        });
+
+
+        this.command_registry.register("sun", |game, cmd| {
+            let sun = &mut game.settings.sun;
+            match cmd.arg(0).map(|x| x.as_str()) {
+                None => (),
+                Some("reset") => *sun = Sun::DEFAULT,
+                Some("intensity") => sun.intensity = cmd.arg(1)?.as_f32()?.max(0.0),
+                Some("ambient") => sun.ambient = cmd.arg(1)?.as_f32()?.clamp(0.0, 1.0),
+                Some("direction") => {
+                    let x = cmd.arg(1)?.as_f32()?;
+                    let y = cmd.arg(2)?.as_f32()?;
+                    let z = cmd.arg(3)?.as_f32()?;
+
+                    let direction = Vec3::new(x, y, z);
+                    if direction.length_squared() == 0.0 { return None }
+                    sun.direction = direction;
+                }
+
+                _ => return None,
+            }
+
+            let direction = sun.direction.normalize();
+            info!("sun: direction {:.2} {:.2} {:.2} intensity {} ambient {}", direction.x, direction.y, direction.z, sun.intensity, sun.ambient);
+            Some(())
+        });
+
+
+        this.command_registry.register("deadzone", |game, cmd| {
+            let deadzone = cmd.arg(0)?.as_f32()?;
+            game.settings.stick_deadzone = deadzone.clamp(0.0, 1.0);
+            Some(())
+        });
+
+
         this.command_registry.register("rd", |game, cmd| {
-            let speed = cmd.arg(0)?.as_i32()?;
-            game.settings.render_distance = speed;
+            // a bigger render distance gets ramped up to, see `simulation_tick`
+            let distance = cmd.arg(0)?.as_i32()?;
+            game.settings.render_distance = distance.max(1);
             game.prev_player_chunk = Some(WorldChunkPos(IVec3::MAX));
             Some(())
         });
@@ -145,7 +434,11 @@ impl Game {
             let item = cmd.arg(0)?.as_str();
             let &kind = ItemKind::ALL.iter().find(|x| x.to_string() == item)?;
 
-            let amount = cmd.arg(1)?.as_u32()?;
+            let amount = cmd.arg(1)?;
+            let amount = match amount.as_str() {
+                "@max" | "@stack" => kind.max_stack_size(),
+                _ => amount.as_u32()?,
+            };
 
             let stacks = amount / kind.max_stack_size();
             let rem = amount % kind.max_stack_size();
@@ -158,12 +451,58 @@ impl Game {
                 );
             }
 
-            let item = Item { amount: rem, kind };
-            game.entities.spawn(
-                EntityKind::dropped_item(item),
-                game.player.body.position
-            );
+            if rem > 0 {
+                let item = Item { amount: rem, kind };
+                game.entities.spawn(
+                    EntityKind::dropped_item(item),
+                    game.player.body.position
+                );
+            }
 
+            info!("give: added {amount} {item}");
+            Some(())
+        });
+
+
+        this.command_registry.register("remove", |game, cmd| {
+            let item = cmd.arg(0)?.as_str();
+            let &kind = ItemKind::ALL.iter().find(|x| x.to_string() == item)?;
+
+            let mut removed = 0;
+            for slot in &mut game.player.inventory {
+                if let Some(item) = slot
+                    && item.kind == kind {
+                    removed += item.amount;
+                    *slot = None;
+                }
+            }
+
+            info!("remove: removed {removed} {item}");
+            Some(())
+        });
+
+
+        this.command_registry.register("fill", |game, cmd| {
+            let corner = |i| Some(IVec3::new(cmd.arg(i)?.as_i32()?, cmd.arg(i+1)?.as_i32()?, cmd.arg(i+2)?.as_i32()?));
+            let (a, b) = (corner(0)?, corner(3)?);
+            let (min, max) = (a.min(b), a.max(b));
+
+            let name = cmd.arg(6)?.as_str();
+            let Some(&voxel) = Voxel::ALL.iter().find(|x| x.to_string() == name)
+            else {
+                warn!("fill: there's no voxel called '{name}'");
+                return Some(());
+            };
+
+            let size = max.as_i64vec3() - min.as_i64vec3() + 1;
+            let volume = size.x * size.y * size.z;
+            if volume > FILL_MAX_VOLUME {
+                warn!("fill: {volume} voxels is too many, the most is {FILL_MAX_VOLUME}");
+                return Some(());
+            }
+
+            let changed = game.world.fill(min, max, voxel);
+            info!("fill: changed {changed} of {volume} voxels to {name}");
             Some(())
         });
 
@@ -178,14 +517,95 @@ impl Game {
             Some(())
         });
 
+
+        this.command_registry.register("setspawn", |game, _| {
+            let pos = game.player.body.position;
+            game.world_spawn = Some(pos);
+            info!("spawn: set to {:.1} {:.1} {:.1}", pos.x, pos.y, pos.z);
+            Some(())
+        });
+
+
+        let respawn = |game: &mut Game, _: &Command| {
+            game.player.body.position = game.spawn_position();
+            game.player.body.velocity = Vec3::ZERO;
+            Some(())
+        };
+        this.command_registry.register("respawn", respawn);
+        this.command_registry.register("home", respawn);
+
+        this.command_registry.register("goto", |game, cmd| {
+            let name = cmd.arg(0)?.as_str();
+            let kind = ItemKind::ALL.iter()
+                .filter_map(|x| x.as_structure())
+                .find(|x| x.item_kind().to_string() == name)?;
+
+            let Some(nearest) = game.structures.nearest(game.player.body.position, kind)
+            else {
+                warn!("goto: there's no {name} in the world");
+                return Some(());
+            };
+
+            let structure = game.structures.get(nearest);
+
+            let top = structure.blocks().map(|x| x.y).max().unwrap_or(0) + 1;
+            let pos = structure.zero_zero() + IVec3::new(0, top, 0);
+            game.player.body.position = pos.as_dvec3() + DVec3::new(0.5, 0.0, 0.5);
+
+            Some(())
+        });
+
+        this.command_registry.register("entities", |game, cmd| {
+            if let Some(cap) = cmd.arg(0) {
+                game.entities.max_entities = cap.as_u32()? as usize;
+            }
+
+            info!("entities: {}/{}", game.entities.count(), game.entities.max_entities);
+            Some(())
+        });
+
+        this.command_registry.register("history", |game, cmd| {
+            game.command_registry.history_cap = cmd.arg(0)?.as_u32()? as usize;
+            game.command_registry.trim_history();
+            Some(())
+        });
+
+        this.command_registry.register("quarry_depth", |game, cmd| {
+            let depth = cmd.arg(0)?.as_u32()?;
+
+            let (pos, _) = game.world.raycast_voxel(game.camera.position, game.camera.front, game.reach())?;
+            let id = *game.world.structure_blocks.get(&pos)?;
+
+            let StructureData::Quarry { max_depth, .. } = &mut game.structures.get_mut(id).data
+            else { return None };
+
+            *max_depth = depth;
+            Some(())
+        });
+
         this.command_registry.register("clear", |game, _| {
             game.player.inventory.iter_mut().for_each(|x| *x = None);
 
             Some(())
         });
 
-        this.command_registry.register("dt", |game, cmd| {
-            game.settings.delta_tick = cmd.arg(0)?.as_f32()?;
+        this.command_registry.register("timescale", |game, cmd| {
+            if let Some(scale) = cmd.arg(0) {
+                let scale = scale.as_f32()?;
+                if scale <= 0.0 || scale > MAX_TIMESCALE { return None }
+
+                game.settings.timescale = scale;
+            }
+
+            info!("timescale: {}x, {} ticks per second", game.settings.timescale, game.settings.timescale * TICKS_PER_SECOND as f32);
+            Some(())
+        });
+
+        this.command_registry.register("min_frame_time", |game, cmd| {
+            let ms = cmd.arg(0)?.as_f32()?;
+            if ms < 0.0 { return None }
+
+            game.settings.min_frame_time = ms / 1000.0;
             Some(())
         });
 
@@ -194,6 +614,271 @@ impl Game {
             Some(())
         });
 
+        this.command_registry.register("save", |game, cmd| {
+            let full = match cmd.arg(0) {
+                Some(arg) if arg.as_str() == "--full" => true,
+                Some(_) => return None,
+                None => false,
+            };
+
+            game.save_ex(full);
+            Some(())
+        });
+
+        this.command_registry.register("watch", |game, cmd| {
+            let ticks = cmd.arg(0)?.as_u32()?;
+
+            let (pos, _) = game.world.raycast_voxel(game.camera.position, game.camera.front, game.reach())?;
+            let &id = game.world.structure_blocks.get(&pos)?;
+
+            let until = game.structures.current_tick + Tick::new(ticks);
+            game.structures.watched = Some((id, until));
+            info!("watch: watching {:?} at '{}' for {ticks} ticks", game.structures.get(id).data.as_kind(), pos);
+            Some(())
+        });
+
+        this.command_registry.register("unwatch", |game, _| {
+            game.structures.watched.take()?;
+            info!("watch: stopped watching");
+            Some(())
+        });
+
+        this.command_registry.register("beltflow", |game, _| {
+            game.structures.show_belt_flow = !game.structures.show_belt_flow;
+            info!("beltflow: {}", if game.structures.show_belt_flow { "on" } else { "off" });
+            Some(())
+        });
+
+        this.command_registry.register("height_limits", |game, cmd| {
+            let min = cmd.arg(0)?.as_i32()?;
+            let max = cmd.arg(1)?.as_i32()?;
+            if min >= max { return None }
+
+            game.world.chunker.limits = HeightLimits { min, max };
+            Some(())
+        });
+
+        this.command_registry.register("seed", |game, cmd| {
+            if let Some(seed) = cmd.arg(0) {
+                let seed = seed.as_str().parse().ok()?;
+                let count = game.world.chunker.set_seed(seed);
+                info!("seed: regenerated {count} untouched chunk(s), edited and saved chunks keep their terrain");
+            }
+
+            info!("seed: {}", game.world.chunker.seed());
+            Some(())
+        });
+
+
+        this.command_registry.register("biome", |game, _| {
+            let pos = game.player.body.position;
+            let sample = game.world.chunker.noise().biome_sample(DVec2::new(pos.x, pos.z));
+            info!("biome: {:?} at {:.1}, {:.1} (height amplitude: {:.2}, ore density: {:.2})",
+                  sample.biome, pos.x, pos.z, sample.height_amplitude, sample.ore_density);
+            Some(())
+        });
+
+        this.command_registry.register("chunkgen", |game, cmd| {
+            let count = match cmd.arg(0) {
+                Some(count) => count.as_u32()? as i32,
+                None => 16,
+            };
+
+            let noise = game.world.chunker.noise();
+            let limits = game.world.chunker.limits;
+            let origin = split_world_pos(game.player.body.position.as_ivec3()).0.0;
+
+            // the height map is the part the simd lanes speed up so
+            // time both ways of sampling it on the same columns
+            let columns = |i: i32| (0..CHUNK_SIZE_I32).flat_map(move |x| (0..CHUNK_SIZE_I32).map(move |z| {
+                IVec2::new((origin.x + i) * CHUNK_SIZE_I32 + x, origin.z * CHUNK_SIZE_I32 + z)
+            }));
+
+            let start = Instant::now();
+            let mut scalar = 0.0;
+            for pos in (0..count).flat_map(columns) {
+                let pos = pos.as_dvec2();
+                scalar += noise.sample(pos) * noise.biome_sample(pos).height_amplitude;
+            }
+            let scalar_time = start.elapsed();
+
+            let start = Instant::now();
+            let mut lanes = 0.0;
+            for pos in (0..count).flat_map(columns).step_by(LANES) {
+                let xs = LaneF64::splat(pos.x as f64);
+                let zs = LaneF64::from_array(std::array::from_fn(|lane| (pos.y + lane as i32) as f64));
+
+                let heights = noise.sample_lanes(xs, zs);
+                let biomes = noise.biome_sample_lanes(xs, zs);
+                for lane in 0..LANES {
+                    lanes += heights[lane] * biomes[lane].height_amplitude;
+                }
+            }
+            let lanes_time = start.elapsed();
+
+            let start = Instant::now();
+            for i in 0..count {
+                Chunk::generate(origin + IVec3::new(i, 0, 0), noise, limits);
+            }
+            let generate_time = start.elapsed();
+
+            info!("chunkgen: height maps for {count} chunk(s): scalar {scalar_time:?}, simd {lanes_time:?} ({:.2}x)",
+                  scalar_time.as_secs_f64() / lanes_time.as_secs_f64().max(f64::EPSILON));
+            info!("chunkgen: generated {count} chunk(s) in {generate_time:?} ({:.1} chunks/s)",
+                  count as f64 / generate_time.as_secs_f64().max(f64::EPSILON));
+
+            if scalar.to_bits() != lanes.to_bits() {
+                warn!("chunkgen: the scalar and simd height maps don't match");
+            }
+
+            Some(())
+        });
+
+        this.command_registry.register("mesh_desync", |game, _| {
+            let desyncs = game.world.chunker.mesh_desyncs();
+            info!("mesh-desync: {} chunk(s) out of sync", desyncs.len());
+
+            for desync in desyncs {
+                let mesh_version = match desync.mesh_version {
+                    Some(version) => version.get().to_string(),
+                    None => "none".to_string(),
+                };
+
+                let state = if desync.is_meshing { "meshing" }
+                            else if desync.is_queued { "queued" }
+                            else { "idle" };
+
+                info!("mesh-desync: {}: chunk v{} mesh {} ({state})",
+                      desync.pos.0, desync.chunk_version.get(), mesh_version);
+            }
+
+            Some(())
+        });
+
+        this.command_registry.register("remesh", |game, _| {
+            let count = game.world.chunker.remesh_all();
+            info!("remesh: queued {count} chunk(s)");
+            Some(())
+        });
+
+        this.command_registry.register("meshstats", |game, _| {
+            let stats = game.world.chunker.mesh_stats();
+            let average = stats.quads as f64 / stats.chunks.max(1) as f64;
+
+            info!("meshstats: {} chunk(s) with {} quads, {average:.1} per chunk", stats.chunks, stats.quads);
+            info!("meshstats: {} lod quads", stats.lod_quads);
+            info!("meshstats: {:.2} MiB allocated for quads", stats.allocated_bytes as f64 / (1024.0 * 1024.0));
+            Some(())
+        });
+
+        this.command_registry.register("creative", |game, _| {
+            game.player.set_creative(!game.player.creative);
+            Some(())
+        });
+
+        this.command_registry.register("hotbar_wrap", |game, _| {
+            game.settings.hotbar_wrap = !game.settings.hotbar_wrap;
+            Some(())
+        });
+
+        this.command_registry.register("pull_when_full", |game, _| {
+            game.settings.pull_when_full = !game.settings.pull_when_full;
+            info!("pull_when_full: {}", game.settings.pull_when_full);
+            Some(())
+        });
+
+        this.command_registry.register("reach", |game, cmd| {
+            if let Some(arg) = cmd.arg(0) {
+                game.settings.reach = arg.as_f32()?.clamp(1.0, PLAYER_MAX_REACH);
+            }
+
+            info!("reach: {}", game.settings.reach);
+            Some(())
+        });
+
+        this.command_registry.register("god_reach", |game, _| {
+            game.settings.god_reach = !game.settings.god_reach;
+            info!("god_reach: {}", game.settings.god_reach);
+            if game.settings.god_reach && !game.player.creative {
+                warn!("god_reach: only applies in creative");
+            }
+            Some(())
+        });
+
+        this.command_registry.register("destroy_area", |game, cmd| {
+            let radius = cmd.arg(0)?.as_f64()?;
+            let center = game.player.body.position;
+
+            let targets : Vec<_> = game.structures.query_radius(center, radius)
+                .into_iter()
+                .map(|id| game.structures.get(id).position)
+                .collect();
+
+            for pos in targets {
+                let item = game.world.break_block(&mut game.structures, &mut game.entities, pos);
+                if !game.player.creative {
+                    game.entities.spawn(
+                        EntityKind::dropped_item(item),
+                        pos.as_dvec3() + DVec3::new(0.5, 0.5, 0.5)
+                    );
+                }
+            }
+
+            Some(())
+        });
+
+        this.command_registry.register("vsync", |game, cmd| {
+            game.settings.present_mode = match cmd.arg(0).map(|x| x.as_str()) {
+                Some("off") => PresentMode::Immediate,
+                Some("on") => PresentMode::Fifo,
+                Some("mailbox") => PresentMode::Mailbox,
+                Some(_) => return None,
+
+                None if game.settings.present_mode == PresentMode::Immediate => PresentMode::Fifo,
+                None => PresentMode::Immediate,
+            };
+            Some(())
+        });
+
+        this.command_registry.register("msaa", |game, cmd| {
+            let count = cmd.arg(0)?.as_u32()?;
+            if ![1, 2, 4, 8].contains(&count) { return None }
+
+            // the renderer falls back if the adapter doesn't support it
+            game.settings.msaa = count;
+            Some(())
+        });
+
+        this.command_registry.register("instant_mesh", |game, _| {
+            game.settings.instant_mesh = !game.settings.instant_mesh;
+            Some(())
+        });
+
+        this.command_registry.register("debug_draw", |game, cmd| {
+            game.settings.debug_draw = DebugDraw::from_str(cmd.arg(0)?.as_str())?;
+            Some(())
+        });
+
+        this.command_registry.register("graph", |game, cmd| {
+            let format = GraphFormat::from_str(cmd.arg(0)?.as_str())?;
+            let path = cmd.arg(1)?.as_str();
+
+            let belts = game.structures.belts(&game.world);
+            let graph = belts.export(&game.structures, format);
+            if let Err(e) = std::fs::write(path, graph) {
+                warn!("graph: unable to write '{path}': {e}");
+                return Some(());
+            }
+
+            info!("graph: wrote {} belt nodes to '{path}' as {format:?}", belts.structure_to_node.len());
+            Some(())
+        });
+
+        this.command_registry.register("debug_layout", |game, _| {
+            game.settings.debug_layout = !game.settings.debug_layout;
+            Some(())
+        });
+
         this.command_registry.register("toggle_frustum", |game, _| {
             if game.lock_frustum.is_some() {
                 game.lock_frustum = None;
@@ -210,13 +895,13 @@ impl Game {
     pub fn call_command(&mut self, command: Command) {
         let Some(func) = self.command_registry.find(command.command())
         else {
-            self.command_registry.previous_commands.push(command);
+            self.command_registry.push_history(command);
             return;
         };
 
         func(self, &command);
 
-        self.command_registry.previous_commands.push(command);
+        self.command_registry.push_history(command);
     }
 
     
@@ -224,12 +909,18 @@ impl Game {
         &mut self,
         structure: StructureKind,
         pos: IVec3,
-        direction: CardinalDirection
+        direction: CardinalDirection,
+        orientation: Orientation,
     ) -> bool {
 
-        let pos = pos - structure.origin(direction);
-        let blocks = structure.blocks(direction);
+        let pos = pos - structure.oriented_origin(direction, orientation);
+        let blocks = structure.oriented_blocks(direction, orientation);
+        let limits = self.world.chunker.limits;
         for offset in blocks {
+            if !limits.contains((pos + offset).y) {
+                return false;
+            }
+
             if !self.world.get_voxel(pos + offset).is_air() {
                 return false;
             }
@@ -239,6 +930,288 @@ impl Game {
     }
 
 
+    ///
+    /// Places whatever the player is holding at `place_position` facing `dir`,
+    /// tipped over by `orientation` if it's orientable. Returns whether
+    /// anything got placed
+    ///
+    fn place_held_item(&mut self, place_position: IVec3, dir: CardinalDirection, orientation: Orientation) -> bool {
+        if !self.world.chunker.limits.contains(place_position.y) { return false }
+
+        let voxel = self.world.get_voxel(place_position);
+        if !voxel.is_air() { return false }
+
+        let Some(Some(item_in_hand)) = self.player.inventory.get(self.player.hand_index())
+        else { return false };
+
+
+        let kind = item_in_hand.kind;
+        if let Some(voxel) = kind.as_voxel() {
+            if !self.player.creative {
+                let _ = self.player.take_item(self.player.hand_index(), 1).unwrap();
+            }
+
+            *self.world.get_voxel_mut(place_position) = voxel;
+
+        } else if let Some(structure_kind) = item_in_hand.kind.as_structure() {
+            let orientation = if structure_kind.is_orientable() { orientation }
+                              else { Orientation::Flat };
+
+            if !self.can_place_structure(structure_kind, place_position, dir, orientation) {
+                return false;
+            }
+
+            let mut structure = Structure::from_kind(structure_kind, place_position, dir);
+            structure.orientation = orientation;
+            if let Some((kind, config)) = self.player.pipette
+                && kind == structure_kind {
+                structure.apply_config(config);
+            }
+
+            if !self.player.creative {
+                let _ = self.player.take_item(self.player.hand_index(), 1).unwrap();
+            }
+            let id = self.structures.add_structure(&mut self.world, structure);
+
+            if structure_kind.is_belt() {
+                self.player.last_belt = Some(place_position);
+            }
+
+            if let StructureData::Assembler { recipe: None, .. } = self.structures.get(id).data {
+                self.ui_layer = UILayer::inventory_view(InventoryMode::assembler(id))
+            }
+        } else {
+            return false;
+        }
+
+
+        if self.settings.instant_mesh {
+            self.world.chunker.mesh_immediately(split_world_pos(place_position).0);
+        }
+
+        self.particles.place_block(kind, place_position, PARTICLE_PLACE_COUNT);
+        true
+    }
+
+
+    ///
+    /// Picks up the dropped item the player is looking at without
+    /// waiting for it to get pulled in, only half of the stack if
+    /// `half` is set. Whatever doesn't fit stays on the ground
+    ///
+    fn pick_up_looked_at_item(&mut self, half: bool) {
+        // items behind a block can't be grabbed through it
+        let mut reach = self.reach();
+        if let Some((pos, _)) = self.world.raycast_voxel(self.camera.position, self.camera.front, reach)
+            && let Some(dist) = ray_aabb(self.camera.position, self.camera.front, pos.as_dvec3(), pos.as_dvec3() + DVec3::ONE) {
+            reach = dist;
+        }
+
+        let Some(id) = self.entities.raycast(self.camera.position, self.camera.front, reach)
+        else { return };
+
+        let entity = self.entities.entities.get_mut(id).unwrap();
+        let EntityKind::DroppedItem { item, .. } = &mut entity.kind;
+
+        let mut taken = *item;
+        if half {
+            taken.amount = taken.amount.div_ceil(2);
+        }
+
+        let rest = self.player.add_item(taken).map_or(0, |rest| rest.amount);
+        item.amount -= taken.amount - rest;
+
+        if item.amount == 0 {
+            self.entities.entities.remove(id);
+        }
+    }
+
+
+    ///
+    /// Which way the held item gets placed at `place_position`. A belt
+    /// placed right next to the last one carries on from it unless the
+    /// player rotated it themselves, anything else faces the way the
+    /// camera does
+    ///
+    fn placement_direction(&self, place_position: IVec3) -> (CardinalDirection, Orientation) {
+        let preview = Orientation::from_preview(self.camera.compass_direction(), self.player.preview_rotation_offset);
+        if self.player.preview_rotation_offset != 0 { return preview }
+
+        let held = self.player.inventory[self.player.hand_index()].and_then(|x| x.kind.as_structure());
+        if !held.is_some_and(|kind| kind.is_belt()) { return preview }
+
+        let Some(last) = self.player.last_belt
+        else { return preview };
+
+        let still_there = self.world.structure_blocks.get(&last)
+            .is_some_and(|&id| self.structures.get(id).data.as_kind().is_belt());
+        if !still_there { return preview }
+
+        match facing_towards(place_position - last) {
+            Some(dir) => (dir, Orientation::Flat),
+            None => preview,
+        }
+    }
+
+
+    ///
+    /// Fills in every cell from `last` to `target` one step at a time
+    /// with the held item. Each one faces the way the line goes, and
+    /// a belt the line came from gets turned to feed into the new one
+    ///
+    fn drag_place(&mut self, last: IVec3, target: IVec3) {
+        let mut cell = last;
+        while cell != target {
+            let diff = target - cell;
+            let step = if diff.x.abs() >= diff.z.abs() { IVec3::new(diff.x.signum(), 0, 0) }
+                       else { IVec3::new(0, 0, diff.z.signum()) };
+
+            let dir = facing_towards(step).unwrap();
+
+            if !self.place_held_item(cell + step, dir, Orientation::Flat) { break }
+
+            if let Some(&id) = self.world.structure_blocks.get(&cell) {
+                let structure = self.structures.get_mut(id);
+                if structure.data.as_kind().is_belt() {
+                    structure.direction = dir;
+                }
+            }
+
+            cell += step;
+            self.player.drag_from = Some(cell);
+        }
+    }
+
+
+    ///
+    /// Only single block things can be dragged into a line,
+    /// anything bigger would just overlap the previous one
+    ///
+    fn can_drag_place(&self) -> bool {
+        let Some(Some(item_in_hand)) = self.player.inventory.get(self.player.hand_index())
+        else { return false };
+
+        if item_in_hand.kind.as_voxel().is_some() { return true }
+
+        item_in_hand.kind.as_structure()
+            .is_some_and(|kind| kind.blocks(CardinalDirection::North).len() == 1)
+    }
+
+
+    ///
+    /// Applies the copied settings to an already placed structure,
+    /// whatever doesn't fit in an assembler after its recipe
+    /// changes gets dropped at the player's feet
+    ///
+    fn paste_settings(&mut self, id: StructureId, config: StructureConfig) {
+        let structure = self.structures.get_mut(id);
+
+        let prev_inv = match (&structure.data, config) {
+            (StructureData::Assembler { recipe, .. }, StructureConfig::Assembler { recipe: Some(new), .. })
+                if *recipe != Some(new) => {
+                structure.inventory.as_mut()
+                    .map(|inventory| core::mem::take(&mut inventory.slots))
+                    .unwrap_or_default()
+            },
+
+            _ => vec![],
+        };
+
+        structure.apply_config(config);
+
+        for item in prev_inv {
+            let Some(item) = item
+            else { continue };
+
+            if structure.can_accept(item) {
+                structure.give_item(item);
+            } else {
+                self.entities.spawn(
+                    EntityKind::dropped_item(item),
+                    self.player.body.position);
+            }
+        }
+    }
+
+
+    ///
+    /// The sky colour fades into the void as the camera
+    /// gets closer to the bottom of the world
+    ///
+    pub fn current_sky_colour(&self) -> Vec4 {
+        let floor = self.world.chunker.limits.min as f64;
+        let height = (self.camera.position.y - floor) as f32;
+        let t = (height / VOID_FADE_DISTANCE).clamp(0.0, 1.0);
+        COLOUR_VOID.lerp(self.sky_colour, t)
+    }
+
+
+
+
+    ///
+    /// How far away the player can mine, place and interact
+    /// right now. Every raycast from the camera should use this
+    /// so what's highlighted is always what can be reached
+    ///
+    pub fn reach(&self) -> f32 {
+        if self.player.creative && self.settings.god_reach {
+            return PLAYER_GOD_REACH;
+        }
+
+        self.settings.reach
+    }
+
+
+    pub fn open_main_menu(&mut self) {
+        self.ui_layer = UILayer::main_menu();
+    }
+
+
+    ///
+    /// Sets how loud everything is, 0.0 is silent and 1.0 is
+    /// as loud as the sounds themselves
+    ///
+    pub fn set_volume(&mut self, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        self.settings.volume = volume;
+
+        let Some(audio) = &mut self.audio
+        else { return };
+
+        let decibels = if volume == 0.0 { Decibels::SILENCE } else { Decibels(20.0 * volume.log10()) };
+        audio.main_track().set_volume(decibels, Tween::default());
+    }
+
+
+    ///
+    /// Where the player goes when they die or run `respawn`
+    ///
+    pub fn spawn_position(&self) -> DVec3 {
+        self.world_spawn.unwrap_or(PLAYER_SPAWN)
+    }
+
+
+    ///
+    /// Drops everything the player is carrying where they
+    /// died and sends them back to spawn with full health
+    ///
+    pub fn kill_player(&mut self) {
+        let position = self.player.body.position;
+        for slot in &mut self.player.inventory {
+            let Some(item) = slot.take()
+            else { continue };
+
+            self.entities.spawn(EntityKind::dropped_item(item), position);
+        }
+
+        self.player.body.position = self.spawn_position();
+        self.player.body.velocity = Vec3::ZERO;
+        self.player.health = PLAYER_MAX_HEALTH;
+        self.player.mining_progress = None;
+        self.craft_queue.clear();
+        self.craft_progress = 0;
+    }
+
 
 
     pub fn handle_input(&mut self, delta_time: f32, input: &mut InputManager) {
@@ -246,8 +1219,17 @@ impl Game {
         if matches!(self.ui_layer, UILayer::Gameplay { .. }) {
             let dt = input.mouse_delta();
             if !dt.is_nan() {
-                self.camera.yaw += dt.x * MOUSE_SENSITIVITY;
-                self.camera.pitch -= dt.y * MOUSE_SENSITIVITY;
+                let mut sensitivity = self.settings.sensitivity * MOUSE_SENSITIVITY;
+
+                // scale by how fast the mouse moved, not how far,
+                // so the curve doesn't depend on the frame rate
+                if self.settings.mouse_acceleration && delta_time > 0.0 {
+                    let speed = dt.length() / delta_time;
+                    sensitivity *= (1.0 + speed * MOUSE_ACCELERATION).min(MOUSE_ACCELERATION_CAP);
+                }
+
+                self.camera.yaw += dt.x * sensitivity.x;
+                self.camera.pitch -= dt.y * sensitivity.y;
                 
                 self.camera.yaw = self.camera.yaw % 360f32.to_radians();
 
@@ -264,16 +1246,11 @@ impl Game {
 
 
             let dt = input.scroll_delta();
+            let wrap = self.settings.hotbar_wrap;
             if input.is_key_pressed(KeyCode::ControlLeft) {
-                if dt.y > 0.0 && self.player.hotbar == PLAYER_ROW_SIZE-1 { self.player.hotbar = 0 }
-                else if dt.y > 0.0 { self.player.hotbar += 1 }
-                if dt.y < 0.0 && self.player.hotbar == 0 { self.player.hotbar = PLAYER_ROW_SIZE-1 }
-                else if dt.y < 0.0 { self.player.hotbar -= 1 }
+                self.player.hotbar = scroll_index(self.player.hotbar, PLAYER_ROW_SIZE, dt.y, wrap);
             } else {
-                if dt.y > 0.0 && self.player.hand == PLAYER_HOTBAR_SIZE-1 { self.player.hand = 0 }
-                else if dt.y > 0.0 { self.player.hand += 1 }
-                if dt.y < 0.0 && self.player.hand == 0 { self.player.hand = PLAYER_HOTBAR_SIZE-1 }
-                else if dt.y < 0.0 { self.player.hand -= 1 }
+                self.player.hand = scroll_index(self.player.hand, PLAYER_HOTBAR_SIZE, dt.y, wrap);
             }
         }
 
@@ -281,9 +1258,14 @@ impl Game {
         // handle keyboard input
         'input: {
             if input.is_key_just_pressed(KeyCode::Escape) {
-                let mut ui_layer = core::mem::replace(&mut self.ui_layer, UILayer::None);
-                ui_layer.close(self, delta_time);
-                self.ui_layer = UILayer::Gameplay { smoothed_dt: delta_time };
+                if self.ui_layer.is_escapable() {
+                    let mut ui_layer = core::mem::replace(&mut self.ui_layer, UILayer::None);
+                    ui_layer.close(self, delta_time);
+                    self.ui_layer = UILayer::Gameplay { smoothed_dt: delta_time };
+                } else if matches!(self.ui_layer, UILayer::Gameplay { .. }) {
+                    self.ui_layer = UILayer::PauseMenu;
+                    break 'input;
+                }
             }
 
             if !matches!(self.ui_layer, UILayer::Gameplay { .. }) {
@@ -304,33 +1286,102 @@ impl Game {
                 dir -= self.camera.front.cross(self.camera.up);
             }
 
-            if input.is_key_pressed(KeyCode::KeyC) {
-                self.camera.fov = 15f32.to_radians();
-            } else {
-                self.camera.fov = 80f32.to_radians();
-            }
+            // left control already scrolls the hotbar
+            let is_sprinting = input.is_key_pressed(KeyCode::AltLeft)
+                && input.is_key_pressed(KeyCode::KeyW)
+                && !input.is_key_pressed(KeyCode::KeyS)
+                && self.player.body.on_ground;
+
+            let zoomed = input.is_key_pressed(KeyCode::KeyC) && !input.is_key_pressed(KeyCode::ControlLeft);
+            let target_fov = if zoomed { self.settings.zoom_fov }
+                             else if is_sprinting { self.settings.fov + PLAYER_SPRINT_FOV }
+                             else { self.settings.fov };
+            self.camera.target_fov = target_fov.to_radians();
+
+            // ease in exponentially so the speed doesn't depend on the
+            // frame rate and tapping C just turns around mid-transition
+            let t = 1.0 - (-PLAYER_FOV_EASING * delta_time).exp();
+            self.camera.fov += (self.camera.target_fov - self.camera.fov) * t;
 
 
             dir.y = 0.0;
             let dir = dir.normalize_or_zero();
-            let mov = dir * self.settings.player_speed;
-            self.player.body.velocity.x = mov.x;
-            self.player.body.velocity.z = mov.z;
+            let mut mov = dir * self.settings.player_speed;
+            if self.player.creative {
+                mov *= 2.0;
+            }
 
+            if is_sprinting {
+                mov *= self.settings.sprint_multiplier;
+            }
+
+            if self.player.body.in_water && !self.player.creative {
+                mov *= PLAYER_SWIM_MULTIPLIER;
+            }
 
-            if input.is_key_pressed(KeyCode::Space) {
+            // ease towards the target speed the same way the fov does,
+            // letting go of everything slows down with friction instead
+            let rate = if dir == Vec3::ZERO { self.settings.friction }
+                       else { self.settings.acceleration };
+            let t = 1.0 - (-rate * delta_time).exp();
+
+            let velocity = &mut self.player.body.velocity;
+            velocity.x += (mov.x - velocity.x) * t;
+            velocity.z += (mov.z - velocity.z) * t;
+
+
+            if self.player.creative {
+                let mut y = 0.0;
+                if input.is_key_pressed(KeyCode::Space) { y += 1.0 }
+                if input.is_key_pressed(KeyCode::ShiftLeft) { y -= 1.0 }
+                self.player.body.velocity.y = y * self.settings.player_speed;
+            } else if self.player.body.in_water {
+                if input.is_key_pressed(KeyCode::Space) {
+                    self.player.body.velocity.y = PLAYER_SWIM_UP_SPEED;
+                }
+            } else if input.is_key_pressed(KeyCode::Space) {
                 self.player.body.velocity.y = 5.0;
             }
 
 
             if let Some(item) = self.player.inventory[self.player.hand_index()]
                 && matches!(item.kind, ItemKind::Voxel(_) | ItemKind::Structure(_)) {
+                // orientable structures can also be tipped up and down
+                let states = match item.kind.as_structure() {
+                    Some(kind) if kind.is_orientable() => 6,
+                    _ => 4,
+                };
+
                 if input.is_key_just_pressed(KeyCode::KeyR) {
                     self.player.preview_rotation_offset += 1;
-                    self.player.preview_rotation_offset %= 4;
                 }
+
+                self.player.preview_rotation_offset %= states;
             } else {
                 self.player.preview_rotation_offset = 0;
+
+                // with nothing to place R turns the structure
+                // the player is looking at instead
+                if input.is_key_just_pressed(KeyCode::KeyR)
+                    && let Some((pos, _)) = self.world.raycast_voxel(self.camera.position, self.camera.front, self.reach())
+                    && let Some(&id) = self.world.structure_blocks.get(&pos) {
+
+                    // refused if the new footprint runs into something
+                    let direction = self.structures.get(id).direction.next_n(1);
+                    if Structure::rotate(id, &mut self.structures, &mut self.world, direction)
+                        && self.settings.instant_mesh {
+                        self.world.chunker.mesh_immediately(split_world_pos(pos).0);
+                    }
+                }
+            }
+
+
+            // the pipette's settings only stick around while
+            // the player is holding what they picked up
+            let held = self.player.inventory[self.player.hand_index()].map(|x| x.kind);
+            if let Some((kind, _)) = self.player.pipette
+                && held != Some(kind.item_kind()) {
+                self.player.pipette = None;
             }
 
 
@@ -340,7 +1391,7 @@ impl Game {
                 } 
 
                 let mut inv_kind = InventoryMode::Recipes;
-                if let Some((raycast, _)) = self.world.raycast_voxel(self.camera.position, self.camera.front, PLAYER_REACH) {
+                if let Some((raycast, _)) = self.world.raycast_voxel(self.camera.position, self.camera.front, self.reach()) {
                     let structure = self.world.structure_blocks.get(&raycast);
                     if let Some(structure) = structure {
                         let structure_kind = self.structures.get(*structure).data.as_kind();
@@ -348,14 +1399,20 @@ impl Game {
                             inv_kind = InventoryMode::Chest(*structure);
                         } else if structure_kind == StructureKind::Silo {
                             inv_kind = InventoryMode::Silo(*structure);
+                        } else if structure_kind == StructureKind::ProviderChest {
+                            inv_kind = InventoryMode::Chest(*structure);
+                        } else if structure_kind == StructureKind::RequesterChest {
+                            inv_kind = InventoryMode::Requester { id: *structure, editing: false };
                         } else if structure_kind == StructureKind::Assembler {
-                            inv_kind = InventoryMode::Assembler(*structure);
+                            inv_kind = InventoryMode::assembler(*structure);
                         } else if structure_kind == StructureKind::Furnace {
                             inv_kind = InventoryMode::Furnace(*structure);
                         } else if structure_kind == StructureKind::SteelFurnace {
                             inv_kind = InventoryMode::Furnace(*structure);
-                        } else if structure_kind == StructureKind::Inserter {
+                        } else if structure_kind.is_inserter() {
                             inv_kind = InventoryMode::Inserter(*structure);
+                        } else if structure_kind == StructureKind::Splitter {
+                            inv_kind = InventoryMode::Splitter { id: *structure, side: 0 };
                         }
                     }
                 }
@@ -372,12 +1429,22 @@ impl Game {
             if input.is_key_just_pressed(KeyCode::KeyG) {
                 info!("generating a belt graph at 'sccs.dot'");
                 let belts = self.structures.belts(&self.world);
-                std::fs::write("sccs.dot", belts.scc_graph()).unwrap();
+                std::fs::write("sccs.dot", belts.export(&self.structures, GraphFormat::Dot)).unwrap();
             }
 
 
             if input.is_key_just_pressed(KeyCode::KeyP) {
-                self.settings.lines = !self.settings.lines;
+                self.settings.debug_draw = match self.settings.debug_draw {
+                    DebugDraw::Off => DebugDraw::Lines,
+                    _ => DebugDraw::Off,
+                };
+            }
+
+
+            if input.is_key_just_pressed(KeyCode::KeyM) {
+                self.minimap.full_screen = !self.minimap.full_screen;
+                // the cached cells cover the old radius
+                self.minimap.update(&mut self.world, self.player.body.position.floor().as_ivec3());
             }
 
 
@@ -389,7 +1456,8 @@ impl Game {
                         timer: 0.0,
                         cursor: 0,
                         just_opened: true,
-                        offset: 1
+                        offset: 1,
+                        completion: None,
                     }
                 }
             }
@@ -400,6 +1468,11 @@ impl Game {
             }
 
 
+            if input.is_key_just_pressed(KeyCode::F4) {
+                self.player.set_creative(!self.player.creative);
+            }
+
+
             if input.is_key_just_pressed(KeyCode::F6) {
                 info!("saving self on-command");
                 let time = Instant::now();
@@ -421,7 +1494,7 @@ impl Game {
             if input.is_key_pressed(KeyCode::KeyQ) {
                 let raycast = self.world.raycast_voxel(self.camera.position,
                                                   self.camera.front,
-                                                  PLAYER_REACH);
+                                                  self.reach());
                 if let Some((pos, n)) = raycast {
                     let voxel = self.world.get_voxel(pos);
                     if voxel.is_structure() {
@@ -435,9 +1508,12 @@ impl Game {
                             for index in 0..structure.available_items_len() {
                                 let item = structure.try_take(index, u32::MAX);
                                 if let Some(item) = item {
-                                    self.entities.spawn(
+                                    // pop it back out towards the player
+                                    self.entities.throw(
                                         EntityKind::dropped_item(item),
                                         pos.as_dvec3() + DVec3::new(0.5, 0.5, 0.5) + n.as_dvec3(),
+                                        -self.camera.front,
+                                        DROPPED_ITEM_TOSS_SPEED,
                                     );
                                     break;
                                 }
@@ -449,6 +1525,13 @@ impl Game {
             }
 
 
+            // grab the looked at item straight away, alt only takes half
+            if input.is_key_just_pressed(KeyCode::KeyF) {
+                self.pick_up_looked_at_item(input.is_alt_pressed());
+            }
+
+
+
 
 
             if input.is_key_pressed(KeyCode::ControlLeft) {
@@ -490,31 +1573,62 @@ impl Game {
 
                 let Some((pos, _))= self.world.raycast_voxel(self.camera.position,
                                                              self.camera.front,
-                                                             PLAYER_REACH)
+                                                             self.reach())
                 else {
                     self.player.mining_progress = None;
                     break 'input_block;
                 };
 
-
-                let Some(mining_progress) = self.player.mining_progress
-                else {
-                    self.player.mining_progress = Some(0);
+                if !self.world.chunker.limits.contains(pos.y) {
+                    self.player.mining_progress = None;
                     break 'input_block;
-                };
+                }
 
 
-                let voxel = self.world.get_voxel(pos);
-                if mining_progress < voxel.base_hardness() {
-                    break 'input_block;
+                if self.player.creative {
+                    // no mining in creative, just rate limit it
+                    if self.player.interact_delay > 0.0 { break 'input_block }
+                    self.player.interact_delay = PLAYER_INTERACT_DELAY;
+
+                } else {
+                    let Some(mining_progress) = self.player.mining_progress
+                    else {
+                        self.player.mining_progress = Some(0);
+                        break 'input_block;
+                    };
+
+
+                    if let Some(&id) = self.world.structure_blocks.get(&pos) {
+                        // structures keep the damage they took so
+                        // letting go doesn't undo the progress
+                        let structure = self.structures.get_mut_without_wake_up(id);
+                        structure.durability = structure.durability.saturating_sub(mining_progress);
+                        self.player.mining_progress = Some(0);
+
+                        if structure.durability > 0 {
+                            break 'input_block;
+                        }
+                    } else {
+                        let voxel = self.world.get_voxel(pos);
+                        if mining_progress < voxel.base_hardness() {
+                            break 'input_block;
+                        }
+                    }
                 }
 
 
                 let item = self.world.break_block(&mut self.structures, &mut self.entities, pos);
-                self.entities.spawn(
-                    EntityKind::dropped_item(item),
-                    pos.as_dvec3() + DVec3::new(0.5, 0.5, 0.5)
-                );
+                self.particles.break_block(item.kind, pos, PARTICLE_BREAK_COUNT);
+                if !self.player.creative {
+                    self.entities.spawn(
+                        EntityKind::dropped_item(item),
+                        pos.as_dvec3() + DVec3::new(0.5, 0.5, 0.5)
+                    );
+                }
+
+                if self.settings.instant_mesh {
+                    self.world.chunker.mesh_immediately(split_world_pos(pos).0);
+                }
 
                 self.player.mining_progress = None;
             }
@@ -524,101 +1638,184 @@ impl Game {
             'input_block: {
                 if input.is_button_just_pressed(MouseButton::Right) {
                     self.player.interact_delay = 0.0;
-                }
-
-                if self.player.interact_delay > 0.0 {
-                    break 'input_block;
+                    self.player.drag_from = None;
                 }
 
                 if !input.is_button_pressed(MouseButton::Right) {
+                    self.player.drag_from = None;
                     break 'input_block;
                 }
 
 
                 let Some((pos, normal)) = self.world.raycast_voxel(self.camera.position,
                                                                    self.camera.front,
-                                                                   PLAYER_REACH)
+                                                                   self.reach())
                 else { break 'input_block };
 
                 let place_position = pos + normal;
 
-                let voxel = self.world.get_voxel(place_position);
-                if !voxel.is_air() { break 'input_block }
 
-                let Some(Some(item_in_hand)) = self.player.inventory.get(self.player.hand_index())
+                // while dragging every cell between the last placed block and the
+                // one under the crosshair gets filled in, one step at a time
+                if let Some(last) = self.player.drag_from
+                    && last.y == place_position.y
+                    && last != place_position
+                    && self.can_drag_place() {
+
+                    self.drag_place(last, place_position);
+                    break 'input_block;
+                }
+
+
+                if self.player.interact_delay > 0.0 {
+                    break 'input_block;
+                }
+
+                let (dir, orientation) = self.placement_direction(place_position);
+                if !self.place_held_item(place_position, dir, orientation) { break 'input_block }
+
+                self.player.drag_from = Some(place_position);
+                self.player.interact_delay = PLAYER_INTERACT_DELAY;
+
+            }
+
+
+
+            'input_block: {
+                if !input.is_button_just_pressed(MouseButton::Middle) {
+                    break 'input_block;
+                }
+
+
+                let Some((pos, _)) = self.world.raycast_voxel(self.camera.position,
+                                                              self.camera.front,
+                                                              self.reach())
                 else { break 'input_block };
 
+                let item = self.world.block_item(&self.structures, pos);
+                self.player.pipette = self.world.structure_blocks.get(&pos)
+                    .map(|&id| self.structures.get(id))
+                    .and_then(|structure| Some((structure.data.as_kind(), structure.config()?)));
+
+                if !self.player.select_item(item.kind) && self.player.creative {
+                    self.player.give_and_select(item.kind);
+                }
+            }
+
 
-                if let Some(voxel) = item_in_hand.kind.as_voxel() {
-                    let _ = self.player.take_item(self.player.hand_index(), 1).unwrap();
 
-                    *self.world.get_voxel_mut(place_position) = voxel;
+            'input_block: {
+                if !input.is_key_pressed(KeyCode::ControlLeft) { break 'input_block }
+
+                let copy = input.is_key_just_pressed(KeyCode::KeyC);
+                let paste = input.is_key_just_pressed(KeyCode::KeyV);
+                if !copy && !paste { break 'input_block }
+
+                let Some((pos, _)) = self.world.raycast_voxel(self.camera.position,
+                                                              self.camera.front,
+                                                              self.reach())
+                else { break 'input_block };
+
+                let Some(&id) = self.world.structure_blocks.get(&pos)
+                else { break 'input_block };
 
-                } else if let Some(structure_kind) = item_in_hand.kind.as_structure() {
-                    let dir = self.camera.compass_direction().next_n(self.player.preview_rotation_offset);
+                let structure = self.structures.get(id);
+                let kind = structure.data.as_kind();
 
-                    if !self.can_place_structure(structure_kind, place_position, dir) {
+                if copy {
+                    let Some(config) = structure.config()
+                    else {
+                        warn!("clipboard: {} has no settings to copy", kind.item_kind().to_string());
                         break 'input_block;
-                    }
-
-                    let structure = Structure::from_kind(structure_kind, place_position, dir);
-                    let _ = self.player.take_item(self.player.hand_index(), 1).unwrap();
-                    let id = self.structures.add_structure(&mut self.world, structure);
+                    };
 
-                    if structure_kind == StructureKind::Assembler {
-                        self.ui_layer = UILayer::inventory_view(InventoryMode::Assembler(id))
-                    }
+                    self.clipboard = Some((kind, config));
+                    info!("clipboard: copied the settings of {}", kind.item_kind().to_string());
+                    break 'input_block;
                 }
 
 
-                self.player.interact_delay = PLAYER_INTERACT_DELAY;
+                let Some((copied_kind, config)) = self.clipboard
+                else {
+                    warn!("clipboard: nothing has been copied yet");
+                    break 'input_block;
+                };
 
+                if copied_kind != kind {
+                    warn!("clipboard: can't paste the settings of {} onto {}",
+                          copied_kind.item_kind().to_string(), kind.item_kind().to_string());
+                    break 'input_block;
+                }
+
+                self.paste_settings(id, config);
+                info!("clipboard: pasted the settings onto {}", kind.item_kind().to_string());
             }
         }
     }
 
 
 
+    ///
+    /// Runs as many ticks as fit into `time_since_last_simulation`
+    /// at the current timescale and returns how many it ran.
+    /// The leftover time carries over to the next call
+    ///
+    pub fn catch_up(&mut self, time_since_last_simulation: &mut f32) -> u32 {
+        debug_assert!(self.settings.timescale > 0.0);
+
+        *time_since_last_simulation = time_since_last_simulation.min(MAX_SIMULATION_CATCH_UP);
+        let tick_duration = DELTA_TICK / self.settings.timescale;
+
+        let mut ticks = 0;
+        while *time_since_last_simulation > tick_duration {
+            self.simulation_tick();
+            *time_since_last_simulation -= tick_duration;
+            ticks += 1;
+        }
+
+        ticks
+    }
+
+
     pub fn simulation_tick(&mut self) {
+        // nothing to simulate until a world is picked
+        // or while the game is paused
+        if matches!(self.ui_layer, UILayer::MainMenu { .. } | UILayer::PauseMenu) { return }
+
         self.current_tick = self.current_tick.inc();
 
         let delta_time = DELTA_TICK;
 
-        if self.current_tick.u32() % (TICKS_PER_SECOND * 120) == 0 {
+        self.write_queued_save();
+
+        // a save file could've set an interval too long to count in ticks
+        let autosave_interval = TICKS_PER_SECOND.checked_mul(self.settings.autosave_interval);
+        if let Some(interval) = autosave_interval
+            && interval != 0
+            && self.current_tick.u32() % interval == 0 {
             info!("autosaving..");
-            self.save();
+            self.autosave();
         }
 
 
-        /*
-        if self.settings.render_distance < self.settings.target_render_distance 
-            && self.world.chunker.mesh_load_queue_len() == 0
-            && self.world.chunker.chunk_load_queue_len() == 0
-            && self.world.chunker.chunk_active_jobs_len() == 0
-            && self.world.chunker.mesh_active_jobs_len() == 0 {
-
-            let player_chunk = IVec3::ZERO;
-            let rd = self.settings.render_distance;
-
-            for y in -rd..=rd {
-                for z in -rd..=rd {
-                    for x in -rd..=rd {
-                        let offset = IVec3::new(x, y, z);
-                        let dist = offset.length_squared();
-                        let chunk_pos = offset + player_chunk;
-                        if dist < rd*rd {
-                            self.world.try_get_chunk(WorldChunkPos(chunk_pos));
-                            self.world.try_get_mesh(chunk_pos);
-                        }
-                    }
-                }
-            }
-
-            self.settings.render_distance += 1;
-            self.settings.render_distance = self.settings.render_distance.min(RENDER_DISTANCE);
-            println!("heyo {}", self.settings.render_distance);
+        // the render distance only grows once everything in the
+        // current one is loaded so the world doesn't try to load
+        // every chunk at once
+        let chunker = &self.world.chunker;
+        if self.render_distance > self.settings.render_distance {
+            self.render_distance = self.settings.render_distance;
+            // reloads everything in range, see below
+            self.prev_player_chunk = Some(WorldChunkPos(IVec3::MAX));
+        } else if self.render_distance < self.settings.render_distance
+            && chunker.mesh_load_queue_len() == 0
+            && chunker.chunk_load_queue_len() == 0
+            && chunker.chunk_active_jobs_len() == 0
+            && chunker.mesh_active_jobs_len() == 0 {
+
+            self.render_distance += 1;
+            // reloads everything in range, see below
+            self.prev_player_chunk = Some(WorldChunkPos(IVec3::MAX));
         }
-        */
 
 
         {
@@ -626,6 +1823,7 @@ impl Game {
             let player_chunk = self.player.body.position.as_ivec3();
             let (player_chunk, _) = split_world_pos(player_chunk);
             let ld = LOAD_DISTANCE;
+            self.world.chunker.focus = player_chunk;
 
             if let Some(old_chunk) = self.prev_player_chunk
                 && old_chunk != player_chunk {
@@ -633,7 +1831,7 @@ impl Game {
                 let mut prev_mask = HashSet::new();
                 let mut curr_mask = HashSet::new();
 
-                let rd = self.settings.render_distance;
+                let rd = self.render_distance;
                 if old_chunk.0 != IVec3::MAX {
                     for z in -rd..rd {
                         for y in -rd..rd {
@@ -649,7 +1847,7 @@ impl Game {
                 }
 
 
-                let rd = self.settings.render_distance+1;
+                let rd = self.render_distance+1;
                 for z in -rd..rd {
                     for y in -rd..rd {
                         for x in -rd..rd {
@@ -685,7 +1883,7 @@ impl Game {
 
             let time = Instant::now();
             let (player_chunk, _) = split_world_pos(self.player.body.position.as_ivec3());
-            let rd = self.settings.render_distance-1;
+            let rd = self.render_distance-1;
 
             let mut unloaded = 0;
 
@@ -709,7 +1907,7 @@ impl Game {
                 };
 
 
-                let rd = self.settings.render_distance;
+                let rd = self.render_distance;
                 let full_unload = offset > rd*rd;
 
                 if self.world.chunker.is_queued_for_meshing(pos) {
@@ -763,23 +1961,33 @@ impl Game {
 
 
             warn!("checking dead chunks took {:?}, unloaded: {unloaded}, render distance {}, size: {}",
-                  time.elapsed(), self.settings.render_distance, self.world.chunker.iter_chunks().count());
+                  time.elapsed(), self.render_distance, self.world.chunker.iter_chunks().count());
         }
 
         if !self.craft_queue.is_empty() && self.player.can_give(self.craft_queue[0].0) {
             self.craft_progress += 1;
             if self.craft_progress == self.craft_queue[0].1 {
                 let (result, _) = self.craft_queue.remove(0);
-                if result.amount != 0 {
-                    self.player.add_item(result);
+                if result.amount != 0
+                    && let Some(rest) = self.player.add_item(result) {
+                    self.entities.spawn(EntityKind::dropped_item(rest), self.player.body.position);
                 }
 
 
-                if result.kind == ItemKind::Radar {
-                    let source = StaticSoundData::from_media_source(std::io::Cursor::new(include_bytes!("../congratz.wav"))).unwrap();
-                    let mut sound = self.audio.play(source.clone()).unwrap();
-                    sound.pause(Tween::default());
-                    self.ui_layer = UILayer::Credits { time: 0.0, audio: sound }
+                if result.kind == ItemKind::Radar
+                    && let Some(audio) = &mut self.audio {
+                    let sound = StaticSoundData::from_media_source(std::io::Cursor::new(include_bytes!("../congratz.wav")))
+                        .map_err(|e| e.to_string())
+                        .and_then(|source| audio.play(source).map_err(|e| e.to_string()));
+
+                    match sound {
+                        Ok(mut sound) => {
+                            sound.pause(Tween::default());
+                            self.ui_layer = UILayer::Credits { time: 0.0, audio: sound }
+                        },
+
+                        Err(e) => warn!("unable to play the credits music: {e}"),
+                    }
                 }
 
                 self.craft_progress = 0;
@@ -792,10 +2000,32 @@ impl Game {
             *progress += 1;
         }
 
+        if self.current_tick.u32() % AMBIENCE_REFRESH_TICKS == 0
+            && let Some(ambience) = &mut self.ambience
+            && let Some(audio) = &mut self.audio {
+            ambience.update_emitters(audio, &self.structures, self.camera.position);
+        }
+
+        if self.current_tick.u32() % MINIMAP_REFRESH_TICKS == 0 {
+            self.minimap.update(&mut self.world, self.player.body.position.floor().as_ivec3());
+        }
+
 
         // handle player physics
         {
-            self.world.move_physics_body(delta_time, &mut self.player.body);
+            let fall_speed = -self.player.body.velocity.y;
+            self.world.move_physics_body(&self.structures, delta_time, &mut self.player.body);
+
+            if self.player.body.on_ground
+                && !self.player.creative
+                && fall_speed > PLAYER_FALL_DAMAGE_SPEED {
+                self.player.damage((fall_speed - PLAYER_FALL_DAMAGE_SPEED) * PLAYER_FALL_DAMAGE_PER_SPEED);
+            }
+
+            self.player.regenerate(delta_time);
+            if self.player.health <= 0.0 {
+                self.kill_player();
+            }
 
             self.camera.position = self.player.body.position;
             self.camera.position.y += 0.8;
@@ -805,6 +2035,8 @@ impl Game {
             // start pulling them if they are in distance
             // and they have been alive for more than 250ms
             {
+                self.player.inventory_full_time = (self.player.inventory_full_time - delta_time).max(0.0);
+
                 let len = self.entities.entities.len();
                 for i in 0..len {
                     let Some(entity) = self.entities.entities.entry_at(i)
@@ -829,21 +2061,33 @@ impl Game {
                             continue;
                         }
 
-                        if !self.player.can_give(*item) { continue };
+                        if !self.player.can_give(*item) {
+                            self.player.inventory_full_time = PLAYER_INVENTORY_FULL_TIME;
+                            if !self.settings.pull_when_full { continue }
+                        }
+
                         *is_attracted = true;
                     } else {
                         let distance = entity.body.position.distance_squared(self.player.body.position);
 
                         let can_give = self.player.can_give(*item);
                         if !can_give {
-                            *is_attracted = false;
-                            continue;
+                            self.player.inventory_full_time = PLAYER_INVENTORY_FULL_TIME;
+                            if !self.settings.pull_when_full {
+                                *is_attracted = false;
+                                continue;
+                            }
                         }
 
                         if distance.abs() < 0.5 {
+                            // hold on to it until something frees up
+                            if !can_give { continue }
+
                             let item = *item;
                             self.entities.entities.remove_entry_at(i);
-                            self.player.add_item(item);
+                            if let Some(rest) = self.player.add_item(item) {
+                                self.entities.spawn(EntityKind::dropped_item(rest), self.player.body.position);
+                            }
 
                         } else {
                             entity.body.position = entity.body.position
@@ -867,7 +2111,7 @@ impl Game {
                 let Some(entity) = self.entities.entities.entry_at(i)
                 else { continue };
 
-                self.world.move_physics_body(delta_time, &mut entity.body)
+                self.world.move_physics_body(&self.structures, delta_time, &mut entity.body)
             }
         }
 
@@ -878,36 +2122,12 @@ impl Game {
 
 
     pub fn render(&mut self, renderer: &mut Renderer, input: &mut InputManager, delta_time: f32) {
-
-        {
-            let mut view = View::default();
-            view.vstack(|view| {
-                view.spacer();
-
-                view.hstack(|view| {
-                    view.spacer();
-                    view.text("3");
-                    view.spacer();
-                });
-
-                view.spacer();
-                view.hstack(|view| {
-                    view.spacer();
-                    view.text("4");
-                    view.spacer();
-                });
-               
-                view.spacer();
-            });
-
-
-            //let spacer_unit = view.spacer_weight(1);
-            let max_size = renderer.window_size();
-            //let mut spacer_unit = (max_size-view.calc_min_size(renderer)) / spacer_unit;
-            //view.render(renderer, max_size, Vec2::ZERO, 1);
+        if let Some(ambience) = &mut self.ambience {
+            ambience.update_listener(&self.camera);
         }
 
-
+        self.particles.update(delta_time);
+        self.particles.render(renderer, &self.camera);
 
         // render entities
         let len = self.entities.entities.len();
@@ -958,11 +2178,35 @@ impl Game {
 
 
 
+        // render the item in hand
+        if let Some(item) = self.player.inventory[self.player.hand_index()]
+            && !matches!(self.ui_layer, UILayer::MainMenu { .. }) {
+
+            let velocity = self.player.body.velocity;
+            if self.player.body.on_ground {
+                self.player.walk_cycle += Vec2::new(velocity.x, velocity.z).length() * delta_time;
+            }
+
+            let bob = Vec3::new(
+                self.player.walk_cycle.sin() * 0.5,
+                (self.player.walk_cycle * 2.0).sin().abs(),
+                0.0,
+            ) * PLAYER_HAND_BOB;
+
+            let model = Mat4::from_translation(PLAYER_HAND_OFFSET + bob)
+                * Mat4::from_rotation_y(33f32.to_radians())
+                * Mat4::from_scale(Vec3::splat(0.4));
+
+            renderer.draw_viewmodel(item.kind, MeshInstance { modulate: Vec4::ONE, model });
+        }
+
+
         // render structures
         for (_, s) in self.structures.structs.iter() {
             // TODO: frustum culling for structures
             s.render(
                 &self.structures,
+                &self.world,
                 &self.camera,
                 renderer,
             );
@@ -974,7 +2218,7 @@ impl Game {
             let Some((pos, norm)) =
                 self.world.raycast_voxel(self.camera.position,
                                          self.camera.front,
-                                         PLAYER_REACH)
+                                         self.reach())
             else { break 'block };
 
             let held_item = self.player.inventory[self.player.hand_index()];
@@ -985,27 +2229,37 @@ impl Game {
 
                 let mut scale = Vec3::ONE;
 
-                let dir = self.camera.compass_direction()
-                    .next_n(self.player.preview_rotation_offset);
+                let (dir, mut orientation) = self.placement_direction(pos + norm);
 
                 let (origin, blocks, colour, mesh) =
                 match held_item.kind {
                     ItemKind::Structure(kind) => {
                         if matches!(kind,   StructureKind::Belt
+                                          | StructureKind::FastBelt
+                                          | StructureKind::ExpressBelt
                                           | StructureKind::Splitter) {
                             scale = Vec3::new(1.0, 0.8, 1.0);
                         }
 
-                        let origin = kind.origin(dir);
+                        // see `Structure::render`
+                        if kind == StructureKind::LargeQuarry {
+                            scale = Vec3::new(7.0 / 5.0, 1.0, 7.0 / 5.0);
+                        }
+
+                        if !kind.is_orientable() {
+                            orientation = Orientation::Flat;
+                        }
+
+                        let origin = kind.oriented_origin(dir, orientation);
                         let can_place =
-                            self.can_place_structure(kind, pos+norm, dir);
+                            self.can_place_structure(kind, pos+norm, dir, orientation);
 
                         let colour = match can_place {
                             true => COLOUR_PASS,
                             false => COLOUR_DENY,
                         };
 
-                        let blocks = kind.blocks(dir);
+                        let blocks : Vec<_> = kind.oriented_blocks(dir, orientation).collect();
 
                         let mesh = renderer.assets.get_item(held_item.kind);
 
@@ -1014,7 +2268,7 @@ impl Game {
 
 
                     ItemKind::Voxel(voxel) => {
-                        (IVec3::ZERO, [IVec3::ZERO].as_ref(),
+                        (IVec3::ZERO, vec![IVec3::ZERO],
                         voxel.colour(), renderer.assets.cube)
                     }
                     _ => unreachable!()
@@ -1029,7 +2283,7 @@ impl Game {
 
                     let zero_zero = (pos + norm) - origin;
                     let position = zero_zero;
-                    for &offset in blocks {
+                    for &offset in &blocks {
                         min = min.min(offset);
                         max = max.max(offset);
                         pos_min = pos_min.min(position + offset);
@@ -1056,13 +2310,67 @@ impl Game {
                 // we use scale here because the mesh should be scaled
                 let model = Mat4::from_scale_rotation_translation(
                     scale * Vec3::splat(0.99),
-                    Quat::from_rotation_y(rot),
+                    orientation_tilt(dir, orientation) * Quat::from_rotation_y(rot),
                     mesh_pos.as_vec3()
                 );
 
                 renderer.draw_mesh(mesh, MeshInstance { modulate: colour, model });
 
 
+                // structures get an outline per block so it's obvious
+                // which one of them is in the way
+                if matches!(held_item.kind, ItemKind::Structure(_)) {
+                    let zero_zero = (pos + norm) - origin;
+                    let limits = self.world.chunker.limits;
+
+                    for &offset in &blocks {
+                        let block = zero_zero + offset;
+                        let is_free = limits.contains(block.y)
+                                      && self.world.get_voxel(block).is_air();
+
+                        let colour = match is_free {
+                            true => COLOUR_PASS,
+                            false => COLOUR_DENY,
+                        };
+
+                        let block_pos = block.as_dvec3() + DVec3::splat(0.5) - self.camera.position;
+                        let model = Mat4::from_scale_rotation_translation(
+                            Vec3::splat(1.01),
+                            Quat::IDENTITY,
+                            block_pos.as_vec3()
+                        );
+
+                        renderer.draw_mesh(
+                            renderer.assets.block_outline_mesh,
+                            MeshInstance { modulate: Vec4::new(colour.x, colour.y, colour.z, 0.8), model }
+                        );
+                    }
+
+                    // and a smaller one where an inserter grabs and drops off
+                    // items, the long inserter's are a tile further out
+                    if let ItemKind::Structure(kind) = held_item.kind
+                        && kind.is_inserter() {
+                        let targets = kind.inserter_inputs().iter().copied().chain([kind.inserter_output()]);
+                        for offset in targets {
+                            let block = zero_zero + rotate_oriented_vector(dir, orientation, offset);
+                            let block_pos = block.as_dvec3() + DVec3::splat(0.5) - self.camera.position;
+                            let model = Mat4::from_scale_rotation_translation(
+                                Vec3::splat(0.5),
+                                Quat::IDENTITY,
+                                block_pos.as_vec3()
+                            );
+
+                            renderer.draw_mesh(
+                                renderer.assets.block_outline_mesh,
+                                MeshInstance { modulate: COLOUR_WHITE.with_w(0.5), model }
+                            );
+                        }
+                    }
+
+                    break 'block;
+                }
+
+
                 // draw the outline
                 // we use dims here because `block_outline_mesh` is 1x1x1
                 let model = Mat4::from_scale_rotation_translation(
@@ -1088,15 +2396,13 @@ impl Game {
                     let strct = self.world.structure_blocks[&pos];
 
                     let strct = self.structures.get(strct);
-                    let blocks = strct.data.as_kind().blocks(strct.direction);
-
                     let mut min = IVec3::MAX;
                     let mut max = IVec3::MIN;
                     let mut pos_min = IVec3::MAX;
                     let mut pos_max = IVec3::MIN;
 
                     let position = strct.zero_zero();
-                    for &offset in blocks {
+                    for offset in strct.blocks() {
                         min = min.min(offset);
                         max = max.max(offset);
                         pos_min = pos_min.min(position + offset);
@@ -1115,8 +2421,13 @@ impl Game {
 
             let colour =
             if let Some(mining_progress) = self.player.mining_progress {
-                let target_hardness = voxel.base_hardness();
-                let progress = mining_progress as f32 / target_hardness as f32;
+                let progress = if let Some(&id) = self.world.structure_blocks.get(&pos) {
+                    let structure = self.structures.get(id);
+                    let max = structure.data.as_kind().durability();
+                    1.0 - structure.durability as f32 / max as f32
+                } else {
+                    mining_progress as f32 / voxel.base_hardness() as f32
+                };
                 let eased = 1.0 - progress.powf(3.0);
                 (Vec4::ONE * eased).with_w(1.0)
             } else {
@@ -1140,6 +2451,12 @@ impl Game {
 
 
 
+        // `Some(is_interactable)` if there's a block in reach
+        let looked_at = self.world.raycast_voxel(self.camera.position, self.camera.front, self.reach())
+            .map(|(raycast, _)| self.world.structure_blocks.get(&raycast)
+                 .is_some_and(|id| self.structures.get(*id).data.is_interactable()));
+
+
         renderer.ui_scale = self.settings.ui_scale;
         // render crossair & hotbar 
         {
@@ -1147,11 +2464,33 @@ impl Game {
 
             // crossair
             let midpoint = window / 2.0;
-            renderer.draw_rect(
-                midpoint - UI_CROSSAIR_SIZE*0.5,
-                Vec2::splat(UI_CROSSAIR_SIZE),
-                UI_CROSSAIR_COLOUR
-            );
+            match looked_at {
+                // a faded dot when nothing is in reach
+                None => renderer.draw_rect(
+                    midpoint - UI_CROSSAIR_SIZE*0.25,
+                    Vec2::splat(UI_CROSSAIR_SIZE*0.5),
+                    UI_CROSSAIR_IDLE_COLOUR
+                ),
+
+                Some(interactable) => {
+                    renderer.draw_rect(
+                        midpoint - UI_CROSSAIR_SIZE*0.5,
+                        Vec2::splat(UI_CROSSAIR_SIZE),
+                        UI_CROSSAIR_COLOUR
+                    );
+
+                    // and an outline around it if E would open something
+                    if interactable {
+                        let size = UI_CROSSAIR_SIZE * 3.0;
+                        let thickness = UI_CROSSAIR_SIZE * 0.25;
+                        let corner = midpoint - size*0.5;
+                        renderer.draw_rect(corner, Vec2::new(size, thickness), UI_CROSSAIR_INTERACT_COLOUR);
+                        renderer.draw_rect(corner + Vec2::new(0.0, size - thickness), Vec2::new(size, thickness), UI_CROSSAIR_INTERACT_COLOUR);
+                        renderer.draw_rect(corner, Vec2::new(thickness, size), UI_CROSSAIR_INTERACT_COLOUR);
+                        renderer.draw_rect(corner + Vec2::new(size - thickness, 0.0), Vec2::new(thickness, size), UI_CROSSAIR_INTERACT_COLOUR);
+                    }
+                },
+            }
 
 
             // hotbar
@@ -1164,6 +2503,33 @@ impl Game {
             );
 
             let mut start = bottom_midpoint - hotbar_size * 0.5;
+
+            if !self.player.creative {
+                let size = Vec2::new(hotbar_size.x - UI_SLOT_PADDING, UI_HEALTH_BAR_HEIGHT);
+                let pos = start - Vec2::new(0.0, UI_HEALTH_BAR_HEIGHT + UI_SLOT_PADDING * 0.5);
+                let health = self.player.health / PLAYER_MAX_HEALTH;
+
+                renderer.draw_rect(pos, size, UI_HOTBAR_UNSELECTED_BG);
+                renderer.draw_rect(pos, size * Vec2::new(health, 1.0), UI_HEALTH_BAR_COLOUR);
+            }
+
+            // flashes above the hotbar while items nearby don't fit
+            if self.player.inventory_full_time > 0.0 {
+                let text = "Inventory Full";
+                let size = renderer.text_size(text, 0.5);
+                let pos = Vec2::new(
+                    midpoint.x,
+                    start.y - UI_HEALTH_BAR_HEIGHT - UI_SLOT_PADDING * 1.5 - size.y
+                );
+
+                let flash = (self.player.inventory_full_time * 6.0).sin() * 0.25 + 0.75;
+                let fade = (self.player.inventory_full_time / 0.25).min(1.0);
+                let colour = COLOUR_DENY.with_w(flash * fade);
+
+                renderer.draw_text_ex(text, pos + UI_TEXT_SHADOW_OFFSET, 0.5, UI_TEXT_SHADOW_COLOUR.with_w(UI_TEXT_SHADOW_COLOUR.w * flash * fade), true, TextAlign::Center);
+                renderer.draw_text_ex(text, pos, 0.5, colour, false, TextAlign::Center);
+            }
+
             let hotbar = self.player.inventory.iter()
                 .enumerate()
                 .skip(self.player.hotbar * PLAYER_HOTBAR_SIZE)
@@ -1192,7 +2558,7 @@ impl Game {
                     if item.amount > 0 {
                         let pos = start+UI_ITEM_OFFSET;
 
-                        renderer.draw_text(
+                        renderer.draw_text_shadowed(
                             format!("{}", item.amount).as_str(),
                             Vec2::new(pos.x, pos.y),
                             UI_ITEM_AMOUNT_SCALE,
@@ -1208,6 +2574,11 @@ impl Game {
         }
 
 
+        if matches!(self.ui_layer, UILayer::Gameplay { .. }) {
+            self.minimap.render(renderer, &self.structures, &self.camera);
+        }
+
+
 
         // render current ui layer
         let mut ui_layer = core::mem::replace(&mut self.ui_layer, UILayer::None);
@@ -1215,7 +2586,10 @@ impl Game {
         if matches!(self.ui_layer, UILayer::None) {
             self.ui_layer = ui_layer;
 
-            let cm = self.ui_layer.is_mouse_locked();
+            // a gamepad has no way of moving the cursor so while
+            // it's the last thing used the cursor stays out of the way
+            let cm = self.ui_layer.is_mouse_locked()
+                || input.last_device() == InputDevice::Gamepad;
             if self.is_mouse_locked != cm {
                 self.is_mouse_locked = cm;
                 let window = renderer.window_size();
@@ -1247,97 +2621,19 @@ impl Game {
 
 
         // render "interact with structure" text
-        if let Some((raycast, _)) = self.world.raycast_voxel(self.camera.position,
-                                                             self.camera.front,
-                                                             PLAYER_REACH)
-           && let Some(structure) = self.world.structure_blocks.get(&raycast) {
-
-            match &self.structures.get(*structure).data {
-                  StructureData::Chest
-                | StructureData::Silo
-                | StructureData::Furnace(_)
-                | StructureData::Assembler { .. } => {
-                    let window = renderer.window_size();
-                    
-                    let text = "Press E to interact";
-                    let size = renderer.text_size(&text, 0.5);
-                    let size = Vec2::new(
-                        window.x*0.5 - size.x*0.5,
-                        window.y - UI_SLOT_PADDING*2.0 - UI_SLOT_SIZE - size.y
-                    );
-
-                    renderer.draw_text(text, size, 0.5, Vec4::ONE);
-
-                },
-                _ => (),
-            }
-        }
-
-/*
-
-        // render current ui layer
-        let mut ui_layer = core::mem::replace(&mut self.ui_layer, UILayer::None);
-        ui_layer.render(self, &input, dt);
-        if matches!(self.ui_layer, UILayer::None) {
-            self.ui_layer = ui_layer;
-
-            let current_cm = self.renderer.window.get_cursor_mode();
-            let cm = self.ui_layer.capture_mode();
-            if current_cm != cm {
-                self.renderer.window.set_cursor_mode(cm);
-
-                let window = self.renderer.window.get_size();
-                self.renderer.window.set_cursor_pos(window.0 as f64 / 2.0,
-                                               window.1 as f64 / 2.0);
-                input.move_cursor(Vec2::NAN);
-                input.move_cursor(Vec2::NAN);
-                input.move_cursor(Vec2::NAN);
-            }
-        }
-
-
-
-        // render item in hand
-        unsafe { 
-            gl::Clear(gl::DEPTH_BUFFER_BIT);
-        }
-
-        let item = self.player.inventory[self.player.hand_index()];
-        if let Some(item) = item {
-            let mut scale = Vec3::ONE;
-
-            let hand_offset = Vec3::new(1.0, -0.5, 1.0); // right, down, forward
-            if let ItemKind::Structure(structure) = item.kind {
-                let blocks = structure.blocks(CardinalDirection::North);
-                let mut min = IVec3::MAX;
-                let mut max = IVec3::MIN;
-
-                for &block in blocks {
-                    min = min.min(block);
-                    max = max.max(block);
-                }
-
-                let size = (max - min).abs() + IVec3::ONE;
-                let size = size.as_vec3().max_element();
-                scale /= size.abs() * 1.5;
-            }
+        if looked_at == Some(true) {
+            let window = renderer.window_size();
 
+            let text = "Press E to interact";
+            let size = renderer.text_size(&text, 0.5);
+            let pos = Vec2::new(
+                window.x*0.5,
+                window.y - UI_SLOT_PADDING*2.0 - UI_SLOT_SIZE - size.y
+            );
 
-            let model = 
-                Mat4::from_rotation_y(-self.camera.yaw)
-                * Mat4::from_rotation_z(self.camera.pitch)
-                * Mat4::from_translation(hand_offset)
-                * Mat4::from_rotation_y(33f32.to_radians())
-                * Mat4::from_scale(scale * 0.8);
-            self.shader_mesh.set_vec4(c"modulate", Vec4::ONE);
-            let mesh = self.renderer.meshes.get(item.kind);
-            self.shader_mesh.set_matrix4(c"model", model);
-            mesh.draw();
+            renderer.draw_text_ex(text, pos, 0.5, Vec4::ONE, false, TextAlign::Center);
         }
 
-
-        self.renderer.end();
-*/
     }
 }
 
@@ -1411,3 +2707,376 @@ fn iterate_diff<T>(
     }
 }
 
+
+///
+/// Moves `index` one step in the direction of `dir` within `0..len`,
+/// either wrapping around or clamping at the ends
+///
+fn scroll_index(index: usize, len: usize, dir: f32, wrap: bool) -> usize {
+    if dir > 0.0 {
+        if wrap { (index + 1) % len }
+        else { (index + 1).min(len - 1) }
+    } else if dir < 0.0 {
+        if wrap { (index + len - 1) % len }
+        else { index.saturating_sub(1) }
+    } else {
+        index
+    }
+}
+
+
+#[test]
+fn scroll_index_wraps() {
+    assert_eq!(scroll_index(PLAYER_HOTBAR_SIZE-1, PLAYER_HOTBAR_SIZE, 1.0, true), 0);
+    assert_eq!(scroll_index(0, PLAYER_HOTBAR_SIZE, -1.0, true), PLAYER_HOTBAR_SIZE-1);
+    assert_eq!(scroll_index(PLAYER_ROW_SIZE-1, PLAYER_ROW_SIZE, 1.0, true), 0);
+    assert_eq!(scroll_index(0, PLAYER_ROW_SIZE, -1.0, true), PLAYER_ROW_SIZE-1);
+
+    // a single scroll only ever moves one slot
+    assert_eq!(scroll_index(0, PLAYER_HOTBAR_SIZE, 1.0, true), 1);
+    assert_eq!(scroll_index(1, PLAYER_HOTBAR_SIZE, -1.0, true), 0);
+    assert_eq!(scroll_index(3, PLAYER_HOTBAR_SIZE, 0.0, true), 3);
+}
+
+
+#[test]
+fn scroll_index_clamps() {
+    assert_eq!(scroll_index(PLAYER_HOTBAR_SIZE-1, PLAYER_HOTBAR_SIZE, 1.0, false), PLAYER_HOTBAR_SIZE-1);
+    assert_eq!(scroll_index(0, PLAYER_HOTBAR_SIZE, -1.0, false), 0);
+    assert_eq!(scroll_index(PLAYER_ROW_SIZE-2, PLAYER_ROW_SIZE, 1.0, false), PLAYER_ROW_SIZE-1);
+}
+
+
+#[test]
+fn add_item_returns_overflow() {
+    let mut player = Game::headless().player;
+    let stack = ItemKind::IronPlate.max_stack_size();
+
+    // every slot is full except for one with room for 5 more plates
+    player.inventory = [Some(Item::new(ItemKind::Coal, ItemKind::Coal.max_stack_size())); PLAYER_INVENTORY_SIZE];
+    player.inventory[3] = Some(Item::new(ItemKind::IronPlate, stack - 5));
+
+    assert_eq!(player.add_item(Item::new(ItemKind::IronPlate, 8)), Some(Item::new(ItemKind::IronPlate, 3)));
+    assert_eq!(player.inventory[3], Some(Item::new(ItemKind::IronPlate, stack)));
+
+    assert_eq!(player.add_item(Item::new(ItemKind::Coal, 1)), Some(Item::new(ItemKind::Coal, 1)));
+
+    player.inventory[7] = None;
+    assert_eq!(player.add_item(Item::new(ItemKind::IronPlate, 2)), None);
+    assert_eq!(player.inventory[7], Some(Item::new(ItemKind::IronPlate, 2)));
+}
+
+
+#[test]
+fn can_give_agrees_with_add_item() {
+    let stack = ItemKind::IronPlate.max_stack_size();
+    let full = Some(Item::new(ItemKind::Coal, ItemKind::Coal.max_stack_size()));
+
+    let cases : &[(&str, &[(usize, Item)], u32)] = &[
+        ("empty inventory", &[], stack * PLAYER_INVENTORY_SIZE as u32),
+        ("partial stack", &[(0, Item::new(ItemKind::IronPlate, stack - 5))], 5),
+        ("partial stacks", &[(0, Item::new(ItemKind::IronPlate, stack - 5)), (4, Item::new(ItemKind::IronPlate, stack - 2))], 7),
+        ("over-max stack", &[(0, Item::new(ItemKind::IronPlate, stack + 10))], 0),
+        ("over-max and partial", &[(0, Item::new(ItemKind::IronPlate, stack + 10)), (1, Item::new(ItemKind::IronPlate, 1))], stack - 1),
+    ];
+
+    for &(name, slots, room) in cases {
+        // every other slot is taken up so only the listed stacks have room
+        let mut player = Game::headless().player;
+        player.inventory = if slots.is_empty() { [None; PLAYER_INVENTORY_SIZE] }
+                           else { [full; PLAYER_INVENTORY_SIZE] };
+        for &(index, item) in slots { player.inventory[index] = Some(item) }
+
+        let total = |player: &Player| player.inventory.iter().flatten()
+            .filter(|x| x.kind == ItemKind::IronPlate)
+            .map(|x| x.amount)
+            .sum::<u32>();
+
+        assert!(player.can_give(Item::new(ItemKind::IronPlate, room)), "{name}");
+        assert!(!player.can_give(Item::new(ItemKind::IronPlate, room + 1)), "{name}");
+
+        // whatever doesn't fit comes back and nothing gets made up
+        let before = total(&player);
+        let overflow = player.add_item(Item::new(ItemKind::IronPlate, room + 3));
+        assert_eq!(overflow, Some(Item::new(ItemKind::IronPlate, 3)), "{name}");
+        assert_eq!(total(&player), before + room, "{name}");
+    }
+}
+
+
+#[test]
+fn add_item_spills_over_into_empty_slots() {
+    let mut player = Game::headless().player;
+    let stack = ItemKind::IronPlate.max_stack_size();
+
+    player.inventory = [None; PLAYER_INVENTORY_SIZE];
+    player.inventory[2] = Some(Item::new(ItemKind::IronPlate, stack - 1));
+
+    assert_eq!(player.add_item(Item::new(ItemKind::IronPlate, stack * 2)), None);
+
+    let stacks : Vec<_> = player.inventory.iter().flatten().map(|x| x.amount).collect();
+    assert_eq!(stacks.iter().sum::<u32>(), stack * 3 - 1);
+    assert!(stacks.iter().all(|&x| x <= stack));
+}
+
+#[test]
+fn headless_inserter_feeds_belt() {
+    let mut game = Game::headless();
+
+    game.structures.place_at_test_site(&mut game.world, StructureKind::Inserter, IVec3::ZERO, CardinalDirection::North);
+
+    let chest = game.structures.place_at_test_site(&mut game.world, StructureKind::Chest, IVec3::new(1, 0, 0), CardinalDirection::North);
+    game.structures.get_mut(chest).inventory.as_mut().unwrap().slots[0] = Some(Item::new(ItemKind::IronPlate, 1));
+
+    let belt = game.structures.place_at_test_site(&mut game.world, StructureKind::Belt, IVec3::new(-3, 0, 0), CardinalDirection::North);
+
+    for _ in 0..TICKS_PER_SECOND * 2 {
+        game.simulation_tick();
+    }
+
+    let chest = &game.structures.get(chest).inventory.as_ref().unwrap().slots;
+    assert!(chest.iter().all(|x| x.is_none()));
+
+    let belt = &game.structures.get(belt).inventory.as_ref().unwrap().slots;
+    let on_belt : Vec<Item> = belt.iter().flatten().copied().collect();
+    assert_eq!(on_belt, [Item::new(ItemKind::IronPlate, 1)]);
+}
+
+
+#[test]
+fn headless_inserter_only_feeds_recipe_ingredients() {
+    use crate::crafting::RECIPES;
+
+    let mut game = Game::headless();
+
+    game.structures.place_at_test_site(&mut game.world, StructureKind::Inserter, IVec3::ZERO, CardinalDirection::North);
+
+    let chest = game.structures.place_at_test_site(&mut game.world, StructureKind::Chest, IVec3::new(1, 0, 0), CardinalDirection::North);
+    let slots = &mut game.structures.get_mut(chest).inventory.as_mut().unwrap().slots;
+    slots[0] = Some(Item::new(ItemKind::Coal, 3));
+    slots[1] = Some(Item::new(ItemKind::Voxel(Voxel::Stone), 5));
+    slots[2] = Some(Item::new(ItemKind::IronPlate, 4));
+
+    // gear wheels only need iron plates
+    let assembler = game.structures.place_at_test_site(&mut game.world, StructureKind::Assembler, IVec3::new(-3, 0, 0), CardinalDirection::North);
+    game.structures.get_mut(assembler).apply_config(StructureConfig::Assembler { recipe: Some(RECIPES[1]), auto_output: false });
+
+    for _ in 0..TICKS_PER_SECOND * 5 {
+        game.simulation_tick();
+    }
+
+    let slots = &game.structures.get(chest).inventory.as_ref().unwrap().slots;
+    assert_eq!(slots[0], Some(Item::new(ItemKind::Coal, 3)));
+    assert_eq!(slots[1], Some(Item::new(ItemKind::Voxel(Voxel::Stone), 5)));
+    assert_eq!(slots[2], None);
+
+    let inventory = game.structures.get(assembler).inventory.as_ref().unwrap();
+    assert!(inventory.slots.iter().flatten().all(|x| matches!(x.kind, ItemKind::IronPlate | ItemKind::IronGearWheel)));
+
+
+    // without a recipe nothing goes in, even though the
+    // inventory is still the one of the old recipe
+    game.structures.get_mut(assembler).apply_config(StructureConfig::Assembler { recipe: None, auto_output: false });
+    game.structures.get_mut(chest).inventory.as_mut().unwrap().slots[2] = Some(Item::new(ItemKind::IronPlate, 4));
+
+    for _ in 0..TICKS_PER_SECOND * 2 {
+        game.simulation_tick();
+    }
+
+    let slots = &game.structures.get(chest).inventory.as_ref().unwrap().slots;
+    assert_eq!(slots[2], Some(Item::new(ItemKind::IronPlate, 4)));
+}
+
+
+#[test]
+fn headless_furnace_smelts() {
+    let mut game = Game::headless();
+
+    let furnace = game.structures.place_at_test_site(&mut game.world, StructureKind::Furnace, IVec3::ZERO, CardinalDirection::North);
+    let inventory = game.structures.get_mut(furnace).inventory.as_mut().unwrap();
+    *inventory.input_mut(0) = Some(Item::new(ItemKind::IronOre, 3));
+    *inventory.input_mut(1) = Some(Item::new(ItemKind::Coal, 2));
+
+    for _ in 0..TICKS_PER_SECOND * 10 {
+        game.simulation_tick();
+    }
+
+    let inventory = game.structures.get(furnace).inventory.as_ref().unwrap();
+    assert!(inventory.input(0).0.is_none());
+    assert_eq!(*inventory.output(0).0, Some(Item::new(ItemKind::IronPlate, 3)));
+}
+
+
+#[test]
+fn headless_craft_queue_advances() {
+    let mut game = Game::headless();
+
+    let count = |game: &Game| game.player.inventory.iter()
+        .flatten()
+        .filter(|x| x.kind == ItemKind::IronGearWheel)
+        .map(|x| x.amount)
+        .sum::<u32>();
+
+    game.craft_queue.push((Item::new(ItemKind::IronGearWheel, 3), 30));
+    game.craft_queue.push((Item::new(ItemKind::IronGearWheel, 3), 30));
+
+    for _ in 0..29 { game.simulation_tick() }
+    assert_eq!(count(&game), 0);
+
+    game.simulation_tick();
+    assert_eq!(count(&game), 3);
+    assert_eq!(game.craft_queue.len(), 1);
+
+    for _ in 0..30 { game.simulation_tick() }
+    assert_eq!(count(&game), 6);
+    assert!(game.craft_queue.is_empty());
+}
+
+
+#[test]
+fn headless_pause_menu_stops_the_simulation() {
+    let mut game = Game::headless();
+
+    game.simulation_tick();
+    let tick = game.current_tick;
+
+    game.ui_layer = UILayer::PauseMenu;
+    for _ in 0..10 { game.simulation_tick() }
+    assert_eq!(game.current_tick, tick);
+
+    game.ui_layer = UILayer::Gameplay { smoothed_dt: 0.0 };
+    game.simulation_tick();
+    assert_eq!(game.current_tick, tick.inc());
+}
+
+
+#[test]
+fn headless_death_respawns_at_world_spawn() {
+    let mut game = Game::headless();
+
+    game.kill_player();
+    assert_eq!(game.player.body.position, PLAYER_SPAWN);
+
+    let spawn = DVec3::new(40.0, 20.0, -12.0);
+    game.world_spawn = Some(spawn);
+    game.player.body.position = DVec3::new(100.0, 5.0, 100.0);
+    game.kill_player();
+    assert_eq!(game.player.body.position, spawn);
+}
+
+
+#[test]
+fn headless_timescale_runs_more_ticks() {
+    let mut game = Game::headless();
+
+    let mut elapsed = DELTA_TICK * 10.5;
+    assert_eq!(game.catch_up(&mut elapsed), 10);
+
+    game.settings.timescale = 4.0;
+    let mut elapsed = DELTA_TICK * 10.1;
+    assert_eq!(game.catch_up(&mut elapsed), 40);
+
+    // a long stall doesn't try to catch up on all of it
+    game.settings.timescale = 1.0;
+    let mut elapsed = 10.0;
+    let ticks = game.catch_up(&mut elapsed);
+    assert!(ticks as f32 <= MAX_SIMULATION_CATCH_UP * TICKS_PER_SECOND as f32);
+}
+
+
+#[test]
+fn headless_full_inventory_warns() {
+    use crate::constants::TEST_SITE;
+
+    let mut game = Game::headless();
+    let stack = ItemKind::IronPlate.max_stack_size();
+
+    // a floor so the item doesn't fall out of reach
+    game.world.fill(TEST_SITE - IVec3::new(2, 1, 2), TEST_SITE + IVec3::new(2, -1, 2), Voxel::Stone);
+
+    game.player.set_creative(true);
+    game.player.body.position = TEST_SITE.as_dvec3() + DVec3::new(0.5, 0.0, 0.5);
+    game.player.inventory = [Some(Item::new(ItemKind::IronPlate, stack)); PLAYER_INVENTORY_SIZE];
+
+    game.entities.spawn(EntityKind::dropped_item(Item::new(ItemKind::Coal, 1)), game.player.body.position);
+
+    for _ in 0..TICKS_PER_SECOND {
+        game.simulation_tick();
+    }
+
+    assert!(game.player.inventory_full_time > 0.0);
+    assert_eq!(game.entities.count(), 1, "the coal has nowhere to go");
+
+    // the warning goes away once there's room for it
+    game.player.inventory[0] = None;
+    for _ in 0..TICKS_PER_SECOND * 3 {
+        game.simulation_tick();
+    }
+
+    assert_eq!(game.player.inventory_full_time, 0.0);
+    assert_eq!(game.entities.count(), 0);
+}
+
+
+#[test]
+fn god_reach_only_in_creative() {
+    let mut game = Game::headless();
+    game.settings.reach = 8.0;
+    game.settings.god_reach = true;
+
+    game.player.set_creative(false);
+    assert_eq!(game.reach(), 8.0);
+
+    game.player.set_creative(true);
+    assert_eq!(game.reach(), PLAYER_GOD_REACH);
+
+    game.settings.god_reach = false;
+    assert_eq!(game.reach(), 8.0);
+}
+
+
+#[test]
+fn headless_drag_placed_belts_face_along_the_line() {
+    use crate::{constants::TEST_SITE, structures::strct::rotate_block_vector};
+
+    let mut game = Game::headless();
+    game.player.set_creative(true);
+    game.player.inventory[game.player.hand_index()] = Some(Item::new(ItemKind::Structure(StructureKind::Belt), 1));
+
+    let facing = |game: &Game, pos: IVec3| {
+        let structure = game.structures.get(game.world.structure_blocks[&pos]);
+        rotate_block_vector(structure.direction, IVec3::new(-1, 0, 0))
+    };
+
+    assert!(game.place_held_item(TEST_SITE, CardinalDirection::North, Orientation::Flat));
+    game.drag_place(TEST_SITE, TEST_SITE + IVec3::new(0, 0, 3));
+
+    for z in 0..=3 {
+        assert_eq!(facing(&game, TEST_SITE + IVec3::new(0, 0, z)), IVec3::Z);
+    }
+
+    // turning the corner turns the belt at the end of the line with it
+    game.drag_place(TEST_SITE + IVec3::new(0, 0, 3), TEST_SITE + IVec3::new(-2, 0, 3));
+
+    assert_eq!(facing(&game, TEST_SITE + IVec3::new(0, 0, 2)), IVec3::Z);
+    for x in -2..=0 {
+        assert_eq!(facing(&game, TEST_SITE + IVec3::new(x, 0, 3)), IVec3::NEG_X);
+    }
+}
+
+
+#[test]
+fn pipette_keeps_the_held_stack() {
+    let mut game = Game::headless();
+    let player = &mut game.player;
+    player.inventory.iter_mut().for_each(|x| *x = None);
+
+    let hand = player.hand_index();
+    player.inventory[hand] = Some(Item::new(ItemKind::Coal, 3));
+
+    assert!(!player.select_item(ItemKind::IronPlate));
+    assert!(player.give_and_select(ItemKind::IronPlate));
+
+    assert_eq!(player.inventory[player.hand_index()].map(|x| x.kind), Some(ItemKind::IronPlate));
+    assert!(player.inventory.contains(&Some(Item::new(ItemKind::Coal, 3))));
+}
@@ -1,27 +1,114 @@
-use std::{collections::HashMap, fmt::Write, ops::Bound};
+use std::{collections::HashMap, fmt::Write, ops::Bound, time::Instant};
 
 use glam::{Vec2, Vec3};
+use image::RgbaImage;
 use save_format::{Arena, Value};
 use sti::format_in;
-use tracing::warn;
+use tracing::{error, info, trace, warn};
+use wgpu::PresentMode;
 
-use crate::{constants::DROPPED_ITEM_SCALE, crafting::{crafting_recipe_index, crafting_recipe_inventory, RECIPES}, directions::CardinalDirection, game::Game, items::{Item, ItemKind}, structures::{inventory::StructureInventory, strct::{InserterState, Structure, StructureData, StructureKind}}, PhysicsBody, Tick};
+use crate::{constants::{DEFAULT_WORLD_NAME, DROPPED_ITEM_SCALE, PLAYER_MAX_HEALTH, PLAYER_MAX_REACH, SAVES_DIR, SAVE_FORMAT_VERSION, TERRAIN_SEED, WORLD_MAX_Y, WORLD_MIN_Y}, crafting::{crafting_recipe_index, crafting_recipe_inventory, RECIPES}, directions::{CardinalDirection, Orientation}, game::Game, items::{Item, ItemKind}, structures::{inventory::{Filter, StructureInventory}, pipes::Fluid, strct::{Furnace, InserterState, Structure, StructureData, StructureKind}}, ui::UILayer, PhysicsBody, Tick};
 
 impl Game {
+    ///
+    /// Replaces the current game with the one saved in the world's
+    /// directory, returns false if there was no save to load or
+    /// it couldn't be migrated to the current format
+    ///
     #[allow(unused_must_use)]
-    pub fn load(&mut self) {
+    pub fn load(&mut self) -> bool {
         let mut game = Game::new();
 
-        let Ok(file) = std::fs::read_to_string("saves/world.sft")
-        else { return };
+        let save_dir = self.world.chunker.save_dir();
+        let Ok(file) = std::fs::read_to_string(format!("{save_dir}/world.sft"))
+        else { return false };
         let arena = save_format::Arena::new();
 
-        let hm = save_format::parse_str(&arena, &file).unwrap();
+        let Ok(mut hm) = save_format::parse_str(&arena, &file)
+        else {
+            error!("save-file: '{save_dir}/world.sft' is corrupt");
+            return false;
+        };
+
+        if let Err(e) = migrate_save(&arena, &mut hm) {
+            error!("save-file: {e}");
+            return false;
+        }
+
+        let Ok(seed) = hm["world.seed"].as_str().parse()
+        else {
+            error!("save-file: the world seed isn't a number");
+            return false;
+        };
+
+        game.world.chunker.set_world(save_dir, seed);
 
         game.current_tick = Tick(hm["current_tick"].as_u32());
         game.structures.current_tick = game.current_tick;
 
         game.settings.ui_scale = hm["ui_scale"].as_f32();
+        if let Some(wrap) = hm.get("hotbar_wrap") {
+            game.settings.hotbar_wrap = wrap.as_u32() != 0;
+        }
+        if let Some(pull) = hm.get("pull_when_full") {
+            game.settings.pull_when_full = pull.as_u32() != 0;
+        }
+        if let Some(reach) = hm.get("reach") {
+            game.settings.reach = reach.as_f32().clamp(1.0, PLAYER_MAX_REACH);
+        }
+        if let Some(god_reach) = hm.get("god_reach") {
+            game.settings.god_reach = god_reach.as_u32() != 0;
+        }
+        if let Some(multiplier) = hm.get("sprint_multiplier") {
+            game.settings.sprint_multiplier = multiplier.as_f32();
+        }
+        if let Some(acceleration) = hm.get("acceleration") {
+            game.settings.acceleration = acceleration.as_f32().max(1.0);
+        }
+        if let Some(friction) = hm.get("friction") {
+            game.settings.friction = friction.as_f32().max(1.0);
+        }
+        if let Some(interval) = hm.get("autosave_interval") {
+            game.settings.autosave_interval = interval.as_u32();
+        }
+        if let Some(render_distance) = hm.get("render_distance") {
+            game.settings.render_distance = render_distance.as_u32() as i32;
+        }
+        if let Some(sensitivity) = hm.get("sensitivity") {
+            game.settings.sensitivity = sensitivity.as_vec3().truncate();
+        }
+        if let Some(volume) = hm.get("volume") {
+            game.set_volume(volume.as_f32());
+        }
+        if let Some(density) = hm.get("fog.density") {
+            game.settings.fog.density = density.as_f32();
+        }
+        game.settings.fog.start = hm.get("fog.start").map(|x| x.as_f32());
+        game.settings.fog.end = hm.get("fog.end").map(|x| x.as_f32());
+        game.settings.fog.colour = hm.get("fog.colour").map(|x| x.as_vec3());
+        if let Some(direction) = hm.get("sun.direction") {
+            game.settings.sun.direction = direction.as_vec3();
+        }
+        if let Some(intensity) = hm.get("sun.intensity") {
+            game.settings.sun.intensity = intensity.as_f32();
+        }
+        if let Some(ambient) = hm.get("sun.ambient") {
+            game.settings.sun.ambient = ambient.as_f32();
+        }
+        if let Some(mode) = hm.get("present_mode") {
+            game.settings.present_mode = match mode.as_str() {
+                "fifo" => PresentMode::Fifo,
+                "mailbox" => PresentMode::Mailbox,
+                _ => PresentMode::Immediate,
+            };
+        }
+        if let Some(msaa) = hm.get("msaa") {
+            game.settings.msaa = msaa.as_u32();
+        }
+
+        let limits = &mut game.world.chunker.limits;
+        limits.min = hm["world.min_y"].as_f32() as i32;
+        limits.max = hm["world.max_y"].as_f32() as i32;
 
         game.camera.yaw = hm["camera.yaw"].as_f32();
         game.camera.pitch = hm["camera.pitch"].as_f32();
@@ -30,8 +117,10 @@ impl Game {
 
         // player
         game.player.body.position = hm["player.body.position"].as_vec3().as_dvec3();
+        game.world_spawn = hm.get("world.spawn").map(|x| x.as_vec3().as_dvec3());
         game.player.body.velocity = hm["player.body.velocity"].as_vec3();
         game.player.hand = hm["player.hand"].as_u32() as usize;
+        game.player.health = hm["player.health"].as_f32();
 
         let mut i = 0;
         loop {
@@ -43,13 +132,27 @@ impl Game {
             let Some(&value) = hm.get(buf.as_str())
             else { i += 1; continue };
 
-            let item = parse_item(value.as_str());
-            game.player.inventory[i] = Some(item);
+            game.player.inventory[i] = parse_item(value.as_str());
 
             i += 1;
         }
 
 
+        // recipes that don't exist anymore get dropped
+        for i in 0.. {
+            buf.clear();
+            write!(buf, "player.pinned_recipes[{i}]");
+
+            let Some(value) = hm.get(buf.as_str())
+            else { break };
+
+            let index = value.as_u32() as usize;
+            if index < RECIPES.len() && !game.player.pinned_recipes.contains(&index) {
+                game.player.pinned_recipes.push(index);
+            }
+        }
+
+
         // structures!
         // yippie, my favourite
 
@@ -61,9 +164,8 @@ impl Game {
             let Some(kind) = hm.get(buf.as_str())
             else { break };
 
-            let item_kind = *ItemKind::ALL.iter().find(|f| f.to_string() == kind.as_str()).unwrap();
-            let ItemKind::Structure(kind) = item_kind
-            else { unreachable!() };
+            let Some(ItemKind::Structure(kind)) = parse_item_kind(kind.as_str())
+            else { i += 1; continue };
 
             buf.clear();
             write!(buf, "structure[{i}].origin");
@@ -81,24 +183,28 @@ impl Game {
 
             buf.clear();
             write!(buf, "structure[{i}].energy");
-            let energy = hm.get(buf.as_str()).copied().unwrap_or(Value::Num(0.0)).as_u32();
+            let energy = hm[buf.as_str()].as_u32();
 
 
             let mut inventory = None;
             let data = match kind {
-                StructureKind::Quarry => {
+                StructureKind::Quarry | StructureKind::LargeQuarry => {
                     buf.clear();
                     write!(buf, "structure[{i}].current_progress");
                     let current_progress = hm[buf.as_str()].as_u32();
 
-                    Some(StructureData::Quarry { current_progress })
+                    buf.clear();
+                    write!(buf, "structure[{i}].max_depth");
+                    let max_depth = hm[buf.as_str()].as_u32();
+
+                    Some(StructureData::Quarry { current_progress, size: kind.quarry_size(), max_depth })
                 },
 
 
-                StructureKind::Inserter => {
+                StructureKind::Inserter | StructureKind::LongInserter => {
                     buf.clear();
                     write!(buf, "structure[{i}].filter");
-                    let filter = hm.get(buf.as_str()).map(|str| ItemKind::ALL.iter().find(|f| f.to_string() == str.as_str()).unwrap()).copied();
+                    let filter = hm.get(buf.as_str()).and_then(|str| parse_item_kind(str.as_str()));
 
                     buf.clear();
                     write!(buf, "structure[{i}].state");
@@ -107,15 +213,19 @@ impl Game {
                         "placing" => {
                             buf.clear();
                             write!(buf, "structure[{i}].item");
-                            let item = parse_item(hm[buf.as_str()].as_str());
-
-                            InserterState::Placing(item)
+                            match parse_item(hm[buf.as_str()].as_str()) {
+                                Some(item) => InserterState::Placing(item),
+                                None => InserterState::Searching,
+                            }
                         }
 
-                        _ => unreachable!(),
+                        state => {
+                            warn!("save: unknown inserter state '{state}', searching again");
+                            InserterState::Searching
+                        }
                     };
 
-                    Some(StructureData::Inserter { state, filter })
+                    Some(StructureData::Inserter { state, filter, reach: kind.inserter_reach() })
                 },
 
                 StructureKind::Splitter => {
@@ -133,8 +243,18 @@ impl Game {
 
                     ];
 
+                    let filters = [0, 1].map(|side| {
+                        buf.clear();
+                        write!(buf, "structure[{i}].filter[{side}]");
+                        hm.get(buf.as_str()).and_then(|str| parse_item_kind(str.as_str()))
+                    });
 
-                    Some(StructureData::Splitter { priority })
+
+                    buf.clear();
+                    write!(buf, "structure[{i}].preferred");
+                    let preferred = hm.get(buf.as_str()).map(|x| x.as_u32() as usize);
+
+                    Some(StructureData::Splitter { priority, filters, preferred })
 
                 },
 
@@ -147,7 +267,64 @@ impl Game {
                         Some(RECIPES[recipe_index as usize])
                     } else { None };
 
-                    Some(StructureData::Assembler { recipe })
+                    buf.clear();
+                    write!(buf, "structure[{i}].auto_output");
+                    let auto_output = hm.get(buf.as_str()).is_some_and(|x| x.as_u32() != 0);
+
+                    let modules = core::array::from_fn(|slot| {
+                        buf.clear();
+                        write!(buf, "structure[{i}].module[{slot}]");
+                        hm.get(buf.as_str()).and_then(|str| parse_item_kind(str.as_str()))
+                    });
+
+                    buf.clear();
+                    write!(buf, "structure[{i}].productivity");
+                    let productivity = hm.get(buf.as_str()).map(|x| x.as_u32().min(999)).unwrap_or(0);
+
+                    Some(StructureData::Assembler { recipe, auto_output, modules, productivity })
+                }
+
+
+                StructureKind::Furnace | StructureKind::SteelFurnace => {
+                    buf.clear();
+                    write!(buf, "structure[{i}].auto_fuel");
+                    let auto_fuel = hm.get(buf.as_str()).is_some_and(|x| x.as_u32() != 0);
+
+                    let mut furnace = Furnace::new(kind);
+                    furnace.auto_fuel = auto_fuel;
+                    Some(StructureData::Furnace(furnace))
+                }
+
+
+                StructureKind::Pipe => {
+                    buf.clear();
+                    write!(buf, "structure[{i}].fluid");
+                    let fluid = hm.get(buf.as_str()).and_then(|str| {
+                        let fluid = Fluid::ALL.iter().find(|f| f.to_string() == str.as_str()).copied();
+                        if fluid.is_none() { warn!("save: unknown fluid '{}', emptying the pipe", str.as_str()) }
+                        fluid
+                    });
+
+                    buf.clear();
+                    write!(buf, "structure[{i}].fluid_amount");
+                    let amount = hm.get(buf.as_str()).filter(|_| fluid.is_some()).map(|x| x.as_u32()).unwrap_or(0);
+
+                    Some(StructureData::Pipe { fluid, amount })
+                }
+
+
+                StructureKind::RequesterChest => {
+                    let mut requests = vec![];
+                    for request_i in 0.. {
+                        buf.clear();
+                        write!(buf, "structure[{i}].request[{request_i}]");
+                        let Some(request) = hm.get(buf.as_str())
+                        else { break };
+
+                        requests.extend(parse_item(request.as_str()));
+                    }
+
+                    Some(StructureData::RequesterChest { requests })
                 }
 
 
@@ -161,6 +338,21 @@ impl Game {
             }
 
             structure.energy.energy = energy;
+
+            buf.clear();
+            write!(buf, "structure[{i}].durability");
+            if let Some(durability) = hm.get(buf.as_str()) {
+                structure.durability = durability.as_u32();
+            }
+
+            buf.clear();
+            write!(buf, "structure[{i}].orientation");
+            if let Some(orientation) = hm.get(buf.as_str()) {
+                match Orientation::from_str(orientation.as_str()) {
+                    Some(orientation) => structure.orientation = orientation,
+                    None => warn!("save: unknown orientation '{}', keeping it flat", orientation.as_str()),
+                }
+            }
             if let Some(inv) = inventory {
                 structure.inventory = Some(StructureInventory::new(inv));
             }
@@ -172,20 +364,171 @@ impl Game {
                     let Some(str) = hm.get(buf.as_str())
                     else { continue; };
 
-                    let item = parse_item(str.as_str());
+                    let Some(item) = parse_item(str.as_str())
+                    else { continue; };
+
                     sinv.slots[inv_i] = Some(item);
                 }
+
+                for inv_i in 0..sinv.filters.len() {
+                    buf.clear();
+                    write!(buf, "structure[{i}].inventory_filter[{inv_i}]");
+                    let Some(str) = hm.get(buf.as_str())
+                    else { continue; };
+
+                    let Some(kind) = parse_item_kind(str.as_str())
+                    else { continue; };
+
+                    sinv.filters[inv_i] = Filter::ItemKind(kind);
+                }
             }
 
             game.structures.add_structure(&mut game.world, structure);
             i += 1;
         }
 
+        game.command_registry.previous_commands = core::mem::take(&mut self.command_registry.previous_commands);
+        game.save_job = self.save_job.take();
+        game.queued_save = self.queued_save.take();
         *self = game;
+        true
     }
 
 
     pub fn save(&mut self) {
+        self.save_ex(false);
+    }
+
+
+    ///
+    /// Replaces the current game with the world named `name`,
+    /// creating it from `seed` if it doesn't exist yet
+    ///
+    pub fn open_world(&mut self, name: &str, seed: u64) {
+        let save_dir = format!("{SAVES_DIR}/{name}");
+
+        let mut game = Game::new();
+        game.world.chunker.set_world(&save_dir, seed);
+
+        if !std::fs::exists(format!("{save_dir}/world.sft")).is_ok_and(|f| f) {
+            trace!("no previous save-state. creating files");
+            let _ = std::fs::create_dir_all(format!("{save_dir}/chunks/"));
+            game.save();
+        }
+
+        if !game.load() { return }
+
+        game.command_registry.previous_commands = core::mem::take(&mut self.command_registry.previous_commands);
+        game.save_job = self.save_job.take();
+        game.queued_save = self.queued_save.take();
+        *self = game;
+    }
+
+
+    ///
+    /// Saves the game state, if `full` is set every loaded chunk
+    /// is rewritten to disk even if it hasn't changed
+    ///
+    pub fn save_ex(&mut self, full: bool) {
+        let Some(snapshot) = self.snapshot(full)
+        else { return };
+
+        let path = format!("{}/world.sft", self.world.chunker.save_dir());
+        self.request_thumbnail();
+
+        // an autosave that's still being written would overwrite
+        // this one with an older state, so it waits its turn
+        if self.is_autosaving() {
+            info!("save: an autosave is still being written, queued it");
+            self.queued_save = Some((path, snapshot));
+            return;
+        }
+
+        std::fs::write(path, snapshot).unwrap();
+    }
+
+
+    ///
+    /// Writes out the save that got queued behind an
+    /// autosave, once that autosave is on disk
+    ///
+    pub fn write_queued_save(&mut self) {
+        if self.is_autosaving() { return }
+
+        let Some((path, snapshot)) = self.queued_save.take()
+        else { return };
+
+        if let Err(e) = std::fs::write(&path, snapshot) {
+            error!("save: unable to write '{path}': {e}");
+        }
+    }
+
+
+    ///
+    /// Waits for the autosave in flight and writes out whatever
+    /// got queued behind it, for when the game's closing
+    ///
+    pub fn finish_saves(&mut self) {
+        if let Some(job) = self.save_job.take() {
+            let _ = job.join();
+        }
+
+        self.write_queued_save();
+    }
+
+
+    fn is_autosaving(&self) -> bool {
+        self.save_job.as_ref().is_some_and(|x| !x.is_finished())
+    }
+
+
+    ///
+    /// Saves the game without blocking on the disk, the state is
+    /// serialized right away but written out on another thread.
+    /// Skipped if the previous autosave is still being written
+    ///
+    pub fn autosave(&mut self) {
+        if self.is_autosaving() {
+            warn!("autosave: the previous save is still in flight, skipping");
+            return;
+        }
+
+        let Some(snapshot) = self.snapshot(false)
+        else { return };
+
+        let path = format!("{}/world.sft", self.world.chunker.save_dir());
+        self.request_thumbnail();
+
+        self.save_job = Some(std::thread::spawn(move || {
+            let time = Instant::now();
+            match std::fs::write(&path, &snapshot) {
+                Ok(()) => info!("autosave: wrote '{path}' in {:?} ({} bytes)", time.elapsed(), snapshot.len()),
+                Err(e) => error!("autosave: unable to write '{path}': {e}"),
+            }
+        }));
+    }
+
+
+    ///
+    /// Has the renderer write the next frame out as the world's
+    /// thumbnail, the world list shows it next to the name
+    ///
+    fn request_thumbnail(&mut self) {
+        self.thumbnail_request = Some(format!("{}/thumbnail.png", self.world.chunker.save_dir()));
+    }
+
+
+    ///
+    /// Serializes the game into the contents of `world.sft`, chunks
+    /// are queued to be saved on their own. Returns `None` if there's
+    /// no world loaded to save
+    ///
+    fn snapshot(&mut self, full: bool) -> Option<String> {
+        self.command_registry.save_history();
+
+        // there's no world to save until one is picked
+        if matches!(self.ui_layer, UILayer::MainMenu { .. }) { return None }
+
         let mut v = Vec::new();
 
         macro_rules! insert {
@@ -195,11 +538,43 @@ impl Game {
             };
         }
 
-        self.world.save();
+        self.world.save(full);
 
         let arena = Arena::new();
+        v.push(("version", Value::Num(SAVE_FORMAT_VERSION as f64)));
         v.push(("current_tick", Value::Num(self.current_tick.u32() as f64)));
         v.push(("ui_scale", Value::Num(self.settings.ui_scale as f64)));
+        v.push(("hotbar_wrap", Value::Num(self.settings.hotbar_wrap as u32 as f64)));
+        v.push(("pull_when_full", Value::Num(self.settings.pull_when_full as u32 as f64)));
+        v.push(("reach", Value::Num(self.settings.reach as f64)));
+        v.push(("god_reach", Value::Num(self.settings.god_reach as u32 as f64)));
+        v.push(("sprint_multiplier", Value::Num(self.settings.sprint_multiplier as f64)));
+        v.push(("acceleration", Value::Num(self.settings.acceleration as f64)));
+        v.push(("friction", Value::Num(self.settings.friction as f64)));
+        v.push(("autosave_interval", Value::Num(self.settings.autosave_interval as f64)));
+        v.push(("render_distance", Value::Num(self.settings.render_distance as f64)));
+        v.push(("sensitivity", Value::Vec3(self.settings.sensitivity.extend(0.0))));
+        v.push(("volume", Value::Num(self.settings.volume as f64)));
+        v.push(("fog.density", Value::Num(self.settings.fog.density as f64)));
+        if let Some(start) = self.settings.fog.start { v.push(("fog.start", Value::Num(start as f64))) }
+        if let Some(end) = self.settings.fog.end { v.push(("fog.end", Value::Num(end as f64))) }
+        if let Some(colour) = self.settings.fog.colour { v.push(("fog.colour", Value::Vec3(colour))) }
+        v.push(("sun.direction", Value::Vec3(self.settings.sun.direction)));
+        v.push(("sun.intensity", Value::Num(self.settings.sun.intensity as f64)));
+        v.push(("sun.ambient", Value::Num(self.settings.sun.ambient as f64)));
+        let present_mode = match self.settings.present_mode {
+            PresentMode::Fifo => "fifo",
+            PresentMode::Mailbox => "mailbox",
+            _ => "immediate",
+        };
+        v.push(("present_mode", Value::String(present_mode)));
+        v.push(("msaa", Value::Num(self.settings.msaa as f64)));
+        // as a string since a `Num` can't hold every u64
+        let seed = format_in!(&arena, "{}", self.world.chunker.seed()).leak();
+        v.push(("world.seed", Value::String(seed)));
+        v.push(("world.min_y", Value::Num(self.world.chunker.limits.min as f64)));
+        v.push(("world.max_y", Value::Num(self.world.chunker.limits.max as f64)));
+        if let Some(spawn) = self.world_spawn { v.push(("world.spawn", Value::Vec3(spawn.as_vec3()))) }
 
         insert!(self.camera.yaw, Num);
         insert!(self.camera.pitch, Num);
@@ -208,6 +583,7 @@ impl Game {
         v.push(("player.body.position", Value::Vec3(self.player.body.position.as_vec3())));
         insert!(self.player.body.velocity, Vec3);
         insert!(self.player.hand, Num);
+        insert!(self.player.health, Num);
 
         
         for (i, item) in self.player.inventory.iter().enumerate() {
@@ -217,6 +593,11 @@ impl Game {
             }
         }
 
+        for (i, index) in self.player.pinned_recipes.iter().enumerate() {
+            let path = format_in!(&arena, "player.pinned_recipes[{i}]").leak();
+            v.push((path, Value::Num(*index as f64)));
+        }
+
         
         // structures
         let mut buf = String::new();
@@ -231,6 +612,9 @@ impl Game {
             v.push((format_in!(&arena, "{buf}.kind").leak(), Value::String(structure.data.as_kind().item_kind().to_string())));
             v.push((format_in!(&arena, "{buf}.origin").leak(), Value::Vec3(structure.position.as_vec3())));
             v.push((format_in!(&arena, "{buf}.energy").leak(), Value::Num(structure.energy.energy as _)));
+            if structure.durability != structure.data.as_kind().durability() {
+                v.push((format_in!(&arena, "{buf}.durability").leak(), Value::Num(structure.durability as _)));
+            }
 
             let direction = match structure.direction {
                 CardinalDirection::North => "north",
@@ -241,6 +625,10 @@ impl Game {
 
             v.push((format_in!(&arena, "{buf}.direction").leak(), Value::String(direction)));
 
+            if structure.orientation != Orientation::Flat {
+                v.push((format_in!(&arena, "{buf}.orientation").leak(), Value::String(structure.orientation.to_string())));
+            }
+
             if let Some(inventory) = &structure.inventory {
                 for (i, item) in inventory.slots.iter().enumerate() {
                     let Some(item) = item
@@ -249,15 +637,23 @@ impl Game {
                     let path = format_in!(&arena, "{buf}.inventory[{}]", i).leak();
                     save_item(&arena, &mut v, path, *item);
                 }
+
+                for (i, filter) in inventory.filters.iter().enumerate() {
+                    let Filter::ItemKind(kind) = filter
+                    else { continue };
+
+                    v.push((format_in!(&arena, "{buf}.inventory_filter[{}]", i).leak(), Value::String(kind.to_string())));
+                }
             }
 
             match &structure.data {
-                StructureData::Quarry { current_progress, } => {
+                StructureData::Quarry { current_progress, max_depth, .. } => {
                     v.push((format_in!(&arena, "{buf}.current_progress").leak(), Value::Num(*current_progress as f64)));
+                    v.push((format_in!(&arena, "{buf}.max_depth").leak(), Value::Num(*max_depth as f64)));
                 },
 
 
-                StructureData::Inserter { state, filter } => {
+                StructureData::Inserter { state, filter, .. } => {
                     if let Some(filter) = filter {
                         v.push((format_in!(&arena, "{buf}.filter").leak(), Value::String(filter.to_string())));
                     }
@@ -283,24 +679,74 @@ impl Game {
                 StructureData::Silo => (),
 
 
-                StructureData::Belt => (),
+                StructureData::ProviderChest => (),
+
+
+                StructureData::RequesterChest { requests } => {
+                    for (n, request) in requests.iter().enumerate() {
+                        let path = format_in!(&arena, "{buf}.request[{n}]").leak();
+                        save_item(&arena, &mut v, path, *request);
+                    }
+                },
+
+
+                StructureData::Belt { .. } => (),
 
 
-                StructureData::Splitter { priority } => {
+                StructureData::Splitter { priority, filters, preferred } => {
                     v.push((format_in!(&arena, "{buf}.priority[0]").leak(), Value::Num(priority[0] as _)));
                     v.push((format_in!(&arena, "{buf}.priority[1]").leak(), Value::Num(priority[1] as _)));
+
+                    if let Some(preferred) = preferred {
+                        v.push((format_in!(&arena, "{buf}.preferred").leak(), Value::Num(*preferred as _)));
+                    }
+
+                    for (side, filter) in filters.iter().enumerate() {
+                        if let Some(filter) = filter {
+                            v.push((format_in!(&arena, "{buf}.filter[{side}]").leak(), Value::String(filter.to_string())));
+                        }
+                    }
                 },
 
 
-                StructureData::Assembler { recipe } => {
+                StructureData::Assembler { recipe, auto_output, modules, productivity } => {
+                    for (slot, module) in modules.iter().enumerate() {
+                        if let Some(module) = module {
+                            v.push((format_in!(&arena, "{buf}.module[{slot}]").leak(), Value::String(module.to_string())));
+                        }
+                    }
+
                     if let Some(recipe) = recipe {
                         let recipe_index = crafting_recipe_index(*recipe);
                         v.push((format_in!(&arena, "{buf}.recipe").leak(), Value::Num(recipe_index as _)));
                     }
+
+                    if *auto_output {
+                        v.push((format_in!(&arena, "{buf}.auto_output").leak(), Value::Num(1.0)));
+                    }
+
+                    if *productivity > 0 {
+                        v.push((format_in!(&arena, "{buf}.productivity").leak(), Value::Num(*productivity as _)));
+                    }
                 }
 
 
-                StructureData::Furnace(_) => {},
+                StructureData::Furnace(furnace) => {
+                    if furnace.auto_fuel {
+                        v.push((format_in!(&arena, "{buf}.auto_fuel").leak(), Value::Num(1.0)));
+                    }
+                },
+
+
+                StructureData::Pump => (),
+
+
+                StructureData::Pipe { fluid, amount } => {
+                    if let Some(fluid) = fluid {
+                        v.push((format_in!(&arena, "{buf}.fluid").leak(), Value::String(fluid.to_string())));
+                        v.push((format_in!(&arena, "{buf}.fluid_amount").leak(), Value::Num(*amount as _)));
+                    }
+                },
             };
         }
 
@@ -329,7 +775,7 @@ impl Game {
             warn!("craft queue isn't saved currently");
         }
 
-        std::fs::write("saves/world.sft", save_format::slice_to_string(&v)).unwrap();
+        Some(save_format::slice_to_string(&v))
     }
 
 
@@ -338,6 +784,62 @@ impl Game {
 
 
 
+///
+/// Returns the name of every world in the saves directory
+///
+pub fn list_worlds() -> Vec<String> {
+    let Ok(dir) = std::fs::read_dir(SAVES_DIR)
+    else { return vec![] };
+
+    let mut worlds = dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("world.sft").exists())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+
+    worlds.sort();
+    worlds
+}
+
+
+///
+/// Loads the thumbnail written the last time `name` was saved,
+/// `None` if it was never saved while being rendered
+///
+pub fn load_thumbnail(name: &str) -> Option<RgbaImage> {
+    let image = image::open(format!("{SAVES_DIR}/{name}/thumbnail.png")).ok()?;
+    Some(image.to_rgba8())
+}
+
+
+pub fn delete_world(name: &str) {
+    if let Err(e) = std::fs::remove_dir_all(format!("{SAVES_DIR}/{name}")) {
+        warn!("unable to delete the world '{name}': {e}");
+    }
+}
+
+
+///
+/// Saves from before there were multiple worlds kept everything
+/// directly in the saves directory, move them into their own world
+///
+pub fn migrate_legacy_save() {
+    let legacy = format!("{SAVES_DIR}/world.sft");
+    if !std::fs::exists(&legacy).is_ok_and(|f| f) { return }
+
+    let world = format!("{SAVES_DIR}/{DEFAULT_WORLD_NAME}");
+    if std::fs::exists(&world).is_ok_and(|f| f) {
+        warn!("can't migrate the old save, '{world}' already exists");
+        return;
+    }
+
+    info!("moving the old save into '{world}'");
+    let _ = std::fs::create_dir_all(&world);
+    let _ = std::fs::rename(legacy, format!("{world}/world.sft"));
+    let _ = std::fs::rename(format!("{SAVES_DIR}/chunks"), format!("{world}/chunks"));
+}
+
+
 fn save_item<'a>(arena: &'a Arena,
                  v: &mut Vec<(&'a str, Value<'a>)>,
                  prefix: &'a str,
@@ -348,16 +850,186 @@ fn save_item<'a>(arena: &'a Arena,
 }
 
 
-fn parse_item(str: &str) -> Item {
-    let (split_pos, _) = str.bytes().enumerate().rev().find(|x| x.1 == b'x').unwrap();
-    let (ident, amount) = str.split_at(split_pos);
-    let ident = ident.trim();
+///
+/// Looks up an item kind by name, filters and modules naming
+/// one this version doesn't know get skipped with a warning
+/// instead of failing the whole load
+///
+fn parse_item_kind(str: &str) -> Option<ItemKind> {
+    let kind = ItemKind::ALL.iter().find(|f| f.to_string() == str).copied();
+    if kind.is_none() {
+        warn!("save: unknown item kind '{str}', skipping it");
+    }
+
+    kind
+}
+
+
+fn parse_item(str: &str) -> Option<Item> {
+    let amount = str.rsplit_once('x').and_then(|(ident, amount)| Some((ident.trim(), amount.parse::<u32>().ok()?)));
+    let Some((ident, amount)) = amount
+    else {
+        warn!("save: '{str}' isn't an item, skipping it");
+        return None;
+    };
+
+    let kind = parse_item_kind(ident)?;
+    Some(Item { amount, kind })
+}
+
+
+
+
+///
+/// Upgrades a save written by an older version of the game to
+/// `SAVE_FORMAT_VERSION`, one version at a time, so the loader
+/// only ever has to read the current layout
+///
+/// Saves from before the version was written count as version 1
+///
+fn migrate_save<'a>(arena: &'a Arena, hm: &mut HashMap<&'a str, Value<'a>>) -> Result<(), String> {
+    let mut version = hm.get("version").map_or(1, |x| x.as_u32());
+    if version > SAVE_FORMAT_VERSION {
+        return Err(format!("the save is from a newer version of the game (version {version}, expected at most {SAVE_FORMAT_VERSION})"));
+    }
+
+    while version < SAVE_FORMAT_VERSION {
+        match version {
+            1 => migrate_v1(arena, hm),
+            2 => migrate_v2(arena, hm),
+            _ => return Err(format!("there's no way to upgrade a version {version} save")),
+        }
+
+        trace!("save-file: migrated from version {version} to {}", version + 1);
+        version += 1;
+    }
+
+    hm.insert("version", Value::Num(version as f64));
+    Ok(())
+}
+
+
+///
+/// Version 1 saves may be missing anything that got added
+/// to the game after it first started saving
+///
+fn migrate_v1<'a>(arena: &'a Arena, hm: &mut HashMap<&'a str, Value<'a>>) {
+    // they were all generated from the same seed
+    let seed = format_in!(arena, "{TERRAIN_SEED}").leak();
+    hm.entry("world.seed").or_insert(Value::String(seed));
+    hm.entry("world.min_y").or_insert(Value::Num(WORLD_MIN_Y as f64));
+    hm.entry("world.max_y").or_insert(Value::Num(WORLD_MAX_Y as f64));
+    hm.entry("player.health").or_insert(Value::Num(PLAYER_MAX_HEALTH as f64));
 
-    let kind = *ItemKind::ALL.iter().find(|f| f.to_string() == ident).unwrap();
-    let amount : u32 = amount[1..].parse().unwrap();
+    for i in 0.. {
+        if !hm.contains_key(format!("structure[{i}].kind").as_str()) { break }
 
-    let item = Item { amount, kind };
-    item
+        let energy = format_in!(arena, "structure[{i}].energy").leak();
+        hm.entry(energy).or_insert(Value::Num(0.0));
+    }
 }
 
 
+///
+/// Quarries used to dig forever
+///
+fn migrate_v2<'a>(arena: &'a Arena, hm: &mut HashMap<&'a str, Value<'a>>) {
+    for i in 0.. {
+        let Some(kind) = hm.get(format!("structure[{i}].kind").as_str())
+        else { break };
+
+        if kind.as_str() != "quarry" { continue }
+
+        let max_depth = format_in!(arena, "structure[{i}].max_depth").leak();
+        hm.entry(max_depth).or_insert(Value::Num(u32::MAX as f64));
+    }
+}
+
+
+#[test]
+fn v1_saves_migrate() {
+    let arena = Arena::new();
+    let v1 = [
+        ("current_tick", Value::Num(20.0)),
+        ("player.hand", Value::Num(0.0)),
+        ("structure[0].kind", Value::String("chest")),
+        ("structure[0].origin", Value::Vec3(Vec3::ZERO)),
+        ("structure[0].direction", Value::String("north")),
+    ];
+
+    let file = save_format::slice_to_string(&v1);
+    let mut hm = save_format::parse_str(&arena, &file).unwrap();
+    migrate_save(&arena, &mut hm).unwrap();
+
+    assert_eq!(hm["version"].as_u32(), SAVE_FORMAT_VERSION);
+    assert_eq!(hm["world.seed"].as_str().parse::<u64>().unwrap(), TERRAIN_SEED);
+    assert_eq!(hm["world.min_y"].as_f32() as i32, WORLD_MIN_Y);
+    assert_eq!(hm["world.max_y"].as_f32() as i32, WORLD_MAX_Y);
+    assert_eq!(hm["player.health"].as_f32(), PLAYER_MAX_HEALTH);
+    assert_eq!(hm["structure[0].energy"].as_u32(), 0);
+    assert!(!hm.contains_key("structure[1].energy"));
+    assert!(!hm.contains_key("structure[0].max_depth"));
+
+    // fields the save already had are left alone
+    assert_eq!(hm["current_tick"].as_u32(), 20);
+}
+
+
+#[test]
+fn newer_saves_are_rejected() {
+    let arena = Arena::new();
+    let newer = [("version", Value::Num((SAVE_FORMAT_VERSION + 1) as f64))];
+
+    let file = save_format::slice_to_string(&newer);
+    let mut hm = save_format::parse_str(&arena, &file).unwrap();
+    assert!(migrate_save(&arena, &mut hm).is_err());
+}
+
+
+#[test]
+fn v2_assemblers_keep_their_recipe() {
+    // recipes are saved by index, so everything a v2 save knew
+    // about has to stay where it was in `RECIPES`
+    let assembler = RECIPES.iter().position(|x| x.result.kind == ItemKind::Structure(StructureKind::Assembler)).unwrap();
+    let radar = RECIPES.iter().position(|x| x.result.kind == ItemKind::Radar).unwrap();
+    assert_eq!((assembler, radar), (18, 19));
+
+    let seed = format!("{TERRAIN_SEED}");
+    let v2 = [
+        ("version", Value::Num(2.0)),
+        ("world.seed", Value::String(&seed)),
+        ("world.min_y", Value::Num(WORLD_MIN_Y as f64)),
+        ("world.max_y", Value::Num(WORLD_MAX_Y as f64)),
+        ("current_tick", Value::Num(0.0)),
+        ("ui_scale", Value::Num(1.0)),
+        ("camera.yaw", Value::Num(0.0)),
+        ("camera.pitch", Value::Num(0.0)),
+        ("player.body.position", Value::Vec3(Vec3::ZERO)),
+        ("player.body.velocity", Value::Vec3(Vec3::ZERO)),
+        ("player.hand", Value::Num(0.0)),
+        ("player.health", Value::Num(PLAYER_MAX_HEALTH as f64)),
+        ("player.pinned_recipes[0]", Value::Num(radar as f64)),
+        ("structure[0].kind", Value::String("assembler")),
+        ("structure[0].origin", Value::Vec3(Vec3::new(0.0, 64.0, 0.0))),
+        ("structure[0].direction", Value::String("north")),
+        ("structure[0].energy", Value::Num(0.0)),
+        ("structure[0].recipe", Value::Num(assembler as f64)),
+    ];
+
+    let save_dir = std::env::temp_dir().join(format!("v2_assemblers_keep_their_recipe_{}", std::process::id()));
+    let save_dir = save_dir.to_str().unwrap();
+    std::fs::create_dir_all(save_dir).unwrap();
+    std::fs::write(format!("{save_dir}/world.sft"), save_format::slice_to_string(&v2)).unwrap();
+
+    let mut game = Game::headless();
+    game.world.chunker.set_world(save_dir, TERRAIN_SEED);
+    assert!(game.load());
+    let _ = std::fs::remove_dir_all(save_dir);
+
+    let (_, structure) = game.structures.structs.iter().next().unwrap();
+    let StructureData::Assembler { recipe: Some(recipe), .. } = structure.data
+    else { panic!("the assembler lost its recipe") };
+
+    assert_eq!(recipe.result.kind, ItemKind::Structure(StructureKind::Assembler));
+    assert_eq!(game.player.pinned_recipes, [radar]);
+}
@@ -6,6 +6,8 @@ use sti::{alloc::{Alloc, GlobalAlloc}, key::Key, vec::KVec};
 pub struct KGenMap<G: Key, K: Key, V, A: Alloc = GlobalAlloc> {
     next: Option<K>,
     vec: KVec<K, (G, KGenVal<K, V>), A>,
+    /// how many slots are occupied, `len` counts the free ones too
+    count: usize,
 }
 
 
@@ -42,6 +44,7 @@ impl<G: Key, K: Key, V, A: Alloc> KGenMap<G, K, V, A> {
         Self {
             next: None,
             vec: KVec::new_in(alloc),
+            count: 0,
         }
     }
 
@@ -90,6 +93,7 @@ impl<G: Key, K: Key, V, A: Alloc> KGenMap<G, K, V, A> {
         let slot = &mut self.vec[key].1;
 
         let slot = core::mem::replace(slot, KGenVal::Free { next: self.next });
+        self.count -= 1;
 
         match slot {
             KGenVal::Occupied(v) => v,
@@ -101,15 +105,21 @@ impl<G: Key, K: Key, V, A: Alloc> KGenMap<G, K, V, A> {
     pub fn len(&self) -> usize { self.vec.len() }
 
 
+    pub fn count(&self) -> usize { self.count }
+
+
     pub fn with_cap_in(alloc: A, cap: usize) -> Self {
         Self {
             next: None,
             vec: KVec::with_cap_in(alloc, cap),
+            count: 0,
         }
     }
 
 
     pub fn insert(&mut self, value: V) -> KeyGen<G, K> {
+        self.count += 1;
+
         if let Some(next) = self.next {
             let (generation, slot) = &mut self.vec[next];
             *generation = unsafe { generation.add(1) };
@@ -145,6 +155,7 @@ impl<G: Key, K: Key, V, A: Alloc> KGenMap<G, K, V, A> {
 
         let slot = core::mem::replace(slot, KGenVal::Free { next: self.next });
         self.next = Some(kg.key);
+        self.count -= 1;
 
         match slot {
             KGenVal::Occupied(v) => v,
@@ -199,6 +210,40 @@ impl<G: Key, K: Key, V, A: Alloc> KGenMap<G, K, V, A> {
         ret
     }
 
+
+    ///
+    /// Like `get_many_mut` but for any amount of keys, `kgs` has
+    /// to be sorted by key without duplicates. Stale keys are skipped
+    ///
+    pub fn get_sorted_mut(&mut self, kgs: &[KeyGen<G, K>]) -> Vec<(KeyGen<G, K>, &mut V)> {
+        debug_assert!(kgs.windows(2).all(|w| w[0].key.usize() < w[1].key.usize()));
+
+        let mut ret = Vec::with_capacity(kgs.len());
+        // `rest` starts at key `offset`, each key splits off the
+        // slots up to and including itself
+        let mut rest : &mut [(G, KGenVal<K, V>)] = &mut self.vec;
+        let mut offset = 0;
+
+        for &kg in kgs {
+            let Some((_, tail)) = core::mem::take(&mut rest).split_at_mut_checked(kg.key.usize() - offset)
+            else { break };
+
+            let Some(((generation, slot), tail)) = tail.split_first_mut()
+            else { break };
+
+            rest = tail;
+            offset = kg.key.usize() + 1;
+
+            if *generation != kg.gen_key { continue }
+
+            if let KGenVal::Occupied(v) = slot {
+                ret.push((kg, v));
+            }
+        }
+
+        ret
+    }
+
 }
 
 
@@ -1,8 +1,12 @@
 use std::collections::HashSet;
 
+use gilrs::{Axis, Button, EventType, Gilrs};
 use glam::Vec2;
+use tracing::warn;
 use winit::{event::MouseButton, keyboard::{KeyCode, PhysicalKey}};
 
+use crate::constants::GAMEPAD_LOOK_SPEED;
+
 #[derive(Debug, Default)]
 pub struct InputManager {
     down_keys: HashSet<PhysicalKey>,
@@ -14,18 +18,145 @@ pub struct InputManager {
     mouse_pos: Vec2,
     scroll_dt: Vec2,
     delta_mouse_pos: Vec2,
+
+    /// `None` if gamepads aren't supported on this platform
+    gilrs: Option<Gilrs>,
+    /// keys and buttons held down through a gamepad, kept apart
+    /// from the keyboard's so releasing one doesn't release the other
+    gamepad_keys: HashSet<PhysicalKey>,
+    gamepad_buttons: HashSet<MouseButton>,
+    last_device: InputDevice,
+}
+
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad,
 }
 
 
 impl InputManager {
     pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .inspect_err(|e| warn!("input: gamepads are unavailable: {e}"))
+            .ok();
+
         Self {
             mouse_pos: Vec2::NAN,
+            gilrs,
             ..Default::default()
         }
     }
 
 
+    ///
+    /// Feeds gamepad input into the same keys, buttons and deltas
+    /// the keyboard and mouse use so nothing downstream has to
+    /// care where the input came from
+    ///
+    /// The left stick walks, the right stick looks around, the
+    /// triggers mine and place, the bumpers scroll the hotbar and
+    /// the face buttons jump, interact and back out of menus
+    ///
+    pub fn poll_gamepad(&mut self, dt: f32, deadzone: f32) {
+        let Some(gilrs) = &mut self.gilrs
+        else { return };
+
+        let mut events = vec![];
+        while let Some(event) = gilrs.next_event() {
+            events.push(event.event);
+        }
+
+        let mut left = Vec2::ZERO;
+        let mut right = Vec2::ZERO;
+        for (_, gamepad) in gilrs.gamepads() {
+            left += Vec2::new(gamepad.value(Axis::LeftStickX), gamepad.value(Axis::LeftStickY));
+            right += Vec2::new(gamepad.value(Axis::RightStickX), gamepad.value(Axis::RightStickY));
+        }
+
+
+        for event in events {
+            match event {
+                EventType::ButtonPressed(button, _) => {
+                    self.last_device = InputDevice::Gamepad;
+                    match button {
+                        Button::LeftTrigger => self.scroll_dt.y -= 1.0,
+                        Button::RightTrigger => self.scroll_dt.y += 1.0,
+                        _ => (),
+                    }
+
+                    match gamepad_binding(button) {
+                        Some(GamepadBinding::Key(key)) => self.set_gamepad_key(key, true),
+                        Some(GamepadBinding::Button(button)) => self.set_gamepad_button(button, true),
+                        None => (),
+                    }
+                },
+
+
+                EventType::ButtonReleased(button, _) => {
+                    match gamepad_binding(button) {
+                        Some(GamepadBinding::Key(key)) => self.set_gamepad_key(key, false),
+                        Some(GamepadBinding::Button(button)) => self.set_gamepad_button(button, false),
+                        None => (),
+                    }
+                },
+
+
+                EventType::AxisChanged(..) => {
+                    if left.length() > deadzone || right.length() > deadzone {
+                        self.last_device = InputDevice::Gamepad;
+                    }
+                },
+
+                _ => (),
+            }
+        }
+
+
+        let left = if left.length() > deadzone { left } else { Vec2::ZERO };
+        self.set_gamepad_key(KeyCode::KeyW, left.y > deadzone);
+        self.set_gamepad_key(KeyCode::KeyS, left.y < -deadzone);
+        self.set_gamepad_key(KeyCode::KeyD, left.x > deadzone);
+        self.set_gamepad_key(KeyCode::KeyA, left.x < -deadzone);
+
+        if right.length() > deadzone {
+            // stick up looks up while the mouse's y grows downwards
+            self.delta_mouse_pos += Vec2::new(right.x, -right.y) * GAMEPAD_LOOK_SPEED * dt;
+        }
+    }
+
+
+    fn set_gamepad_key(&mut self, key: KeyCode, is_down: bool) {
+        let key = PhysicalKey::Code(key);
+        if is_down {
+            if self.gamepad_keys.insert(key) {
+                self.just_pressed_key.push(key);
+            }
+        } else {
+            self.gamepad_keys.remove(&key);
+        }
+    }
+
+
+    fn set_gamepad_button(&mut self, button: MouseButton, is_down: bool) {
+        if is_down {
+            if self.gamepad_buttons.insert(button) {
+                self.just_pressed_button.push(button);
+            }
+        } else {
+            self.gamepad_buttons.remove(&button);
+        }
+    }
+
+
+    ///
+    /// Whichever of the keyboard & mouse or a gamepad was used last
+    ///
+    pub fn last_device(&self) -> InputDevice { self.last_device }
+
+
     pub fn update(&mut self) {
         self.just_pressed_key.clear();
         self.just_pressed_button.clear();
@@ -41,6 +172,7 @@ impl InputManager {
 
 
     pub fn set_pressed_key(&mut self, key: PhysicalKey) {
+        self.last_device = InputDevice::KeyboardMouse;
         self.down_keys.insert(key);
         self.just_pressed_key.push(key);
     }
@@ -52,6 +184,7 @@ impl InputManager {
 
 
     pub fn set_pressed_button(&mut self, button: MouseButton) {
+        self.last_device = InputDevice::KeyboardMouse;
         self.down_buttons.insert(button);
         self.just_pressed_button.push(button);
     }
@@ -63,6 +196,7 @@ impl InputManager {
 
 
     pub fn set_delta(&mut self, delta: Vec2) {
+        self.last_device = InputDevice::KeyboardMouse;
         self.delta_mouse_pos = delta;
     }
 
@@ -73,6 +207,7 @@ impl InputManager {
 
 
     pub fn scroll(&mut self, sdt: Vec2) {
+        self.last_device = InputDevice::KeyboardMouse;
         self.scroll_dt = sdt;
     }
 
@@ -82,7 +217,7 @@ impl InputManager {
 
     pub fn is_key_pressed(&self, key: KeyCode) -> bool {
         let key = PhysicalKey::Code(key);
-        self.down_keys.contains(&key)
+        self.down_keys.contains(&key) || self.gamepad_keys.contains(&key)
     }
 
 
@@ -93,7 +228,7 @@ impl InputManager {
 
 
     pub fn is_button_pressed(&self, button: MouseButton) -> bool {
-        self.down_buttons.contains(&button)
+        self.down_buttons.contains(&button) || self.gamepad_buttons.contains(&button)
     }
 
 
@@ -160,3 +295,21 @@ impl InputManager {
 
 
 
+
+
+enum GamepadBinding {
+    Key(KeyCode),
+    Button(MouseButton),
+}
+
+
+fn gamepad_binding(button: Button) -> Option<GamepadBinding> {
+    match button {
+        Button::South => Some(GamepadBinding::Key(KeyCode::Space)),
+        Button::West => Some(GamepadBinding::Key(KeyCode::KeyE)),
+        Button::East => Some(GamepadBinding::Key(KeyCode::Escape)),
+        Button::RightTrigger2 => Some(GamepadBinding::Button(MouseButton::Left)),
+        Button::LeftTrigger2 => Some(GamepadBinding::Button(MouseButton::Right)),
+        _ => None,
+    }
+}
@@ -4,9 +4,8 @@ use glam::{DVec3, IVec2, IVec3, Vec3};
 use image::{codecs::png::PngDecoder, ImageDecoder};
 use rand::random;
 use sti::{define_key, vec::KVec};
-use tracing::error;
 
-use crate::{constants::DROPPED_ITEM_SCALE, mesh::Mesh, renderer::{textures::{TextureAtlasBuilder, TextureId}}, structures::strct::StructureKind, voxel_world::voxel::Voxel, PhysicsBody, Tick};
+use crate::{constants::DROPPED_ITEM_SCALE, mesh::Mesh, renderer::{textures::{TextureAtlasBuilder, TextureId}}, structures::strct::{BeltTier, StructureKind}, voxel_world::voxel::Voxel, PhysicsBody, Tick};
 
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -37,6 +36,9 @@ pub enum ItemKind {
     CircuitBoard,
     Brick,
 
+    SpeedModule,
+    ProductivityModule,
+
     Radar,
 }
 
@@ -50,6 +52,8 @@ pub struct Assets {
     pub meshes: KVec<MeshIndex, Mesh>,
     pub cube: MeshIndex,
     pub block_outline_mesh: MeshIndex,
+    /// the models of belts fed from the side, by tier and side
+    belt_curves: HashMap<(BeltTier, i32), MeshIndex>,
 }
 
 
@@ -65,6 +69,23 @@ impl Item {
     pub fn with_amount(self, amount: u32) -> Item {
         Item::new(self.kind, amount)
     }
+
+
+    ///
+    /// How many more of the item fit on this stack, a stack
+    /// that's somehow past its max has no room left
+    ///
+    pub fn room_left(self) -> u32 {
+        self.room_left_in(u32::MAX)
+    }
+
+
+    ///
+    /// `room_left` for a stack in a slot that holds at most `max_amount`
+    ///
+    pub fn room_left_in(self, max_amount: u32) -> u32 {
+        max_amount.min(self.kind.max_stack_size()).saturating_sub(self.amount)
+    }
 }
 
 
@@ -72,6 +93,7 @@ impl ItemKind {
     pub const ALL : &[ItemKind] = &[
         ItemKind::Voxel(Voxel::Dirt),
         ItemKind::Voxel(Voxel::Stone),
+        ItemKind::Voxel(Voxel::Water),
         ItemKind::CopperOre,
         ItemKind::IronOre,
         ItemKind::Coal,
@@ -88,15 +110,26 @@ impl ItemKind {
         ItemKind::CircuitBoard,
         ItemKind::Brick,
 
+        ItemKind::SpeedModule,
+        ItemKind::ProductivityModule,
+
         ItemKind::Structure(StructureKind::Quarry),
+        ItemKind::Structure(StructureKind::LargeQuarry),
         ItemKind::Structure(StructureKind::Inserter),
+        ItemKind::Structure(StructureKind::LongInserter),
         ItemKind::Structure(StructureKind::Chest),
         ItemKind::Structure(StructureKind::Silo),
+        ItemKind::Structure(StructureKind::ProviderChest),
+        ItemKind::Structure(StructureKind::RequesterChest),
         ItemKind::Structure(StructureKind::Belt),
+        ItemKind::Structure(StructureKind::FastBelt),
+        ItemKind::Structure(StructureKind::ExpressBelt),
         ItemKind::Structure(StructureKind::Splitter),
         ItemKind::Structure(StructureKind::Assembler),
         ItemKind::Structure(StructureKind::Furnace),
         ItemKind::Structure(StructureKind::SteelFurnace),
+        ItemKind::Structure(StructureKind::Pump),
+        ItemKind::Structure(StructureKind::Pipe),
 
         ItemKind::Radar,
     ];
@@ -108,16 +141,25 @@ impl ItemKind {
             ItemKind::CopperOre => "copper_ore",
             ItemKind::IronOre => "iron_ore",
             ItemKind::Structure(StructureKind::Belt) => "belt",
+            ItemKind::Structure(StructureKind::FastBelt) => "fast_belt",
+            ItemKind::Structure(StructureKind::ExpressBelt) => "express_belt",
             ItemKind::Structure(StructureKind::Splitter) => "splitter",
             ItemKind::Structure(StructureKind::Inserter) => "inserter",
+            ItemKind::Structure(StructureKind::LongInserter) => "long_inserter",
             ItemKind::Structure(StructureKind::Chest) => "chest",
             ItemKind::Structure(StructureKind::Silo) => "silo",
+            ItemKind::Structure(StructureKind::ProviderChest) => "provider_chest",
+            ItemKind::Structure(StructureKind::RequesterChest) => "requester_chest",
             ItemKind::Structure(StructureKind::Quarry) => "quarry",
+            ItemKind::Structure(StructureKind::LargeQuarry) => "large_quarry",
             ItemKind::Structure(StructureKind::Assembler) => "assembler",
             ItemKind::Structure(StructureKind::Furnace) => "furnace",
             ItemKind::Structure(StructureKind::SteelFurnace) => "steel_furnace",
+            ItemKind::Structure(StructureKind::Pump) => "pump",
+            ItemKind::Structure(StructureKind::Pipe) => "pipe",
             ItemKind::Voxel(Voxel::Dirt) => "dirt_block",
             ItemKind::Voxel(Voxel::Stone) => "stone_block",
+            ItemKind::Voxel(Voxel::Water) => "water",
 
             ItemKind::IronPlate => "iron_plate",
             ItemKind::CopperPlate => "copper_plate",
@@ -131,6 +173,9 @@ impl ItemKind {
             ItemKind::CircuitBoard => "circuit_board",
             ItemKind::Brick => "brick",
 
+            ItemKind::SpeedModule => "speed_module",
+            ItemKind::ProductivityModule => "productivity_module",
+
             ItemKind::Radar => "radar",
 
             ItemKind::Voxel(_) => "invalid",
@@ -144,16 +189,25 @@ impl ItemKind {
             ItemKind::CopperOre => "Copper Ore",
             ItemKind::IronOre => "Iron Ore",
             ItemKind::Structure(StructureKind::Belt) => "§eBelt",
+            ItemKind::Structure(StructureKind::FastBelt) => "§eFast Belt",
+            ItemKind::Structure(StructureKind::ExpressBelt) => "§eExpress Belt",
             ItemKind::Structure(StructureKind::Splitter) => "§eSplitter",
             ItemKind::Structure(StructureKind::Inserter) => "§eInserter",
+            ItemKind::Structure(StructureKind::LongInserter) => "§eLong Inserter",
             ItemKind::Structure(StructureKind::Chest) => "§eChest",
             ItemKind::Structure(StructureKind::Silo) => "§eSilo",
+            ItemKind::Structure(StructureKind::ProviderChest) => "§eProvider Chest",
+            ItemKind::Structure(StructureKind::RequesterChest) => "§eRequester Chest",
             ItemKind::Structure(StructureKind::Quarry) => "§eQuarry",
+            ItemKind::Structure(StructureKind::LargeQuarry) => "§eLarge Quarry",
             ItemKind::Structure(StructureKind::Assembler) => "§eAssembler",
             ItemKind::Structure(StructureKind::Furnace) => "§eFurnace",
             ItemKind::Structure(StructureKind::SteelFurnace) => "§eSteel Furnace",
+            ItemKind::Structure(StructureKind::Pump) => "§ePump",
+            ItemKind::Structure(StructureKind::Pipe) => "§ePipe",
             ItemKind::Voxel(Voxel::Dirt) => "Dirt Block",
             ItemKind::Voxel(Voxel::Stone) => "Stone Block",
+            ItemKind::Voxel(Voxel::Water) => "§bWater",
 
             ItemKind::IronPlate => "Iron Plate",
             ItemKind::CopperPlate => "Copper Plate",
@@ -167,6 +221,9 @@ impl ItemKind {
             ItemKind::CircuitBoard => "Circuit Board",
             ItemKind::Brick => "Brick",
 
+            ItemKind::SpeedModule => "§bSpeed Module",
+            ItemKind::ProductivityModule => "§aProductivity Module",
+
             ItemKind::Radar => "§dRadar",
 
             ItemKind::Voxel(_) => "invalid",
@@ -175,8 +232,20 @@ impl ItemKind {
 
 
 
+    ///
+    /// The most of the item a single slot can hold, the player,
+    /// structures and dropped items all go through this
+    ///
     pub fn max_stack_size(self) -> u32 {
-        100
+        match self {
+            ItemKind::Structure(_) => 50,
+
+              ItemKind::SpeedModule
+            | ItemKind::ProductivityModule
+            | ItemKind::Radar => 20,
+
+            _ => 100,
+        }
     }
 
 
@@ -188,6 +257,11 @@ impl ItemKind {
     }
 
 
+    pub fn is_module(self) -> bool {
+        matches!(self, ItemKind::SpeedModule | ItemKind::ProductivityModule)
+    }
+
+
     pub fn as_structure(self) -> Option<StructureKind> {
         match self {
             ItemKind::Structure(structure) => Some(structure),
@@ -198,7 +272,11 @@ impl ItemKind {
 
 
 impl Assets {
-    pub fn new(device: &wgpu::Device, texture_atlas: &mut TextureAtlasBuilder) -> Self {
+    ///
+    /// Loads every item's texture and model, anything that can't be
+    /// loaded falls back to a white cube and gets added to `errors`
+    ///
+    pub fn new(device: &wgpu::Device, texture_atlas: &mut TextureAtlasBuilder, errors: &mut Vec<String>) -> Self {
         let textures_dir = Path::new("assets/textures");
 
         let mut textures = HashMap::with_capacity(ItemKind::ALL.len());
@@ -219,20 +297,20 @@ impl Assets {
             // load texture
             let path = textures_dir.join(item.to_string()).with_added_extension("png");
 
-            let texture = match File::open(&path) {
-                Ok(buf) => {
-                    let buf = BufReader::new(buf);
-                    let asset = PngDecoder::new(buf).unwrap();
-                    let dims = asset.dimensions();
-                    let dims = IVec2::new(dims.0 as _, dims.1 as _);
-
-                    let mut data = vec![0; asset.total_bytes() as usize];
-                    asset.read_image(&mut data).unwrap();
-
+            let texture = match load_png(&path) {
+                Ok((dims, data)) => {
                     let id = texture_atlas.register(dims, &data);
 
                     if let ItemKind::Structure(kind) = item {
-                        models.insert(item, meshes.push(kind.create_mesh(device)));
+                        let mesh = match kind.create_mesh(device) {
+                            Ok(mesh) => meshes.push(mesh),
+                            Err(e) => {
+                                errors.push(e);
+                                white_mesh.clone()
+                            },
+                        };
+
+                        models.insert(item, mesh);
                     } else {
                         let mut vertices = vec![];
                         let mut indices = vec![];
@@ -255,8 +333,8 @@ impl Assets {
                     id
                 }
 
-                Err(_) => {
-                    error!("unable to find a texture for '{}'", item.to_string());
+                Err(e) => {
+                    errors.push(format!("unable to load the texture for '{}': {e}", item.to_string()));
                     models.insert(item, white_mesh.clone());
                     white_texture
                 },
@@ -266,8 +344,34 @@ impl Assets {
         }
 
 
+        let block_outline_mesh = match Mesh::from_vmf(device, "assets/models/block_outline.vmf") {
+            Ok(mesh) => meshes.push(mesh),
+            Err(e) => {
+                errors.push(e);
+                white_mesh.clone()
+            },
+        };
+
+
+        let mut belt_curves = HashMap::new();
+        for tier in BeltTier::ALL {
+            for curve in [-1, 1] {
+                let mesh = match tier.create_curve_mesh(device, curve) {
+                    Ok(mesh) => meshes.push(mesh),
+                    Err(e) => {
+                        errors.push(e);
+                        white_mesh.clone()
+                    },
+                };
+
+                belt_curves.insert((tier, curve), mesh);
+            }
+        }
+
+
         Self {
-            block_outline_mesh: meshes.push(Mesh::from_vmf(device, "assets/models/block_outline.vmf")),
+            block_outline_mesh,
+            belt_curves,
             models,
             textures,
             cube: white_mesh,
@@ -280,12 +384,33 @@ impl Assets {
         *self.models.get(&kind).unwrap()
     }
 
+    pub fn get_belt_curve(&self, tier: BeltTier, curve: i32) -> MeshIndex {
+        *self.belt_curves.get(&(tier, curve)).unwrap()
+    }
+
     pub fn get_ico(&self, kind: ItemKind) -> TextureId {
         *self.textures.get(&kind).unwrap()
     }
 }
 
 
+///
+/// Reads the png at `path` into its dimensions and raw pixel data
+///
+fn load_png(path: &Path) -> Result<(IVec2, Vec<u8>), String> {
+    let file = File::open(path).map_err(|e| format!("'{}': {e}", path.display()))?;
+    let asset = PngDecoder::new(BufReader::new(file)).map_err(|e| format!("'{}': {e}", path.display()))?;
+
+    let dims = asset.dimensions();
+    let dims = IVec2::new(dims.0 as _, dims.1 as _);
+
+    let mut data = vec![0; asset.total_bytes() as usize];
+    asset.read_image(&mut data).map_err(|e| format!("'{}': {e}", path.display()))?;
+
+    Ok((dims, data))
+}
+
+
 impl core::fmt::Debug for Item {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?} x{}", self.kind, self.amount)
@@ -0,0 +1,408 @@
+//!
+//! The engine behind the factory game, `main.rs` is only a thin
+//! binary around `app::App`.
+//!
+//! The public API is what's re-exported here along with the `game`,
+//! `structures`, `voxel_world`, `items` and `crafting` modules. The
+//! rest are public so the binary and the tests can get at them but
+//! are internal and hidden from the docs, expect them to change.
+//!
+
+#![feature(portable_simd)]
+#![feature(btree_cursors)]
+#![feature(str_as_str)]
+#![feature(path_add_extension)]
+#![feature(if_let_guard)]
+#![feature(generic_arg_infer)]
+#![feature(iter_array_chunks)]
+#![feature(seek_stream_len)]
+
+// public api
+pub mod app;
+pub mod game;
+pub mod structures;
+pub mod voxel_world;
+pub mod items;
+pub mod crafting;
+pub mod directions;
+pub mod constants;
+
+// internal
+#[doc(hidden)] pub mod mesh;
+#[doc(hidden)] pub mod quad;
+#[doc(hidden)] pub mod renderer;
+#[doc(hidden)] pub mod input;
+#[doc(hidden)] pub mod gen_map;
+#[doc(hidden)] pub mod ui;
+#[doc(hidden)] pub mod save_system;
+#[doc(hidden)] pub mod commands;
+#[doc(hidden)] pub mod perlin;
+#[doc(hidden)] pub mod frustum;
+#[doc(hidden)] pub mod buddy_allocator;
+#[doc(hidden)] pub mod free_list;
+#[doc(hidden)] pub mod octree;
+#[doc(hidden)] pub mod entities;
+#[doc(hidden)] pub mod ambience;
+#[doc(hidden)] pub mod minimap;
+#[doc(hidden)] pub mod particles;
+
+pub use app::App;
+pub use game::Game;
+
+use std::{f32::consts::{PI, TAU}, ops};
+
+use constants::{PLAYER_HOTBAR_SIZE, PLAYER_MAX_HEALTH, PLAYER_REGEN_DELAY, PLAYER_REGEN_PER_SECOND};
+use directions::CardinalDirection;
+use sti::define_key;
+use glam::{DVec3, IVec3, Mat4, Vec3};
+use items::{Item, ItemKind};
+
+use crate::structures::strct::{StructureConfig, StructureKind};
+
+
+
+define_key!(EntityId(u32));
+
+
+
+#[derive(Clone, Copy, Debug)]
+pub struct PhysicsBody {
+    position: DVec3,
+    velocity: Vec3,
+
+    aabb_dims: Vec3,
+    /// if set the body ignores walkable structures like belts
+    pass_through_walkable: bool,
+    has_gravity: bool,
+    /// set by `move_physics_body` when the body
+    /// came to rest on something below it
+    on_ground: bool,
+    /// set by `move_physics_body` while the body is swimming
+    in_water: bool,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub struct Tick(u32);
+
+
+impl Tick {
+    pub const NEVER : Tick = Tick(0);
+
+
+    pub fn new(num: u32) -> Self {
+        Self(num)
+    }
+
+    
+    pub fn initial() -> Self { Self::new(1) }
+
+
+    pub fn inc(mut self) -> Self {
+        self.0 += 1;
+        self
+    }
+
+
+    pub fn elapsed_since(self, initial: Tick) -> Tick {
+        Tick(initial.0 - self.0)
+    }
+
+
+    pub fn u32(self) -> u32 { self.0 }
+
+}
+
+
+impl ops::Add for Tick {
+    type Output = Tick;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0 + rhs.0)
+    }
+}
+
+
+impl ops::Sub for Tick {
+    type Output = Tick;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0 - rhs.0)
+    }
+}
+
+
+pub struct Player {
+    body: PhysicsBody,
+    inventory: [Option<Item>; 30],
+    hand: usize,
+    hotbar: usize,
+    mining_progress: Option<u32>,
+    interact_delay: f32,
+    health: f32,
+    /// seconds since the player last took damage,
+    /// health only regenerates after a while
+    time_since_damage: f32,
+    /// creative players fly, break blocks instantly
+    /// and don't use up items when placing
+    creative: bool,
+    /// settings picked up with the pipette, applied to
+    /// the next structure of the same kind that's placed
+    pipette: Option<(StructureKind, StructureConfig)>,
+    /// indices into `RECIPES` the player pinned
+    /// to the front of the crafting menu
+    pinned_recipes: Vec<usize>,
+
+    // this is used to rotate a structure's preview
+    preview_rotation_offset: u8,
+    /// the last block placed while holding right click,
+    /// dragging from it places a line of blocks
+    drag_from: Option<IVec3>,
+    /// the last belt the player placed, the next
+    /// one placed next to it carries on from it
+    last_belt: Option<IVec3>,
+    /// how far the player has walked on the ground,
+    /// drives the bobbing of the item in their hand
+    walk_cycle: f32,
+    /// seconds left of the "Inventory Full" warning, kept
+    /// up while there are items nearby that don't fit
+    inventory_full_time: f32,
+}
+
+
+impl Player {
+    pub fn set_creative(&mut self, creative: bool) {
+        self.creative = creative;
+        self.body.has_gravity = !creative;
+        self.body.velocity.y = 0.0;
+        self.mining_progress = None;
+    }
+
+
+    pub fn damage(&mut self, amount: f32) {
+        self.health = (self.health - amount).max(0.0);
+        self.time_since_damage = 0.0;
+    }
+
+
+    pub fn regenerate(&mut self, delta_time: f32) {
+        self.time_since_damage += delta_time;
+        if self.time_since_damage < PLAYER_REGEN_DELAY { return }
+
+        self.health = (self.health + PLAYER_REGEN_PER_SECOND * delta_time).min(PLAYER_MAX_HEALTH);
+    }
+
+
+    ///
+    /// Whether all of `item` fits in the inventory, the
+    /// same room `add_item` would put it into
+    ///
+    pub fn can_give(&self, item: Item) -> bool {
+        let room = self.inventory.iter()
+            .map(|slot| match slot {
+                Some(inv_item) if inv_item.kind == item.kind => inv_item.room_left(),
+                Some(_) => 0,
+                None => item.kind.max_stack_size(),
+            })
+            .fold(0u32, u32::saturating_add);
+
+        room >= item.amount
+    }
+
+
+    ///
+    /// Fills up existing stacks of `item` first and then empty
+    /// slots, returns whatever didn't fit in the inventory
+    ///
+    pub fn add_item(&mut self, mut item: Item) -> Option<Item> {
+        let (before, now) = self.inventory.split_at_mut(self.hotbar * PLAYER_HOTBAR_SIZE);
+        for slot in now.iter_mut().chain(before.iter_mut()) {
+            let Some(inv_item) = slot
+            else { continue };
+
+            if inv_item.kind != item.kind { continue }
+
+            let addition = item.amount.min(inv_item.room_left());
+            inv_item.amount += addition;
+            item.amount -= addition;
+            if item.amount == 0 {
+                return None;
+            }
+        }
+
+
+        for slot in now.iter_mut().chain(before.iter_mut()) {
+            if slot.is_some() { continue }
+
+            let addition = item.amount.min(item.kind.max_stack_size());
+
+            let mut slot_item = item;
+            slot_item.amount = addition;
+            *slot = Some(slot_item);
+
+            item.amount -= addition;
+
+            if item.amount == 0 {
+                return None;
+            }
+        }
+
+        Some(item)
+    }
+
+
+    ///
+    /// Puts a stack of `kind` into the player's hand, switching to
+    /// it if it's on the current hotbar row and swapping it in otherwise
+    ///
+    /// Returns false if the player doesn't have any
+    ///
+    pub fn select_item(&mut self, kind: ItemKind) -> bool {
+        let row = self.hotbar * PLAYER_HOTBAR_SIZE;
+        let on_hotbar = (0..PLAYER_HOTBAR_SIZE)
+            .find(|&i| self.inventory[row + i].is_some_and(|x| x.kind == kind));
+
+        if let Some(hand) = on_hotbar {
+            self.hand = hand;
+            return true;
+        }
+
+        let Some(index) = self.inventory.iter().position(|x| x.is_some_and(|x| x.kind == kind))
+        else { return false };
+
+        self.inventory.swap(index, self.hand_index());
+        true
+    }
+
+
+    ///
+    /// Puts a full stack of `kind` in an empty slot and selects it,
+    /// the hand if it's empty and otherwise the first empty slot on
+    /// the hotbar, then the rest of the inventory. Returns false if
+    /// there's nowhere to put it
+    ///
+    pub fn give_and_select(&mut self, kind: ItemKind) -> bool {
+        let row = self.hotbar * PLAYER_HOTBAR_SIZE;
+        let hand = self.hand_index();
+
+        let slot = if self.inventory[hand].is_none() { Some(hand) }
+                   else { (row..row + PLAYER_HOTBAR_SIZE).chain(0..self.inventory.len()).find(|&i| self.inventory[i].is_none()) };
+
+        let Some(slot) = slot
+        else { return false };
+
+        self.inventory[slot] = Some(Item::new(kind, kind.max_stack_size()));
+        self.select_item(kind)
+    }
+
+
+    pub fn hand_index(&self) -> usize {
+        self.hotbar * PLAYER_HOTBAR_SIZE + self.hand
+    }
+
+
+    pub fn take_item(&mut self, index: usize, amount: u32) -> Option<Item> {
+        let slot = self.inventory.get_mut(index)?.as_mut()?;
+
+        if slot.amount < amount {
+            return None;
+        }
+
+        slot.amount -= amount;
+        let slot = *slot;
+        if slot.amount == 0 {
+            self.inventory[index] = None;
+        }
+
+
+        Some(Item { amount, kind: slot.kind })
+    }
+}
+
+
+#[derive(Debug)]
+pub struct Camera {
+    position: DVec3,
+    front: Vec3,
+    up: Vec3,
+
+    pitch: f32,
+    yaw: f32,
+
+    fov: f32,
+    /// the fov `fov` is easing towards
+    target_fov: f32,
+    aspect_ratio: f32,
+    near: f32,
+    far: f32,
+
+}
+
+
+impl Camera {
+    pub fn perspective_matrix(&self) -> Mat4 {
+        glam::Mat4::perspective_rh_gl(self.fov, self.aspect_ratio, self.near, self.far)
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_to_rh(Vec3::ZERO, self.front, self.up)
+    }
+
+
+    pub fn compass_direction(&self) -> CardinalDirection {
+        let mut angle = self.yaw % TAU;
+
+        if angle < 0.0 { angle += TAU }
+
+        let angle = angle;
+        let sector = (angle / (PI/2.0)).round() as i32 % 4;
+
+        match sector {
+            0 => CardinalDirection::South,
+            1 => CardinalDirection::West,
+            2 => CardinalDirection::North,
+            3 => CardinalDirection::East,
+            _ => unreachable!(),
+        }
+    }
+
+
+    pub fn right(&self) -> Vec3 {
+        self.up.cross(self.front)
+    }
+}
+
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h_ = h / 60.0;
+    let x = c * (1.0 - (h_ % 2.0 - 1.0).abs());
+    
+    let (r1, g1, b1) = match h_ as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    let m = l - c / 2.0;
+    let (r, g, b) = (r1 + m, g1 + m, b1 + m);
+
+    let to_255 = |v: f64| (v * 255.0).round().clamp(0.0, 255.0) as u8;
+
+    (to_255(r), to_255(g), to_255(b))
+}
+
+fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+// Usage
+fn hsl_to_hex(h: f64, s: f64, l: f64) -> String {
+    let (r, g, b) = hsl_to_rgb(h, s, l);
+    rgb_to_hex(r, g, b)
+}
+
+
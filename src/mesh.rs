@@ -20,23 +20,31 @@ pub struct Mesh {
     pub vertices: Buffer,
     pub indices : Buffer,
     pub index_count: u32,
+
+    /// the corners of the mesh's bounding box, before it
+    /// gets moved around by a `MeshInstance`
+    pub min: Vec3,
+    pub max: Vec3,
 }
 
 
 impl Mesh {
-    pub fn from_vmf(device: &wgpu::Device, path: &str) -> Mesh {
+    pub fn from_vmf(device: &wgpu::Device, path: &str) -> Result<Mesh, String> {
         if !path.ends_with(".vmf") {
             warn!("mesh path should have the extension .vmf");
         }
 
-        let Ok(mut file) = std::fs::File::open(path)
-        else { panic!("mesh: no such file as {path}") };
+        let mut file = std::fs::File::open(path)
+            .map_err(|e| format!("unable to open the mesh '{path}': {e}"))?;
         
         let mut data = Vec::with_capacity(file.stream_len().unwrap_or(0) as _);
-        file.read_to_end(&mut data).unwrap();
+        file.read_to_end(&mut data)
+            .map_err(|e| format!("unable to read the mesh '{path}': {e}"))?;
+
+        let model = VoxelMesh::decode(&data)
+            .map_err(|_| format!("'{path}' isn't a valid mesh"))?;
 
-        let model = VoxelMesh::decode(&data).unwrap();
-        Mesh::new(device, &model.vertices, &model.indices)
+        Ok(Mesh::new(device, &model.vertices, &model.indices))
     }
 
 
@@ -56,11 +64,51 @@ impl Mesh {
         });
 
 
+        // the position is the first attribute of a vertex, see `vertex_desc`
+        let mut min = Vec3::MAX;
+        let mut max = Vec3::MIN;
+        let bytes : &[u8] = bytemuck::cast_slice(vertices);
+        for vertex in bytes.chunks_exact(size_of::<voxel_mesher::Vertex>()) {
+            let pos = Vec3::from_array(bytemuck::pod_read_unaligned(&vertex[..12]));
+            min = min.min(pos);
+            max = max.max(pos);
+        }
+
+        if vertices.is_empty() {
+            min = Vec3::ZERO;
+            max = Vec3::ZERO;
+        }
+
+
         Self {
             vertices: vertices_buffer,
             indices: indices_buffer,
             index_count: indices.len() as _,
+            min,
+            max,
+        }
+    }
+
+
+    ///
+    /// The bounding box of the mesh once it's transformed by `model`
+    ///
+    pub fn bounds(&self, model: Mat4) -> (Vec3, Vec3) {
+        let mut min = Vec3::MAX;
+        let mut max = Vec3::MIN;
+        for corner in 0..8 {
+            let corner = Vec3::select(
+                glam::BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0),
+                self.max,
+                self.min,
+            );
+
+            let corner = model.transform_point3(corner);
+            min = min.min(corner);
+            max = max.max(corner);
         }
+
+        (min, max)
     }
 }
 
@@ -0,0 +1,178 @@
+use glam::{IVec3, Vec2, Vec4};
+
+use crate::{constants::{CHUNK_SIZE_I32, COLOUR_DARK_GREY, COLOUR_SCREEN_DIM, MINIMAP_FULL_RADIUS, MINIMAP_RADIUS, MINIMAP_RESOLUTION, UI_MAP_PLAYER_COLOUR, UI_MAP_STRUCT_COLOUR, UI_MINIMAP_PADDING, UI_MINIMAP_SIZE}, renderer::{textures::TextureId, Renderer}, structures::Structures, voxel_world::{split_world_pos, voxel::Voxel, VoxelWorld}, Camera};
+
+
+///
+/// A top-down view of the loaded world around the player.
+/// Sampling the surface is too slow to do every frame so
+/// the colours get cached and only refreshed every few ticks,
+/// each refresh gets uploaded into a texture the next frame
+///
+pub struct Minimap {
+    /// `MINIMAP_RESOLUTION` rows of `MINIMAP_RESOLUTION` columns,
+    /// north at the top, `None` if nothing is loaded there
+    cells: Vec<Option<Vec4>>,
+    /// the block the cells were sampled around
+    centre: IVec3,
+    /// whether the map covers the whole screen instead of a corner
+    pub full_screen: bool,
+    /// `Some` once the cells have been uploaded and
+    /// haven't changed since
+    texture: Option<TextureId>,
+}
+
+
+impl Minimap {
+    pub fn new() -> Self {
+        Self {
+            cells: vec![None; MINIMAP_RESOLUTION * MINIMAP_RESOLUTION],
+            centre: IVec3::ZERO,
+            full_screen: false,
+            texture: None,
+        }
+    }
+
+
+    ///
+    /// How many blocks the map reaches in each direction
+    ///
+    pub fn radius(&self) -> i32 {
+        if self.full_screen { MINIMAP_FULL_RADIUS }
+        else { MINIMAP_RADIUS }
+    }
+
+
+    ///
+    /// How many blocks a single cell covers
+    ///
+    fn step(&self) -> i32 {
+        (self.radius() * 2 / MINIMAP_RESOLUTION as i32).max(1)
+    }
+
+
+    pub fn update(&mut self, world: &mut VoxelWorld, centre: IVec3) {
+        self.centre = centre;
+
+        let radius = self.radius();
+        let step = self.step();
+
+        for z in 0..MINIMAP_RESOLUTION {
+            for x in 0..MINIMAP_RESOLUTION {
+                let column_x = centre.x - radius + x as i32 * step;
+                let column_z = centre.z - radius + z as i32 * step;
+
+                self.cells[z * MINIMAP_RESOLUTION + x] = surface(world, column_x, column_z)
+                    .map(|voxel| voxel.colour());
+            }
+        }
+
+        self.texture = None;
+    }
+
+
+    pub fn render(&mut self, renderer: &mut Renderer, structures: &Structures, camera: &Camera) {
+        let window = renderer.window_size();
+
+        let (pos, size) = if self.full_screen {
+            let size = window.min_element() - UI_MINIMAP_PADDING * 2.0;
+            renderer.draw_rect(Vec2::ZERO, window, COLOUR_SCREEN_DIM);
+            ((window - size) * 0.5, size)
+        } else {
+            let pos = Vec2::new(window.x - UI_MINIMAP_SIZE - UI_MINIMAP_PADDING, UI_MINIMAP_PADDING);
+            (pos, UI_MINIMAP_SIZE)
+        };
+
+        renderer.draw_rect(pos, Vec2::splat(size), COLOUR_DARK_GREY);
+
+
+        let texture = *self.texture.get_or_insert_with(|| {
+            // unloaded cells are left transparent
+            let pixels : Vec<u8> = self.cells.iter()
+                .flat_map(|cell| match cell {
+                    Some(colour) => [to_srgb(colour.x), to_srgb(colour.y), to_srgb(colour.z), (colour.w.clamp(0.0, 1.0) * 255.0) as u8],
+                    None => [0; 4],
+                })
+                .collect();
+
+            renderer.upload_minimap(&pixels)
+        });
+
+        renderer.draw_tex_rect(pos, Vec2::splat(size), texture, Vec4::ONE);
+
+        let cell_size = size / MINIMAP_RESOLUTION as f32;
+
+
+        let radius = self.radius();
+        let step = self.step() as f32;
+        let to_map = |offset: Vec2| pos + (offset + radius as f32) / step * cell_size;
+        let dot_size = cell_size.max(3.0);
+
+        for (_, structure) in structures.structs.iter() {
+            let offset = structure.position - self.centre;
+            if offset.x.abs() >= radius || offset.z.abs() >= radius { continue }
+
+            renderer.draw_rect(
+                to_map(Vec2::new(offset.x as f32, offset.z as f32)) - dot_size * 0.5,
+                Vec2::splat(dot_size),
+                UI_MAP_STRUCT_COLOUR,
+            );
+        }
+
+
+        // the player keeps moving in between refreshes
+        // so they're placed by the camera and not the centre
+        let offset = camera.position.as_vec3() - self.centre.as_vec3();
+        let offset = Vec2::new(offset.x, offset.z)
+            .clamp(Vec2::splat(-radius as f32), Vec2::splat(radius as f32));
+        let player = to_map(offset);
+
+        let facing = camera.compass_direction().as_ivec3();
+        let facing = Vec2::new(facing.x as f32, facing.z as f32);
+
+        let player_size = dot_size * 2.0;
+        renderer.draw_rect(player - player_size * 0.5, Vec2::splat(player_size), UI_MAP_PLAYER_COLOUR);
+        renderer.draw_rect(
+            player + facing * player_size - dot_size * 0.5,
+            Vec2::splat(dot_size),
+            UI_MAP_PLAYER_COLOUR,
+        );
+    }
+}
+
+
+///
+/// `draw_rect` colours are linear but the texture is srgb
+///
+fn to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 { c * 12.92 }
+            else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (c * 255.0).round() as u8
+}
+
+
+///
+/// The topmost non-air voxel of the column at `x`, `z`,
+/// chunks that aren't loaded are skipped over
+///
+fn surface(world: &mut VoxelWorld, x: i32, z: i32) -> Option<Voxel> {
+    let limits = world.chunker.limits;
+    let top = (limits.max - 1).div_euclid(CHUNK_SIZE_I32);
+    let bottom = limits.min.div_euclid(CHUNK_SIZE_I32);
+
+    for chunk_y in (bottom..=top).rev() {
+        let (chunk_pos, local) = split_world_pos(IVec3::new(x, chunk_y * CHUNK_SIZE_I32, z));
+        let Some(chunk) = world.chunker.get_chunk(chunk_pos)
+        else { continue };
+
+        if chunk.data.is_none() { continue }
+
+        for y in (0..CHUNK_SIZE_I32).rev() {
+            let voxel = chunk.get(local.with_y(y));
+            if !voxel.is_air() { return Some(voxel) }
+        }
+    }
+
+    None
+}
@@ -1,4 +1,4 @@
-use std::{cell::Cell, num::{NonZeroU16, NonZeroU32}, rc::Rc};
+use std::{cell::Cell, collections::HashMap, num::{NonZeroU16, NonZeroU32}, rc::Rc};
 
 use glam::{DVec3, IVec3, UVec3};
 use rand::seq::IndexedRandom;
@@ -13,13 +13,30 @@ use crate::{constants::{CHUNK_SIZE_I32, QUAD_VERTICES, REGION_SIZE, RENDER_DISTA
 pub struct MeshOctree {
     pub nodes: KVec<u16, Node>,
     first_free: NodeId,
+    /// every leaf's draw args so `render` doesn't have to walk
+    /// the tree every frame, in no particular order
+    draws: Vec<ChunkDraw>,
+    /// where each leaf's entry is in `draws`
+    draw_slots: HashMap<NodeId, usize>,
+    /// leaves handed out through `get_mut` since the last `render`,
+    /// their entries get rebuilt before drawing
+    dirty: Vec<NodeId>,
 }
 
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct NodeId(NonZeroU16);
 
 
+#[derive(Debug)]
+struct ChunkDraw {
+    node: NodeId,
+    pos: ChunkPos,
+    mesh: [Option<DrawIndirectArgs>; 12],
+    lod: Option<[Option<DrawIndirectArgs>; 12]>,
+}
+
+
 #[derive(Debug)]
 pub enum Node {
     Internal([NodeId; 8]),
@@ -29,7 +46,11 @@ pub enum Node {
 
 #[derive(Debug)]
 pub struct Leaf {
-    pub mesh: [Option<ChunkFaceMesh>; 6]
+    /// the 6 opaque faces followed by the 6 water faces
+    pub mesh: [Option<ChunkFaceMesh>; 12],
+    /// a coarser mesh drawn instead of `mesh` past the lod distance,
+    /// only built once the chunk is far enough away to need it
+    pub lod: Option<[Option<ChunkFaceMesh>; 12]>,
 }
 
 
@@ -87,17 +108,6 @@ impl MeshOctree {
     }
 
 
-    #[inline]
-    fn child_idx_to_delta(idx: usize, height: u32) -> ChunkPos {
-        let idx = idx as u32;
-        return
-            ChunkPos(UVec3::new
-            (((idx >> 0) & 1) << height,
-             ((idx >> 1) & 1) << height,
-             ((idx >> 2) & 1) << height));
-    }
-
-
     pub fn new() -> MeshOctree {
         let mut nodes = KVec::new();
         nodes.push(Node::Internal([NodeId::INVALID; 8]));
@@ -105,6 +115,9 @@ impl MeshOctree {
         return MeshOctree {
             nodes,
             first_free: NodeId::INVALID,
+            draws: vec![],
+            draw_slots: HashMap::new(),
+            dirty: vec![],
         };
     }
 
@@ -129,6 +142,7 @@ impl MeshOctree {
             else {
                 let child_id = children[child_idx];
                 if child_id != NodeId::INVALID {
+                    this.forget_draw(child_id);
                     this.free(child_id);
                 }
 
@@ -140,7 +154,18 @@ impl MeshOctree {
         }
 
 
-        rec(self, chunk_pos, 0, Self::HEIGHT, Box::new(mesh))
+        let node = rec(self, chunk_pos, 0, Self::HEIGHT, Box::new(mesh));
+        let leaf = self.get(node);
+        let draw = ChunkDraw {
+            node,
+            pos: chunk_pos,
+            mesh: draw_args(&leaf.mesh),
+            lod: leaf.lod.as_ref().map(draw_args),
+        };
+
+        self.draw_slots.insert(node, self.draws.len());
+        self.draws.push(draw);
+        node
     }
 
     pub fn remove(&mut self, chunk_pos: ChunkPos) {
@@ -178,6 +203,7 @@ impl MeshOctree {
                 let child_id = children[child_idx];
                 if child_id != NodeId::INVALID {
                     assert!(matches!(this.nodes[child_id.0.get()], Node::Leaf(_)));
+                    this.forget_draw(child_id);
                     this.free(child_id);
                     return true;
                 }
@@ -193,110 +219,115 @@ impl MeshOctree {
     }
 
 
+    ///
+    /// Pushes the draw args of every chunk within `rd` that's in
+    /// view into `buffer`, or `water_buffer` for water faces. Chunks
+    /// past `lod_distance` without a coarse mesh yet get pushed to
+    /// `lod_buffer` so one gets built
+    ///
     pub fn render(
-        &self, pos0: ChunkPos, region: RegionPos,
+        &mut self, region: RegionPos,
         player_chunk: WorldChunkPos, camera: DVec3,
         frustum: &Frustum, buffer: &mut Vec<DrawIndirectArgs>,
-        remesh_buffer: &mut Vec<WorldChunkPos>, rd: i32,
+        water_buffer: &mut Vec<DrawIndirectArgs>,
+        rd: i32,
+        lod_buffer: &mut Vec<WorldChunkPos>, lod_distance: i32,
         counter: &mut u32)
     {
+        self.rebuild_dirty_draws();
 
-        fn rec(
-            this: &MeshOctree, pos0: ChunkPos, at: u16, height: u32,
-            region: RegionPos, player_chunk: WorldChunkPos, camera: DVec3,
-            frustum: &Frustum, buffer: &mut Vec<DrawIndirectArgs>,
-            remesh_buffer: &mut Vec<WorldChunkPos>, rd: i32, counter: &mut u32,
-        ) {
+        let region_pos = region.0 * REGION_SIZE as i32;
 
-            let chunk_pos = (region.0 * REGION_SIZE as i32) + pos0.0.as_ivec3();
+        let min = region_pos * CHUNK_SIZE_I32;
+        let max = (region_pos + IVec3::splat(Self::SIZE as i32)) * CHUNK_SIZE_I32;
 
-            let size = 2i32.pow(height);
-            let min = chunk_pos * CHUNK_SIZE_I32;
-            let max = (chunk_pos + IVec3::splat(size)) * CHUNK_SIZE_I32;
+        let min = (min.as_dvec3() - camera).as_vec3();
+        let max = (max.as_dvec3() - camera).as_vec3();
 
+        if !frustum.is_box_visible(min, max) {
+            return;
+        }
 
-            let min = (min.as_dvec3() - camera).as_vec3();
-            let max = (max.as_dvec3() - camera).as_vec3();
 
-            let is_visible = frustum.is_box_visible(min, max);
-            if !is_visible {
-                return;
+        for draw in &self.draws {
+            let chunk_pos = region_pos + draw.pos.0.as_ivec3();
+            let offset = chunk_pos - player_chunk.0;
+
+            if offset.length_squared() > rd*rd {
+                continue;
             }
 
+            let min = chunk_pos * CHUNK_SIZE_I32;
+            let max = (chunk_pos + IVec3::ONE) * CHUNK_SIZE_I32;
+            
+            let min = (min.as_dvec3() - camera).as_vec3();
+            let max = (max.as_dvec3() - camera).as_vec3();
 
-            if height > 0 {
-                let children = this.nodes[at].internal();
-                for idx in 0..8 {
-                    let child_id = children[idx];
-                    if child_id != NodeId::INVALID {
-                        let d = MeshOctree::child_idx_to_delta(idx, height - 1);
-                        rec(
-                            this, ChunkPos(pos0.0 + d.0), child_id.0.get(),
-                            height - 1, region, player_chunk, camera,
-                            frustum, buffer, remesh_buffer, rd, counter
-                        );
-                    }
-                }
+            if !frustum.is_box_visible(min, max) {
+                continue;
             }
-            else {
-                let leaf = this.nodes[at].leaf();
-                let chunk_pos = (region.0 * REGION_SIZE as i32) + pos0.0.as_ivec3();
-                let offset = chunk_pos - player_chunk.0;
 
-                if offset.length_squared() > rd*rd {
-                    return;
-                }
 
-                let min = chunk_pos * CHUNK_SIZE_I32;
-                let max = (chunk_pos + IVec3::ONE) * CHUNK_SIZE_I32;
-                
-                let min = (min.as_dvec3() - camera).as_vec3();
-                let max = (max.as_dvec3() - camera).as_vec3();
+            let dir_from_camera = offset.as_vec3().normalize();
 
-                if !frustum.is_box_visible(min, max) {
-                    return;
+            // until the coarse mesh is ready the full one
+            // keeps getting drawn so nothing pops out of view
+            let meshes = if offset.length_squared() > lod_distance*lod_distance {
+                match &draw.lod {
+                    Some(lod) => lod,
+                    None => {
+                        lod_buffer.push(WorldChunkPos(chunk_pos));
+                        &draw.mesh
+                    },
+                }
+            } else { &draw.mesh };
+
+            for (i, args) in meshes.iter().enumerate() {
+                let Some(args) = args
+                else { continue };
+
+                // water faces can be seen from both sides
+                // so they can't be culled by their normal
+                let is_water = i >= 6;
+                let normal = Direction::NORMALS[i % 6];
+                if !is_water && dir_from_camera.dot(normal) > 0.0 {
+                    continue
                 }
 
-
-                let dir_from_camera = offset.as_vec3().normalize();
-
-                for (i, mesh) in leaf.mesh.iter().enumerate() {
-                    let Some(mesh) = mesh
-                    else { continue };
-
-                    if mesh.index_count == 0 {
-                        warn!("an empty mesh was generated");
-                        continue;
-                    }
-
-
-                    let normal = Direction::NORMALS[i];
-                    if dir_from_camera.dot(normal) > 0.0 {
-                        continue
-                    }
+                *counter += args.instance_count * 6;
+                let buffer = if is_water { &mut *water_buffer } else { &mut *buffer };
+                buffer.push(*args);
+            }
+        }
+    }
 
 
-                    let vo = mesh.quads.offset as u32;
-                    let vs = mesh.quads.size as u32;
+    ///
+    /// Rebuilds the `draws` entries of the leaves that
+    /// were handed out through `get_mut` since last time
+    ///
+    fn rebuild_dirty_draws(&mut self) {
+        for node in core::mem::take(&mut self.dirty) {
+            // the leaf might've been removed since
+            let Some(&slot) = self.draw_slots.get(&node)
+            else { continue };
+
+            let leaf = self.nodes[node.0.get()].leaf();
+            let draw = &mut self.draws[slot];
+            draw.mesh = draw_args(&leaf.mesh);
+            draw.lod = leaf.lod.as_ref().map(draw_args);
+        }
+    }
 
-                    *counter += vs * 6;
-                    buffer.push(DrawIndirectArgs {
-                        instance_count: vs,
-                        first_instance: vo,
-                        vertex_count: 6,
-                        first_vertex: 0,
-                    });
 
-                }
+    fn forget_draw(&mut self, node: NodeId) {
+        let Some(slot) = self.draw_slots.remove(&node)
+        else { return };
 
-            }
+        self.draws.swap_remove(slot);
+        if let Some(moved) = self.draws.get(slot) {
+            self.draw_slots.insert(moved.node, slot);
         }
-
-
-        rec(
-            self, pos0, 0, Self::HEIGHT, region,
-            player_chunk, camera, frustum, buffer, remesh_buffer, rd, counter
-        );
     }
 
 
@@ -329,9 +360,29 @@ impl MeshOctree {
 
 
     pub fn get_mut(&mut self, node: NodeId) -> &mut Leaf {
+        self.dirty.push(node);
         self.nodes[node.0.get()].leaf_mut()
     }
 
 }
 
 
+fn draw_args(meshes: &[Option<ChunkFaceMesh>; 12]) -> [Option<DrawIndirectArgs>; 12] {
+    core::array::from_fn(|i| {
+        let mesh = meshes[i].as_ref()?;
+
+        if mesh.index_count == 0 {
+            warn!("an empty mesh was generated");
+            return None;
+        }
+
+        Some(DrawIndirectArgs {
+            instance_count: mesh.quads.size as u32,
+            first_instance: mesh.quads.offset as u32,
+            vertex_count: 6,
+            first_vertex: 0,
+        })
+    })
+}
+
+
@@ -0,0 +1,131 @@
+use glam::{DVec3, IVec3, Mat4, Vec3};
+use rand::random;
+
+use crate::{constants::{COLOUR_WHITE, PARTICLE_LIFETIME, PARTICLE_MAX, PARTICLE_SIZE, PARTICLE_SPEED}, items::ItemKind, mesh::MeshInstance, renderer::Renderer, Camera};
+
+
+///
+/// Short lived bits of blocks that fly off when they're broken
+/// or placed. They're purely visual so they don't collide with
+/// anything, don't get saved and run on the frame time
+///
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+
+
+struct Particle {
+    kind: ItemKind,
+    position: DVec3,
+    velocity: Vec3,
+    age: f32,
+}
+
+
+impl Particles {
+    pub fn new() -> Self {
+        Self { particles: vec![] }
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+
+    ///
+    /// Spawns `count` particles of `kind` spread through the block
+    /// at `pos`, flying outwards at up to `speed`. Once there's
+    /// `PARTICLE_MAX` of them the oldest ones get dropped
+    ///
+    pub fn burst(&mut self, kind: ItemKind, pos: IVec3, count: usize, speed: f32) {
+        for _ in 0..count {
+            let offset = random::<Vec3>();
+            let velocity = (offset - Vec3::splat(0.5)) * 2.0 * speed + Vec3::Y * speed * 0.5;
+
+            self.particles.push(Particle {
+                kind,
+                position: pos.as_dvec3() + offset.as_dvec3(),
+                velocity,
+                age: 0.0,
+            });
+        }
+
+        if self.particles.len() > PARTICLE_MAX {
+            let excess = self.particles.len() - PARTICLE_MAX;
+            self.particles.drain(..excess);
+        }
+    }
+
+
+    pub fn break_block(&mut self, kind: ItemKind, pos: IVec3, count: usize) {
+        self.burst(kind, pos, count, PARTICLE_SPEED);
+    }
+
+
+    ///
+    /// A smaller, slower puff for a block that just got placed
+    ///
+    pub fn place_block(&mut self, kind: ItemKind, pos: IVec3, count: usize) {
+        self.burst(kind, pos, count, PARTICLE_SPEED * 0.3);
+    }
+
+
+    pub fn update(&mut self, delta_time: f32) {
+        for particle in &mut self.particles {
+            particle.velocity.y -= 9.8 * delta_time;
+            particle.position += (particle.velocity * delta_time).as_dvec3();
+            particle.age += delta_time;
+        }
+
+        self.particles.retain(|x| x.age < PARTICLE_LIFETIME);
+    }
+
+
+    ///
+    /// Draws every particle as a tiny cube in the colour of the
+    /// block it came off, shrinking and fading as it ages
+    ///
+    pub fn render(&self, renderer: &mut Renderer, camera: &Camera) {
+        let cube = renderer.assets.cube;
+
+        for particle in &self.particles {
+            let t = (particle.age / PARTICLE_LIFETIME).clamp(0.0, 1.0);
+            let scale = Vec3::splat(PARTICLE_SIZE * (1.0 - t * 0.5));
+            let pos = (particle.position - camera.position).as_vec3();
+
+            // structures don't have a single colour so they just get white bits
+            let colour = match particle.kind {
+                ItemKind::Voxel(voxel) => voxel.colour(),
+                _ => COLOUR_WHITE,
+            };
+
+            // the cube spans 0..1, shift it back so it's centered on the particle
+            let instance = MeshInstance {
+                modulate: colour.with_w(1.0 - t),
+                model: Mat4::from_translation(pos) * Mat4::from_scale(scale) * Mat4::from_translation(Vec3::splat(-0.5)),
+            };
+
+            renderer.draw_mesh(cube, instance);
+        }
+    }
+}
+
+
+#[test]
+fn test_particles_are_capped_and_expire() {
+    use crate::{constants::PARTICLE_BREAK_COUNT, voxel_world::voxel::Voxel};
+
+    let mut particles = Particles::new();
+    for _ in 0..PARTICLE_MAX {
+        particles.break_block(ItemKind::Voxel(Voxel::Stone), IVec3::ZERO, PARTICLE_BREAK_COUNT);
+    }
+
+    assert_eq!(particles.len(), PARTICLE_MAX);
+
+    particles.update(PARTICLE_LIFETIME * 0.5);
+    assert_eq!(particles.len(), PARTICLE_MAX);
+
+    particles.update(PARTICLE_LIFETIME);
+    assert_eq!(particles.len(), 0);
+}
@@ -3,21 +3,21 @@ pub mod uniform;
 pub mod ssbo;
 pub mod gpu_allocator;
 
-use std::{cell::Cell, collections::HashMap, mem::offset_of, ops::{Deref, DerefMut}, ptr::null_mut, time::{SystemTime, UNIX_EPOCH}};
+use std::{cell::Cell, collections::HashMap, ffi::CStr, mem::offset_of, ops::{Deref, DerefMut}, ptr::null_mut, sync::mpsc, time::{SystemTime, UNIX_EPOCH}};
 
 use bytemuck::{Pod, Zeroable};
-use glam::{IVec2, IVec3, Mat4, UVec3, Vec2, Vec2Swizzles, Vec3, Vec3Swizzles, Vec4, Vec4Swizzles};
+use glam::{IVec2, IVec3, Mat4, Vec2, Vec3, Vec3Swizzles, Vec4, Vec4Swizzles};
 use gpu_allocator::GPUAllocator;
-use image::{EncodableLayout, GenericImage, GenericImageView, RgbaImage};
+use image::{DynamicImage, EncodableLayout, GenericImage, GenericImageView, RgbaImage};
 use ssbo::{ResizableBuffer, SSBO};
 use sti::{key::Key, static_assert_eq, vec::KVec};
 use textures::{TextureAtlasBuilder, TextureId, UiShaderUniform, UiTextureAtlasManager};
-use tracing::warn;
+use tracing::{error, info, warn};
 use uniform::Uniform;
 use wgpu::{util::{BufferInitDescriptor, DeviceExt, StagingBelt}, wgt::DrawIndirectArgs, BufferUsages, TextureUsages, *};
 use winit::window::Window;
 
-use crate::{constants::{CHUNK_SIZE, FONT_SIZE, MSAA_SAMPLE_COUNT, QUAD_VERTICES, UI_DELTA_Z, UI_Z_MAX, UI_Z_MIN, VOXEL_TEXTURE_ATLAS_TILE_CAP, VOXEL_TEXTURE_ATLAS_TILE_SIZE}, directions::CardinalDirection, free_list::FreeKVec, frustum::Frustum, items::{Assets, ItemKind, MeshIndex}, mesh::MeshInstance, voxel_world::{chunker::ChunkPos, mesh::{ChunkMeshFramedata, ChunkQuadInstance, VoxelMeshIndex}, split_world_pos, VoxelWorld}, Camera};
+use crate::{constants::{CHUNK_SIZE, FONT_SIZE, MINIMAP_RESOLUTION, MSAA_SAMPLE_COUNT, QUAD_VERTICES, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH, UI_THUMBNAIL_SLOTS, UI_DELTA_Z, UI_TEXT_SHADOW_COLOUR, UI_TEXT_SHADOW_OFFSET, UI_Z_MAX, UI_Z_MIN, VOXEL_TEXTURE_ATLAS_TILE_CAP, VOXEL_TEXTURE_ATLAS_TILE_SIZE}, directions::CardinalDirection, free_list::FreeKVec, frustum::Frustum, items::{Assets, ItemKind, MeshIndex}, mesh::MeshInstance, voxel_world::{mesh::{ChunkMeshFramedata, ChunkQuadInstance, VoxelMeshIndex}, split_world_pos, VoxelWorld}, Camera};
 
 
 // the renderer is done,
@@ -30,9 +30,16 @@ pub struct Renderer {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
+    /// the present modes the adapter supports for this surface
+    pub present_modes: Vec<PresentMode>,
     pub window: &'static Window,
 
-    pub framebuffer: wgpu::TextureView,
+    /// `None` without msaa, the world gets drawn straight to the surface then
+    pub framebuffer: Option<wgpu::TextureView>,
+    /// how many samples the world is drawn with, see `set_sample_count`
+    pub sample_count: u32,
+    /// the msaa sample counts both the surface and depth formats support
+    pub sample_counts: Vec<u32>,
     pub ui_depth_texture: DepthBuffer,
 
     pub voxel_pipeline: VoxelPipeline,
@@ -50,12 +57,38 @@ pub struct Renderer {
     pub ui_atlases: UiTextureAtlasManager,
 
     pub line_size: f32,
+    /// how far below the top of a line the baseline sits,
+    /// the rest of `line_size` is left for descenders
+    pub baseline: f32,
     pub characters: HashMap<char, Character>,
     pub white_texture: TextureId,
+    /// blank textures in the assets atlas that save
+    /// thumbnails get written into, see `upload_thumbnail`
+    thumbnail_slots: Vec<TextureId>,
+    /// the minimap's cells, one pixel each, see `upload_minimap`
+    minimap_texture: TextureId,
     pub ui_vertex_buff: ResizableBuffer<UIVertex>,
 
     pub mesh_draws: KVec<MeshIndex, Vec<MeshInstance>>,
+    /// the item in the player's hand, its model is relative to
+    /// the camera and it's drawn over the world after clearing depth
+    pub viewmodel: Option<(MeshIndex, MeshInstance)>,
     pub assets: Assets,
+
+    /// a copy of the surface waiting on the gpu before
+    /// it can be shrunk down and written out as a thumbnail
+    thumbnail: Option<PendingThumbnail>,
+}
+
+
+struct PendingThumbnail {
+    buffer: wgpu::Buffer,
+    path: String,
+    width: u32,
+    height: u32,
+    bytes_per_row: u32,
+    is_bgra: bool,
+    mapped: mpsc::Receiver<Result<(), BufferAsyncError>>,
 }
 
 
@@ -68,6 +101,18 @@ pub struct DrawRect {
 }
 
 
+///
+/// Where `draw_text_ex` anchors each line of text
+/// relative to the x position it was given
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+
 #[derive(Debug)]
 pub struct Character {
     pub texture: TextureId,
@@ -81,8 +126,94 @@ pub struct RenderSettings<'a> {
     pub camera: &'a Camera,
     pub skybox: Vec4,
     pub render_distance: u32,
+    /// chunks further away than this are drawn with coarser meshes
+    pub lod_distance: u32,
     pub frustum: Option<Frustum>,
-    pub lines: bool,
+    pub debug_draw: DebugDraw,
+    pub fog: Fog,
+    pub sun: Sun,
+    /// the surface texture being drawn to and where to write a
+    /// thumbnail of this frame, taken before the UI is drawn
+    pub thumbnail: Option<(&'a wgpu::Texture, String)>,
+}
+
+
+///
+/// Overrides for the fog at the edge of the world, anything left
+/// as `None` follows the render distance or the sky colour
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Fog {
+    /// in blocks away from the camera
+    pub start: Option<f32>,
+    pub end: Option<f32>,
+    /// how thick the fog is past `end`, 0 turns it off
+    pub density: f32,
+    pub colour: Option<Vec3>,
+}
+
+
+impl Fog {
+    pub const DEFAULT : Fog = Fog { start: None, end: None, density: 1.0, colour: None };
+}
+
+
+///
+/// The one directional light the world and every mesh is shaded
+/// with, faces turned away from it only get the ambient light
+///
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sun {
+    /// points from the ground towards the sun, doesn't have to be normalized
+    pub direction: Vec3,
+    /// how much light a face looking straight at the sun gets on top of `ambient`
+    pub intensity: f32,
+    pub ambient: f32,
+}
+
+
+impl Sun {
+    pub const DEFAULT : Sun = Sun { direction: Vec3::new(0.5, 1.0, 0.3), intensity: 0.6, ambient: 0.4 };
+}
+
+
+///
+/// Ways of drawing the world to help track down meshing
+/// bugs, the line modes draw triangle edges instead of faces
+///
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DebugDraw {
+    Off,
+    /// chunks and meshes both drawn as lines
+    Lines,
+    ChunkLines,
+    StructureLines,
+    /// chunk faces coloured by the direction they face
+    Normals,
+}
+
+
+impl DebugDraw {
+    pub fn from_str(str: &str) -> Option<DebugDraw> {
+        Some(match str {
+            "off" => DebugDraw::Off,
+            "lines" => DebugDraw::Lines,
+            "chunk_lines" => DebugDraw::ChunkLines,
+            "structure_lines" => DebugDraw::StructureLines,
+            "normals" => DebugDraw::Normals,
+            _ => return None,
+        })
+    }
+
+
+    pub fn chunk_lines(self) -> bool {
+        matches!(self, DebugDraw::Lines | DebugDraw::ChunkLines)
+    }
+
+
+    pub fn mesh_lines(self) -> bool {
+        matches!(self, DebugDraw::Lines | DebugDraw::StructureLines)
+    }
 }
 
 
@@ -93,7 +224,8 @@ pub struct VoxelShaderUniform {
     pub projection: Mat4,
     pub modulate: Vec4,
     pub camera_block: IVec3,
-    pub pad_00: f32,
+    /// seconds, only used to scroll the water texture
+    pub time: f32,
     pub camera_offset: Vec3,
     pub pad_01: f32,
     pub fog_color: Vec3,
@@ -101,10 +233,15 @@ pub struct VoxelShaderUniform {
     pub fog_density: f32,
     pub fog_start: f32,
     pub fog_end: f32,
-    pub pad_03: f32,
+    /// non-zero to colour faces by their normal instead of their texture
+    pub debug_normals: u32,
+    pub sun_direction: Vec3,
+    pub sun_intensity: f32,
+    pub sun_ambient: f32,
+    pub pad_03: [f32; 3],
 }
 
-static_assert_eq!(size_of::<VoxelShaderUniform>(), 208);
+static_assert_eq!(size_of::<VoxelShaderUniform>(), 240);
 
 
 #[derive(Clone, Copy, Pod, Zeroable)]
@@ -112,12 +249,21 @@ static_assert_eq!(size_of::<VoxelShaderUniform>(), 208);
 pub struct MeshShaderUniform {
     pub view: Mat4,
     pub projection: Mat4,
+    pub sun_direction: Vec3,
+    pub sun_intensity: f32,
+    pub sun_ambient: f32,
+    pub pad_01: [f32; 3],
 }
 
+static_assert_eq!(size_of::<MeshShaderUniform>(), 160);
+
 
 pub struct VoxelPipeline {
     pub pipeline: RenderPipeline,
     pub line_pipeline: RenderPipeline,
+    /// blends the water faces over the terrain, nothing
+    /// gets culled and the depth buffer isn't written to
+    pub water_pipeline: RenderPipeline,
     pub frame_uniform: Uniform<VoxelShaderUniform>,
     pub model_uniform: SSBO<ChunkMeshFramedata>,
     pub depth_buffer: DepthBuffer,
@@ -125,9 +271,22 @@ pub struct VoxelPipeline {
     pub chunk_offsets: FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>,
     pub instances: GPUAllocator<ChunkQuadInstance>,
     pub indirect_buf: ResizableBuffer<DrawIndirectArgs>,
+    /// the draw args uploaded to `indirect_buf` last frame, only
+    /// the entries that changed since then get written again
+    pub indirect: Vec<DrawIndirectArgs>,
+    /// the frame before last's args, kept around so neither
+    /// vec has to be reallocated every frame
+    pub prev_indirect: Vec<DrawIndirectArgs>,
+    /// the water draws of this frame, they're appended
+    /// to `indirect` after all the opaque ones
+    pub water_indirect: Vec<DrawIndirectArgs>,
     pub vertex_buf: Buffer,
 
     pub texture: BindGroup,
+
+    /// kept so the pipelines can be rebuilt when the sample count changes
+    layout: PipelineLayout,
+    shader: ShaderModule,
 }
 
 
@@ -137,6 +296,10 @@ pub struct MeshPipeline {
     pub frame_uniform: Uniform<MeshShaderUniform>,
 
     pub instance_buffer: ResizableBuffer<MeshInstance>,
+
+    /// kept so the pipelines can be rebuilt when the sample count changes
+    layout: PipelineLayout,
+    shader: ShaderModule,
 }
 
 
@@ -144,6 +307,10 @@ impl Renderer {
     pub async fn new(window: Window) -> Self {
         let window = Box::leak(Box::new(window));
 
+        // every asset that couldn't be loaded, they all get a
+        // placeholder and are reported together at the end
+        let mut asset_errors = vec![];
+
         let size = window.inner_size();
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
@@ -184,18 +351,40 @@ impl Renderer {
 
         let surface_capabilities = surface.get_capabilities(&adapter);
 
+        let present_modes = surface_capabilities.present_modes.clone();
+        info!("supported present modes: {present_modes:?}");
+
         let surface_format = surface_capabilities.formats.iter()
             .find(|f| f.is_srgb())
             .copied()
             .unwrap_or(surface_capabilities.formats[0]);
 
 
+        let surface_features = adapter.get_texture_format_features(surface_format);
+        let depth_features = adapter.get_texture_format_features(TextureFormat::Depth32Float);
+        let sample_counts = [1, 2, 4, 8].into_iter()
+            .filter(|&count| surface_features.flags.sample_count_supported(count)
+                             && depth_features.flags.sample_count_supported(count))
+            .collect::<Vec<_>>();
+        info!("supported msaa sample counts: {sample_counts:?}");
+
+        // the settings get applied once a world is loaded
+        let sample_count = if sample_counts.contains(&MSAA_SAMPLE_COUNT) { MSAA_SAMPLE_COUNT }
+                           else { 1 };
+
+
+        // save thumbnails are copied straight out of the surface
+        let usage = if surface_capabilities.usages.contains(TextureUsages::COPY_SRC) { TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC }
+                    else { TextureUsages::RENDER_ATTACHMENT };
+
         let config = wgpu::SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Immediate,
+            // the settings get applied once a world is loaded
+            present_mode: if present_modes.contains(&PresentMode::Immediate) { PresentMode::Immediate }
+                          else { PresentMode::Fifo },
             alpha_mode: surface_capabilities.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
@@ -221,63 +410,7 @@ impl Renderer {
             });
 
 
-            let targets = &[Some(wgpu::ColorTargetState { // 4.
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })];
-
-            let mut desc = wgpu::RenderPipelineDescriptor {
-                label: Some("mesh-render-pipeline"),
-                layout: Some(&rpl),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_main"), // 1.
-                    buffers: &[
-                        crate::mesh::vertex_desc(),
-                        MeshInstance::desc(),
-                    ], 
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                },
-                fragment: Some(wgpu::FragmentState { // 3.
-                    module: &shader,
-                    entry_point: Some("fs_main"),
-                    targets,
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList, // 1.
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw, // 2.
-                    cull_mode: Some(Face::Back),
-                    //cull_mode: None,
-                    // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    // Requires Features::DEPTH_CLIP_CONTROL
-                    unclipped_depth: false,
-                    // Requires Features::CONSERVATIVE_RASTERIZATION
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState {
-                    count: MSAA_SAMPLE_COUNT, // 2.
-                    mask: !0, // 3.
-                    alpha_to_coverage_enabled: false, // 4.
-                },
-                multiview: None, // 5.
-                cache: None, // 6.
-            };
-
-
-            let render_pipeline = device.create_render_pipeline(&desc);
-            desc.primitive.polygon_mode = wgpu::PolygonMode::Line;
-            let line_render_pipeline = device.create_render_pipeline(&desc);
+            let (render_pipeline, line_render_pipeline) = MeshPipeline::create_pipelines(&device, config.format, &rpl, &shader, sample_count);
 
             let instance_buffer = ResizableBuffer::new("mesh-instance-buffer", &device, BufferUsages::COPY_SRC | BufferUsages::COPY_DST | BufferUsages::VERTEX, 128);
 
@@ -286,6 +419,8 @@ impl Renderer {
                 line_pipeline: line_render_pipeline,
                 frame_uniform: mesh_shader_uniform,
                 instance_buffer,
+                layout: rpl,
+                shader,
             }
         };
 
@@ -305,13 +440,16 @@ impl Renderer {
 
 
 
-            let diffuse_bytes = include_bytes!("../textures.png");
-            let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
+            let diffuse_image = match load_voxel_textures(include_bytes!("../textures.png")) {
+                Ok(image) => image,
+                Err(e) => {
+                    asset_errors.push(e);
+                    placeholder_voxel_textures()
+                },
+            };
             let diffuse_image = diffuse_image.flipv();
 
             let dims = diffuse_image.dimensions();
-            assert_eq!(dims.0, VOXEL_TEXTURE_ATLAS_TILE_SIZE * VOXEL_TEXTURE_ATLAS_TILE_CAP);
-            assert_eq!(dims.1, VOXEL_TEXTURE_ATLAS_TILE_SIZE);
 
             let texture_size = wgpu::Extent3d {
                 width: dims.0,
@@ -381,7 +519,9 @@ impl Renderer {
                 );
             }
 
-            mipmap_visual_image.save("mipmaps.png").unwrap();
+            if let Err(e) = mipmap_visual_image.save("mipmaps.png") {
+                warn!("unable to write mipmaps.png: {e}");
+            }
 
             let diffuse_texture_view = diffuse_texture.create_view(&wgpu::TextureViewDescriptor::default());
             let diffuse_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -446,79 +586,9 @@ impl Renderer {
             });
 
 
-            let depth_texture = DepthBuffer::new(&device, config.width, config.height, MSAA_SAMPLE_COUNT); 
-
-            let targets = &[Some(wgpu::ColorTargetState { // 4.
-                        format: config.format,
-                        blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })];
-            let mut desc = wgpu::RenderPipelineDescriptor {
-                label: Some("voxel-render-pipeline"),
-                layout: Some(&rpl),
-                vertex: wgpu::VertexState {
-                    module: &shader,
-                    entry_point: Some("vs_main"), // 1.
-                    buffers: &[
-                        wgpu::VertexBufferLayout {
-                            array_stride: 16,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: &[
-                                wgpu::VertexAttribute {
-                                    format: wgpu::VertexFormat::Sint32x3,
-                                    offset: 0,
-                                    shader_location: 0,
-                                },
-                                wgpu::VertexAttribute {
-                                    format: wgpu::VertexFormat::Uint32,
-                                    offset: 12,
-                                    shader_location: 1,
-                                },
-                            ],
-                        },
-                        ChunkQuadInstance::desc(),
-                    ], 
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                },
-                fragment: Some(wgpu::FragmentState { // 3.
-                    module: &shader,
-                    entry_point: Some("fs_main"),
-                    targets,
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList, // 1.
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Cw, // 2.
-                    cull_mode: Some(wgpu::Face::Back),
-                    //cull_mode: None,
-                    // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    // Requires Features::DEPTH_CLIP_CONTROL
-                    unclipped_depth: false,
-                    // Requires Features::CONSERVATIVE_RASTERIZATION
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState {
-                    count: MSAA_SAMPLE_COUNT, // 2.
-                    mask: !0, // 3.
-                    alpha_to_coverage_enabled: false, // 4.
-                },
-                multiview: None, // 5.
-                cache: None, // 6.
-            };
-
+            let depth_texture = DepthBuffer::new(&device, config.width, config.height, sample_count); 
 
-            let render_pipeline = device.create_render_pipeline(&desc);
-            desc.primitive.polygon_mode = wgpu::PolygonMode::Line;
-            let line_render_pipeline = device.create_render_pipeline(&desc);
+            let (render_pipeline, line_render_pipeline, water_render_pipeline) = VoxelPipeline::create_pipelines(&device, config.format, &rpl, &shader, sample_count);
 
 
             let vertex = device.create_buffer_init(&BufferInitDescriptor {
@@ -537,14 +607,20 @@ impl Renderer {
             VoxelPipeline {
                 pipeline: render_pipeline,
                 line_pipeline: line_render_pipeline,
+                water_pipeline: water_render_pipeline,
                 frame_uniform: voxel_shader_uniform,
                 model_uniform: ssbo,
                 depth_buffer: depth_texture,
                 vertex_buf: vertex,
                 instances: GPUAllocator::new(&device, 1),
                 indirect_buf: indirect,
+                indirect: vec![],
+                prev_indirect: vec![],
+                water_indirect: vec![],
                 chunk_offsets: FreeKVec::new(),
                 texture: diffuse_bind_group,
+                layout: rpl,
+                shader,
             }
         };
 
@@ -552,6 +628,7 @@ impl Renderer {
 
         let mut ui_atlases = UiTextureAtlasManager::new(&device);
         let line_size;
+        let baseline;
         let characters;
         let white;
         let ui_depth_texture = DepthBuffer::new(&device, config.width, config.height, 1); 
@@ -559,71 +636,19 @@ impl Renderer {
         {
 
 
-            let mut chars = HashMap::new();
             let mut atlas = TextureAtlasBuilder::new(TextureFormat::R8Unorm);
 
-            let mut biggest_y_size : f32 = 0.0;
-
-
-            let mut ft = null_mut();
-            if unsafe { freetype::freetype::FT_Init_FreeType(&mut ft) } != 0 {
-                panic!("failed to init freetype library");
-            }
-
-
-            let mut face = null_mut();
-            if unsafe { freetype::freetype::FT_New_Face(ft, c"font.ttf".as_ptr(), 0, &mut face) } != 0 {
-                panic!("failed to load font");
-            }
-
-            unsafe { freetype::freetype::FT_Set_Pixel_Sizes(face, FONT_SIZE, FONT_SIZE) };
-            for c in 0..128 {
-                if unsafe { freetype::freetype::FT_Load_Char(face, c as _, freetype::freetype::FT_LOAD_RENDER as _) } != 0 {
-                    panic!("failed to load glyph '{}'", char::from_u32(c).unwrap());
-                }
-
-
-                unsafe {
-                    let dims = IVec2::new(
-                        (*(*face).glyph).bitmap.width as _,
-                        (*(*face).glyph).bitmap.rows as _,
-                    );
-
-                    let slice = if dims != IVec2::ZERO { core::slice::from_raw_parts((*(*face).glyph).bitmap.buffer, (dims.x * dims.y) as usize) }
-                                else { &[] };
-                    let texture = atlas.register(dims, slice);
-
-                    let character = Character {
-                        texture,
-                        size: IVec2::new(
-                            (*(*face).glyph).bitmap.width as _,
-                            (*(*face).glyph).bitmap.rows as _,
-                        ),
-                        bearing: IVec2::new(
-                            (*(*face).glyph).bitmap_left as _,
-                            (*(*face).glyph).bitmap_top as _,
-                        ),
-                        advance: (*(*face).glyph).advance.x as _,
-                    };
-
-                    let h = character.size.y as f32;
-                    if h > biggest_y_size {
-                        dbg!(char::from_u32(c).unwrap());
-                    }
-
-                    biggest_y_size = biggest_y_size.max(h);
-
-                    chars.insert(char::from_u32(c).unwrap(), character);
-                }
-            }
-
-            unsafe {
-                freetype::freetype::FT_Done_Face(face);
-                freetype::freetype::FT_Done_FreeType(ft);
-            }
+            let (chars, ascent, descent) = match load_font(&mut atlas, c"font.ttf") {
+                Ok(font) => font,
+                Err(e) => {
+                    asset_errors.push(e);
+                    placeholder_font(&mut atlas)
+                },
+            };
 
             white = atlas.register(IVec2::new(32, 32), &[255; 32*32]);
-            line_size = biggest_y_size;
+            line_size = ascent + descent;
+            baseline = ascent;
             characters = chars;
             let atlas = atlas.build(&device, &queue);
 
@@ -735,11 +760,21 @@ impl Renderer {
             ui_atlases.register(atlas, render_pipeline, bg);
         }
 
-        let framebuffer = create_multisampled_framebuffer(&device, &config);
+        let framebuffer = create_multisampled_framebuffer(&device, &config, sample_count);
 
 
         let mut assets_ta = TextureAtlasBuilder::new(TextureFormat::Rgba8UnormSrgb);
-        let assets = Assets::new(&device, &mut assets_ta);
+        let assets = Assets::new(&device, &mut assets_ta, &mut asset_errors);
+
+        let thumbnail_dims = IVec2::new(THUMBNAIL_WIDTH as i32, THUMBNAIL_HEIGHT as i32);
+        let blank_thumbnail = vec![0; (THUMBNAIL_WIDTH * THUMBNAIL_HEIGHT * 4) as usize];
+        let thumbnail_slots = (0..UI_THUMBNAIL_SLOTS)
+            .map(|_| assets_ta.register(thumbnail_dims, &blank_thumbnail))
+            .collect();
+
+        let minimap_dims = IVec2::splat(MINIMAP_RESOLUTION as i32);
+        let minimap_texture = assets_ta.register(minimap_dims, &vec![0; MINIMAP_RESOLUTION * MINIMAP_RESOLUTION * 4]);
+
         let assets_ta = assets_ta.build(&device, &queue);
 
 
@@ -854,6 +889,14 @@ impl Renderer {
 
 
 
+        if !asset_errors.is_empty() {
+            error!("{} asset(s) failed to load, placeholders are used in their place:", asset_errors.len());
+            for e in &asset_errors {
+                error!("  {e}");
+            }
+        }
+
+
         let this = Self {
             window,
             ui_vertex_buff: ResizableBuffer::new("ui-vertex-buffer", &device, BufferUsages::VERTEX | BufferUsages::COPY_SRC | BufferUsages::COPY_DST, 128),
@@ -865,18 +908,26 @@ impl Renderer {
             device,
             queue,
             config,
+            present_modes,
             mesh_pipeline,
             voxel_pipeline,
             staging_buffer: StagingBelt::new(128 << 20),
             framebuffer,
+            sample_count,
+            sample_counts,
             ui_atlases,
             line_size,
+            baseline,
             characters,
             white_texture: white,
+            thumbnail_slots,
+            minimap_texture,
 
             mesh_draws: KVec::new(),
+            viewmodel: None,
             assets,
             ui_depth_texture,
+            thumbnail: None,
         };
 
         this
@@ -884,7 +935,11 @@ impl Renderer {
 
 
     pub fn end(&mut self, mut encoder: wgpu::CommandEncoder, voxel_world: &mut VoxelWorld, output_texture: &TextureView, settings: RenderSettings) {
-        let framebuffer = &self.framebuffer;
+        // without msaa the world is drawn straight to the surface
+        let (framebuffer, resolve_target) = match &self.framebuffer {
+            Some(framebuffer) => (framebuffer, Some(output_texture)),
+            None => (output_texture, None),
+        };
 
 
         let camera = settings.camera.position;
@@ -893,52 +948,82 @@ impl Renderer {
 
 
         self.staging_buffer.recall();
+        self.write_thumbnail();
+
+
+        let frustum = match &settings.frustum {
+            Some(f) => f.clone(),
+            None => Frustum::compute(projection, view),
+        };
 
 
         let triangle_count = self.triangle_count.get_mut();
         // prepare voxel buffers
         let indirect_len;
+        let water_indirect_len;
         {
             let voxel_pipeline = &mut self.voxel_pipeline;
 
             let (player_chunk, _) = split_world_pos(camera.as_ivec3());
 
-            let mut indirect : Vec<DrawIndirectArgs> = vec![];
-
-            let frustum = match &settings.frustum{
-                Some(f) => f.clone(),
-                None => Frustum::compute(projection, view),
-            };
+            core::mem::swap(&mut voxel_pipeline.indirect, &mut voxel_pipeline.prev_indirect);
+            let indirect = &mut voxel_pipeline.indirect;
+            indirect.clear();
 
+            let water_indirect = &mut voxel_pipeline.water_indirect;
+            water_indirect.clear();
 
-            let mut buf = vec![];
-            for (pos, region) in voxel_world.chunker.regions() {
-                region.octree().render(
-                    ChunkPos(UVec3::ZERO),
+            let mut lod_buf = vec![];
+            // every region's draw args are cached in its octree,
+            // only the chunks that changed get rebuilt
+            let regions : Vec<_> = voxel_world.chunker.regions().map(|(pos, _)| pos).collect();
+            for pos in regions {
+                voxel_world.chunker.get_region_or_insert(pos).octree_mut().render(
                     pos,
                     player_chunk,
                     camera,
                     &frustum,
-                    &mut indirect,
-                    &mut buf,
+                    indirect,
+                    water_indirect,
                     settings.render_distance as i32,
+                    &mut lod_buf,
+                    settings.lod_distance as i32,
                     triangle_count,
                 );
             }
 
 
-            for b in buf { voxel_world.chunker.get_mesh_or_queue(b); }
+            for b in lod_buf { voxel_world.chunker.queue_lod_mesh(b); }
 
-            if !indirect.is_empty() {
+            // both share the indirect buffer, the water
+            // just gets drawn from the opaque draws' end
+            indirect_len = indirect.len();
+            water_indirect_len = water_indirect.len();
+            indirect.extend_from_slice(water_indirect);
+
+            // most frames only a handful of chunks pop in or out
+            // of view so only upload the range that changed
+            let indirect = &voxel_pipeline.indirect;
+            let prev = &voxel_pipeline.prev_indirect;
+            let is_same = |(a, b): (&DrawIndirectArgs, &DrawIndirectArgs)| bytemuck::bytes_of(a) == bytemuck::bytes_of(b);
+
+            let first = indirect.iter().zip(prev).position(|x| !is_same(x))
+                .unwrap_or(indirect.len().min(prev.len()));
+
+            let last = if indirect.len() == prev.len() {
+                indirect.len() - indirect.iter().rev().zip(prev.iter().rev()).position(|x| !is_same(x)).unwrap_or(0)
+            } else { indirect.len() };
+
+            if first < last {
                 voxel_pipeline.indirect_buf.resize(&self.device, &mut encoder, indirect.len());
-                voxel_pipeline.indirect_buf.write(&mut self.staging_buffer, &mut encoder, &self.device, 0, &indirect);
+                voxel_pipeline.indirect_buf.write(&mut self.staging_buffer, &mut encoder, &self.device, first, &indirect[first..last]);
             }
-
-            indirect_len = indirect.len();
         }
 
 
         // prepare mesh buffers
+        let mut transparent = vec![];
+        let mut viewmodel = None;
         'meshes: {
             let mut buf = vec![];
 
@@ -952,14 +1037,35 @@ impl Renderer {
             }
 
 
-            for (_, instances) in &mut self.mesh_draws {
+            // transparent meshes have to be drawn back to front
+            // to blend properly, instances are relative to the
+            // camera so their translation is the offset from it
+            for (index, instances) in &mut self.mesh_draws {
                 for instance in instances {
-                    if instance.modulate.w != 1.0 {
-                        buf.push(*instance);
-                    }
+                    if instance.modulate.w == 1.0 { continue }
+
+                    let (min, max) = self.assets.meshes[index].bounds(instance.model);
+                    if !frustum.is_box_visible(min, max) { continue }
+
+                    transparent.push((index, *instance));
                 }
             }
 
+            transparent.sort_by(|a, b| {
+                let a = a.1.model.w_axis.truncate().length_squared();
+                let b = b.1.model.w_axis.truncate().length_squared();
+                b.total_cmp(&a)
+            });
+
+            buf.extend(transparent.iter().map(|x| x.1));
+
+            // the viewmodel goes last so it can be drawn in its own pass
+            if let Some((mesh, mut instance)) = self.viewmodel {
+                instance.model = view.inverse() * instance.model;
+                viewmodel = Some((mesh, buf.len() as u32));
+                buf.push(instance);
+            }
+
             if buf.is_empty() { break 'meshes }
 
             self.mesh_pipeline.instance_buffer.resize(&self.device, &mut encoder, buf.len());
@@ -972,8 +1078,8 @@ impl Renderer {
             label: Some("world-render-pass"),
             color_attachments: &[
                 Some(wgpu::RenderPassColorAttachment {
-                    view: &framebuffer,
-                    resolve_target: Some(&output_texture),
+                    view: framebuffer,
+                    resolve_target,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Clear(wgpu::Color { r: c.x, g: c.y, b: c.z, a: c.w }),
                         store: wgpu::StoreOp::Store,
@@ -1001,7 +1107,12 @@ impl Renderer {
         // render voxel world
         {
             let rd = settings.render_distance;
-            let fog_distance = (rd - 1) as f32;
+            let fog_distance = (rd - 1) as f32 * CHUNK_SIZE as f32;
+
+            let fog = settings.fog;
+            let fog_end = fog.end.unwrap_or(fog_distance);
+            // the shader divides by the gap between the two
+            let fog_start = fog.start.unwrap_or(fog_distance * 0.9).min(fog_end - 1.0);
 
             let uniform = VoxelShaderUniform {
                 view,
@@ -1011,19 +1122,25 @@ impl Renderer {
                 camera_block: camera.floor().as_ivec3(),
                 camera_offset: (camera - camera.floor()).as_vec3(),
 
-                fog_color: settings.skybox.xyz(),
-                fog_density: 1.0,
-                fog_start: fog_distance * CHUNK_SIZE as f32 * 0.9,
-                fog_end: fog_distance * CHUNK_SIZE as f32,
-                pad_00: 0.0,
+                fog_color: fog.colour.unwrap_or(settings.skybox.xyz()),
+                fog_density: fog.density,
+                fog_start,
+                fog_end,
+                // the water scrolls a whole number of tiles
+                // every hour so wrapping around doesn't jump
+                time: (SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs_f64() % 3600.0) as f32,
                 pad_01: 0.0,
                 pad_02: 0.0,
-                pad_03: 0.0,
+                debug_normals: (settings.debug_draw == DebugDraw::Normals) as u32,
+                sun_direction: settings.sun.direction.normalize_or(Vec3::Y),
+                sun_intensity: settings.sun.intensity,
+                sun_ambient: settings.sun.ambient,
+                pad_03: [0.0; 3],
             };
 
             let voxel_pipeline = &mut self.voxel_pipeline;
 
-            pass.set_pipeline(if settings.lines { &voxel_pipeline.line_pipeline } else { &voxel_pipeline.pipeline });
+            pass.set_pipeline(if settings.debug_draw.chunk_lines() { &voxel_pipeline.line_pipeline } else { &voxel_pipeline.pipeline });
 
             voxel_pipeline.frame_uniform.update(&self.queue, &uniform);
             voxel_pipeline.frame_uniform.use_uniform(&mut pass);
@@ -1038,11 +1155,15 @@ impl Renderer {
 
         // draw meshes
         {
-            pass.set_pipeline(if settings.lines { &self.mesh_pipeline.line_pipeline } else { &self.mesh_pipeline.pipeline });
+            pass.set_pipeline(if settings.debug_draw.mesh_lines() { &self.mesh_pipeline.line_pipeline } else { &self.mesh_pipeline.pipeline });
 
             self.mesh_pipeline.frame_uniform.update(&self.queue, &MeshShaderUniform {
                 view,
                 projection,
+                sun_direction: settings.sun.direction.normalize_or(Vec3::Y),
+                sun_intensity: settings.sun.intensity,
+                sun_ambient: settings.sun.ambient,
+                pad_01: [0.0; 3],
             });
 
             self.mesh_pipeline.frame_uniform.use_uniform(&mut pass);
@@ -1054,39 +1175,116 @@ impl Renderer {
 
                 let mesh = &self.assets.meshes[index];
 
-                *triangle_count += mesh.index_count * instances.len() as u32;
-
                 pass.set_vertex_buffer(0, mesh.vertices.slice(..));
                 pass.set_index_buffer(mesh.indices.slice(..), IndexFormat::Uint32);
 
                 let len = instances.iter().filter(|x| x.modulate.w == 1.0).count() as u32;
+                *triangle_count += mesh.index_count * len;
                 pass.draw_indexed(0..mesh.index_count, 0, counter..counter+len);
                 counter += len;
+                instances.clear();
 
             }
 
-            for (index, instances) in &mut self.mesh_draws {
-                if instances.is_empty() { continue }
-
-                let mesh = &self.assets.meshes[index];
+            // the transparent instances are sorted by distance so
+            // only neighbouring ones of the same mesh share a draw
+            for run in transparent.chunk_by(|a, b| a.0 == b.0) {
+                let mesh = &self.assets.meshes[run[0].0];
                 pass.set_vertex_buffer(0, mesh.vertices.slice(..));
                 pass.set_index_buffer(mesh.indices.slice(..), IndexFormat::Uint32);
 
-                let len = instances.iter().filter(|x| x.modulate.w != 1.0).count() as u32;
+                let len = run.len() as u32;
+                *triangle_count += mesh.index_count * len;
                 pass.draw_indexed(0..mesh.index_count, 0, counter..counter+len);
                 counter += len;
-                instances.clear();
-
             }
 
 
         }
 
 
+        // draw water
+        // it goes after everything opaque so whatever's
+        // behind it has already been drawn to blend with
+        if water_indirect_len > 0 && !settings.debug_draw.chunk_lines() {
+            let voxel_pipeline = &self.voxel_pipeline;
+
+            pass.set_pipeline(&voxel_pipeline.water_pipeline);
+
+            voxel_pipeline.frame_uniform.use_uniform(&mut pass);
+            pass.set_bind_group(1, voxel_pipeline.model_uniform.bind_group(), &[]);
+            pass.set_bind_group(2, &voxel_pipeline.texture, &[]);
+
+            pass.set_vertex_buffer(0, voxel_pipeline.vertex_buf.slice(..));
+            pass.set_vertex_buffer(1, voxel_pipeline.instances.ssbo.buffer.slice(..));
+
+            let offset = (indirect_len * size_of::<DrawIndirectArgs>()) as u64;
+            pass.multi_draw_indirect(&voxel_pipeline.indirect_buf.buffer, offset, water_indirect_len as _);
+        }
+
+
 
 
         drop(pass);
 
+
+        // draw the item in hand
+        // the depth buffer is cleared first so it never clips into the world
+        if let Some((mesh, instance)) = viewmodel {
+            self.viewmodel = None;
+
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("viewmodel-render-pass"),
+                color_attachments: &[
+                    Some(wgpu::RenderPassColorAttachment {
+                        view: framebuffer,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                        depth_slice: None,
+                    }),
+                ],
+
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.voxel_pipeline.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+
+                    stencil_ops: None,
+                }),
+
+                ..Default::default()
+            });
+
+            pass.set_pipeline(&self.mesh_pipeline.pipeline);
+            self.mesh_pipeline.frame_uniform.use_uniform(&mut pass);
+            pass.set_vertex_buffer(1, self.mesh_pipeline.instance_buffer.buffer.slice(..));
+
+            let mesh = &self.assets.meshes[mesh];
+            pass.set_vertex_buffer(0, mesh.vertices.slice(..));
+            pass.set_index_buffer(mesh.indices.slice(..), IndexFormat::Uint32);
+
+            *triangle_count += mesh.index_count;
+            pass.draw_indexed(0..mesh.index_count, 0, instance..instance+1);
+        }
+
+
+        // copy the world out before the UI gets drawn over it
+        let thumbnail = match settings.thumbnail {
+            Some((_, path)) if self.thumbnail.is_some() => {
+                warn!("a thumbnail is already being written, skipping '{path}'");
+                None
+            },
+
+            Some((texture, path)) => copy_thumbnail(&self.device, &mut encoder, texture, path),
+            None => None,
+        };
+
+
         // draw UI
         let mut z = UI_Z_MIN;
 
@@ -1182,6 +1380,105 @@ impl Renderer {
         self.staging_buffer.finish();
 
         self.queue.submit(std::iter::once(encoder.finish()));
+
+        // the buffer can only be mapped once the copy is submitted
+        if let Some((thumbnail, sender)) = thumbnail {
+            thumbnail.buffer.slice(..).map_async(MapMode::Read, move |result| { let _ = sender.send(result); });
+            self.thumbnail = Some(thumbnail);
+        }
+    }
+
+
+    ///
+    /// Writes out the pending thumbnail if the gpu finished copying it,
+    /// the resizing and encoding happen off the render thread
+    ///
+    fn write_thumbnail(&mut self) {
+        let Some(thumbnail) = &self.thumbnail
+        else { return };
+
+        let result = match thumbnail.mapped.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err(BufferAsyncError),
+        };
+
+        let thumbnail = self.thumbnail.take().unwrap();
+        if let Err(e) = result {
+            warn!("unable to read back the thumbnail '{}': {e}", thumbnail.path);
+            return;
+        }
+
+        let row_len = thumbnail.width as usize * 4;
+        let mut pixels = Vec::with_capacity(row_len * thumbnail.height as usize);
+        {
+            let data = thumbnail.buffer.slice(..).get_mapped_range();
+            for row in data.chunks(thumbnail.bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..row_len]);
+            }
+        }
+
+        thumbnail.buffer.unmap();
+
+        let PendingThumbnail { path, width, height, is_bgra, .. } = thumbnail;
+        rayon::spawn(move || {
+            if is_bgra {
+                pixels.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+            }
+
+            let Some(image) = RgbaImage::from_raw(width, height, pixels)
+            else { return };
+
+            let image = image::imageops::thumbnail(&image, THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+            match image.save(&path) {
+                Ok(()) => info!("wrote thumbnail '{path}'"),
+                Err(e) => warn!("unable to write the thumbnail '{path}': {e}"),
+            }
+        });
+    }
+
+
+    ///
+    /// Reconfigures the surface to present with `mode`, falling back
+    /// to `Fifo` which every adapter has to support.
+    /// Returns the mode that ended up being used
+    ///
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> PresentMode {
+        let mode = if self.present_modes.contains(&mode) { mode }
+        else {
+            warn!("present mode {mode:?} isn't supported, falling back to {:?}", PresentMode::Fifo);
+            PresentMode::Fifo
+        };
+
+        self.config.present_mode = mode;
+        self.surface.configure(&self.device, &self.config);
+        mode
+    }
+
+
+    ///
+    /// Rebuilds the framebuffer, the depth buffer and the world's
+    /// pipelines to draw with `count` samples, a count the adapter
+    /// doesn't support keeps the current one.
+    /// Returns the count that ended up being used
+    ///
+    pub fn set_sample_count(&mut self, count: u32) -> u32 {
+        if !self.sample_counts.contains(&count) {
+            warn!("msaa sample count {count} isn't supported, keeping {}", self.sample_count);
+            return self.sample_count;
+        }
+
+        self.sample_count = count;
+        self.framebuffer = create_multisampled_framebuffer(&self.device, &self.config, count);
+        self.voxel_pipeline.depth_buffer = DepthBuffer::new(&self.device, self.config.width, self.config.height, count);
+
+        let mesh = &mut self.mesh_pipeline;
+        (mesh.pipeline, mesh.line_pipeline) = MeshPipeline::create_pipelines(&self.device, self.config.format, &mesh.layout, &mesh.shader, count);
+
+        let voxel = &mut self.voxel_pipeline;
+        (voxel.pipeline, voxel.line_pipeline, voxel.water_pipeline) = VoxelPipeline::create_pipelines(&self.device, self.config.format, &voxel.layout, &voxel.shader, count);
+
+        count
     }
 
 
@@ -1191,50 +1488,50 @@ impl Renderer {
 
 
     pub fn draw_text(&mut self, text: &str, pos: Vec2, scale: f32, default_colour: Vec4) {
-        self.draw_text_ex(text, pos, scale, default_colour, false);
+        self.draw_text_ex(text, pos, scale, default_colour, false, TextAlign::Left);
     }
 
-    pub fn draw_text_ex(&mut self, text: &str, pos: Vec2, scale: f32, default_colour: Vec4, discard_colour_codes: bool) {
-        let mut x;
+
+    ///
+    /// Draws the text on top of a dark drop shadow so it stays
+    /// readable on light backgrounds.
+    ///
+    /// The shadow doesn't count towards `text_size`
+    ///
+    pub fn draw_text_shadowed(&mut self, text: &str, pos: Vec2, scale: f32, default_colour: Vec4) {
+        let shadow = UI_TEXT_SHADOW_COLOUR.with_w(UI_TEXT_SHADOW_COLOUR.w * default_colour.w);
+        self.draw_text_ex(text, pos + UI_TEXT_SHADOW_OFFSET, scale, shadow, true, TextAlign::Left);
+        self.draw_text(text, pos, scale, default_colour);
+    }
+
+
+    ///
+    /// Draws `text` with its first line's top at `pos.y`.
+    /// Each line is measured with `text_size` and anchored
+    /// to `pos.x` according to `align`
+    ///
+    pub fn draw_text_ex(&mut self, text: &str, pos: Vec2, scale: f32, default_colour: Vec4, discard_colour_codes: bool, align: TextAlign) {
         let mut y = pos.y;
         let mut active_colour = default_colour;
 
         for l in text.lines() {
-            x = pos.x;
+            let mut x = match align {
+                TextAlign::Left => pos.x,
+                TextAlign::Center => pos.x - self.text_size(l, scale).x * 0.5,
+                TextAlign::Right => pos.x - self.text_size(l, scale).x,
+            };
+
+            let baseline = y + self.baseline * scale;
             y += self.line_size * scale;
 
 
-            let mut line_size = 0.0f32;
             let mut iter = l.chars();
             while let Some(c) = iter.next() {
                 if c == '§' {
                     let colour_code = iter.next().unwrap();
 
                     if discard_colour_codes { continue };
-                    active_colour = match colour_code {
-                        '0' => Vec4::ZERO,
-                        '1' => Vec4::new(0.0, 0.0, 0.4, 1.0),
-                        '2' => Vec4::new(0.0, 0.4, 0.0, 1.0),
-                        '3' => Vec4::new(0.0, 0.4, 0.4, 1.0),
-                        '4' => Vec4::new(0.4, 0.0, 0.0, 1.0),
-                        '5' => Vec4::new(0.4, 0.0, 0.4, 1.0),
-                        '6' => Vec4::new(1.0, 0.4, 0.0, 1.0),
-                        '7' => Vec4::new(0.4, 0.4, 0.4, 1.0),
-                        '8' => Vec4::new(0.1, 0.1, 0.1, 1.0),
-                        '9' => Vec4::new(0.1, 0.1, 1.0, 1.0),
-                        'a' => Vec4::new(0.1, 1.0, 0.1, 1.0),
-                        'b' => Vec4::new(0.1, 1.0, 1.0, 1.0),
-                        'c' => Vec4::new(1.0, 0.1, 0.1, 1.0),
-                        'd' => Vec4::new(1.0, 0.1, 1.0, 1.0),
-                        'e' => Vec4::new(1.0, 1.0, 0.5, 1.0),
-                        'f' => Vec4::ONE,
-                        'r' => default_colour,
-
-                        _ => {
-                            warn!("invalid colour code '§{}', resetting to default colour", colour_code);
-                            default_colour
-                        },
-                    };
+                    active_colour = colour_from_code(colour_code, default_colour);
                     continue
                 }
 
@@ -1242,12 +1539,11 @@ impl Renderer {
                 else { warn!("[renderer] draw-text: character not registered '{c}'"); continue };
 
                 let xpos = x + ch.bearing.x as f32 * scale;
-                let ypos = y - (ch.size.y + ch.bearing.y) as f32 * scale * 0.5;
+                let ypos = baseline - ch.bearing.y as f32 * scale;
                 x += (ch.advance >> 6) as f32 * scale;
 
                 let w = ch.size.x as f32 * scale;
                 let h = ch.size.y as f32 * scale;
-                line_size = line_size.max(h);
 
                 let dims = Vec2::new(w, h);
                 self.draw_tex_rect(Vec2::new(xpos, ypos), dims, ch.texture, active_colour.with_w(default_colour.w));
@@ -1265,39 +1561,16 @@ impl Renderer {
 
         for l in text.lines() {
             x = pos.x;
+            let baseline = y + self.baseline * scale;
             y += self.line_size * scale;
 
 
-            let mut line_size = 0.0f32;
             let mut iter = l.chars();
             while let Some(c) = iter.next() {
                 if c == '§' {
                     let colour_code = iter.next().unwrap();
 
-                    active_colour = match colour_code {
-                        '0' => Vec4::ZERO,
-                        '1' => Vec4::new(0.0, 0.0, 0.4, 1.0),
-                        '2' => Vec4::new(0.0, 0.4, 0.0, 1.0),
-                        '3' => Vec4::new(0.0, 0.4, 0.4, 1.0),
-                        '4' => Vec4::new(0.4, 0.0, 0.0, 1.0),
-                        '5' => Vec4::new(0.4, 0.0, 0.4, 1.0),
-                        '6' => Vec4::new(1.0, 0.4, 0.0, 1.0),
-                        '7' => Vec4::new(0.4, 0.4, 0.4, 1.0),
-                        '8' => Vec4::new(0.1, 0.1, 0.1, 1.0),
-                        '9' => Vec4::new(0.1, 0.1, 1.0, 1.0),
-                        'a' => Vec4::new(0.1, 1.0, 0.1, 1.0),
-                        'b' => Vec4::new(0.1, 1.0, 1.0, 1.0),
-                        'c' => Vec4::new(1.0, 0.1, 0.1, 1.0),
-                        'd' => Vec4::new(1.0, 0.1, 1.0, 1.0),
-                        'e' => Vec4::new(1.0, 1.0, 0.5, 1.0),
-                        'f' => Vec4::ONE,
-                        'r' => default_colour,
-
-                        _ => {
-                            warn!("invalid colour code '§{}', resetting to default colour", colour_code);
-                            default_colour
-                        },
-                    };
+                    active_colour = colour_from_code(colour_code, default_colour);
                     continue
                 }
 
@@ -1305,12 +1578,11 @@ impl Renderer {
                 else { warn!("[renderer] draw-text: character not registered '{c}'"); continue };
 
                 let xpos = x + ch.bearing.x as f32 * scale;
-                let ypos = y - (ch.size.y + ch.bearing.y) as f32 * scale * 0.5;
+                let ypos = baseline - ch.bearing.y as f32 * scale;
                 x += (ch.advance >> 6) as f32 * scale;
 
                 let w = ch.size.x as f32 * scale;
                 let h = ch.size.y as f32 * scale;
-                line_size = line_size.max(h);
 
                 let dims = Vec2::new(w, h);
                 self.draw_tex_rect(Vec2::new(xpos, ypos), dims, ch.texture, active_colour);
@@ -1369,6 +1641,30 @@ impl Renderer {
     }
 
 
+    ///
+    /// Writes `image` into the `slot`th thumbnail texture so it can
+    /// be drawn with `draw_tex_rect`. Returns `None` if there aren't
+    /// that many slots or the image isn't thumbnail sized
+    ///
+    pub fn upload_thumbnail(&mut self, slot: usize, image: &RgbaImage) -> Option<TextureId> {
+        let texture = *self.thumbnail_slots.get(slot)?;
+        if image.dimensions() != (THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT) { return None }
+
+        self.ui_atlases.write(&self.queue, texture, image.as_raw());
+        Some(texture)
+    }
+
+
+    ///
+    /// Writes the minimap's `MINIMAP_RESOLUTION` by `MINIMAP_RESOLUTION`
+    /// srgb `pixels` into its texture, returns the texture to draw
+    ///
+    pub fn upload_minimap(&mut self, pixels: &[u8]) -> TextureId {
+        self.ui_atlases.write(&self.queue, self.minimap_texture, pixels);
+        self.minimap_texture
+    }
+
+
     pub fn draw_tex_rect_z(&mut self, pos: Vec3, dims: Vec2, tex: TextureId, modulate: Vec4) {
         let rect = DrawRect {
             modulate,
@@ -1391,27 +1687,21 @@ impl Renderer {
     }
 
 
-    pub fn draw_item(&mut self, item_kind: ItemKind, mut instance: MeshInstance) {
-        if let ItemKind::Structure(structure) = item_kind {
-            let blocks = structure.blocks(CardinalDirection::North);
-            let mut min = IVec3::MAX;
-            let mut max = IVec3::MIN;
-
-            for &block in blocks {
-                min = min.min(block);
-                max = max.max(block);
-            }
-
-            let size = (max - min).abs() + IVec3::ONE;
-            let size = size.as_vec3().max_element();
-            let (scale, rot, trans) = instance.model.to_scale_rotation_translation();
-            let scale = scale / size;
-            instance.model = Mat4::from_scale_rotation_translation(scale, rot, trans);
-        }
+    pub fn draw_item(&mut self, item_kind: ItemKind, instance: MeshInstance) {
+        let instance = fit_item(item_kind, instance);
+        let mesh = self.assets.get_item(item_kind);
+        self.draw_mesh(mesh, instance);
+    }
 
 
+    ///
+    /// Draws `item_kind` on top of everything else in the world,
+    /// `instance`'s model is relative to the camera instead of the world
+    ///
+    pub fn draw_viewmodel(&mut self, item_kind: ItemKind, instance: MeshInstance) {
+        let instance = fit_item(item_kind, instance);
         let mesh = self.assets.get_item(item_kind);
-        self.draw_mesh(mesh, instance);
+        self.viewmodel = Some((mesh, instance));
     }
 
 
@@ -1451,6 +1741,236 @@ impl Renderer {
 }
 
 
+///
+/// Rasterizes the ascii range of the font at `path` into `atlas`,
+/// returns the glyphs along with how far they reach above and
+/// below the baseline. Glyphs the font doesn't have are left out
+///
+fn load_font(atlas: &mut TextureAtlasBuilder, path: &CStr) -> Result<(HashMap<char, Character>, f32, f32), String> {
+    let mut chars = HashMap::new();
+
+    // how far glyphs reach above and below the baseline
+    let mut ascent : f32 = 0.0;
+    let mut descent : f32 = 0.0;
+
+
+    let mut ft = null_mut();
+    if unsafe { freetype::freetype::FT_Init_FreeType(&mut ft) } != 0 {
+        return Err("failed to init the freetype library".to_string());
+    }
+
+
+    let mut face = null_mut();
+    if unsafe { freetype::freetype::FT_New_Face(ft, path.as_ptr(), 0, &mut face) } != 0 {
+        unsafe { freetype::freetype::FT_Done_FreeType(ft) };
+        return Err(format!("failed to load the font '{}'", path.to_string_lossy()));
+    }
+
+    unsafe { freetype::freetype::FT_Set_Pixel_Sizes(face, FONT_SIZE, FONT_SIZE) };
+    for c in 0..128 {
+        if unsafe { freetype::freetype::FT_Load_Char(face, c as _, freetype::freetype::FT_LOAD_RENDER as _) } != 0 {
+            warn!("failed to load glyph '{}'", char::from_u32(c).unwrap().escape_default());
+            continue;
+        }
+
+
+        unsafe {
+            let dims = IVec2::new(
+                (*(*face).glyph).bitmap.width as _,
+                (*(*face).glyph).bitmap.rows as _,
+            );
+
+            let slice = if dims != IVec2::ZERO { core::slice::from_raw_parts((*(*face).glyph).bitmap.buffer, (dims.x * dims.y) as usize) }
+                        else { &[] };
+            let texture = atlas.register(dims, slice);
+
+            let character = Character {
+                texture,
+                size: dims,
+                bearing: IVec2::new(
+                    (*(*face).glyph).bitmap_left as _,
+                    (*(*face).glyph).bitmap_top as _,
+                ),
+                advance: (*(*face).glyph).advance.x as _,
+            };
+
+            ascent = ascent.max(character.bearing.y as f32);
+            descent = descent.max((character.size.y - character.bearing.y) as f32);
+
+            chars.insert(char::from_u32(c).unwrap(), character);
+        }
+    }
+
+    unsafe {
+        freetype::freetype::FT_Done_Face(face);
+        freetype::freetype::FT_Done_FreeType(ft);
+    }
+
+    Ok((chars, ascent, descent))
+}
+
+
+///
+/// Stands in for the font when it can't be loaded, every printable
+/// character is the same hollow box so text still takes up about
+/// the room it would and the menus stay usable
+///
+fn placeholder_font(atlas: &mut TextureAtlasBuilder) -> (HashMap<char, Character>, f32, f32) {
+    let size = IVec2::new(FONT_SIZE as i32 / 2, FONT_SIZE as i32 * 3 / 4);
+    let border = 3;
+
+    let mut data = vec![0; (size.x * size.y) as usize];
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let is_edge = x < border || y < border || x >= size.x - border || y >= size.y - border;
+            if is_edge { data[(y * size.x + x) as usize] = 255 }
+        }
+    }
+
+    let box_texture = atlas.register(size, &data);
+    let space_texture = atlas.register(IVec2::ZERO, &[]);
+
+    let mut chars = HashMap::new();
+    for c in ' '..='~' {
+        let (texture, size) = if c == ' ' { (space_texture, IVec2::ZERO) }
+                              else { (box_texture, size) };
+
+        chars.insert(c, Character {
+            texture,
+            size,
+            bearing: IVec2::new(border, size.y),
+            // in 1/64ths of a pixel like freetype's
+            advance: ((size.x + border * 2) << 6) as u32,
+        });
+    }
+
+    (chars, size.y as f32, 0.0)
+}
+
+
+///
+/// Decodes the voxel texture atlas, it has to be one
+/// row of `VOXEL_TEXTURE_ATLAS_TILE_CAP` square tiles
+///
+fn load_voxel_textures(bytes: &[u8]) -> Result<DynamicImage, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| format!("failed to decode textures.png: {e}"))?;
+
+    let expected = (VOXEL_TEXTURE_ATLAS_TILE_SIZE * VOXEL_TEXTURE_ATLAS_TILE_CAP, VOXEL_TEXTURE_ATLAS_TILE_SIZE);
+    if image.dimensions() != expected {
+        let (w, h) = image.dimensions();
+        return Err(format!("textures.png is {w}x{h}, expected {}x{}", expected.0, expected.1));
+    }
+
+    Ok(image)
+}
+
+
+///
+/// A magenta and black checkerboard the size of
+/// the voxel texture atlas, every tile looks the same
+///
+fn placeholder_voxel_textures() -> DynamicImage {
+    let width = VOXEL_TEXTURE_ATLAS_TILE_SIZE * VOXEL_TEXTURE_ATLAS_TILE_CAP;
+    let height = VOXEL_TEXTURE_ATLAS_TILE_SIZE;
+    let cell = (VOXEL_TEXTURE_ATLAS_TILE_SIZE / 2).max(1);
+
+    let image = RgbaImage::from_fn(width, height, |x, y| {
+        if (x / cell + y / cell) % 2 == 0 { image::Rgba([255, 0, 255, 255]) }
+        else { image::Rgba([0, 0, 0, 255]) }
+    });
+
+    DynamicImage::ImageRgba8(image)
+}
+
+
+///
+/// Records a copy of `texture` into a readback buffer, the buffer
+/// has to be mapped after submission through the returned sender,
+/// `Renderer::write_thumbnail` picks it up once the gpu is done
+///
+/// Returns `None` if the surface can't be copied from
+///
+fn copy_thumbnail(device: &wgpu::Device, encoder: &mut CommandEncoder, texture: &wgpu::Texture, path: String) -> Option<(PendingThumbnail, mpsc::Sender<Result<(), BufferAsyncError>>)> {
+    if !texture.usage().contains(TextureUsages::COPY_SRC) {
+        warn!("the surface doesn't support being copied from, skipping '{path}'");
+        return None;
+    }
+
+    let is_bgra = match texture.format() {
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+        format => {
+            warn!("can't write a thumbnail from a {format:?} surface, skipping '{path}'");
+            return None;
+        }
+    };
+
+    let size = texture.size();
+    let bytes_per_row = (size.width * 4).next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("thumbnail-readback-buffer"),
+        size: (bytes_per_row * size.height) as u64,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: None,
+            },
+        },
+        Extent3d { depth_or_array_layers: 1, ..size },
+    );
+
+    let (sender, mapped) = mpsc::channel();
+    Some((PendingThumbnail { buffer, path, width: size.width, height: size.height, bytes_per_row, is_bgra, mapped }, sender))
+}
+
+
+///
+/// The colour a `§` code switches text to,
+/// `r` and unknown codes go back to `default`
+///
+fn colour_from_code(code: char, default: Vec4) -> Vec4 {
+    match code {
+        '0' => Vec4::ZERO,
+        '1' => Vec4::new(0.0, 0.0, 0.4, 1.0),
+        '2' => Vec4::new(0.0, 0.4, 0.0, 1.0),
+        '3' => Vec4::new(0.0, 0.4, 0.4, 1.0),
+        '4' => Vec4::new(0.4, 0.0, 0.0, 1.0),
+        '5' => Vec4::new(0.4, 0.0, 0.4, 1.0),
+        '6' => Vec4::new(1.0, 0.4, 0.0, 1.0),
+        '7' => Vec4::new(0.4, 0.4, 0.4, 1.0),
+        '8' => Vec4::new(0.1, 0.1, 0.1, 1.0),
+        '9' => Vec4::new(0.1, 0.1, 1.0, 1.0),
+        'a' => Vec4::new(0.1, 1.0, 0.1, 1.0),
+        'b' => Vec4::new(0.1, 1.0, 1.0, 1.0),
+        'c' => Vec4::new(1.0, 0.1, 0.1, 1.0),
+        'd' => Vec4::new(1.0, 0.1, 1.0, 1.0),
+        'e' => Vec4::new(1.0, 1.0, 0.5, 1.0),
+        'f' => Vec4::ONE,
+        'r' => default,
+
+        _ => {
+            warn!("invalid colour code '§{}', resetting to default colour", code);
+            default
+        },
+    }
+}
+
+
 #[repr(C)]
 #[derive(Pod, Zeroable, Clone, Copy, Debug)]
 pub struct UIVertex {
@@ -1463,6 +1983,172 @@ pub struct UIVertex {
 }
 
 
+
+impl MeshPipeline {
+    fn create_pipelines(
+        device: &wgpu::Device,
+        format: TextureFormat,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+        sample_count: u32,
+    ) -> (RenderPipeline, RenderPipeline) {
+        let targets = &[Some(wgpu::ColorTargetState { // 4.
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })];
+
+        let mut desc = wgpu::RenderPipelineDescriptor {
+            label: Some("mesh-render-pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"), // 1.
+                buffers: &[
+                    crate::mesh::vertex_desc(),
+                    MeshInstance::desc(),
+                ], 
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState { // 3.
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw, // 2.
+                cull_mode: Some(Face::Back),
+                //cull_mode: None,
+                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+                polygon_mode: wgpu::PolygonMode::Fill,
+                // Requires Features::DEPTH_CLIP_CONTROL
+                unclipped_depth: false,
+                // Requires Features::CONSERVATIVE_RASTERIZATION
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count, // 2.
+                mask: !0, // 3.
+                alpha_to_coverage_enabled: false, // 4.
+            },
+            multiview: None, // 5.
+            cache: None, // 6.
+        };
+
+
+        let render_pipeline = device.create_render_pipeline(&desc);
+        desc.primitive.polygon_mode = wgpu::PolygonMode::Line;
+        let line_render_pipeline = device.create_render_pipeline(&desc);
+
+        (render_pipeline, line_render_pipeline)
+    }
+}
+
+
+impl VoxelPipeline {
+    ///
+    /// The opaque, wireframe and water pipelines
+    ///
+    fn create_pipelines(
+        device: &wgpu::Device,
+        format: TextureFormat,
+        layout: &PipelineLayout,
+        shader: &ShaderModule,
+        sample_count: u32,
+    ) -> (RenderPipeline, RenderPipeline, RenderPipeline) {
+        let targets = &[Some(wgpu::ColorTargetState { // 4.
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })];
+        let mut desc = wgpu::RenderPipelineDescriptor {
+            label: Some("voxel-render-pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: Some("vs_main"), // 1.
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: 16,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &[
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Sint32x3,
+                                offset: 0,
+                                shader_location: 0,
+                            },
+                            wgpu::VertexAttribute {
+                                format: wgpu::VertexFormat::Uint32,
+                                offset: 12,
+                                shader_location: 1,
+                            },
+                        ],
+                    },
+                    ChunkQuadInstance::desc(),
+                ], 
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState { // 3.
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Cw, // 2.
+                cull_mode: Some(wgpu::Face::Back),
+                //cull_mode: None,
+                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+                polygon_mode: wgpu::PolygonMode::Fill,
+                // Requires Features::DEPTH_CLIP_CONTROL
+                unclipped_depth: false,
+                // Requires Features::CONSERVATIVE_RASTERIZATION
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count, // 2.
+                mask: !0, // 3.
+                alpha_to_coverage_enabled: false, // 4.
+            },
+            multiview: None, // 5.
+            cache: None, // 6.
+        };
+
+
+        let render_pipeline = device.create_render_pipeline(&desc);
+        desc.primitive.polygon_mode = wgpu::PolygonMode::Line;
+        let line_render_pipeline = device.create_render_pipeline(&desc);
+
+        desc.label = Some("voxel-water-render-pipeline");
+        desc.primitive.polygon_mode = wgpu::PolygonMode::Fill;
+        desc.primitive.cull_mode = None;
+        desc.depth_stencil.as_mut().unwrap().depth_write_enabled = false;
+        let water_render_pipeline = device.create_render_pipeline(&desc);
+
+        (render_pipeline, line_render_pipeline, water_render_pipeline)
+    }
+}
+
+
 impl UIVertex {
     pub fn new(position: Vec2, uv: Vec2, modulate: Vec4, z: f32) -> Self {
         Self {
@@ -1502,6 +2188,32 @@ impl UIVertex {
 }
 
 
+///
+/// Structures are scaled down by their largest side
+/// so they take up about as much room as an item
+///
+fn fit_item(item_kind: ItemKind, mut instance: MeshInstance) -> MeshInstance {
+    if let ItemKind::Structure(structure) = item_kind {
+        let blocks = structure.blocks(CardinalDirection::North);
+        let mut min = IVec3::MAX;
+        let mut max = IVec3::MIN;
+
+        for &block in blocks {
+            min = min.min(block);
+            max = max.max(block);
+        }
+
+        let size = (max - min).abs() + IVec3::ONE;
+        let size = size.as_vec3().max_element();
+        let (scale, rot, trans) = instance.model.to_scale_rotation_translation();
+        let scale = scale / size;
+        instance.model = Mat4::from_scale_rotation_translation(scale, rot, trans);
+    }
+
+    instance
+}
+
+
 pub fn point_in_rect(point: Vec2, rect_pos: Vec2, rect_size: Vec2) -> bool {
     point.x >= rect_pos.x &&
     point.y >= rect_pos.y &&
@@ -1611,10 +2323,17 @@ impl DepthBuffer {
 }
 
 
+///
+/// The texture the world gets drawn to before being resolved
+/// onto the surface, `None` if `sample_count` is 1
+///
 pub fn create_multisampled_framebuffer(
     device: &wgpu::Device,
     config: &wgpu::SurfaceConfiguration,
-) -> wgpu::TextureView {
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count == 1 { return None }
+
     let size = wgpu::Extent3d {
         width: config.width,
         height: config.height,
@@ -1625,7 +2344,7 @@ pub fn create_multisampled_framebuffer(
     let multisampled_frame_descriptor = &wgpu::TextureDescriptor {
         size,
         mip_level_count: 1,
-        sample_count: MSAA_SAMPLE_COUNT,
+        sample_count,
         dimension: wgpu::TextureDimension::D2,
         format: config.format,
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -1633,9 +2352,11 @@ pub fn create_multisampled_framebuffer(
         view_formats: &[],
     };
 
-    device
+    let view = device
         .create_texture(multisampled_frame_descriptor)
-        .create_view(&wgpu::TextureViewDescriptor::default())
+        .create_view(&wgpu::TextureViewDescriptor::default());
+
+    Some(view)
 }
 
 
@@ -1646,7 +2367,7 @@ pub struct View {
 }
 
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 enum ViewDirection {
     #[default]
     None,
@@ -1657,6 +2378,17 @@ enum ViewDirection {
 }
 
 
+impl ViewDirection {
+    fn axis(self) -> Option<usize> {
+        match self {
+            ViewDirection::None => None,
+            ViewDirection::H => Some(0),
+            ViewDirection::V => Some(1),
+        }
+    }
+}
+
+
 impl View {
     pub fn hstack<'a>(&'a mut self, lambda: impl FnOnce(&mut View)) -> Stack<'a> {
         let mut view = View::default();
@@ -1715,34 +2447,26 @@ impl View {
 
 
     pub fn text(&mut self, str: &str) {
-        self.elements.push(Element::Text(str.to_string()));
+        self.text_ex(str, 1.0);
+    }
+
+
+    pub fn text_ex(&mut self, str: &str, scale: f32) {
+        self.elements.push(Element::Text(str.to_string(), scale));
     }
 
 
+    ///
+    /// The smallest size this view can be laid out in,
+    /// flexboxes don't contribute anything past their `min`
+    ///
     pub fn calc_min_size(&self, renderer: &Renderer) -> Vec2 {
         let mut min_size = Vec2::ZERO;
         for element in &self.elements {
-            let size = match element {
-                Element::Rect(rect) => {
-                    rect.min
-                },
-
-                Element::View(view) => {
-                    view.calc_min_size(renderer)
-                },
-
-                Element::Text(str) => {
-                    let size = renderer.text_size(str, 1.0);
-                    size
-                },
-            };
+            let size = element.min_size(renderer);
 
-
-            let d = match self.direction {
-                ViewDirection::None => continue,
-                ViewDirection::H => 0,
-                ViewDirection::V => 1,
-            };
+            let Some(d) = self.direction.axis()
+            else { min_size = min_size.max(size); continue };
 
             min_size[d] += size[d];
             let d = (d+1) % 2;
@@ -1753,106 +2477,173 @@ impl View {
     }
 
 
+    ///
+    /// Returns true if the view wants to grow along the axis `d`
+    ///
+    fn is_flexible(&self, d: usize) -> bool {
+        self.elements.iter().any(|x| x.is_flexible(self.direction, d))
+    }
+
+
+    ///
+    /// Lays out and draws the view inside of `given_size`
+    /// starting from `start_pos`, returning the size it took up.
+    ///
+    /// The layout happens in two passes, first the minimum size of
+    /// every element is calculated and then whatever space is left
+    /// over along the stack's axis is split evenly between the
+    /// flexible elements.
+    ///
     pub fn render(
         &self, renderer: &mut Renderer,
         given_size: Vec2,
-        start_pos: Vec2, depth: u32
+        start_pos: Vec2, debug_layout: bool,
     ) -> Vec2 {
 
+        let Some(d) = self.direction.axis()
+        else {
+            // zstacks just overlay everything on top of each other
+            let mut curr_size = Vec2::ZERO;
+            for elem in &self.elements {
+                let size = elem.render(renderer, self.direction, given_size, start_pos, debug_layout);
+                curr_size = curr_size.max(size);
+            }
 
-        let min_size = self.calc_min_size(renderer);
+            if debug_layout {
+                renderer.draw_rect(start_pos, curr_size, Vec4::new(0.0, 0.1, 0.0, 0.1));
+            }
+
+            return curr_size;
+        };
+
+
+        // first pass, figure out the minimum sizes
+        let min_sizes = self.elements.iter()
+            .map(|x| x.min_size(renderer))
+            .collect::<Vec<_>>();
+
+        let min_size : f32 = min_sizes.iter().map(|x| x[d]).sum();
         let flex_boxes_count = self.elements.iter()
-            .map(|x| if matches!(x, Element::Rect(ElementRect { kind: RectKind::Flexbox, .. })) { 1 } else { 0 })
-            .sum::<u32>();
-        let left_over_space = given_size - min_size;
-        let spacer_size = left_over_space / flex_boxes_count as f32;
+            .filter(|x| x.is_flexible(self.direction, d))
+            .count();
+
+        let left_over_space = (given_size[d] - min_size).max(0.0);
+        let flex_size = if flex_boxes_count == 0 { 0.0 }
+                        else { left_over_space / flex_boxes_count as f32 };
 
 
+        // second pass, hand out the left over space
         let mut pos = start_pos;
         let mut curr_size = Vec2::ZERO;
+        let cd = (d+1) % 2;
 
-        
-        let d = match self.direction {
-            ViewDirection::None => None,
-            ViewDirection::H => Some(0),
-            ViewDirection::V => Some(1),
-        };
+        for (elem, min) in self.elements.iter().zip(min_sizes) {
+            let mut size = given_size;
+            size[d] = min[d];
+            if elem.is_flexible(self.direction, d) {
+                size[d] += flex_size;
+            }
 
-        
-        for elem in &self.elements {
-            let size = match elem {
-                Element::Rect(rect) => {
-                    if let Some(d) = d && let RectKind::Flexbox = rect.kind {
-                        let mut size = rect.min;
-                        size[d] = size[d].max(spacer_size[d]);
-                        renderer.draw_rect(pos.xy(), size.xy(), rect.colour);
-                        dbg!(rect.colour);
+            let used = elem.render(renderer, self.direction, size, pos, debug_layout);
 
-                        size
+            pos[d] += size[d];
+            curr_size[d] += size[d];
+            curr_size[cd] = curr_size[cd].max(used[cd]);
+        }
 
-                    } else if let RectKind::Frame = rect.kind {
-                        renderer.draw_rect(pos.xy(), rect.min.xy(), rect.colour);
-                        rect.min
 
-                    } else {
-                        warn!("uhhh?");
-                        continue;
-                    }
-                }
+        if debug_layout {
+            renderer.draw_rect(start_pos, curr_size, Vec4::new(0.0, 0.1, 0.0, 0.1));
+        }
 
+        curr_size
+    }
+}
 
-                Element::View(view) => {
-                    let mut view_size = given_size - pos;
-                    if let Some(d) = d && flex_boxes_count != 0 {
-                        view_size[d] = view.calc_min_size(renderer)[d];
-                    }
 
-                    view.render(renderer, view_size, pos, depth)
-                },
+#[derive(Debug)]
+enum Element {
+    Rect(ElementRect),
 
+    View(View),
 
-                Element::Text(text) => {
-                    let size = renderer.text_size(text, 1.0);
-                    renderer.draw_rect(pos.xy(), size, Vec4::ZERO);
-                    renderer.draw_text(text, pos.xy(), 1.0, Vec4::ONE);
-                    size
-                },
-            };
+    Text(String, f32),
+}
 
 
-            renderer.draw_rect(pos.xy(), size.xy(), Vec4::new(0.5, 0.0, 0.0, 0.3));
+impl Element {
+    fn min_size(&self, renderer: &Renderer) -> Vec2 {
+        match self {
+            Element::Rect(rect) => rect.min,
+            Element::View(view) => view.calc_min_size(renderer),
+            Element::Text(str, scale) => renderer.text_size(str, *scale),
+        }
+    }
 
-            if let Some(d) = d {
-                pos[d] += size[d];
 
-                curr_size[d] = curr_size[d].max(size[d]);
-                let d = (d+1) % 2;
-                curr_size[d] = curr_size[d].max(size[d]);
-            } else {
-                curr_size = curr_size.max(size);
-            }
+    ///
+    /// `parent` is the direction of the view this element is in,
+    /// flexboxes only grow along their parent's axis
+    ///
+    fn is_flexible(&self, parent: ViewDirection, d: usize) -> bool {
+        match self {
+            Element::Rect(rect) => {
+                matches!(rect.kind, RectKind::Flexbox)
+                && parent.axis().is_none_or(|axis| axis == d)
+            },
+
+            Element::View(view) => view.is_flexible(d),
+            Element::Text(..) => false,
         }
+    }
 
 
-        if let Some(d) = d && flex_boxes_count != 0 {
-            curr_size[d] = given_size[d];
-        }
+    fn render(
+        &self, renderer: &mut Renderer,
+        parent: ViewDirection,
+        given_size: Vec2,
+        pos: Vec2, debug_layout: bool,
+    ) -> Vec2 {
 
+        let size = match self {
+            Element::Rect(rect) => {
+                let size = match (rect.kind, parent.axis()) {
+                    (RectKind::Frame, _) => rect.min,
 
+                    (RectKind::Flexbox, Some(d)) => {
+                        let mut size = rect.min;
+                        size[d] = size[d].max(given_size[d]);
+                        size
+                    },
 
-        renderer.draw_rect(start_pos.xy(), curr_size.xy(), Vec4::new(0.0, 0.1, 0.0, 0.1));
-        curr_size
-    }
-}
+                    (RectKind::Flexbox, None) => rect.min.max(given_size),
+                };
 
+                if rect.colour.w != 0.0 {
+                    renderer.draw_rect(pos, size, rect.colour);
+                }
 
-#[derive(Debug)]
-enum Element {
-    Rect(ElementRect),
+                size
+            }
 
-    View(View),
 
-    Text(String),
+            Element::View(view) => view.render(renderer, given_size, pos, debug_layout),
+
+
+            Element::Text(text, scale) => {
+                let size = renderer.text_size(text, *scale);
+                renderer.draw_text(text, pos, *scale, Vec4::ONE);
+                size
+            },
+        };
+
+
+        if debug_layout {
+            renderer.draw_rect(pos, size, Vec4::new(0.5, 0.0, 0.0, 0.3));
+        }
+
+        size
+    }
 }
 
 
@@ -1866,13 +2657,13 @@ pub struct Rect<'a> {
 
 #[derive(Default, Debug)]
 pub struct ElementRect {
-    colour: Vec4,
-    kind: RectKind,
-    min: Vec2,
+    pub colour: Vec4,
+    pub kind: RectKind,
+    pub min: Vec2,
 }
 
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Copy)]
 pub enum RectKind {
     #[default]
     Frame,
@@ -1905,20 +2696,6 @@ impl<'a> DerefMut for Rect<'a> {
 }
 
 
-pub struct VStack<'a> {
-    parent: &'a mut View,
-    body: View,
-
-}
-
-
-impl<'a> Drop for VStack<'a> {
-    fn drop(&mut self) {
-        self.parent.elements.push(Element::View(core::mem::take(&mut self.body)));
-    }
-}
-
-
 
 pub struct Stack<'a> {
     parent: &'a mut View,
@@ -1932,5 +2709,3 @@ impl<'a> Drop for Stack<'a> {
         self.parent.elements.push(Element::View(core::mem::take(&mut self.body)));
     }
 }
-
-
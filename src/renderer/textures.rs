@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 
 use bytemuck::{Pod, Zeroable};
-use glam::{IVec2, Mat4, Vec4};
+use glam::{IVec2, Mat4, Vec2, Vec4};
 use sti::{define_key, vec::KVec};
-use wgpu::{BindGroup, Extent3d, RenderPipeline, Sampler, ShaderStages, TextureDimension, TextureFormat, TextureView};
+use wgpu::{BindGroup, Extent3d, RenderPipeline, Sampler, ShaderStages, Texture, TextureDimension, TextureFormat, TextureView};
 
 use super::{uniform::Uniform, UIVertex};
 
@@ -26,6 +26,7 @@ pub struct TextureAtlasBuilder {
 #[derive(Debug)]
 pub struct TextureAtlas {
     uvs: KVec<TextureListId, Vec4>,
+    texture: Texture,
     pub view: TextureView,
     pub sampler: Sampler,
     format: TextureFormat,
@@ -70,6 +71,41 @@ impl UiTextureAtlasManager {
         &mut self.atlases.get_mut(&texture.0).unwrap().3
     }
 
+
+    ///
+    /// Overwrites the pixels of `texture` in its atlas,
+    /// `data` has to be exactly the size it was registered with
+    ///
+    pub fn write(&self, queue: &wgpu::Queue, texture: TextureId, data: &[u8]) {
+        let atlas = &self.atlases[&texture.0].0;
+        let uv = atlas.uvs[texture.1];
+        let line = atlas.texture.width() as f32;
+
+        let origin = (Vec2::new(uv.x, uv.y) * line).round().as_uvec2();
+        let dims = (Vec2::new(uv.z - uv.x, uv.w - uv.y) * line).round().as_uvec2();
+
+        let pixel_size = atlas.format.block_copy_size(Some(wgpu::TextureAspect::All)).unwrap();
+        assert_eq!((dims.x * dims.y * pixel_size) as usize, data.len(),
+                   "format: {:?}, pixel_size: {pixel_size}, dims: {dims}", atlas.format);
+
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &atlas.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: origin.x, y: origin.y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(pixel_size*dims.x),
+                rows_per_image: Some(dims.y),
+            },
+
+            Extent3d { width: dims.x, height: dims.y, depth_or_array_layers: 1 },
+        );
+    }
+
 }
 
 
@@ -217,6 +253,7 @@ impl TextureAtlasBuilder {
 
         TextureAtlas {
             uvs,
+            texture: diffuse_texture,
             view: diffuse_texture_view,
             sampler: diffuse_sampler,
             format: self.data_format,
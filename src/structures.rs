@@ -1,17 +1,25 @@
 pub mod strct;
 pub mod work_queue;
 pub mod belts;
+pub mod pipes;
+pub mod logistics;
 pub mod inventory;
+pub mod transfer;
 
 
+use belts::{Belts, NodeId};
 use glam::{DVec3, IVec3, Mat4, Quat, Vec3, Vec4};
 use inventory::StructureInventory;
-use sti::{define_key, hash::fxhash::fxhash32};
-use strct::{rotate_block_vector, InserterState, Structure, StructureData, StructureKind};
-use tracing::warn;
+use pipes::Fluid;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::{HashMap, HashSet};
+use sti::{define_key, hash::fxhash::fxhash32, vec::KVec};
+use strct::{facing_towards, orientation_tilt, rotate_block_vector, rotate_oriented_vector, InserterState, Structure, StructureData, StructureKind};
+use tracing::{info, warn};
+use transfer::TransferRate;
 use work_queue::WorkQueue;
 
-use crate::{constants::{DROPPED_ITEM_SCALE, FURNACE_COST_PER_SMELT, TICKS_PER_SECOND}, crafting::{Recipe, FURNACE_RECIPES}, directions::CardinalDirection, entities::EntityMap, gen_map::{KGenMap, KeyGen}, items::{Item, ItemKind}, mesh::MeshInstance, renderer::Renderer, structures::inventory::SlotKind, voxel_world::{split_world_pos, voxel::Voxel, VoxelWorld}, Camera, Tick};
+use crate::{constants::{BELT_JAM_UPDATES, BELT_MAX_SPEED, BELT_UPDATE_INTERVAL, COLOUR_DENY, COLOUR_PASS, COLOUR_WARN, DROPPED_ITEM_SCALE, FURNACE_COST_PER_SMELT, LOGISTICS_TRANSFER_RATE, OIL_ENERGY_PER_UNIT, PRODUCTIVITY_MODULE_BONUS, PUMP_RATE, SPEED_MODULE_BONUS, STRUCTURE_GRID_CELL, TEST_SITE, TICKS_PER_SECOND}, crafting::{Recipe, FURNACE_RECIPES}, directions::CardinalDirection, entities::EntityMap, gen_map::{KGenMap, KeyGen}, items::{Item, ItemKind}, mesh::MeshInstance, renderer::Renderer, structures::inventory::{Filter, SlotKind}, voxel_world::{split_world_pos, voxel::Voxel, VoxelWorld, SURROUNDING_OFFSETS}, Camera, Tick};
 
 define_key!(pub StructureKey(u32));
 define_key!(pub StructureGen(u32));
@@ -25,6 +33,26 @@ pub struct Structures {
     pub work_queue: WorkQueue,
     pub to_be_awoken: Vec<StructureId>,
     pub current_tick: Tick,
+
+    /// a structure whose item transfers get logged until the given tick
+    pub watched: Option<(StructureId, Tick)>,
+
+    /// every structure bucketed by the `STRUCTURE_GRID_CELL`
+    /// sized cell its `position` is in
+    grid: HashMap<IVec3, Vec<StructureId>>,
+
+    /// how many belt updates in a row the belt or splitter at a
+    /// position held items without any of them moving
+    belt_stalls: HashMap<IVec3, u32>,
+    /// pumps sitting idle because their pipe network already
+    /// carries another fluid, kept so each one only gets warned
+    /// about once
+    blocked_pumps: HashSet<IVec3>,
+    /// how many items each structure has taken in and handed
+    /// out so far this tick, see `StructureKind::transfer_rate`
+    transfers: HashMap<StructureId, TransferRate>,
+    /// tint belts by how full they are and highlight jammed ones
+    pub show_belt_flow: bool,
 }
 
 
@@ -35,17 +63,110 @@ impl Structures {
             work_queue: WorkQueue::new(),
             current_tick: Tick::initial(),
             to_be_awoken: vec![],
+            watched: None,
+            grid: HashMap::new(),
+            belt_stalls: HashMap::new(),
+            blocked_pumps: HashSet::new(),
+            transfers: HashMap::new(),
+            show_belt_flow: false,
         }
     }
 
 
     pub fn insert(&mut self, structure: Structure) -> StructureId {
-        StructureId(self.structs.insert(structure))
+        let cell = grid_cell(structure.position);
+        let id = StructureId(self.structs.insert(structure));
+        self.grid.entry(cell).or_default().push(id);
+        id
     }
 
 
     pub fn remove(&mut self, id: StructureId) -> Structure {
-        self.structs.remove(id.0)
+        let structure = self.structs.remove(id.0);
+
+        let cell = grid_cell(structure.position);
+        let bucket = self.grid.get_mut(&cell).unwrap();
+        bucket.retain(|&x| x != id);
+        if bucket.is_empty() {
+            self.grid.remove(&cell);
+        }
+
+        self.belt_stalls.remove(&structure.position);
+        self.blocked_pumps.remove(&structure.position);
+        structure
+    }
+
+
+    ///
+    /// Every structure whose `position` is within `radius` of `pos`,
+    /// only looks at the grid cells the sphere touches
+    ///
+    pub fn query_radius(&self, pos: DVec3, radius: f64) -> Vec<StructureId> {
+        let min = grid_cell((pos - radius).floor().as_ivec3());
+        let max = grid_cell((pos + radius).floor().as_ivec3());
+
+        let mut result = vec![];
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    let Some(bucket) = self.grid.get(&IVec3::new(x, y, z))
+                    else { continue };
+
+                    result.extend(bucket.iter()
+                        .filter(|&&id| grid_distance_squared(self.get(id), pos) <= radius * radius));
+                }
+            }
+        }
+
+        result
+    }
+
+
+    ///
+    /// The structure of `kind` whose `position` is closest to `pos`.
+    /// Searches outwards one ring of grid cells at a time and stops
+    /// once no cell further out could hold anything closer
+    ///
+    pub fn nearest(&self, pos: DVec3, kind: StructureKind) -> Option<StructureId> {
+        let centre = grid_cell(pos.floor().as_ivec3());
+        let furthest = self.grid.keys()
+            .map(|cell| (*cell - centre).abs().max_element())
+            .max()?;
+
+        let mut best : Option<(f64, StructureId)> = None;
+        for ring in 0..=furthest {
+            // everything on this ring is at least this far away
+            let min_dist = ((ring - 1).max(0) * STRUCTURE_GRID_CELL) as f64;
+            if let Some((dist, _)) = best
+                && dist < min_dist * min_dist {
+                break;
+            }
+
+            for x in -ring..=ring {
+                for y in -ring..=ring {
+                    // only the shell of the cube is on this ring
+                    let on_shell = x.abs() == ring || y.abs() == ring;
+                    let step = if on_shell { 1 } else { (2 * ring) as usize };
+
+                    for z in (-ring..=ring).step_by(step) {
+                        let Some(bucket) = self.grid.get(&(centre + IVec3::new(x, y, z)))
+                        else { continue };
+
+                        for &id in bucket {
+                            let structure = self.get(id);
+                            if structure.data.as_kind() != kind { continue }
+
+                            let dist = grid_distance_squared(structure, pos);
+                            if best.is_none_or(|(best, _)| dist < best) {
+                                best = Some((dist, id));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, id)| id)
     }
 
 
@@ -84,10 +205,66 @@ impl Structures {
     }
 
 
+    pub fn log_transfer(&self, id: StructureId, action: &str, item: Item) {
+        let Some((watched, until)) = self.watched
+        else { return };
+
+        if watched != id || self.current_tick > until { return }
+
+        info!("watch: tick {}: {action} {}x {}", self.current_tick.u32(), item.amount, item.kind.to_string());
+    }
+
+
+    ///
+    /// How many more items `id` can take in this tick
+    ///
+    pub fn accept_budget(&self, id: StructureId) -> u32 {
+        let rate = self.get(id).data.as_kind().transfer_rate();
+        let used = self.transfers.get(&id).map_or(0, |x| x.accept);
+        rate.accept.saturating_sub(used)
+    }
+
+
+    ///
+    /// How many more items `id` can hand out this tick
+    ///
+    pub fn provide_budget(&self, id: StructureId) -> u32 {
+        let rate = self.get(id).data.as_kind().transfer_rate();
+        let used = self.transfers.get(&id).map_or(0, |x| x.provide);
+        rate.provide.saturating_sub(used)
+    }
+
+
+    ///
+    /// Counts `amount` items moving out of `from` and into `to`
+    /// against their transfer rates for the rest of the tick
+    ///
+    pub fn record_transfer(&mut self, from: Option<StructureId>, to: Option<StructureId>, amount: u32) {
+        if let Some(from) = from {
+            let used = self.transfers.entry(from).or_insert(TransferRate { accept: 0, provide: 0 });
+            used.provide = used.provide.saturating_add(amount);
+        }
+
+        if let Some(to) = to {
+            let used = self.transfers.entry(to).or_insert(TransferRate { accept: 0, provide: 0 });
+            used.accept = used.accept.saturating_add(amount);
+        }
+    }
+
+
     pub fn process(&mut self, entities: &mut EntityMap, world: &mut VoxelWorld) {
         self.current_tick = self.current_tick.inc();
-        if self.current_tick.0 % 5 == 0 {
+        self.transfers.clear();
+
+        if let Some((_, until)) = self.watched
+            && self.current_tick > until {
+            info!("watch: tick {}: stopped watching", self.current_tick.u32());
+            self.watched = None;
+        }
+        if self.current_tick.0 % BELT_UPDATE_INTERVAL == 0 {
             self.update_belts(world);
+            self.update_pipes(world);
+            self.update_logistics();
         }
 
         let to_be_updated = self.work_queue.process(self.current_tick);
@@ -99,8 +276,28 @@ impl Structures {
             Structure::wake_up(id, self, world);
         }
 
-        for id in to_be_updated {
-            Structure::update(id.1, self, entities, world);
+        // self-contained furnaces and assemblers don't care about order so they run first, in parallel
+        let (mut local, rest) : (Vec<_>, Vec<_>) = to_be_updated.into_iter()
+            .map(|(_, id)| id)
+            .partition(|&id| self.watched.is_none_or(|(watched, _)| watched != id)
+                             && self.get(id).is_self_contained());
+
+        local.sort();
+        local.dedup();
+
+        let schedules : Vec<_> = self.structs.get_sorted_mut(&local)
+            .into_par_iter()
+            .map(|(id, structure)| (StructureId(id), structure.update_self_contained()))
+            .collect();
+
+        for (id, schedule) in schedules {
+            if let Some(ticks) = schedule {
+                self.schedule_in(id, ticks);
+            }
+        }
+
+        for id in rest {
+            Structure::update(id, self, entities, world);
         }
     }
 
@@ -111,8 +308,7 @@ impl Structures {
 
         let placement_origin = structure.zero_zero();
 
-        let blocks = structure.data.as_kind().blocks(structure.direction);
-        for offset in blocks {
+        for offset in structure.blocks() {
             let pos = placement_origin + offset;
             let (chunk_pos, voxel_pos) = split_world_pos(pos);
             let chunk = world.get_chunk_mut(chunk_pos);
@@ -125,14 +321,68 @@ impl Structures {
     }
 
 
+    ///
+    /// Places a `kind` at `offset` from `TEST_SITE`. There's no
+    /// terrain up there so tests can build whatever they need
+    /// without having to clear the space first
+    ///
+    #[doc(hidden)]
+    pub fn place_at_test_site(&mut self, world: &mut VoxelWorld, kind: StructureKind, offset: IVec3, direction: CardinalDirection) -> StructureId {
+        let structure = Structure::from_kind(kind, TEST_SITE + offset, direction);
+        self.add_structure(world, structure)
+    }
+
+
     fn update_belts(&mut self, world: &mut VoxelWorld) {
         let belts = self.belts(world);
+        let mut moved = KVec::from_value(belts.nodes.len(), false);
+
+        let mut curves = KVec::from_value(belts.nodes.len(), None);
+        for &node_id in &belts.worklist {
+            curves[node_id] = self.belt_curve(world, self.get(belts.node(node_id).structure_id));
+        }
+
+        // faster belts get extra passes so items on them move
+        // further per update, a slower belt further down the chain
+        // only accepts items on the passes it moves in itself
+        for pass in 0..BELT_MAX_SPEED {
+            self.update_belt_pass(&belts, &curves, pass, &mut moved);
+        }
 
 
+        for &node_id in &belts.worklist {
+            let structure = self.get(belts.node(node_id).structure_id);
+            let is_empty = structure.inventory.as_ref().unwrap().slots.iter().all(|x| x.is_none());
+            let position = structure.position;
+
+            if moved[node_id] || is_empty {
+                self.belt_stalls.remove(&position);
+            } else {
+                *self.belt_stalls.entry(position).or_default() += 1;
+            }
+        }
+    }
+
+
+    ///
+    /// Moves the items on every belt and splitter faster than `pass`
+    /// forward by a slot, `moved` is set for the ones that did.
+    /// `curves` is every node's `belt_curve`
+    ///
+    fn update_belt_pass(&mut self, belts: &Belts, curves: &KVec<NodeId, Option<i32>>, pass: u32, moved: &mut KVec<NodeId, bool>) {
         // we iterate in reverse because belts
         // update from the last node to the first
-        for &node in belts.worklist.iter().rev() {
-            let node = belts.node(node);
+        for &node_id in belts.worklist.iter().rev() {
+            let node = belts.node(node_id);
+
+            let speed = match &self.structs.get(node.structure_id.0).unwrap().data {
+                StructureData::Belt { tier } => tier.speed(),
+                _ => 1,
+            };
+
+            if pass >= speed { continue }
+
+            let [curve1, curve2] = node.outputs.map(|x| x.and_then(|x| curves[x]));
 
             // extract out the references
             let [structure, output1, output2] = match node.outputs {
@@ -170,28 +420,193 @@ impl Structures {
             let inventory = structure.inventory.as_mut().unwrap();
             let inventory = &mut inventory.slots;
             match &mut structure.data {
-                StructureData::Belt { } => {
+                StructureData::Belt { .. } => {
                     assert!(output2.is_none());
                     let output = output1;
-                    Self::process_lanes(&mut inventory[..4], output);
+                    moved[node_id] |= Self::process_lanes(&mut inventory[..4], output, curve1, pass);
                 },
 
 
                 StructureData::Splitter { .. } => {
-                    for (lane, output) in [output1, output2].into_iter().enumerate() {
+                    for (lane, (output, curve)) in [(output1, curve1), (output2, curve2)].into_iter().enumerate() {
                         let inventory = &mut inventory[lane*4..(lane+1)*4];
-                        Self::process_lanes(inventory, output);
+                        moved[node_id] |= Self::process_lanes(inventory, output, curve, pass);
                     }
                 },
 
                 _ => unreachable!(),
             }
+        }
+    }
+
+
+    ///
+    /// Whether the belt or splitter has held items without
+    /// moving any of them for `BELT_JAM_UPDATES` belt updates
+    ///
+    pub fn is_belt_jammed(&self, structure: &Structure) -> bool {
+        self.belt_stalls.get(&structure.position).is_some_and(|&x| x >= BELT_JAM_UPDATES)
+    }
+
+
+    ///
+    /// The colour belts are tinted with while `show_belt_flow` is on,
+    /// going from `COLOUR_PASS` when empty to `COLOUR_WARN` when full
+    /// and `COLOUR_DENY` when jammed. White for everything else
+    ///
+    fn belt_flow_colour(&self, structure: &Structure) -> Vec4 {
+        if !self.show_belt_flow { return Vec4::ONE }
+        if !matches!(structure.data, StructureData::Belt { .. } | StructureData::Splitter { .. }) { return Vec4::ONE }
+        if self.is_belt_jammed(structure) { return COLOUR_DENY }
+
+        let slots = &structure.inventory.as_ref().unwrap().slots;
+        let fullness = slots.iter().flatten().count() as f32 / slots.len() as f32;
+        COLOUR_PASS.lerp(COLOUR_WARN, fullness)
+    }
+
+
+    ///
+    /// Pumps fluid into every pipe network, lets furnaces next
+    /// to oil burn some of it and spreads whatever's left evenly
+    /// between the network's pipes
+    ///
+    fn update_pipes(&mut self, world: &mut VoxelWorld) {
+        let pipes = self.pipes(world);
+
+        for network in pipes.networks.krange() {
+            let network = &pipes.networks[network];
+            let mut fluid = network.fluid;
+            let mut amount = network.amount;
+
+            for &node in &network.nodes {
+                let structure = self.get(pipes.node(node).structure_id);
+                if !matches!(structure.data, StructureData::Pump) { continue }
+
+                let position = structure.position;
+                let pumped = Fluid::pumped_from(world.get_voxel(position - IVec3::Y));
+                if let Some(carried) = fluid
+                    && carried != pumped {
+                    if self.blocked_pumps.insert(position) {
+                        warn!("pump at {position} can't push {} into a network of {}, it'll sit idle",
+                              pumped.to_string(), carried.to_string());
+                    }
+
+                    continue;
+                }
+
+                self.blocked_pumps.remove(&position);
+
+                fluid = Some(pumped);
+                amount = (amount + PUMP_RATE).min(network.capacity());
+            }
+
+
+            if fluid == Some(Fluid::Oil) {
+                for &consumer in &network.consumers {
+                    let consumer_structure = self.get(consumer);
+                    let cost = match &consumer_structure.data {
+                        StructureData::Furnace(furnace) => furnace.cost_per_smelt(),
+                        _ => FURNACE_COST_PER_SMELT,
+                    };
+
+                    let energy = consumer_structure.energy.energy;
+                    if energy >= cost { continue }
+
+                    // a smaller top up further down might still fit
+                    let units = (cost - energy).div_ceil(OIL_ENERGY_PER_UNIT);
+                    if units > amount { continue }
+
+                    amount -= units;
+
+                    // wakes the furnace up if it ran out of fuel
+                    self.get_mut(consumer).energy.energy += units * OIL_ENERGY_PER_UNIT;
+                }
+            }
+
+            if amount == 0 { fluid = None }
+
+
+            let pipe_count = network.pipes.max(1);
+            let share = amount / pipe_count;
+            let mut remainder = amount % pipe_count;
+
+            for &node in &network.nodes {
+                let structure = self.get_mut_without_wake_up(pipes.node(node).structure_id);
+                let StructureData::Pipe { fluid: pipe_fluid, amount: pipe_amount } = &mut structure.data
+                else { continue };
+
+                *pipe_fluid = fluid;
+                *pipe_amount = share + (remainder > 0) as u32;
+                remainder = remainder.saturating_sub(1);
+            }
+        }
+    }
+
+
+    fn update_logistics(&mut self) {
+        let logistics = self.logistics();
 
+        for network in logistics.networks.krange() {
+            let network = &logistics.networks[network];
+            // the rate is per tick but it only runs every belt update
+            let mut budget = LOGISTICS_TRANSFER_RATE * BELT_UPDATE_INTERVAL;
+
+            'requesters: for &requester in &network.requesters {
+                let StructureData::RequesterChest { requests } = &self.get(requester).data
+                else { unreachable!() };
+
+                'requests: for request in requests.clone() {
+                    let inventory = self.get(requester).inventory.as_ref().unwrap();
+                    let stocked : u32 = inventory.slots.iter()
+                        .flatten()
+                        .filter(|x| x.kind == request.kind)
+                        .map(|x| x.amount)
+                        .sum();
+
+                    let mut missing = request.amount.saturating_sub(stocked);
+
+                    for &provider in &network.providers {
+                        if missing == 0 { break }
+
+                        let slots = &self.get(provider).inventory.as_ref().unwrap().slots;
+                        let Some(index) = slots.iter().position(|x| x.is_some_and(|x| x.kind == request.kind))
+                        else { continue };
+
+                        let amount = slots[index].unwrap().amount.min(missing).min(budget)
+                            .min(self.provide_budget(provider))
+                            .min(self.accept_budget(requester));
+                        if amount == 0 { continue }
+
+                        let item = Item::new(request.kind, amount);
+                        // another request might still fit
+                        if !self.get(requester).can_accept(item) { continue 'requests }
+
+                        let slot = &mut self.get_mut_without_wake_up(provider).inventory.as_mut().unwrap().slots[index];
+                        let remaining = slot.as_mut().unwrap();
+                        remaining.amount -= amount;
+                        if remaining.amount == 0 { *slot = None }
+
+                        self.get_mut(requester).give_item(item);
+                        self.record_transfer(Some(provider), Some(requester), amount);
+                        self.log_transfer(requester, "received", item);
+
+                        missing -= amount;
+                        budget -= amount;
+                        if budget == 0 { break 'requesters }
+                    }
+                }
+            }
         }
     }
 
 
-    fn process_lanes(inventory: &mut [Option<Item>], mut output: Option<&mut Structure>) {
+    ///
+    /// Moves the items on a belt's two lanes forward, into
+    /// `output` if they're at the front. `output_curve` is the
+    /// output's `belt_curve`. Returns whether any moved
+    ///
+    fn process_lanes(inventory: &mut [Option<Item>], mut output: Option<&mut Structure>, output_curve: Option<i32>, pass: u32) -> bool {
+        let mut moved = false;
         for i in 0..4 {
             let lane = i/2;
             let i = i%2;
@@ -199,27 +614,43 @@ impl Structures {
 
             if i > 0 && inventory[i-1].is_none() {
                 let item = &mut inventory[i];
+                moved |= item.is_some();
                 inventory[i-1] = item.take();
                 continue;
             }
 
             let item = &mut inventory[i];
+            let had_item = item.is_some();
             let Some(output_structure) = &mut output
             else { continue };
 
             match &mut output_structure.data {
-                StructureData::Belt { } => {
+                StructureData::Belt { tier } => {
+                    if pass >= tier.speed() { continue }
+
+                    // lanes are numbered in each belt's own frame so
+                    // they keep to the same side going round a curve
                     let inventory = &mut output_structure.inventory.as_mut().unwrap().slots;
-                    if inventory[lane * 2 + 1].is_none() {
-                        inventory[lane * 2 + 1] = item.take();
+                    let slot = belt_entry_slot(output_curve, lane);
+                    if inventory[slot].is_none() {
+                        inventory[slot] = item.take();
                     }
                 },
 
 
-                StructureData::Splitter { priority } => {
+                StructureData::Splitter { priority, filters, preferred } => {
+                    if pass > 0 { continue }
+
+                    let kind = item.map(|x| x.kind);
+                    let first = preferred.unwrap_or(priority[lane] as usize);
                     for side in [0, 1] {
                         let inventory = &mut output_structure.inventory.as_mut().unwrap().slots;
-                        let side = (priority[lane] as usize + side) % 2;
+                        let side = (first + side) % 2;
+                        if let Some(kind) = kind
+                            && !splitter_side_accepts(*filters, side, kind) {
+                            continue;
+                        }
+
                         let inventory = &mut inventory[side*4..(side+1)*4];
                         let inventory = &mut inventory[lane*2..(lane+1)*2];
 
@@ -228,6 +659,7 @@ impl Structures {
                             *slot = item.take();
                             priority[lane] += 1;
                             priority[lane] %= 2;
+                            break;
                         }
 
                     }
@@ -235,7 +667,11 @@ impl Structures {
 
                 _ => unreachable!(),
             }
+
+            moved |= had_item && item.is_none();
         }
+
+        moved
     }
 
 }
@@ -261,11 +697,23 @@ impl Structure {
             return;
         }
 
+        if let StructureData::Furnace(furnace) = &structure.data
+            && furnace.auto_fuel {
+            Structure::pull_fuel(id, structures, world);
+        }
+
+        let structure = structures.get_mut_without_wake_up(id);
+        if let StructureData::Assembler { auto_output: true, .. } = &structure.data {
+            Structure::push_output(id, structures, world);
+        }
+
+        let structure = structures.get_mut_without_wake_up(id);
         let dir = structure.direction;
         let zz = structure.zero_zero();
+        let kind = structure.data.as_kind();
 
         match &mut structure.data {
-            StructureData::Quarry { current_progress } => {
+            StructureData::Quarry { current_progress, size, max_depth } => {
                 let inventory = &mut structure.inventory.as_mut().unwrap();
                 debug_assert!(inventory.outputs_len() == 1);
 
@@ -280,11 +728,12 @@ impl Structure {
                 }
 
 
-                let x = *current_progress % 3;
-                let z = (*current_progress / 3) % 3;
-                let y = *current_progress / 9;
+                let pos = quarry_offset(*current_progress, *size);
+                if (-pos.y) as u32 > *max_depth {
+                    structure.is_asleep = true;
+                    return;
+                }
 
-                let pos = IVec3::new(x as i32 + 1, -(y as i32) - 1, z as i32 + 1);
                 let pos = rotate_block_vector(dir, pos);
 
                 let voxel = world.get_voxel(zz + pos);
@@ -306,22 +755,26 @@ impl Structure {
             },
 
 
-            StructureData::Inserter { state, filter } => {
+            StructureData::Inserter { state, filter, .. } => {
                 let mut final_state = InserterState::Searching;
 
-                let output_structure_position = zz + rotate_block_vector(structure.direction, IVec3::new(-1, 0, 0));
-                let input_structure_position = zz + rotate_block_vector(structure.direction, IVec3::new(3, 0, 0));
+                let orientation = structure.orientation;
+                let output_structure_position = zz + rotate_oriented_vector(dir, orientation, kind.inserter_output());
+                let mut input_structure_positions = kind.inserter_inputs().iter()
+                    .map(|&offset| zz + rotate_oriented_vector(dir, orientation, offset));
                 let filter = *filter;
 
 
                 'body: { match state {
                     InserterState::Searching => {
-                        let Some(input_structure_id) = world.structure_blocks.get(&input_structure_position)
+                        let Some(input_structure_id) = input_structure_positions.find_map(|pos| world.structure_blocks.get(&pos))
                         else { break 'body };
                         let Some(output_structure_id) = world.structure_blocks.get(&output_structure_position)
                         else { break 'body };
 
                         let input_structure = structures.get(*input_structure_id);
+                        if structures.provide_budget(*input_structure_id) == 0 { break 'body }
+                        if structures.accept_budget(id) == 0 { break 'body }
 
 
                         let available_items_len = input_structure.available_items_len();
@@ -343,13 +796,17 @@ impl Structure {
 
                             let output_structure = structures.get(*output_structure_id);
                             if !output_structure.can_accept(item)
-                                && output_structure.data.as_kind() != StructureKind::Belt {
+                                && !output_structure.data.as_kind().is_belt() {
                                 // better luck next time
                                 continue;
                             }
 
                             // yippie!
-                            structures.get_mut(*input_structure_id).try_take(index, 1).unwrap();
+                            let input_structure_id = *input_structure_id;
+                            structures.get_mut(input_structure_id).try_take(index, 1).unwrap();
+                            structures.record_transfer(Some(input_structure_id), Some(id), 1);
+                            structures.log_transfer(input_structure_id, "took", item);
+                            structures.log_transfer(id, "picked up", item);
 
                             final_state = InserterState::Placing(item);
                             break 'body;
@@ -363,8 +820,17 @@ impl Structure {
                         else { break 'body };
 
                         let item = *item;
-                        let output_structure = structures.get_mut(*output_structure_id);
-                        if let StructureData::Belt = &mut output_structure.data {
+                        let output_structure_id = *output_structure_id;
+
+                        // try again next tick once the budgets reset
+                        if structures.accept_budget(output_structure_id) == 0
+                            || structures.provide_budget(id) == 0 {
+                            structures.schedule_in(id, 1);
+                            return;
+                        }
+
+                        let output_structure = structures.get_mut(output_structure_id);
+                        if let StructureData::Belt { .. } = &mut output_structure.data {
                             let inventory = &mut output_structure.inventory.as_mut().unwrap().slots;
                             let lane = placement_lane(dir, output_structure.direction);
                             let inventory = &mut inventory[lane*2..(lane+1)*2];
@@ -373,6 +839,9 @@ impl Structure {
                                 let slot = &mut inventory[index];
                                 if slot.is_none() {
                                     *slot = Some(item);
+                                    structures.record_transfer(Some(id), Some(output_structure_id), 1);
+                                    structures.log_transfer(output_structure_id, "received", item);
+                                    structures.log_transfer(id, "placed", item);
                                     final_state = InserterState::Searching;
                                     break 'body;
                                 }
@@ -390,6 +859,9 @@ impl Structure {
                         }
 
                         output_structure.give_item(item);
+                        structures.record_transfer(Some(id), Some(output_structure_id), 1);
+                        structures.log_transfer(output_structure_id, "received", item);
+                        structures.log_transfer(id, "placed", item);
 
                         let structure = structures.get_mut_without_wake_up(id);
 
@@ -418,31 +890,46 @@ impl Structure {
             },
 
 
-            StructureData::Assembler { recipe } => {
+            StructureData::Assembler { recipe, auto_output, modules, productivity } => {
                 let Some(recipe) = recipe
                 else { structure.is_asleep = true; return };
 
-                let inventory = structure.inventory.as_mut().unwrap();
-                let output = inventory.output_mut(0);
-                match output {
-                    Some(v) => v.amount += recipe.result.amount,
-                    None => *output = Some(recipe.result),
+                let bonus = assembler_craft(structure.inventory.as_mut().unwrap(), *recipe, modules, productivity);
+
+                let recipe = *recipe;
+                let auto_output = *auto_output;
+                let modules = *modules;
+                structures.log_transfer(id, "produced", recipe.result);
+                if bonus {
+                    structures.log_transfer(id, "bonus", recipe.result);
                 }
 
-                if try_consume(inventory, *recipe) {
-                    let time = recipe.time;
+                if auto_output {
+                    Structure::push_output(id, structures, world);
+                }
+
+                let structure = structures.get_mut_without_wake_up(id);
+                let inventory = structure.inventory.as_mut().unwrap();
+                if try_consume(inventory, recipe) {
+                    recipe.requirements.iter().for_each(|&item| structures.log_transfer(id, "consumed", item));
+                    let time = assembler_time(recipe, &modules);
                     structures.schedule_in(id, time);
                 } else {
                     structure.is_asleep = true;
+                    Structure::poll_output(id, structures);
                 }
 
             }
 
 
             StructureData::Furnace(furnace) => {
-                furnace.process(structure.inventory.as_mut().unwrap());
-                if let Some(schedule) = furnace.attempt(structure.inventory.as_mut().unwrap(), &mut structure.energy) {
+                if let Some(schedule) = furnace.step(structure.inventory.as_mut().unwrap(), &mut structure.energy) {
                     structures.schedule_in(id, schedule);
+                } else if furnace.auto_fuel && structure.inventory.as_ref().unwrap().input(0).0.is_some() {
+                    // nothing wakes us up when a neighbour gets fuel
+                    // so keep polling while there's something to smelt
+                    furnace.waiting_for_fuel = true;
+                    structures.schedule_in(id, TICKS_PER_SECOND);
                 } else {
                     structure.is_asleep = true;
                 }
@@ -451,16 +938,78 @@ impl Structure {
 
             StructureData::Chest { .. } => {},
             StructureData::Silo { .. } => {},
+            StructureData::ProviderChest => {},
+            StructureData::RequesterChest { .. } => {},
             StructureData::Belt { .. } => {},
             StructureData::Splitter { .. } => {},
+            StructureData::Pump => {},
+            StructureData::Pipe { .. } => {},
+        }
+    }
+
+
+    ///
+    /// Whether the next `update` only reads and writes this
+    /// structure, so it can run alongside other updates
+    ///
+    fn is_self_contained(&self) -> bool {
+        if self.is_asleep { return false }
+
+        match &self.data {
+            StructureData::Furnace(furnace) => !furnace.auto_fuel,
+            StructureData::Assembler { auto_output, .. } => !auto_output,
+            _ => false,
         }
     }
 
 
+    ///
+    /// `update` for structures that are `is_self_contained`,
+    /// returns in how many ticks it wants to be updated again
+    /// or `None` if it fell asleep
+    ///
+    fn update_self_contained(&mut self) -> Option<u32> {
+        let schedule = match &mut self.data {
+            StructureData::Assembler { recipe, modules, productivity, .. } => 'assembler: {
+                let Some(recipe) = *recipe
+                else { break 'assembler None };
+
+                let inventory = self.inventory.as_mut().unwrap();
+                assembler_craft(inventory, recipe, modules, productivity);
+                try_consume(inventory, recipe).then(|| assembler_time(recipe, modules))
+            },
+
+
+            StructureData::Furnace(furnace) => furnace.step(self.inventory.as_mut().unwrap(), &mut self.energy),
+
+
+            _ => unreachable!("only furnaces and assemblers are self contained"),
+        };
+
+        if schedule.is_none() {
+            self.is_asleep = true;
+        }
+
+        schedule
+    }
+
+
     pub fn wake_up(id: StructureId, structures: &mut Structures, world: &mut VoxelWorld) {
         let structure = structures.get_mut_without_wake_up(id);
         assert!(structure.is_asleep);
 
+        if let StructureData::Furnace(furnace) = &structure.data
+            && furnace.auto_fuel {
+            Structure::pull_fuel(id, structures, world);
+        }
+
+        let structure = structures.get_mut_without_wake_up(id);
+        if let StructureData::Assembler { auto_output: true, .. } = &structure.data {
+            Structure::push_output(id, structures, world);
+        }
+
+        let structure = structures.get_mut_without_wake_up(id);
+
         let dir = structure.direction;
         let zz = structure.zero_zero();
 
@@ -468,13 +1017,16 @@ impl Structure {
         structure.is_asleep = false;
 
         match &mut structure.data {
-            StructureData::Quarry { current_progress, .. } => {
+            StructureData::Quarry { current_progress, size, max_depth } => {
                 loop {
+                    let pos = quarry_offset(*current_progress, *size);
+
+                    // done digging
+                    if (-pos.y) as u32 > *max_depth {
+                        structure.is_asleep = true;
+                        break;
+                    }
 
-                    let x = *current_progress % 3;
-                    let z = (*current_progress / 3) % 3;
-                    let y = *current_progress / 9;
-                    let pos = IVec3::new(x as i32 + 1, -(y as i32) - 1, z as i32 + 1);
                     let pos = rotate_block_vector(dir, pos);
                     let voxel = world.get_voxel(zz + pos);
 
@@ -499,17 +1051,21 @@ impl Structure {
             },
 
 
-            StructureData::Assembler { recipe } => {
+            StructureData::Assembler { recipe, modules, .. } => {
                 let Some(recipe) = recipe
                 else { structure.is_asleep = true; return };
 
+                let recipe = *recipe;
+                let modules = *modules;
                 let inventory = structure.inventory.as_mut().unwrap();
 
-                if try_consume(inventory, *recipe) {
-                    let time = recipe.time;
+                if try_consume(inventory, recipe) {
+                    recipe.requirements.iter().for_each(|&item| structures.log_transfer(id, "consumed", item));
+                    let time = assembler_time(recipe, &modules);
                     structures.schedule_in(id, time);
                 } else {
                     structure.is_asleep = true;
+                    Structure::poll_output(id, structures);
                 }
             }
 
@@ -517,6 +1073,9 @@ impl Structure {
             StructureData::Furnace(furnace) => {
                 if let Some(schedule) = furnace.attempt(structure.inventory.as_mut().unwrap(), &mut structure.energy) {
                     structures.schedule_in(id, schedule);
+                } else if furnace.auto_fuel && structure.inventory.as_ref().unwrap().input(0).0.is_some() {
+                    furnace.waiting_for_fuel = true;
+                    structures.schedule_in(id, TICKS_PER_SECOND);
                 } else {
                     structure.is_asleep = true;
                 }
@@ -525,23 +1084,244 @@ impl Structure {
 
             StructureData::Chest { .. } => {}
             StructureData::Silo { .. } => {}
+            StructureData::ProviderChest => {}
+            StructureData::RequesterChest { .. } => {}
             StructureData::Belt { .. } => {}
             StructureData::Splitter { .. } => {}
+            StructureData::Pump => {}
+            StructureData::Pipe { .. } => {}
         }
     }
 
 
 
-    pub fn render(&self, structures: &Structures, camera: &Camera, renderer: &mut Renderer) {
+    ///
+    /// Tops up an empty fuel slot from an adjacent chest, silo or belt
+    /// when the furnace can't afford another smelt
+    ///
+    fn pull_fuel(id: StructureId, structures: &mut Structures, world: &VoxelWorld) {
+        let structure = structures.get(id);
+        let StructureData::Furnace(furnace) = &structure.data
+        else { return };
+
+        if structure.energy.energy >= furnace.cost_per_smelt() { return }
+
+        let inventory = structure.inventory.as_ref().unwrap();
+        if inventory.input(1).0.is_some() { return }
+
+        let zz = structure.zero_zero();
+        for offset in structure.blocks() {
+            for neighbour in SURROUNDING_OFFSETS {
+                let pos = zz + offset + *neighbour;
+                let Some(&other_id) = world.structure_blocks.get(&pos)
+                else { continue };
+
+                if other_id == id { continue }
+
+                let other = structures.get(other_id);
+                if !matches!(other.data,   StructureData::Chest
+                                         | StructureData::Silo
+                                         | StructureData::ProviderChest
+                                         | StructureData::RequesterChest { .. }
+                                         | StructureData::Belt { .. }) {
+                    continue;
+                }
+
+                for index in 0..other.available_items_len() {
+                    let other = structures.get(other_id);
+                    let Some(item) = *other.available_item(index)
+                    else { continue };
+
+                    if !Filter::Fuel.is_valid(item.kind) { continue }
+
+                    let item = structures.get_mut(other_id).try_take(index, 1).unwrap();
+                    structures.log_transfer(other_id, "took", item);
+
+                    let structure = structures.get_mut_without_wake_up(id);
+                    *structure.inventory.as_mut().unwrap().input_mut(1) = Some(item);
+                    structures.log_transfer(id, "received", item);
+                    return;
+                }
+            }
+        }
+    }
+
+
+    ///
+    /// Moves the assembler's output onto whatever belt, chest or silo
+    /// sits against its front face, one item at a time
+    ///
+    fn push_output(id: StructureId, structures: &mut Structures, world: &VoxelWorld) {
+        let structure = structures.get(id);
+        let dir = structure.direction;
+        let zz = structure.zero_zero();
+
+        for y in 0..3 {
+            for z in 0..3 {
+                let pos = zz + rotate_block_vector(dir, IVec3::new(-1, y, z));
+                let Some(&target_id) = world.structure_blocks.get(&pos)
+                else { continue };
+
+                if target_id == id { continue }
+
+                loop {
+                    let Some(item) = *structures.get(id).available_item(0)
+                    else { return };
+
+                    let item = Item { amount: 1, ..item };
+                    let target = structures.get_mut(target_id);
+                    match &target.data {
+                        StructureData::Belt { .. } => {
+                            let lane = placement_lane(dir, target.direction);
+                            let inventory = &mut target.inventory.as_mut().unwrap().slots;
+                            let Some(slot) = inventory[lane*2..(lane+1)*2].iter_mut().find(|x| x.is_none())
+                            else { break };
+
+                            *slot = Some(item);
+                        },
+
+
+                          StructureData::Chest
+                        | StructureData::Silo
+                        | StructureData::ProviderChest
+                        | StructureData::RequesterChest { .. } => {
+                            if !target.can_accept(item) { break }
+                            target.give_item(item);
+                        },
+
+
+                        _ => break,
+                    }
+
+                    structures.log_transfer(target_id, "received", item);
+                    let item = structures.get_mut_without_wake_up(id).try_take(0, 1).unwrap();
+                    structures.log_transfer(id, "pushed", item);
+                }
+            }
+        }
+    }
+
+
+    ///
+    /// Puts `module` into an assembler's module `slot` and returns
+    /// whatever was there, a craft that's already running keeps
+    /// its progress but finishes at the new speed
+    ///
+    pub fn swap_module(id: StructureId, structures: &mut Structures, slot: usize, module: Option<ItemKind>) -> Option<ItemKind> {
+        let structure = structures.get_mut_without_wake_up(id);
+        let StructureData::Assembler { recipe, modules, .. } = &mut structure.data
+        else { unreachable!() };
+
+        let old_modules = *modules;
+        let old = core::mem::replace(&mut modules[slot], module);
+        let new_modules = *modules;
+
+        let Some(recipe) = *recipe
+        else { return old };
+
+        if let Some(tick) = structures.work_queue.find(id) {
+            let old_time = assembler_time(recipe, &old_modules);
+            let new_time = assembler_time(recipe, &new_modules);
+
+            let remaining = tick.u32().saturating_sub(structures.current_tick.u32());
+            let remaining = (remaining as u64 * new_time as u64 / old_time as u64) as u32;
+
+            structures.work_queue.remove(tick, id);
+            structures.schedule_in(id, remaining.max(1));
+        }
+
+        old
+    }
+
+
+    ///
+    /// Turns an already placed structure to face `direction`, moving its
+    /// blocks in the world over to the new footprint. Refuses and returns
+    /// false if the new footprint would run into anything but itself
+    ///
+    pub fn rotate(id: StructureId, structures: &mut Structures, world: &mut VoxelWorld, direction: CardinalDirection) -> bool {
+        let structure = structures.get(id);
+        let kind = structure.data.as_kind();
+        let old_origin = structure.zero_zero();
+        let new_origin = structure.position - kind.oriented_origin(direction, structure.orientation);
+
+        for offset in kind.oriented_blocks(direction, structure.orientation) {
+            let pos = new_origin + offset;
+            if !world.chunker.limits.contains(pos.y) { return false }
+
+            let is_self = world.structure_blocks.get(&pos) == Some(&id);
+            if !is_self && !world.get_voxel(pos).is_air() { return false }
+        }
+
+
+        for offset in structure.blocks() {
+            let pos = old_origin + offset;
+            *world.get_voxel_mut(pos) = Voxel::Air;
+            world.structure_blocks.remove(&pos).unwrap();
+        }
+
+        let structure = structures.get_mut(id);
+        structure.direction = direction;
+
+        for offset in structure.blocks() {
+            let pos = new_origin + offset;
+            *world.get_voxel_mut(pos) = Voxel::StructureBlock;
+            world.structure_blocks.insert(pos, id);
+        }
+
+
+        // anything that was working off of the old facing starts over
+        match &mut structure.data {
+            StructureData::Quarry { current_progress, .. } => *current_progress = 0,
+            StructureData::Splitter { priority, .. } => *priority = [0, 0],
+
+            StructureData::Inserter { .. }
+            | StructureData::Chest
+            | StructureData::Silo
+            | StructureData::ProviderChest
+            | StructureData::RequesterChest { .. }
+            | StructureData::Belt { .. }
+            | StructureData::Assembler { .. }
+            | StructureData::Furnace(_)
+            | StructureData::Pump
+            | StructureData::Pipe { .. } => (),
+        }
+
+        true
+    }
+
+
+    ///
+    /// Nothing wakes an assembler up when the space in front of it
+    /// frees up, so keep checking back while a full output is what's
+    /// stalling it
+    ///
+    fn poll_output(id: StructureId, structures: &mut Structures) {
+        let structure = structures.get(id);
+        let StructureData::Assembler { recipe: Some(recipe), auto_output: true, .. } = &structure.data
+        else { return };
+
+        let (output, meta) = structure.inventory.as_ref().unwrap().output(0);
+        if let Some(output) = output
+            && output.amount + recipe.result.amount > meta.max_amount {
+            structures.to_be_awoken.push(id);
+        }
+    }
+
+
+    pub fn render(&self, structures: &Structures, world: &VoxelWorld, camera: &Camera, renderer: &mut Renderer) {
         let kind = self.data.as_kind();
 
+        // how far along the belts are until they next move their items
+        let progress = (structures.current_tick.u32() % BELT_UPDATE_INTERVAL) as f32 / BELT_UPDATE_INTERVAL as f32;
+
         let position = self.zero_zero();
-        let mesh = renderer.assets.get_item(kind.item_kind());
+        let mut mesh = renderer.assets.get_item(kind.item_kind());
+        let modulate = structures.belt_flow_colour(self);
 
-        let blocks = self.data.as_kind().blocks(self.direction);
         let mut pos_min = IVec3::MAX;
         let mut pos_max = IVec3::MIN;
-        for offset in blocks {
+        for offset in self.blocks() {
             pos_min = pos_min.min(position + offset);
             pos_max = pos_max.max(position + offset);
         }
@@ -552,148 +1332,85 @@ impl Structure {
         let mut dims = Vec3::ONE;
         'm: {
         match &self.data {
-            StructureData::Belt => {
-                dims.y *= 0.7;
-                let inventory = &self.inventory.as_ref().unwrap().slots;
-
-                let base = mesh_position + rotate_block_vector(self.direction, IVec3::new(-24, 11, 0)).as_vec3() / 32.0;
-                let base = base + Vec3::new(0.0, 0.05, 0.0);
-
-                let mut left_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, -1)).as_vec3() * 0.3;
-                for item in &inventory[..2] {
-                    left_base += rotate_block_vector(self.direction, IVec3::new(1, 0, 0)).as_vec3() * 0.5;
-                    if let Some(item) = item {
-                        let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
-                                  else { 90f32.to_radians() };
-
-                        let instance = MeshInstance {
-                            modulate: Vec4::ONE,
-                            model: Mat4::from_scale_rotation_translation(
-                                Vec3::splat(DROPPED_ITEM_SCALE), 
-                                Quat::from_rotation_x(rot), 
-                                left_base
-                            ),
-                        };
-
-                        renderer.draw_item(item.kind, instance);
-                    }
-                }
-
-                let mut right_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, 1)).as_vec3() * 0.3;
-                for item in &inventory[2..4] {
-                    right_base += rotate_block_vector(self.direction, IVec3::new(1, 0, 0)).as_vec3() * 0.5;
-                    if let Some(item) = item {
-                        let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
-                                  else { 90f32.to_radians() };
-
-                        let instance = MeshInstance {
-                            modulate: Vec4::ONE,
-                            model: Mat4::from_scale_rotation_translation(
-                                Vec3::splat(DROPPED_ITEM_SCALE), 
-                                Quat::from_rotation_x(rot), 
-                                right_base
-                            ),
-                        };
-
-                        renderer.draw_item(item.kind, instance);
-                    }
-                }
+            // its own recoloured copy of the regular quarry's model, stretched over the bigger frame
+            StructureData::Quarry { size: 5, .. } => {
+                dims.x *= 7.0 / 5.0;
+                dims.z *= 7.0 / 5.0;
             }
 
 
-           StructureData::Splitter { .. } => {
+            StructureData::Belt { tier } => {
                 dims.y *= 0.7;
-                let inventory = &self.inventory.as_ref().unwrap().slots;
-
-                let base = mesh_position + rotate_block_vector(self.direction, IVec3::new(-24, 11, 16)).as_vec3() / 32.0;
-                let base = base + Vec3::new(0.0, 0.05, 0.0);
-
-                let mut left_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, -1)).as_vec3() * 0.3;
-                for item in &inventory[..2] {
-                    left_base += rotate_block_vector(self.direction, IVec3::new(1, 0, 0)).as_vec3() * 0.5;
-                    if let Some(item) = item {
-                        let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
-                                  else { 90f32.to_radians() };
-
-                        let instance = MeshInstance {
-                            modulate: Vec4::ONE,
-                            model: Mat4::from_scale_rotation_translation(
-                                Vec3::splat(DROPPED_ITEM_SCALE), 
-                                Quat::from_rotation_x(rot), 
-                                left_base
-                            ),
-                        };
-
-                        renderer.draw_item(item.kind, instance);
-                    }
-                }
+                let slots = &self.inventory.as_ref().unwrap().slots;
+                let speed = tier.speed();
 
-                let mut right_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, 1)).as_vec3() * 0.3;
-                for item in &inventory[2..4] {
-                    right_base += rotate_block_vector(self.direction, IVec3::new(1, 0, 0)).as_vec3() * 0.5;
-                    if let Some(item) = item {
-                        let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
-                                  else { 90f32.to_radians() };
-
-                        let instance = MeshInstance {
-                            modulate: Vec4::ONE,
-                            model: Mat4::from_scale_rotation_translation(
-                                Vec3::splat(DROPPED_ITEM_SCALE), 
-                                Quat::from_rotation_x(rot), 
-                                right_base
-                            ),
-                        };
-
-                        renderer.draw_item(item.kind, instance);
-                    }
+                let forward = rotate_block_vector(self.direction, IVec3::new(-1, 0, 0)).as_vec3();
+                let right = rotate_block_vector(self.direction, IVec3::new(0, 0, 1)).as_vec3();
+                let base = mesh_position + Vec3::new(0.0, 11.0 / 32.0 + 0.05, 0.0);
+
+                const OUTPUTS : &[IVec3] = &[
+                    IVec3::new(-1,  0,  0),
+                    IVec3::new(-1,  1,  0),
+                    IVec3::new(-1, -1,  0),
+                ];
+
+                let curve = structures.belt_curve(world, self);
+
+                for (lane, side) in [(0, -1.0), (1, 1.0)] {
+                    let front_free = self.lane_output_free(structures, world, OUTPUTS, lane);
+                    let slots = &slots[lane*2..(lane+1)*2];
+
+                    let Some(curve) = curve
+                    else {
+                        let lane_base = base + right * side * 0.3;
+                        self.render_lane(renderer, slots, front_free, progress, speed, |t| lane_base + forward * (t - 0.5));
+                        continue;
+                    };
+
+                    // items go round a quarter circle centred on the inner
+                    // corner, so the inner lane's path ends up shorter
+                    let curve = curve as f32;
+                    let corner = base + forward * 0.5 + right * curve * 0.5;
+                    let radius = (curve * 0.5 - side * 0.3).abs();
+
+                    self.render_lane(renderer, slots, front_free, progress, speed, |t| {
+                        let angle = t.min(1.0) * 90f32.to_radians();
+                        corner
+                            - forward * radius * angle.cos()
+                            - right * curve * radius * angle.sin()
+                            + forward * (t - 1.0).max(0.0)
+                    });
                 }
 
-                let base = mesh_position + rotate_block_vector(self.direction, IVec3::new(-24, 11, -16)).as_vec3() / 32.0;
-                let base = base + Vec3::new(0.0, 0.05, 0.0);
-
-                let mut left_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, -1)).as_vec3() * 0.3;
-                for item in &inventory[4..6] {
-                    left_base += rotate_block_vector(self.direction, IVec3::new(1, 0, 0)).as_vec3() * 0.5;
-                    if let Some(item) = item {
-                        let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
-                                  else { 90f32.to_radians() };
-
-                        let instance = MeshInstance {
-                            modulate: Vec4::ONE,
-                            model: Mat4::from_scale_rotation_translation(
-                                Vec3::splat(DROPPED_ITEM_SCALE), 
-                                Quat::from_rotation_x(rot), 
-                                left_base
-                            ),
-                        };
-
-                        renderer.draw_item(item.kind, instance);
-                    }
+
+                if let Some(curve) = curve {
+                    mesh = renderer.assets.get_belt_curve(*tier, curve);
                 }
+            }
 
-                let mut right_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, 1)).as_vec3() * 0.3;
-                for item in &inventory[6..8] {
-                    right_base += rotate_block_vector(self.direction, IVec3::new(1, 0, 0)).as_vec3() * 0.5;
-                    if let Some(item) = item {
-                        let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
-                                  else { 90f32.to_radians() };
-
-                        let instance = MeshInstance {
-                            modulate: Vec4::ONE,
-                            model: Mat4::from_scale_rotation_translation(
-                                Vec3::splat(DROPPED_ITEM_SCALE), 
-                                Quat::from_rotation_x(rot), 
-                                right_base
-                            ),
-                        };
-
-                        renderer.draw_item(item.kind, instance);
+
+            StructureData::Splitter { .. } => {
+                dims.y *= 0.7;
+                let slots = &self.inventory.as_ref().unwrap().slots;
+                let forward = rotate_block_vector(self.direction, IVec3::new(-1, 0, 0)).as_vec3();
+
+                // the first four slots feed the belt in front of the
+                // splitter's origin block and the last four the other one
+                for (half, z, output) in [(0, -16, IVec3::new(-1, 0, 0)), (1, 16, IVec3::new(-1, 0, 1))] {
+                    let base = mesh_position + rotate_block_vector(self.direction, IVec3::new(0, 11, z)).as_vec3() / 32.0;
+                    let base = base + Vec3::new(0.0, 0.05, 0.0);
+                    let slots = &slots[half*4..(half+1)*4];
+
+                    for (lane, side) in [(0, -1), (1, 1)] {
+                        let lane_base = base + rotate_block_vector(self.direction, IVec3::new(0, 0, side)).as_vec3() * 0.3;
+                        let front_free = self.lane_output_free(structures, world, &[output], lane);
+                        self.render_lane(renderer, &slots[lane*2..(lane+1)*2], front_free, progress, 1, |t| lane_base + forward * (t - 0.5));
                     }
                 }
             }
 
 
-            StructureData::Assembler { recipe }=> {
+            StructureData::Assembler { recipe, .. }=> {
                 let Some(recipe) = recipe
                 else { break 'm };
 
@@ -719,15 +1436,141 @@ impl Structure {
         let rot = self.direction.as_ivec3().as_vec3();
         let rot = rot.x.atan2(rot.z);
         let rot = rot + 90f32.to_radians();
-        let model = Mat4::from_translation(mesh_position) * Mat4::from_scale(dims) * Mat4::from_rotation_y(rot);
+        let model = Mat4::from_translation(mesh_position)
+                  * Mat4::from_scale(dims)
+                  * Mat4::from_quat(orientation_tilt(self.direction, self.orientation))
+                  * Mat4::from_rotation_y(rot);
 
         let instance = MeshInstance {
-            modulate: Vec4::ONE,
+            modulate,
             model,
         };
 
         renderer.draw_mesh(mesh, instance);
     }
+
+
+    ///
+    /// Draws the items in one lane of a belt, `slots` goes from the
+    /// front of the lane to the back. `path` maps how far along the
+    /// belt an item is, from 0 at the back edge to 1 at the front, to
+    /// where it's drawn. An item slides towards the slot in front of it
+    /// while that one's free so it doesn't jump half a block every time
+    /// the belts update, up to `speed` slots on the faster belts
+    ///
+    fn render_lane(&self, renderer: &mut Renderer, slots: &[Option<Item>], front_free: bool, progress: f32, speed: u32, path: impl Fn(f32) -> Vec3) {
+        for (i, item) in slots.iter().enumerate() {
+            let Some(item) = item
+            else { continue };
+
+            // once it's got a clear run off the front of the
+            // belt only its own speed holds the item back
+            let free = slots[..i].iter().rev().take_while(|x| x.is_none()).count();
+            let free = if free == i && front_free { speed } else { free as u32 };
+            let slide = progress * free.min(speed) as f32;
+
+            let rot = if matches!(item.kind, ItemKind::Structure(_)) { 0.0 }
+                      else { 90f32.to_radians() };
+
+            let instance = MeshInstance {
+                modulate: Vec4::ONE,
+                model: Mat4::from_scale_rotation_translation(
+                    Vec3::splat(DROPPED_ITEM_SCALE),
+                    Quat::from_rotation_x(rot),
+                    path(0.75 - (i as f32 - slide) * 0.5),
+                ),
+            };
+
+            renderer.draw_item(item.kind, instance);
+        }
+    }
+
+
+    ///
+    /// Whether the item at the front of `lane` has room to move onto
+    /// the belt or splitter at the first of `outputs` that has one
+    ///
+    fn lane_output_free(&self, structures: &Structures, world: &VoxelWorld, outputs: &[IVec3], lane: usize) -> bool {
+        for &offset in outputs {
+            let position = self.position + rotate_block_vector(self.direction, offset);
+            let Some(&id) = world.structure_blocks.get(&position)
+            else { continue };
+
+            let output = structures.get(id);
+            let slots = &output.inventory.as_ref().unwrap().slots;
+            match output.data {
+                StructureData::Belt { .. } => return slots[belt_entry_slot(structures.belt_curve(world, output), lane)].is_none(),
+                StructureData::Splitter { .. } => return (0..2).any(|side| slots[side * 4 + lane * 2 + 1].is_none()),
+                _ => (),
+            }
+        }
+
+        false
+    }
+}
+
+
+///
+/// The slot of `lane` that an item coming onto a belt goes into,
+/// `curve` is the belt's `belt_curve`. The inner lane of a curve
+/// is a lot shorter than the outer one so items skip straight to
+/// its front slot
+///
+fn belt_entry_slot(curve: Option<i32>, lane: usize) -> usize {
+    // lane 0 runs along the belt's left side, see `render`
+    let side = lane as i32 * 2 - 1;
+    if curve == Some(side) { lane * 2 }
+    else { lane * 2 + 1 }
+}
+
+
+///
+/// How long `recipe` takes in an assembler with `modules` installed
+///
+pub fn assembler_time(recipe: Recipe, modules: &[Option<ItemKind>]) -> u32 {
+    let speed = modules.iter().filter(|x| **x == Some(ItemKind::SpeedModule)).count();
+    let multiplier = (1.0 - speed as f32 * SPEED_MODULE_BONUS).max(0.0);
+    ((recipe.time as f32 * multiplier) as u32).max(1)
+}
+
+
+///
+/// The chance of an assembler with `modules` installed
+/// producing a second result for free
+///
+pub fn assembler_productivity(modules: &[Option<ItemKind>]) -> f32 {
+    let productivity = modules.iter().filter(|x| **x == Some(ItemKind::ProductivityModule)).count();
+    productivity as f32 * PRODUCTIVITY_MODULE_BONUS
+}
+
+
+///
+/// Puts a finished craft of `recipe` into the output and moves
+/// `productivity` along, returns whether it made a bonus craft.
+/// Every craft adds the modules' bonus chance to `productivity`
+/// so the bonus crafts come at a steady rate and the same
+/// factory always plays out the same way
+///
+pub fn assembler_craft(inventory: &mut StructureInventory, recipe: Recipe, modules: &[Option<ItemKind>], productivity: &mut u32) -> bool {
+    let output = inventory.output_mut(0);
+    match output {
+        Some(v) => v.amount += recipe.result.amount,
+        None => *output = Some(recipe.result),
+    }
+
+    *productivity += (assembler_productivity(modules) * 1000.0).round() as u32;
+    let is_due = *productivity >= 1000;
+    if is_due { *productivity -= 1000 }
+
+    // the bonus craft is lost if the output can't hold it
+    let (output, meta) = inventory.output(0);
+    let bonus = is_due
+                && output.unwrap().amount + recipe.result.amount <= meta.max_amount;
+    if bonus {
+        inventory.output_mut(0).as_mut().unwrap().amount += recipe.result.amount;
+    }
+
+    bonus
 }
 
 
@@ -768,6 +1611,19 @@ pub fn try_consume(inventory: &mut StructureInventory, recipe: Recipe) -> bool {
 }
 
 
+///
+/// Where a quarry that's mined `progress` blocks digs next, relative
+/// to its zero zero and before rotating it. It clears a `size`x`size`
+/// layer inside of its frame before moving a layer down
+///
+pub fn quarry_offset(progress: u32, size: u32) -> IVec3 {
+    let x = progress % size;
+    let z = (progress / size) % size;
+    let y = progress / (size * size);
+    IVec3::new(x as i32 + 1, -(y as i32) - 1, z as i32 + 1)
+}
+
+
 pub fn quarry_efficiency(y_pos: f32) -> f32 {
     if y_pos > 0.0 { return 1.0 }
     1.0 + (y_pos * 0.001).powi(2)
@@ -795,6 +1651,20 @@ pub fn rotate_vector(direction: Vec3, v: Vec3) -> IVec3 {
 
 
 
+
+///
+/// Whether an item of `kind` is allowed to leave through
+/// the splitter's `side` output
+///
+fn splitter_side_accepts(filters: [Option<ItemKind>; 2], side: usize, kind: ItemKind) -> bool {
+    match filters[side] {
+        Some(filter) => filter == kind,
+        // unfiltered outputs take whatever the other one doesn't want
+        None => filters[1 - side] != Some(kind),
+    }
+}
+
+
 fn placement_lane(inserter_dir: CardinalDirection, belt_dir: CardinalDirection) -> usize {
     use CardinalDirection as CD;
 
@@ -810,3 +1680,623 @@ fn placement_lane(inserter_dir: CardinalDirection, belt_dir: CardinalDirection)
         _ => 0,
     }
 }
+
+
+#[test]
+fn test_furnace_auto_fuel() {
+    use crate::{constants::COAL_ENERGY_PER_UNIT, structures::strct::Furnace};
+
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let furnace = structures.place_at_test_site(&mut world, StructureKind::Furnace, IVec3::new(2, 0, 1), CardinalDirection::North);
+    let structure = structures.get_mut_without_wake_up(furnace);
+    let mut data = Furnace::new(StructureKind::Furnace);
+    data.auto_fuel = true;
+    structure.data = StructureData::Furnace(data);
+    structure.energy.energy = 0;
+    *structure.inventory.as_mut().unwrap().input_mut(0) = Some(Item { amount: 10, kind: ItemKind::IronOre });
+
+    let chest = structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::new(-1, 0, 0), CardinalDirection::North);
+    structures.get_mut_without_wake_up(chest).inventory.as_mut().unwrap().slots[0] = Some(Item { amount: 5, kind: ItemKind::Coal });
+
+    for _ in 0..TICKS_PER_SECOND * 2 * 15 {
+        structures.process(&mut entities, &mut world);
+    }
+
+    let output = *structures.get(furnace).inventory.as_ref().unwrap().output(0).0;
+    assert_eq!(output, Some(Item { amount: 10, kind: ItemKind::IronPlate }));
+
+    let coal_used = (10 * FURNACE_COST_PER_SMELT).div_ceil(COAL_ENERGY_PER_UNIT);
+    let coal = structures.get(chest).inventory.as_ref().unwrap().slots[0];
+    assert_eq!(coal.map(|x| x.amount), Some(5 - coal_used));
+}
+
+
+#[test]
+fn test_pumps_fill_their_network() {
+    use crate::constants::PIPE_CAPACITY;
+
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    // nothing but air under the pump so it pumps water
+    structures.place_at_test_site(&mut world, StructureKind::Pump, IVec3::ZERO, CardinalDirection::North);
+    let pipe = structures.place_at_test_site(&mut world, StructureKind::Pipe, IVec3::new(1, 0, 0), CardinalDirection::North);
+
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+
+    let StructureData::Pipe { fluid, amount } = structures.get(pipe).data
+    else { unreachable!() };
+    assert_eq!(fluid, Some(Fluid::Water));
+    assert_eq!(amount, PUMP_RATE);
+
+    for _ in 0..BELT_UPDATE_INTERVAL * (PIPE_CAPACITY / PUMP_RATE + 1) {
+        structures.process(&mut entities, &mut world);
+    }
+
+    let StructureData::Pipe { amount, .. } = structures.get(pipe).data
+    else { unreachable!() };
+    assert_eq!(amount, PIPE_CAPACITY);
+}
+
+
+#[test]
+fn test_oil_tops_up_every_furnace_it_can() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let pipe = structures.place_at_test_site(&mut world, StructureKind::Pipe, IVec3::ZERO, CardinalDirection::North);
+    structures.get_mut_without_wake_up(pipe).data = StructureData::Pipe { fluid: Some(Fluid::Oil), amount: 5 };
+
+    // one furnace needs more oil than there is, the other only
+    // needs a single unit. whichever comes first the second
+    // one should still get its top up
+    let empty = structures.place_at_test_site(&mut world, StructureKind::Furnace, IVec3::new(3, 0, 0), CardinalDirection::North);
+    structures.get_mut_without_wake_up(empty).energy.energy = 0;
+
+    let almost_full = structures.place_at_test_site(&mut world, StructureKind::Furnace, IVec3::new(0, 0, 2), CardinalDirection::North);
+    structures.get_mut_without_wake_up(almost_full).energy.energy = FURNACE_COST_PER_SMELT - 1;
+
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+
+    assert_eq!(structures.get(empty).energy.energy, 0);
+    assert_eq!(structures.get(almost_full).energy.energy, FURNACE_COST_PER_SMELT - 1 + OIL_ENERGY_PER_UNIT);
+
+    let StructureData::Pipe { fluid, amount } = structures.get(pipe).data
+    else { unreachable!() };
+    assert_eq!(fluid, Some(Fluid::Oil));
+    assert_eq!(amount, 4);
+}
+
+
+#[test]
+fn test_requesters_keep_asking_after_a_full_request() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let provider = structures.place_at_test_site(&mut world, StructureKind::ProviderChest, IVec3::ZERO, CardinalDirection::North);
+    let slots = &mut structures.get_mut_without_wake_up(provider).inventory.as_mut().unwrap().slots;
+    slots[0] = Some(Item::new(ItemKind::Coal, 5));
+    slots[1] = Some(Item::new(ItemKind::IronPlate, 100));
+
+    // every slot's taken by iron plates so the coal can't go
+    // in but more iron plates can
+    let requester = structures.place_at_test_site(&mut world, StructureKind::RequesterChest, IVec3::new(3, 0, 0), CardinalDirection::North);
+    let structure = structures.get_mut_without_wake_up(requester);
+    structure.data = StructureData::RequesterChest {
+        requests: vec![Item::new(ItemKind::Coal, 5), Item::new(ItemKind::IronPlate, 100)],
+    };
+    structure.inventory.as_mut().unwrap().slots.fill(Some(Item::new(ItemKind::IronPlate, 1)));
+
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+
+    let slots = &structures.get(requester).inventory.as_ref().unwrap().slots;
+    let plates : u32 = slots.iter().flatten().filter(|x| x.kind == ItemKind::IronPlate).map(|x| x.amount).sum();
+    let moved = plates - slots.len() as u32;
+    assert!(moved > 0);
+    assert!(moved <= LOGISTICS_TRANSFER_RATE * BELT_UPDATE_INTERVAL);
+    assert!(slots.iter().flatten().all(|x| x.kind == ItemKind::IronPlate));
+}
+
+
+#[test]
+fn test_productivity_bonus_is_steady() {
+    use crate::crafting::{crafting_recipe_inventory, RECIPES};
+
+    let recipe = RECIPES[0];
+    let mut inventory = StructureInventory::new(crafting_recipe_inventory(0));
+    let modules = [Some(ItemKind::ProductivityModule)];
+    let mut productivity = 0;
+
+    // one module is a tenth of a craft, so every tenth craft is a bonus
+    let bonuses : Vec<bool> = (0..20)
+        .map(|_| {
+            // empty it out so the bonus always fits
+            *inventory.output_mut(0) = None;
+            assembler_craft(&mut inventory, recipe, &modules, &mut productivity)
+        })
+        .collect();
+
+    let expected : Vec<bool> = (1..=20).map(|i| i % 10 == 0).collect();
+    assert_eq!(bonuses, expected);
+    assert_eq!(productivity, 0);
+}
+
+
+#[test]
+fn test_steel_furnace_is_faster_and_cheaper() {
+    let smelt = |kind: StructureKind| {
+        let mut world = VoxelWorld::new();
+        let mut entities = EntityMap::new();
+        let mut structures = Structures::new();
+
+        let furnace = structures.place_at_test_site(&mut world, kind, IVec3::ZERO, CardinalDirection::North);
+        let structure = structures.get_mut_without_wake_up(furnace);
+        structure.energy.energy = 10_000;
+        *structure.inventory.as_mut().unwrap().input_mut(0) = Some(Item { amount: 10, kind: ItemKind::IronOre });
+
+        let mut ticks = 0;
+        while structures.get(furnace).inventory.as_ref().unwrap().output(0).0.as_ref().map(|x| x.amount) != Some(10) {
+            structures.process(&mut entities, &mut world);
+            ticks += 1;
+            assert!(ticks < TICKS_PER_SECOND * 60 * 10, "{kind:?} never finished smelting");
+        }
+
+        (ticks, 10_000 - structures.get(furnace).energy.energy)
+    };
+
+    let (basic_ticks, basic_energy) = smelt(StructureKind::Furnace);
+    let (steel_ticks, steel_energy) = smelt(StructureKind::SteelFurnace);
+
+    // the steel furnace's multiplier is half the regular one's
+    assert!(steel_ticks * 2 <= basic_ticks + 2, "steel {steel_ticks} vs basic {basic_ticks}");
+    assert!(steel_energy < basic_energy);
+}
+
+
+fn grid_cell(pos: IVec3) -> IVec3 {
+    pos.div_euclid(IVec3::splat(STRUCTURE_GRID_CELL))
+}
+
+
+fn grid_distance_squared(structure: &Structure, pos: DVec3) -> f64 {
+    (structure.position.as_dvec3() + DVec3::splat(0.5)).distance_squared(pos)
+}
+
+
+#[test]
+fn test_belt_jam_detection() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    // nothing in front so it backs up
+    let belt = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::ZERO, CardinalDirection::North);
+    structures.get_mut_without_wake_up(belt).inventory.as_mut().unwrap().slots[1] = Some(Item::new(ItemKind::IronPlate, 1));
+
+    // the item still has room to move up to the front of the lane
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+    assert!(!structures.is_belt_jammed(structures.get(belt)));
+
+    for _ in 0..BELT_UPDATE_INTERVAL * (BELT_JAM_UPDATES + 1) {
+        structures.process(&mut entities, &mut world);
+    }
+    assert!(structures.is_belt_jammed(structures.get(belt)));
+
+    structures.get_mut_without_wake_up(belt).inventory.as_mut().unwrap().slots.fill(None);
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+    assert!(!structures.is_belt_jammed(structures.get(belt)));
+}
+
+
+#[test]
+fn test_faster_belts_move_items_further() {
+    let moved_to = |kind| {
+        let mut world = VoxelWorld::new();
+        let mut entities = EntityMap::new();
+        let mut structures = Structures::new();
+
+        // a line of belts feeding towards -x, the item starts at the back
+        let belts : Vec<_> = (0..3).rev()
+            .map(|x| structures.place_at_test_site(&mut world, kind, IVec3::new(x, 0, 0), CardinalDirection::North))
+            .collect();
+        structures.get_mut_without_wake_up(belts[0]).inventory.as_mut().unwrap().slots[1] = Some(Item::new(ItemKind::IronPlate, 1));
+
+        for _ in 0..BELT_UPDATE_INTERVAL {
+            structures.process(&mut entities, &mut world);
+        }
+
+        // which belt it's on and which slot of the lane
+        belts.iter().enumerate().find_map(|(i, &belt)| {
+            let slots = &structures.get(belt).inventory.as_ref().unwrap().slots;
+            slots.iter().position(|x| x.is_some()).map(|slot| (i, slot))
+        })
+    };
+
+    assert_eq!(moved_to(StructureKind::Belt), Some((0, 0)));
+    assert_eq!(moved_to(StructureKind::FastBelt), Some((1, 1)));
+    assert_eq!(moved_to(StructureKind::ExpressBelt), Some((1, 0)));
+}
+
+
+#[test]
+fn test_spatial_queries() {
+    let mut world = VoxelWorld::new();
+    let mut structures = Structures::new();
+
+    let mut place = |kind, x| structures.place_at_test_site(&mut world, kind, IVec3::new(x, 0, 0), CardinalDirection::North);
+    let near_chest = place(StructureKind::Chest, 5);
+    let far_chest = place(StructureKind::Chest, -70);
+    let belt = place(StructureKind::Belt, 1);
+    let removed = place(StructureKind::Chest, 2);
+
+    structures.remove(removed);
+
+    let pos = TEST_SITE.as_dvec3() + DVec3::splat(0.5);
+    assert_eq!(structures.nearest(pos, StructureKind::Chest), Some(near_chest));
+    assert_eq!(structures.nearest(pos, StructureKind::Belt), Some(belt));
+    assert_eq!(structures.nearest(pos - DVec3::new(60.5, 0.0, 0.0), StructureKind::Chest), Some(far_chest));
+    assert_eq!(structures.nearest(pos, StructureKind::Furnace), None);
+
+    let mut close = structures.query_radius(pos, 8.0);
+    let mut expected = vec![near_chest, belt];
+    close.sort();
+    expected.sort();
+    assert_eq!(close, expected);
+    assert_eq!(structures.query_radius(pos, 100.0).len(), 3);
+}
+
+
+#[test]
+fn test_furnaces_update_in_parallel() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let furnaces : Vec<_> = (0..32).map(|i| {
+        let furnace = structures.place_at_test_site(&mut world, StructureKind::Furnace, IVec3::new(i * 4, 0, 0), CardinalDirection::North);
+        let structure = structures.get_mut_without_wake_up(furnace);
+        structure.energy.energy = 10_000;
+        *structure.inventory.as_mut().unwrap().input_mut(0) = Some(Item { amount: 3, kind: ItemKind::IronOre });
+        furnace
+    }).collect();
+
+    // one of them goes through the regular update
+    structures.watched = Some((furnaces[7], Tick::new(u32::MAX)));
+
+    for _ in 0..TICKS_PER_SECOND * 60 {
+        structures.process(&mut entities, &mut world);
+    }
+
+    for furnace in furnaces {
+        let furnace = structures.get(furnace);
+        assert_eq!(*furnace.inventory.as_ref().unwrap().output(0).0, Some(Item { amount: 3, kind: ItemKind::IronPlate }));
+        assert!(furnace.is_asleep);
+    }
+}
+
+
+#[test]
+fn test_quarry_offset() {
+    // a regular quarry clears the 3x3 inside its frame
+    assert_eq!(quarry_offset(0, 3), IVec3::new(1, -1, 1));
+    assert_eq!(quarry_offset(2, 3), IVec3::new(3, -1, 1));
+    assert_eq!(quarry_offset(3, 3), IVec3::new(1, -1, 2));
+    assert_eq!(quarry_offset(8, 3), IVec3::new(3, -1, 3));
+    assert_eq!(quarry_offset(9, 3), IVec3::new(1, -2, 1));
+
+    // and a large one the 5x5
+    assert_eq!(quarry_offset(4, 5), IVec3::new(5, -1, 1));
+    assert_eq!(quarry_offset(5, 5), IVec3::new(1, -1, 2));
+    assert_eq!(quarry_offset(24, 5), IVec3::new(5, -1, 5));
+    assert_eq!(quarry_offset(25, 5), IVec3::new(1, -2, 1));
+    assert_eq!(quarry_offset(25 * 3 + 7, 5), IVec3::new(3, -4, 2));
+
+    // the whole layer stays inside of the frame
+    for size in [3, 5] {
+        for progress in 0..size * size {
+            let offset = quarry_offset(progress, size);
+            assert!(offset.x >= 1 && offset.x <= size as i32);
+            assert!(offset.z >= 1 && offset.z <= size as i32);
+            assert_eq!(offset.y, -1);
+        }
+    }
+}
+
+
+#[test]
+fn test_belt_curve() {
+    let mut world = VoxelWorld::new();
+    let mut structures = Structures::new();
+
+    let belt = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::ZERO, CardinalDirection::North);
+    assert_eq!(structures.belt_curve(&world, structures.get(belt)), None);
+
+    // fed from the side it turns
+    structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::new(0, 0, 1), CardinalDirection::East);
+    assert_eq!(structures.belt_curve(&world, structures.get(belt)), Some(1));
+
+    // but not once something feeds into it from behind
+    structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::new(1, 0, 0), CardinalDirection::North);
+    assert_eq!(structures.belt_curve(&world, structures.get(belt)), None);
+}
+
+
+#[test]
+fn test_items_keep_their_lane_round_curves() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    // fed from its right, so lane 1 is the inner one
+    let feeder = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::new(0, 0, 1), CardinalDirection::East);
+    let curve = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::ZERO, CardinalDirection::North);
+    let after = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::new(-1, 0, 0), CardinalDirection::North);
+    assert_eq!(structures.belt_curve(&world, structures.get(curve)), Some(1));
+
+    let outer = Some(Item::new(ItemKind::IronPlate, 1));
+    let inner = Some(Item::new(ItemKind::CopperPlate, 1));
+    let slots = &mut structures.get_mut_without_wake_up(feeder).inventory.as_mut().unwrap().slots;
+    slots[0] = outer;
+    slots[2] = inner;
+
+    let slots = |structures: &Structures, id| structures.get(id).inventory.as_ref().unwrap().slots.clone();
+
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+
+    // the inner lane is shorter so its item skips to the front
+    assert_eq!(slots(&structures, curve), [None, outer, inner, None]);
+
+    for _ in 0..BELT_UPDATE_INTERVAL {
+        structures.process(&mut entities, &mut world);
+    }
+
+    assert_eq!(slots(&structures, curve), [outer, None, None, None]);
+    assert_eq!(slots(&structures, after), [None, None, None, inner]);
+}
+
+
+#[test]
+fn test_oriented_blocks() {
+    use crate::directions::Orientation;
+
+    // lying flat is the same as the plain rotation
+    for dir in [CardinalDirection::North, CardinalDirection::East, CardinalDirection::South, CardinalDirection::West] {
+        let blocks : Vec<_> = StructureKind::Inserter.oriented_blocks(dir, Orientation::Flat).collect();
+        assert_eq!(blocks, StructureKind::Inserter.blocks(dir));
+    }
+
+    // tipped up the inserter becomes a column that drops its items off the top
+    let blocks : Vec<_> = StructureKind::Inserter.oriented_blocks(CardinalDirection::East, Orientation::Up).collect();
+    assert_eq!(blocks, [IVec3::new(0, 0, 0), IVec3::new(0, -1, 0), IVec3::new(0, -2, 0)]);
+    assert_eq!(rotate_oriented_vector(CardinalDirection::East, Orientation::Up, IVec3::new(-1, 0, 0)), IVec3::new(0, 1, 0));
+    assert_eq!(rotate_oriented_vector(CardinalDirection::East, Orientation::Down, IVec3::new(-1, 0, 0)), IVec3::new(0, -1, 0));
+}
+
+
+#[test]
+fn test_long_inserter_reach() {
+    let pos = TEST_SITE;
+
+    // (direction, inputs, output) relative to where the inserter was placed
+    let cases = [
+        (CardinalDirection::North, [IVec3::new( 2, 0,  0), IVec3::new( 3, 0,  0)], IVec3::new(-4, 0,  0)),
+        (CardinalDirection::East,  [IVec3::new( 0, 0,  2), IVec3::new( 0, 0,  3)], IVec3::new( 0, 0, -4)),
+        (CardinalDirection::South, [IVec3::new(-2, 0,  0), IVec3::new(-3, 0,  0)], IVec3::new( 4, 0,  0)),
+        (CardinalDirection::West,  [IVec3::new( 0, 0, -2), IVec3::new( 0, 0, -3)], IVec3::new( 0, 0,  4)),
+    ];
+
+    for (dir, inputs, output) in cases {
+        let inserter = Structure::from_kind(StructureKind::LongInserter, pos, dir);
+        let kind = inserter.data.as_kind();
+        assert_eq!(kind, StructureKind::LongInserter);
+
+        let zz = inserter.zero_zero();
+        let scanned : Vec<_> = kind.inserter_inputs().iter().map(|&x| zz + rotate_block_vector(dir, x)).collect();
+        assert_eq!(scanned, inputs.map(|x| pos + x));
+        assert_eq!(zz + rotate_block_vector(dir, kind.inserter_output()), pos + output);
+
+        // same body as the regular one, the tile right
+        // next to either end is the one it reaches over
+        let blocks : Vec<_> = inserter.blocks().map(|x| zz + x).collect();
+        assert_eq!(blocks.len(), 3);
+        assert!(!blocks.contains(&(pos + inputs[0] / 2)));
+        assert!(!blocks.contains(&(pos + output * 3 / 4)));
+    }
+
+    // the regular one still grabs right next to its ends
+    let inserter = Structure::from_kind(StructureKind::Inserter, pos, CardinalDirection::North);
+    let zz = inserter.zero_zero();
+    assert_eq!(StructureKind::Inserter.inserter_inputs().iter().map(|&x| zz + x).collect::<Vec<_>>(), [pos + IVec3::new(1, 0, 0)]);
+    assert_eq!(zz + StructureKind::Inserter.inserter_output(), pos + IVec3::new(-3, 0, 0));
+}
+
+
+#[test]
+fn test_facing_towards() {
+    for dir in [CardinalDirection::North, CardinalDirection::East, CardinalDirection::South, CardinalDirection::West] {
+        let front = rotate_block_vector(dir, IVec3::new(-1, 0, 0));
+        assert_eq!(facing_towards(front), Some(dir));
+    }
+
+    // only the four flat neighbours have a direction
+    assert_eq!(facing_towards(IVec3::new(0, 1, 0)), None);
+    assert_eq!(facing_towards(IVec3::new(2, 0, 0)), None);
+}
+
+
+#[test]
+fn test_rotate_moves_footprint() {
+    let mut world = VoxelWorld::new();
+    let mut structures = Structures::new();
+
+    let pos = TEST_SITE;
+    let id = structures.place_at_test_site(&mut world, StructureKind::Splitter, IVec3::ZERO, CardinalDirection::North);
+
+    let footprint = |structures: &Structures| {
+        let structure = structures.get(id);
+        structure.blocks().map(|offset| structure.zero_zero() + offset).collect::<Vec<_>>()
+    };
+
+    let old = footprint(&structures);
+    assert!(Structure::rotate(id, &mut structures, &mut world, CardinalDirection::East));
+    assert_eq!(structures.get(id).direction, CardinalDirection::East);
+
+    let new = footprint(&structures);
+    for block in &new {
+        assert_eq!(world.structure_blocks.get(block), Some(&id));
+    }
+
+    for block in old.iter().filter(|block| !new.contains(block)) {
+        assert!(world.get_voxel(*block).is_air());
+        assert!(world.structure_blocks.get(block).is_none());
+    }
+
+
+    // something in the way of the next footprint stops it
+    let south = StructureKind::Splitter.oriented_blocks(CardinalDirection::South, structures.get(id).orientation)
+        .map(|offset| pos - StructureKind::Splitter.oriented_origin(CardinalDirection::South, structures.get(id).orientation) + offset)
+        .find(|block| !new.contains(block))
+        .unwrap();
+
+    let chest = Structure::from_kind(StructureKind::Chest, south, CardinalDirection::North);
+    structures.add_structure(&mut world, chest);
+
+    assert!(!Structure::rotate(id, &mut structures, &mut world, CardinalDirection::South));
+    assert_eq!(structures.get(id).direction, CardinalDirection::East);
+    assert_eq!(footprint(&structures), new);
+}
+
+
+#[test]
+fn test_transfer_budget_resets_every_tick() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let chest = structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::ZERO, CardinalDirection::North);
+
+    let rate = StructureKind::Chest.transfer_rate();
+    assert_eq!(structures.accept_budget(chest), rate.accept);
+
+    structures.record_transfer(None, Some(chest), 3);
+    assert_eq!(structures.accept_budget(chest), rate.accept.saturating_sub(3));
+    assert_eq!(structures.provide_budget(chest), rate.provide);
+
+    structures.process(&mut entities, &mut world);
+    assert_eq!(structures.accept_budget(chest), rate.accept);
+}
+
+
+#[test]
+fn test_inserters_respect_transfer_rates() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let inserter = structures.place_at_test_site(&mut world, StructureKind::Inserter, IVec3::ZERO, CardinalDirection::North);
+    let source = structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::new(1, 0, 0), CardinalDirection::North);
+    let target = structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::new(-3, 0, 0), CardinalDirection::North);
+    structures.get_mut_without_wake_up(source).inventory.as_mut().unwrap().slots[0] = Some(Item::new(ItemKind::IronPlate, 10));
+    structures.get_mut_without_wake_up(inserter).is_asleep = false;
+
+    // use up all but one item of the source's rate so it
+    // behaves like a chest that only hands out one per tick
+    let rate = StructureKind::Chest.transfer_rate();
+    structures.record_transfer(Some(source), None, rate.provide - 1);
+
+    // picks one up, places it, then finds the source spent
+    for _ in 0..3 {
+        Structure::update(inserter, &mut structures, &mut entities, &mut world);
+    }
+
+    let count = |structures: &Structures, id| -> u32 {
+        structures.get(id).inventory.as_ref().unwrap().slots.iter().flatten().map(|x| x.amount).sum()
+    };
+
+    assert_eq!(structures.provide_budget(source), 0);
+    assert_eq!(count(&structures, source), 9);
+    assert_eq!(count(&structures, target), 1);
+}
+
+
+#[test]
+fn test_transfer_rate_throttles_inserters() {
+    let mut world = VoxelWorld::new();
+    let mut entities = EntityMap::new();
+    let mut structures = Structures::new();
+
+    let inserter = structures.place_at_test_site(&mut world, StructureKind::Inserter, IVec3::ZERO, CardinalDirection::North);
+    let source = structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::new(1, 0, 0), CardinalDirection::North);
+    let target = structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::new(-3, 0, 0), CardinalDirection::North);
+    structures.get_mut_without_wake_up(source).inventory.as_mut().unwrap().slots[0] = Some(Item::new(ItemKind::IronPlate, 10));
+
+    let count = |structures: &Structures, id| -> u32 {
+        structures.get(id).inventory.as_ref().unwrap().slots.iter().flatten().map(|x| x.amount).sum()
+    };
+
+    // the chests have plenty of budget left, it's the
+    // inserter's own rate that stops it after one item
+    let rate = StructureKind::Inserter.transfer_rate();
+    for _ in 0..10 {
+        structures.get_mut_without_wake_up(inserter).is_asleep = false;
+        Structure::update(inserter, &mut structures, &mut entities, &mut world);
+    }
+
+    assert!(structures.provide_budget(source) > 0);
+    assert_eq!(structures.accept_budget(inserter), 0);
+    assert_eq!(count(&structures, target), rate.provide);
+
+    // and it picks up where it left off once the tick's over
+    structures.process(&mut entities, &mut world);
+    for _ in 0..10 {
+        structures.get_mut_without_wake_up(inserter).is_asleep = false;
+        Structure::update(inserter, &mut structures, &mut entities, &mut world);
+    }
+
+    assert_eq!(count(&structures, target), 2 * rate.provide);
+    assert_eq!(count(&structures, source), 10 - 2 * rate.provide);
+}
+
+
+#[test]
+fn test_belt_graph_export() {
+    use belts::GraphFormat;
+    use sti::key::Key;
+
+    let mut world = VoxelWorld::new();
+    let mut structures = Structures::new();
+
+    // `first` feeds into `second`
+    let first = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::new(1, 0, 0), CardinalDirection::North);
+    let second = structures.place_at_test_site(&mut world, StructureKind::Belt, IVec3::ZERO, CardinalDirection::North);
+
+    let belts = structures.belts(&world);
+    let from = belts.structure_to_node[&first].usize();
+    let to = belts.structure_to_node[&second].usize();
+
+    let adjacency = belts.export(&structures, GraphFormat::Adjacency);
+    assert!(adjacency.lines().any(|x| x == format!("{from} -> {to}")));
+    assert!(adjacency.lines().any(|x| x == format!("{to} ->")));
+
+    let json = belts.export(&structures, GraphFormat::Json);
+    assert!(json.contains(&format!("\"edges\":[{{\"from\":{from},\"to\":{to}}}]")));
+    assert!(json.contains(&format!("\"structure_id\":{},\"kind\":\"Belt\",\"position\":[{},{},{}],\"direction\":\"North\"", first.0.key.usize(), TEST_SITE.x + 1, TEST_SITE.y, TEST_SITE.z)));
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, fmt::Write};
 use glam::IVec3;
 use sti::{define_key, key::Key, vec::KVec};
 
-use crate::{hsl_to_hex, structures::strct::{rotate_block_vector, StructureKind}, voxel_world::VoxelWorld};
+use crate::{hsl_to_hex, structures::strct::{rotate_block_vector, Structure, StructureKind}, voxel_world::VoxelWorld};
 
 use super::{StructureId, Structures};
 
@@ -27,31 +27,18 @@ impl Structures {
 
 
             let mut output = [None, None];
-            let mut supports_multioutput = false;
-            let positions : &[_] = match structure.data.as_kind() {
-                StructureKind::Belt => &[
-                    IVec3::new(-1,  0,  0),
-                    IVec3::new(-1,  1,  0),
-                    IVec3::new(-1, -1,  0),
-                ],
-
-
-                StructureKind::Splitter => {
-                    supports_multioutput = true;
-                    &[
-                        IVec3::new(-1,  0,  0),
-                        IVec3::new(-1,  0,  1),
-                    ]
-                }
-                _ => continue,
-            };
+            let kind = structure.data.as_kind();
+            let supports_multioutput = kind == StructureKind::Splitter;
+            let Some(positions) = output_offsets(kind)
+            else { continue };
 
             for position in positions {
                 let position = rotate_block_vector(structure.direction, *position);
                 let position = structure.position + position;
                 if let Some(&output_structure) = world.structure_blocks.get(&position) {
                     let structure = self.get(output_structure);
-                    if matches!(structure.data.as_kind(), StructureKind::Belt | StructureKind::Splitter) {
+                    let kind = structure.data.as_kind();
+                    if kind.is_belt() || kind == StructureKind::Splitter {
                         let node_id = if let Some(&node_id) = struct_to_node.get(&output_structure) {
                             node_id
                         } else {
@@ -240,6 +227,61 @@ impl Structures {
 
     }
 
+
+    ///
+    /// A belt curves when nothing feeds into it from behind and exactly
+    /// one belt or splitter feeds into it from the side. Returns which side that is
+    /// as a z offset in the belt's own frame, see `rotate_block_vector`
+    ///
+    pub fn belt_curve(&self, world: &VoxelWorld, structure: &Structure) -> Option<i32> {
+        if !structure.data.as_kind().is_belt() { return None }
+
+        let feeds_in = |offset: IVec3| {
+            let position = structure.position + rotate_block_vector(structure.direction, offset);
+            let Some(&other) = world.structure_blocks.get(&position)
+            else { return false };
+
+            let other = self.get(other);
+            let Some(offsets) = output_offsets(other.data.as_kind())
+            else { return false };
+
+            offsets.iter().any(|&offset| other.position + rotate_block_vector(other.direction, offset) == structure.position)
+        };
+
+        if feeds_in(IVec3::new(1, 0, 0)) { return None }
+
+        match (feeds_in(IVec3::new(0, 0, -1)), feeds_in(IVec3::new(0, 0, 1))) {
+            (true, false) => Some(-1),
+            (false, true) => Some(1),
+            _ => None,
+        }
+    }
+}
+
+
+///
+/// Where a belt-like structure of `kind` can put its items, relative to
+/// its origin and facing north. Belts take the first one that has a belt
+/// while splitters spread their items over all of them
+///
+fn output_offsets(kind: StructureKind) -> Option<&'static [IVec3]> {
+    match kind {
+        StructureKind::Belt
+        | StructureKind::FastBelt
+        | StructureKind::ExpressBelt => Some(&[
+            IVec3::new(-1,  0,  0),
+            IVec3::new(-1,  1,  0),
+            IVec3::new(-1, -1,  0),
+        ]),
+
+
+        StructureKind::Splitter => Some(&[
+            IVec3::new(-1,  0,  0),
+            IVec3::new(-1,  0,  1),
+        ]),
+
+        _ => None,
+    }
 }
 
 
@@ -257,6 +299,32 @@ pub struct SccNode {
 }
 
 
+///
+/// What `Belts::export` writes the belt graph out as
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// the strongly connected components as graphviz clusters
+    Dot,
+    /// every node and edge with the structure behind it
+    Json,
+    /// one line per node listing the nodes it outputs to
+    Adjacency,
+}
+
+
+impl GraphFormat {
+    pub fn from_str(str: &str) -> Option<GraphFormat> {
+        Some(match str {
+            "dot" => GraphFormat::Dot,
+            "json" => GraphFormat::Json,
+            "adjacency" | "adj" | "txt" => GraphFormat::Adjacency,
+            _ => return None,
+        })
+    }
+}
+
+
 pub struct Belts {
     pub worklist: Vec<NodeId>,
     pub structure_to_node: HashMap<StructureId, NodeId>,
@@ -317,4 +385,87 @@ impl Belts {
         output
 
     }
+
+
+    pub fn export(&self, structures: &Structures, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.scc_graph(),
+            GraphFormat::Json => self.json_graph(structures),
+            GraphFormat::Adjacency => self.adjacency_list(),
+        }
+    }
+
+
+    ///
+    /// The graph as `{ "nodes": [...], "edges": [...] }` where every
+    /// node has its `node_id`, `structure_id`, `kind`, `position`,
+    /// `direction` and `outputs`, and every edge is a `from`/`to` pair
+    ///
+    pub fn json_graph(&self, structures: &Structures) -> String {
+        let mut output = String::new();
+        let _ = write!(output, "{{\"nodes\":[");
+
+        let mut first = true;
+        for node_id in self.nodes.krange() {
+            let Some(node) = &self.nodes[node_id]
+            else { continue };
+
+            let structure = structures.get(node.structure_id);
+            let pos = structure.position;
+
+            if !first { output.push(',') }
+            first = false;
+
+            let _ = write!(output, "{{\"node_id\":{},\"structure_id\":{},\"kind\":\"{:?}\",\"position\":[{},{},{}],\"direction\":\"{:?}\",\"outputs\":[",
+                           node_id.usize(), node.structure_id.0.key.usize(), structure.data.as_kind(),
+                           pos.x, pos.y, pos.z, structure.direction);
+
+            for (i, link) in node.outputs.iter().flatten().enumerate() {
+                if i > 0 { output.push(',') }
+                let _ = write!(output, "{}", link.usize());
+            }
+
+            let _ = write!(output, "]}}");
+        }
+
+        let _ = write!(output, "],\"edges\":[");
+
+        let mut first = true;
+        for node_id in self.nodes.krange() {
+            let Some(node) = &self.nodes[node_id]
+            else { continue };
+
+            for link in node.outputs.iter().flatten() {
+                if !first { output.push(',') }
+                first = false;
+
+                let _ = write!(output, "{{\"from\":{},\"to\":{}}}", node_id.usize(), link.usize());
+            }
+        }
+
+        let _ = write!(output, "]}}");
+        output
+    }
+
+
+    ///
+    /// One `node -> output output` line per node,
+    /// a node with no outputs just ends after the arrow
+    ///
+    pub fn adjacency_list(&self) -> String {
+        let mut output = String::new();
+        for node_id in self.nodes.krange() {
+            let Some(node) = &self.nodes[node_id]
+            else { continue };
+
+            let _ = write!(output, "{} ->", node_id.usize());
+            for link in node.outputs.iter().flatten() {
+                let _ = write!(output, " {}", link.usize());
+            }
+
+            output.push('\n');
+        }
+
+        output
+    }
 }
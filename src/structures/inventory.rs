@@ -3,6 +3,9 @@ use crate::items::{Item, ItemKind};
 #[derive(Debug)]
 pub struct StructureInventory {
     pub slots: Vec<Option<Item>>,
+    /// filters set by the player on top of the slot's own,
+    /// a chest with these set works as a simple sorter
+    pub filters: Vec<Filter>,
     pub(super) meta: &'static [SlotMeta],
 }
 
@@ -39,22 +42,33 @@ impl StructureInventory {
     pub fn new(meta: &'static [SlotMeta]) -> Self {
         Self {
             slots: vec![None; meta.len()],
+            filters: vec![Filter::None; meta.len()],
             meta,
         }
     }
 
 
+    ///
+    /// Whether the slot at `index` takes items of `kind`,
+    /// both the slot's own filter and the player's have to allow it
+    ///
+    pub fn slot_accepts(&self, index: usize, kind: ItemKind) -> bool {
+        let meta_filter = match self.meta[index].kind {
+            SlotKind::Input { filter } => filter,
+            SlotKind::Storage => Filter::None,
+            SlotKind::Output => return false,
+        };
+
+        meta_filter.is_valid(kind) && self.filters[index].is_valid(kind)
+    }
+
+
     pub fn can_accept(&self, mut item: Item) -> bool {
         for index in 0..self.meta.len() {
             let meta = self.meta[index];
 
             let max_amount = meta.max_amount.min(item.kind.max_stack_size());
-            if meta.kind == SlotKind::Output {
-                continue;
-            }
-
-            if let SlotKind::Input { filter } = meta.kind 
-                && !filter.is_valid(item.kind) {
+            if !self.slot_accepts(index, item.kind) {
                 continue;
             }
 
@@ -63,7 +77,7 @@ impl StructureInventory {
                 Some(curr_item) => {
                     if curr_item.kind != item.kind { continue }
 
-                    max_amount - curr_item.amount.min(max_amount)
+                    curr_item.room_left_in(meta.max_amount)
                 },
 
                 None => {
@@ -89,12 +103,7 @@ impl StructureInventory {
             let meta = self.meta[index];
             let max_amount = meta.max_amount.min(item.kind.max_stack_size());
 
-            if meta.kind == SlotKind::Output {
-                continue;
-            }
-
-            if let SlotKind::Input { filter } = meta.kind 
-                && !filter.is_valid(item.kind) {
+            if !self.slot_accepts(index, item.kind) {
                 continue;
             }
 
@@ -103,9 +112,8 @@ impl StructureInventory {
             match slot {
                 Some(curr_item) => {
                     if curr_item.kind != item.kind { continue }
-                    debug_assert!(curr_item.amount <= max_amount);
 
-                    let available = max_amount - curr_item.amount;
+                    let available = curr_item.room_left_in(meta.max_amount);
                     let amount = available.min(item.amount);
                     item.amount -= amount;
                     curr_item.amount += amount;
@@ -217,6 +225,76 @@ impl StructureInventory {
 
         Some(item)
     }
+
+
+    ///
+    /// Merges partial stacks and orders the storage slots by item
+    /// name then amount. Input, output and filtered slots are left
+    /// untouched so items never end up in a slot that wouldn't accept them.
+    /// Returns false and leaves the inventory as is if the sorted
+    /// stacks wouldn't fit, see `sort_slots`
+    ///
+    pub fn sort(&mut self) -> bool {
+        let indices = (0..self.meta.len())
+            .filter(|&i| self.meta[i].kind == SlotKind::Storage && self.filters[i] == Filter::None)
+            .collect::<Vec<_>>();
+
+        let Some(max_amount) = indices.iter().map(|&i| self.meta[i].max_amount).min()
+        else { return true };
+
+        let mut slots = indices.iter().map(|&i| self.slots[i]).collect::<Vec<_>>();
+        if !sort_slots(&mut slots, max_amount) { return false }
+
+        for (i, item) in indices.into_iter().zip(slots) {
+            self.slots[i] = item;
+        }
+
+        true
+    }
+}
+
+
+///
+/// Merges partial stacks of the same kind and orders the
+/// slots by item name then amount, empty slots go last.
+/// Returns false without touching `slots` if the stacks need
+/// more slots than there are, which can happen when a slot
+/// held more than `max_amount`
+///
+pub fn sort_slots(slots: &mut [Option<Item>], max_amount: u32) -> bool {
+    let mut items = slots.iter().flatten().copied().collect::<Vec<_>>();
+    items.sort_by_key(|x| x.kind.to_string());
+
+    let mut merged : Vec<Item> = Vec::with_capacity(items.len());
+    for item in items {
+        let max = max_amount.min(item.kind.max_stack_size());
+        let mut amount = item.amount;
+
+        if let Some(last) = merged.last_mut()
+            && last.kind == item.kind
+            && last.amount < max {
+            let moved = (max - last.amount).min(amount);
+            last.amount += moved;
+            amount -= moved;
+        }
+
+        while amount > 0 {
+            let stack = amount.min(max);
+            merged.push(item.with_amount(stack));
+            amount -= stack;
+        }
+    }
+
+    if merged.len() > slots.len() { return false }
+
+    merged.sort_by(|a, b| a.kind.to_string().cmp(b.kind.to_string()).then(b.amount.cmp(&a.amount)));
+
+    let mut merged = merged.into_iter();
+    for slot in slots.iter_mut() {
+        *slot = merged.next();
+    }
+
+    true
 }
 
 
@@ -237,3 +315,23 @@ impl Filter {
         }
     }
 }
+
+
+#[test]
+fn test_sort_slots_keeps_stacks_that_dont_fit() {
+    let original = [Some(Item::new(ItemKind::IronPlate, 80)), Some(Item::new(ItemKind::Coal, 10))];
+
+    // the plates split into two stacks of 50 and 30 which
+    // leaves no slot for the coal
+    let mut slots = original;
+    assert!(!sort_slots(&mut slots, 50));
+    assert_eq!(slots, original);
+
+    let mut slots = [original[0], original[1], None];
+    assert!(sort_slots(&mut slots, 50));
+    assert_eq!(slots, [
+        Some(Item::new(ItemKind::Coal, 10)),
+        Some(Item::new(ItemKind::IronPlate, 50)),
+        Some(Item::new(ItemKind::IronPlate, 30)),
+    ]);
+}
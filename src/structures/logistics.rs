@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use glam::IVec3;
+use sti::{define_key, vec::KVec};
+
+use crate::{constants::LOGISTICS_RANGE, structures::strct::StructureData};
+
+use super::{StructureId, Structures};
+
+
+define_key!(pub NetworkId(u32));
+
+
+impl Structures {
+    ///
+    /// Groups every provider and requester chest into networks,
+    /// two chests share a network if they're within `LOGISTICS_RANGE`
+    /// of each other or of a chest in between them
+    ///
+    pub fn logistics(&self) -> Logistics {
+        let mut structure_to_network : HashMap<StructureId, NetworkId> = HashMap::new();
+        let mut networks : KVec<NetworkId, Network> = KVec::new();
+
+        let mut visit_stack = Vec::with_capacity(64);
+
+        // the cells are as big as the range so everything in range
+        // of a chest is in its own cell or one of the 26 around it
+        let mut cells : HashMap<IVec3, Vec<StructureId>> = HashMap::new();
+        for (k, structure) in self.structs.iter() {
+            if !structure.data.as_kind().is_logistics_chest() { continue }
+            cells.entry(logistics_cell(structure.position)).or_default().push(StructureId(k));
+        }
+
+
+        for (k, structure) in self.structs.iter() {
+            let id = StructureId(k);
+            if !structure.data.as_kind().is_logistics_chest() { continue }
+            if structure_to_network.contains_key(&id) { continue }
+
+            let network_id = networks.push(Network { providers: vec![], requesters: vec![] });
+
+            structure_to_network.insert(id, network_id);
+            visit_stack.push(id);
+
+            while let Some(id) = visit_stack.pop() {
+                let position = self.get(id).position;
+                let network = &mut networks[network_id];
+
+                match self.get(id).data {
+                    StructureData::ProviderChest => network.providers.push(id),
+                    StructureData::RequesterChest { .. } => network.requesters.push(id),
+                    _ => unreachable!(),
+                }
+
+
+                let cell = logistics_cell(position);
+                for x in -1..=1 {
+                    for y in -1..=1 {
+                        for z in -1..=1 {
+                            let Some(bucket) = cells.get(&(cell + IVec3::new(x, y, z)))
+                            else { continue };
+
+                            for &other_id in bucket {
+                                if structure_to_network.contains_key(&other_id) { continue }
+
+                                let other = self.get(other_id);
+                                if (other.position - position).abs().max_element() > LOGISTICS_RANGE { continue }
+
+                                structure_to_network.insert(other_id, network_id);
+                                visit_stack.push(other_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        assert!(visit_stack.len() == 0);
+
+        Logistics {
+            structure_to_network,
+            networks,
+        }
+    }
+}
+
+
+fn logistics_cell(pos: IVec3) -> IVec3 {
+    pos.div_euclid(IVec3::splat(LOGISTICS_RANGE))
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    pub providers: Vec<StructureId>,
+    pub requesters: Vec<StructureId>,
+}
+
+
+pub struct Logistics {
+    pub structure_to_network: HashMap<StructureId, NetworkId>,
+    pub networks: KVec<NetworkId, Network>,
+}
+
+
+impl Logistics {
+    pub fn network_of(&self, structure: StructureId) -> Option<(NetworkId, &Network)> {
+        let network = *self.structure_to_network.get(&structure)?;
+        Some((network, &self.networks[network]))
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use sti::{define_key, vec::KVec};
+
+use crate::{constants::PIPE_CAPACITY, structures::strct::StructureData, voxel_world::{voxel::Voxel, VoxelWorld, SURROUNDING_OFFSETS}};
+
+use super::{StructureId, Structures};
+
+
+define_key!(pub NodeId(u32));
+define_key!(pub NetworkId(u32));
+
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Fluid {
+    Water,
+    Oil,
+}
+
+
+impl Fluid {
+    pub const ALL : &[Fluid] = &[Fluid::Water, Fluid::Oil];
+
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Fluid::Water => "water",
+            Fluid::Oil => "oil",
+        }
+    }
+
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fluid::Water => "§bWater",
+            Fluid::Oil => "§8Oil",
+        }
+    }
+
+
+    ///
+    /// What a pump sitting on top of `voxel` pulls out of the ground,
+    /// coal seams give oil and anything else gives water
+    ///
+    pub fn pumped_from(voxel: Voxel) -> Fluid {
+        match voxel {
+            Voxel::Coal => Fluid::Oil,
+            _ => Fluid::Water,
+        }
+    }
+}
+
+
+impl Structures {
+    pub fn pipes(&self, world: &VoxelWorld) -> Pipes {
+        let mut struct_to_node : HashMap<StructureId, NodeId> = HashMap::new();
+        let mut nodes : KVec<NodeId, Node> = KVec::new();
+
+        // create graph
+        for (k, structure) in self.structs.iter() {
+            let id = StructureId(k);
+            if !structure.data.as_kind().is_fluid_carrier() { continue }
+
+            let node = nodes.push(Node { links: vec![], consumers: vec![], structure_id: id });
+            struct_to_node.insert(id, node);
+        }
+
+
+        for node_id in nodes.krange() {
+            let structure = self.get(nodes[node_id].structure_id);
+            let position = structure.position;
+
+            for offset in SURROUNDING_OFFSETS {
+                let Some(&other) = world.structure_blocks.get(&(position + offset))
+                else { continue };
+
+                if let Some(&link) = struct_to_node.get(&other) {
+                    nodes[node_id].links.push(link);
+                    continue;
+                }
+
+
+                let node = &mut nodes[node_id];
+                if self.get(other).data.as_kind().burns_fluid() && !node.consumers.contains(&other) {
+                    node.consumers.push(other);
+                }
+            }
+        }
+
+
+
+        // every link goes both ways so each strongly connected
+        // component of the graph is just a connected network
+        let mut node_to_network : KVec<NodeId, NetworkId> = KVec::from_value(nodes.len(), NetworkId(u32::MAX));
+        let mut networks : KVec<NetworkId, Network> = KVec::new();
+
+        let mut visit_stack = Vec::with_capacity(64);
+
+        for index in nodes.krange() {
+            if node_to_network[index] != NetworkId(u32::MAX) { continue }
+
+            let network_id = networks.push(Network {
+                nodes: vec![],
+                consumers: vec![],
+                fluid: None,
+                amount: 0,
+                pipes: 0,
+            });
+
+            node_to_network[index] = network_id;
+            visit_stack.push(index);
+
+            while let Some(index) = visit_stack.pop() {
+                let node = &nodes[index];
+                let network = &mut networks[network_id];
+                network.nodes.push(index);
+
+                for consumer in &node.consumers {
+                    if !network.consumers.contains(consumer) {
+                        network.consumers.push(*consumer);
+                    }
+                }
+
+
+                if let StructureData::Pipe { fluid, amount } = self.get(node.structure_id).data {
+                    network.pipes += 1;
+
+                    // mixed networks keep whichever fluid got there first
+                    if let Some(fluid) = fluid
+                        && network.fluid.is_none_or(|x| x == fluid) {
+                        network.fluid = Some(fluid);
+                        network.amount += amount;
+                    }
+                }
+
+
+                for &link in &node.links {
+                    if node_to_network[link] != NetworkId(u32::MAX) { continue }
+
+                    node_to_network[link] = network_id;
+                    visit_stack.push(link);
+                }
+            }
+        }
+
+        assert!(visit_stack.len() == 0);
+
+        Pipes {
+            structure_to_node: struct_to_node,
+            nodes,
+            node_to_network,
+            networks,
+        }
+    }
+}
+
+
+pub struct Node {
+    pub links: Vec<NodeId>,
+    /// adjacent structures that can burn what the pipe carries
+    pub consumers: Vec<StructureId>,
+    pub structure_id: StructureId,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    pub nodes: Vec<NodeId>,
+    pub consumers: Vec<StructureId>,
+    pub fluid: Option<Fluid>,
+    /// the fluid in the network as a whole, it
+    /// gets split evenly between the pipes
+    pub amount: u32,
+    pub pipes: u32,
+}
+
+
+pub struct Pipes {
+    pub structure_to_node: HashMap<StructureId, NodeId>,
+    pub nodes: KVec<NodeId, Node>,
+    pub node_to_network: KVec<NodeId, NetworkId>,
+    pub networks: KVec<NetworkId, Network>,
+}
+
+
+impl Pipes {
+    pub fn node(&self, node: NodeId) -> &Node {
+        &self.nodes[node]
+    }
+
+
+    pub fn network_of(&self, structure: StructureId) -> Option<(NetworkId, &Network)> {
+        let node = self.structure_to_node.get(&structure)?;
+        let network = self.node_to_network[*node];
+        Some((network, &self.networks[network]))
+    }
+}
+
+
+impl Network {
+    pub fn capacity(&self) -> u32 {
+        self.pipes * PIPE_CAPACITY
+    }
+}
+
@@ -1,6 +1,6 @@
-use glam::IVec3;
+use glam::{IVec3, Quat};
 
-use crate::{constants::{COAL_ENERGY_PER_UNIT, FURNACE_COST_PER_SMELT}, crafting::{Recipe, FURNACE_RECIPES}, directions::CardinalDirection, items::{Item, ItemKind}, mesh::Mesh, structures::{inventory::Filter}};
+use crate::{constants::{ASSEMBLER_MODULE_SLOTS, ASSEMBLER_TRANSFER_RATE, BELT_TRANSFER_RATE, CHEST_TRANSFER_RATE, COAL_ENERGY_PER_UNIT, FURNACE_COST_PER_SMELT, FURNACE_TRANSFER_RATE, INSERTER_TRANSFER_RATE, QUARRY_MAX_DEPTH, STEEL_FURNACE_COST_PER_SMELT, TICKS_PER_SECOND}, crafting::{crafting_recipe_index, crafting_recipe_inventory, Recipe, FURNACE_RECIPES}, directions::{CardinalDirection, Orientation}, items::{Item, ItemKind}, mesh::Mesh, structures::{inventory::Filter, pipes::Fluid, transfer::TransferRate}};
 
 use super::inventory::{SlotKind, SlotMeta, StructureInventory};
 
@@ -8,11 +8,16 @@ use super::inventory::{SlotKind, SlotMeta, StructureInventory};
 pub struct Structure {
     pub position: IVec3,
     pub direction: CardinalDirection,
+    /// always `Orientation::Flat` unless the kind `is_orientable`
+    pub orientation: Orientation,
     pub data: StructureData,
 
     pub inventory: Option<StructureInventory>,
     pub energy: StructureEnergy,
 
+    /// how many more ticks of mining it takes to break the structure
+    pub durability: u32,
+
     pub is_asleep: bool,
 }
 
@@ -21,27 +26,82 @@ pub struct Structure {
 pub enum StructureData {
     Quarry {
         current_progress: u32,
+        /// the width of the square the quarry digs out
+        size: u32,
+        /// how many layers down the quarry digs before stopping
+        max_depth: u32,
     },
 
     Inserter {
         state: InserterState,
         filter: Option<ItemKind>,
+        /// how many tiles past either end of
+        /// the arm the inserter grabs and drops
+        reach: u32,
     },
 
     Chest,
     Silo,
-    Belt,
+
+    /// hands its contents out to requester chests in the same network
+    ProviderChest,
+
+    RequesterChest {
+        /// the items, and how many of each, the chest
+        /// wants to be kept stocked with
+        requests: Vec<Item>,
+    },
+
+    Belt {
+        tier: BeltTier,
+    },
 
     Splitter {
+        /// which output each lane tries first next,
+        /// flips every time it hands an item off
         priority: [u8; 2],
+        /// items matching an output's filter only go
+        /// to that output, the rest go to unfiltered ones
+        filters: [Option<ItemKind>; 2],
+        /// the output that gets items whenever it has room,
+        /// `None` alternates between the two
+        preferred: Option<usize>,
     },
 
 
     Assembler {
         recipe: Option<Recipe>,
+        auto_output: bool,
+        /// speed and productivity modules, handed back
+        /// to the player when the assembler is mined
+        modules: [Option<ItemKind>; ASSEMBLER_MODULE_SLOTS],
+        /// how close the next bonus craft from productivity
+        /// modules is, in thousandths of a craft
+        productivity: u32,
     },
 
     Furnace(Furnace),
+
+    Pump,
+
+    Pipe {
+        fluid: Option<Fluid>,
+        /// this pipe's share of its network's fluid
+        amount: u32,
+    },
+}
+
+
+///
+/// The settings a player can configure on a structure,
+/// copied off of a placed one with the pipette
+///
+#[derive(Clone, Copy, Debug)]
+pub enum StructureConfig {
+    Inserter { filter: Option<ItemKind> },
+    Splitter { priority: [u8; 2], filters: [Option<ItemKind>; 2], preferred: Option<usize> },
+    Assembler { recipe: Option<Recipe>, auto_output: bool },
+    Furnace { auto_fuel: bool },
 }
 
 
@@ -52,17 +112,75 @@ pub enum InserterState {
 }
 
 
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum BeltTier {
+    Normal,
+    Fast,
+    Express,
+}
+
+
+impl BeltTier {
+    pub const ALL : [BeltTier; 3] = [BeltTier::Normal, BeltTier::Fast, BeltTier::Express];
+
+
+    ///
+    /// How many slots an item moves forward
+    /// every time the belts get updated
+    ///
+    pub fn speed(self) -> u32 {
+        match self {
+            BeltTier::Normal => 1,
+            BeltTier::Fast => 2,
+            BeltTier::Express => 3,
+        }
+    }
+
+
+    pub fn kind(self) -> StructureKind {
+        match self {
+            BeltTier::Normal => StructureKind::Belt,
+            BeltTier::Fast => StructureKind::FastBelt,
+            BeltTier::Express => StructureKind::ExpressBelt,
+        }
+    }
+
+
+    ///
+    /// The model of a belt of this tier that's fed from the side,
+    /// `curve` is the side as in `Structures::belt_curve`. They're
+    /// made by `scripts/generate_belt_curves.py`
+    ///
+    pub fn create_curve_mesh(self, device: &wgpu::Device, curve: i32) -> Result<Mesh, String> {
+        let side = if curve < 0 { "left" } else { "right" };
+        match self {
+            BeltTier::Normal => Mesh::from_vmf(device, &format!("assets/models/belt_curve_{side}.vmf")),
+            BeltTier::Fast => Mesh::from_vmf(device, &format!("assets/models/fast_belt_curve_{side}.vmf")),
+            BeltTier::Express => Mesh::from_vmf(device, &format!("assets/models/express_belt_curve_{side}.vmf")),
+        }
+    }
+}
+
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum StructureKind {
     Quarry,
+    LargeQuarry,
     Inserter,
+    LongInserter,
     Chest,
     Silo,
+    ProviderChest,
+    RequesterChest,
     Belt,
+    FastBelt,
+    ExpressBelt,
     Splitter,
     Assembler,
     Furnace,
     SteelFurnace,
+    Pump,
+    Pipe,
 }
 
 
@@ -129,14 +247,17 @@ impl StructureData {
     fn from_kind(kind: StructureKind) -> (Self, Option<StructureInventory>) {
         match kind {
 
-            StructureKind::Quarry => {
+            StructureKind::Quarry
+            | StructureKind::LargeQuarry => {
                 const SLOTS : &[SlotMeta] = &[SlotMeta::new(1, SlotKind::Output)];
-                (Self::Quarry { current_progress: 0 }, Some(StructureInventory::new(SLOTS)))
+                let data = Self::Quarry { current_progress: 0, size: kind.quarry_size(), max_depth: QUARRY_MAX_DEPTH };
+                (data, Some(StructureInventory::new(SLOTS)))
             },
 
 
-            StructureKind::Inserter => {
-                (Self::Inserter { state: InserterState::Searching, filter: None }, None)
+            StructureKind::Inserter
+            | StructureKind::LongInserter => {
+                (Self::Inserter { state: InserterState::Searching, filter: None, reach: kind.inserter_reach() }, None)
             },
 
 
@@ -152,19 +273,30 @@ impl StructureData {
             },
 
 
-            StructureKind::Belt => {
-                const SLOTS : &[SlotMeta] = &[SlotMeta::new(1, SlotKind::Storage); 4];
-                (Self::Belt, Some(StructureInventory::new(SLOTS)))
+            StructureKind::ProviderChest => {
+                const SLOTS : &[SlotMeta] = &[SlotMeta::new(u32::MAX, SlotKind::Storage); 3*3];
+                (Self::ProviderChest, Some(StructureInventory::new(SLOTS)))
             },
 
 
+            StructureKind::RequesterChest => {
+                const SLOTS : &[SlotMeta] = &[SlotMeta::new(u32::MAX, SlotKind::Storage); 3*3];
+                (Self::RequesterChest { requests: vec![] }, Some(StructureInventory::new(SLOTS)))
+            },
+
+
+            StructureKind::Belt => Self::belt(BeltTier::Normal),
+            StructureKind::FastBelt => Self::belt(BeltTier::Fast),
+            StructureKind::ExpressBelt => Self::belt(BeltTier::Express),
+
+
             StructureKind::Splitter => {
                 const SLOTS : &[SlotMeta] = &[SlotMeta::new(1, SlotKind::Storage); 8];
-                (Self::Splitter { priority: [0; 2] }, Some(StructureInventory::new(SLOTS)))
+                (Self::Splitter { priority: [0; 2], filters: [None; 2], preferred: None }, Some(StructureInventory::new(SLOTS)))
             },
 
 
-            StructureKind::Assembler => (Self::Assembler { recipe: None }, None),
+            StructureKind::Assembler => (Self::Assembler { recipe: None, auto_output: false, modules: [None; ASSEMBLER_MODULE_SLOTS], productivity: 0 }, None),
 
 
             StructureKind::Furnace => {
@@ -174,7 +306,7 @@ impl StructureData {
                     SlotMeta::new(u32::MAX, SlotKind::Output)
                 ];
 
-                (Self::Furnace(Furnace::new(2)), Some(StructureInventory::new(SLOTS)))
+                (Self::Furnace(Furnace::new(StructureKind::Furnace)), Some(StructureInventory::new(SLOTS)))
             },
 
             StructureKind::SteelFurnace => {
@@ -184,26 +316,58 @@ impl StructureData {
                     SlotMeta::new(u32::MAX, SlotKind::Output)
                 ];
 
-                (Self::Furnace(Furnace::new(1)), Some(StructureInventory::new(SLOTS)))
+                (Self::Furnace(Furnace::new(StructureKind::SteelFurnace)), Some(StructureInventory::new(SLOTS)))
             },
+
+
+            StructureKind::Pump => (Self::Pump, None),
+            StructureKind::Pipe => (Self::Pipe { fluid: None, amount: 0 }, None),
         }
     }
 
 
+    fn belt(tier: BeltTier) -> (Self, Option<StructureInventory>) {
+        const SLOTS : &[SlotMeta] = &[SlotMeta::new(1, SlotKind::Storage); 4];
+        (Self::Belt { tier }, Some(StructureInventory::new(SLOTS)))
+    }
+
+
     pub fn as_kind(&self) -> StructureKind {
         match self {
-            StructureData::Quarry { .. } => StructureKind::Quarry,
-            StructureData::Inserter { .. } => StructureKind::Inserter,
+            StructureData::Quarry { size: 3, .. } => StructureKind::Quarry,
+            StructureData::Quarry { size: 5, .. } => StructureKind::LargeQuarry,
+            StructureData::Quarry { .. } => unreachable!(),
+            StructureData::Inserter { reach: 1, .. } => StructureKind::Inserter,
+            StructureData::Inserter { reach: 2, .. } => StructureKind::LongInserter,
+            StructureData::Inserter { .. } => unreachable!(),
             StructureData::Chest { .. } => StructureKind::Chest,
             StructureData::Silo { .. } => StructureKind::Silo ,
-            StructureData::Belt { .. } => StructureKind::Belt,
+            StructureData::ProviderChest => StructureKind::ProviderChest,
+            StructureData::RequesterChest { .. } => StructureKind::RequesterChest,
+            StructureData::Belt { tier } => tier.kind(),
             StructureData::Splitter { .. } => StructureKind::Splitter,
             StructureData::Assembler { .. } => StructureKind::Assembler,
-            StructureData::Furnace(furnace) if furnace.multiplier == 2 => StructureKind::Furnace,
-            StructureData::Furnace(furnace) if furnace.multiplier == 1 => StructureKind::SteelFurnace,
-            StructureData::Furnace(_) => unreachable!(),
+            StructureData::Furnace(furnace) => furnace.kind,
+            StructureData::Pump => StructureKind::Pump,
+            StructureData::Pipe { .. } => StructureKind::Pipe,
         }
     }
+
+
+    ///
+    /// Whether looking at the structure prompts the player to
+    /// press E, the ones with an inventory worth opening
+    ///
+    pub fn is_interactable(&self) -> bool {
+        matches!(self,
+              StructureData::Chest
+            | StructureData::Silo
+            | StructureData::ProviderChest
+            | StructureData::RequesterChest { .. }
+            | StructureData::Furnace(_)
+            | StructureData::Assembler { .. }
+        )
+    }
 }
 
 
@@ -215,9 +379,61 @@ impl Structure {
             data,
             position: pos,
             direction,
+            orientation: Orientation::Flat,
             is_asleep: true,
             inventory: inv,
             energy: StructureEnergy { energy: COAL_ENERGY_PER_UNIT/2 },
+            durability: kind.durability(),
+        }
+    }
+
+
+    pub fn config(&self) -> Option<StructureConfig> {
+        match &self.data {
+            StructureData::Inserter { filter, .. } => Some(StructureConfig::Inserter { filter: *filter }),
+            StructureData::Splitter { priority, filters, preferred } => Some(StructureConfig::Splitter { priority: *priority, filters: *filters, preferred: *preferred }),
+            StructureData::Assembler { recipe, auto_output, .. } => Some(StructureConfig::Assembler { recipe: *recipe, auto_output: *auto_output }),
+            StructureData::Furnace(furnace) => Some(StructureConfig::Furnace { auto_fuel: furnace.auto_fuel }),
+
+            StructureData::Quarry { .. }
+            | StructureData::Chest
+            | StructureData::Silo
+            | StructureData::ProviderChest
+            | StructureData::RequesterChest { .. }
+            | StructureData::Belt { .. }
+            | StructureData::Pump
+            | StructureData::Pipe { .. } => None,
+        }
+    }
+
+
+    ///
+    /// Copies `config` onto the structure, configs meant for a
+    /// different kind are ignored. A new recipe replaces the
+    /// assembler's inventory so see `Game::paste_settings`
+    /// for applying it to one that's already running
+    ///
+    pub fn apply_config(&mut self, config: StructureConfig) {
+        match (&mut self.data, config) {
+            (StructureData::Inserter { filter, .. }, StructureConfig::Inserter { filter: new }) => *filter = new,
+            (StructureData::Splitter { priority, filters, preferred }, StructureConfig::Splitter { priority: new_priority, filters: new, preferred: new_preferred }) => {
+                *priority = new_priority;
+                *filters = new;
+                *preferred = new_preferred;
+            },
+            (StructureData::Furnace(furnace), StructureConfig::Furnace { auto_fuel }) => furnace.auto_fuel = auto_fuel,
+
+            (StructureData::Assembler { recipe, auto_output, .. }, StructureConfig::Assembler { recipe: new, auto_output: new_auto_output }) => {
+                *auto_output = new_auto_output;
+                *recipe = new;
+
+                if let Some(new) = new {
+                    let slots = crafting_recipe_inventory(crafting_recipe_index(new));
+                    self.inventory = Some(StructureInventory::new(slots));
+                }
+            },
+
+            _ => (),
         }
     }
 
@@ -228,7 +444,15 @@ impl Structure {
 
 
     pub fn zero_zero(&self) -> IVec3 {
-        self.position - self.data.as_kind().origin(self.direction)
+        self.position - self.data.as_kind().oriented_origin(self.direction, self.orientation)
+    }
+
+
+    ///
+    /// The blocks the structure takes up relative to `zero_zero`
+    ///
+    pub fn blocks(&self) -> impl Iterator<Item = IVec3> {
+        self.data.as_kind().oriented_blocks(self.direction, self.orientation)
     }
 
 
@@ -241,11 +465,11 @@ impl Structure {
                 let input = inv.input(0).0;
 
                 let result = if let Some(input) = input {
-                    input.kind == item.kind && input.amount < 5 && input.amount + item.amount <= input.kind.max_stack_size()
+                    input.kind == item.kind && input.amount < 5 && item.amount <= input.room_left()
                 } else if let Some(output) = output {
                     let curr_recipe = FURNACE_RECIPES.iter().find(|x| x.result.kind == output.kind).unwrap();
                     let input = curr_recipe.requirements[0];
-                    input.kind == item.kind && input.amount + item.amount <= input.kind.max_stack_size()
+                    input.kind == item.kind && item.amount <= input.room_left()
                 } else {
                     FURNACE_RECIPES.iter().find(|x| x.requirements[0].kind == item.kind).is_some()
                 };
@@ -255,6 +479,20 @@ impl Structure {
             }
 
 
+            StructureData::Assembler { recipe, .. } => {
+                // only the current recipe's ingredients, the inventory can
+                // still be the old recipe's if the recipe got cleared
+                let Some(recipe) = recipe
+                else { return false };
+
+                if !recipe.requirements.iter().any(|x| x.kind == item.kind) {
+                    return false;
+                }
+
+                self.inventory.as_ref().is_some_and(|inv| inv.can_accept(item))
+            }
+
+
             _ => {
                 let Some(inventory) = &self.inventory
                 else { return false };
@@ -276,11 +514,11 @@ impl Structure {
 
 
                 let result = if let Some(input) = input {
-                    input.kind == item.kind && input.amount + item.amount <= input.kind.max_stack_size()
+                    input.kind == item.kind && item.amount <= input.room_left()
                 } else if let Some(output) = output {
                     let curr_recipe = FURNACE_RECIPES.iter().find(|x| x.result.kind == output.kind).unwrap();
                     let input = curr_recipe.requirements[0];
-                    input.kind == item.kind && input.amount + item.amount <= input.kind.max_stack_size()
+                    input.kind == item.kind && item.amount <= input.room_left()
                 } else {
                     FURNACE_RECIPES.iter().find(|x| x.requirements[0].kind == item.kind).is_some()
                 };
@@ -309,11 +547,11 @@ impl Structure {
                 let input = inv.input_mut(0);
 
                 let can_add_to_burning = if let Some(input) = input {
-                    input.kind == item.kind && input.amount + item.amount <= input.kind.max_stack_size()
+                    input.kind == item.kind && item.amount <= input.room_left()
                 } else if let Some(output) = output {
                     let curr_recipe = FURNACE_RECIPES.iter().find(|x| x.result.kind == output.kind).unwrap();
                     let input = curr_recipe.requirements[0];
-                    input.kind == item.kind && input.amount + item.amount <= input.kind.max_stack_size()
+                    input.kind == item.kind && item.amount <= input.room_left()
                 } else {
                     FURNACE_RECIPES.iter().find(|x| x.requirements[0].kind == item.kind).is_some()
                 };
@@ -429,7 +667,28 @@ impl StructureKind {
                 )
             },
 
-            StructureKind::Inserter => {
+            StructureKind::LargeQuarry => {
+                blocks_arr!(dir,
+                    IVec3::new(0, 0, 0), IVec3::new(1, 0, 0),
+                    IVec3::new(2, 0, 0), IVec3::new(3, 0, 0),
+                    IVec3::new(4, 0, 0), IVec3::new(5, 0, 0),
+                    IVec3::new(6, 0, 0),
+
+                    IVec3::new(0, 0, 1), IVec3::new(6, 0, 1),
+                    IVec3::new(0, 0, 2), IVec3::new(6, 0, 2),
+                    IVec3::new(0, 0, 3), IVec3::new(6, 0, 3),
+                    IVec3::new(0, 0, 4), IVec3::new(6, 0, 4),
+                    IVec3::new(0, 0, 5), IVec3::new(6, 0, 5),
+
+                    IVec3::new(0, 0, 6), IVec3::new(1, 0, 6),
+                    IVec3::new(2, 0, 6), IVec3::new(3, 0, 6),
+                    IVec3::new(4, 0, 6), IVec3::new(5, 0, 6),
+                    IVec3::new(6, 0, 6)
+                )
+            },
+
+            StructureKind::Inserter
+            | StructureKind::LongInserter => {
                 blocks_arr!(dir,
                     IVec3::new(0, 0, 0),
                     IVec3::new(1, 0, 0), 
@@ -438,7 +697,9 @@ impl StructureKind {
             }
 
 
-            StructureKind::Chest => {
+            StructureKind::Chest
+            | StructureKind::ProviderChest
+            | StructureKind::RequesterChest => {
                 blocks_arr!(dir,
                     IVec3::ZERO)
             }
@@ -460,7 +721,9 @@ impl StructureKind {
             }
 
 
-            StructureKind::Belt => {
+            StructureKind::Belt
+            | StructureKind::FastBelt
+            | StructureKind::ExpressBelt => {
                 blocks_arr!(dir,
                     IVec3::ZERO)
             }
@@ -506,6 +769,13 @@ impl StructureKind {
             }
 
 
+            StructureKind::Pump
+            | StructureKind::Pipe => {
+                blocks_arr!(dir,
+                    IVec3::ZERO)
+            }
+
+
             StructureKind::SteelFurnace => {
                 blocks_arr!(dir,
                     IVec3::new(0, 0, 0), IVec3::new(1, 0, 0), IVec3::new(2, 0, 0),
@@ -528,29 +798,228 @@ impl StructureKind {
     pub fn origin(self, dir: CardinalDirection) -> IVec3 {
         match self {
             StructureKind::Quarry => rotate_block_vector(dir, IVec3::new(4, 0, 2)),
+            StructureKind::LargeQuarry => rotate_block_vector(dir, IVec3::new(6, 0, 3)),
             StructureKind::Inserter => rotate_block_vector(dir, IVec3::new(2, 0, 0)),
+            StructureKind::LongInserter => rotate_block_vector(dir, IVec3::new(2, 0, 0)),
             StructureKind::Chest => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
             StructureKind::Silo => rotate_block_vector(dir, IVec3::new(2, 0, 1)),
+            StructureKind::ProviderChest => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
+            StructureKind::RequesterChest => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
             StructureKind::Belt => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
+            StructureKind::FastBelt => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
+            StructureKind::ExpressBelt => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
             StructureKind::Splitter => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
             StructureKind::Assembler => rotate_block_vector(dir, IVec3::new(2, 0, 1)),
             StructureKind::Furnace => rotate_block_vector(dir, IVec3::new(2, 0, 1)),
             StructureKind::SteelFurnace => rotate_block_vector(dir, IVec3::new(2, 0, 1)),
+            StructureKind::Pump => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
+            StructureKind::Pipe => rotate_block_vector(dir, IVec3::new(0, 0, 0)),
+        }
+    }
+
+
+    ///
+    /// `blocks` for a structure that might be tipped up or down
+    ///
+    pub fn oriented_blocks(self, dir: CardinalDirection, orientation: Orientation) -> impl Iterator<Item = IVec3> {
+        self.blocks(CardinalDirection::North).iter()
+            .map(move |&offset| rotate_oriented_vector(dir, orientation, offset))
+    }
+
+
+    ///
+    /// `origin` for a structure that might be tipped up or down
+    ///
+    pub fn oriented_origin(self, dir: CardinalDirection, orientation: Orientation) -> IVec3 {
+        rotate_oriented_vector(dir, orientation, self.origin(CardinalDirection::North))
+    }
+
+
+    ///
+    /// How many ticks of mining it takes to break a
+    /// structure, like `Voxel::base_hardness` for blocks
+    ///
+    pub fn durability(self) -> u32 {
+        match self {
+            StructureKind::Belt => TICKS_PER_SECOND / 3,
+            StructureKind::FastBelt => TICKS_PER_SECOND / 2,
+            StructureKind::ExpressBelt => TICKS_PER_SECOND * 2 / 3,
+            StructureKind::Splitter => TICKS_PER_SECOND / 2,
+            StructureKind::Inserter => TICKS_PER_SECOND / 2,
+            StructureKind::LongInserter => TICKS_PER_SECOND / 2,
+            StructureKind::Chest => TICKS_PER_SECOND / 2,
+            StructureKind::Silo => TICKS_PER_SECOND,
+            StructureKind::ProviderChest => TICKS_PER_SECOND / 2,
+            StructureKind::RequesterChest => TICKS_PER_SECOND / 2,
+            StructureKind::Quarry => TICKS_PER_SECOND,
+            StructureKind::LargeQuarry => TICKS_PER_SECOND * 2,
+            StructureKind::Assembler => TICKS_PER_SECOND,
+            StructureKind::Furnace => TICKS_PER_SECOND,
+            StructureKind::SteelFurnace => TICKS_PER_SECOND * 2,
+            StructureKind::Pump => TICKS_PER_SECOND / 2,
+            StructureKind::Pipe => TICKS_PER_SECOND / 3,
         }
     }
 
 
-    pub fn create_mesh(self, device: &wgpu::Device) -> Mesh {
+    ///
+    /// How tall each of the structure's blocks is for collisions
+    ///
+    pub fn collision_height(self) -> f32 {
+        match self {
+            StructureKind::Belt => 0.8,
+            StructureKind::FastBelt => 0.8,
+            StructureKind::ExpressBelt => 0.8,
+            StructureKind::Splitter => 0.8,
+            _ => 1.0,
+        }
+    }
+
+
+    ///
+    /// Whether the player can walk through the structure
+    ///
+    pub fn is_walkable(self) -> bool {
+        self.is_belt() || self == StructureKind::Splitter
+    }
+
+
+    pub fn is_belt(self) -> bool {
+        matches!(self, StructureKind::Belt | StructureKind::FastBelt | StructureKind::ExpressBelt)
+    }
+
+
+    ///
+    /// Whether the structure can be placed facing up or down
+    /// on top of the four flat directions
+    ///
+    pub fn is_orientable(self) -> bool {
+        matches!(self, StructureKind::Inserter | StructureKind::LongInserter | StructureKind::Pipe)
+    }
+
+
+    pub fn is_inserter(self) -> bool {
+        matches!(self, StructureKind::Inserter | StructureKind::LongInserter)
+    }
+
+
+    ///
+    /// How many tiles away from its body an inserter
+    /// of this kind drops items off
+    ///
+    pub fn inserter_reach(self) -> u32 {
+        match self {
+            StructureKind::Inserter => 1,
+            StructureKind::LongInserter => 2,
+            _ => unreachable!(),
+        }
+    }
+
+
+    ///
+    /// Where an inserter of this kind looks for items to take,
+    /// closest first, relative to `zero_zero` while facing north.
+    /// The long inserter skips the tile next to its body so it
+    /// can grab over a belt or a wall
+    ///
+    pub fn inserter_inputs(self) -> &'static [IVec3] {
+        match self {
+            StructureKind::Inserter => &[IVec3::new(3, 0, 0)],
+            StructureKind::LongInserter => &[IVec3::new(4, 0, 0), IVec3::new(5, 0, 0)],
+            _ => unreachable!(),
+        }
+    }
+
+
+    ///
+    /// Where an inserter of this kind puts items down,
+    /// relative to `zero_zero` while facing north
+    ///
+    pub fn inserter_output(self) -> IVec3 {
+        IVec3::new(-(self.inserter_reach() as i32), 0, 0)
+    }
+
+
+    ///
+    /// Whether the structure connects up with pipes
+    ///
+    pub fn is_fluid_carrier(self) -> bool {
+        matches!(self, StructureKind::Pipe | StructureKind::Pump)
+    }
+
+
+    ///
+    /// Whether the structure takes fuel from an adjacent oil pipe
+    ///
+    pub fn burns_fluid(self) -> bool {
+        matches!(self, StructureKind::Furnace | StructureKind::SteelFurnace)
+    }
+
+
+    ///
+    /// Whether the structure is part of a logistics network
+    ///
+    pub fn is_logistics_chest(self) -> bool {
+        matches!(self, StructureKind::ProviderChest | StructureKind::RequesterChest)
+    }
+
+
+    ///
+    /// How many items a structure of this kind takes in and
+    /// hands out per tick, see `Structures::accept_budget`
+    ///
+    pub fn transfer_rate(self) -> TransferRate {
+        match self {
+            StructureKind::Furnace | StructureKind::SteelFurnace => FURNACE_TRANSFER_RATE,
+            StructureKind::Assembler => ASSEMBLER_TRANSFER_RATE,
+            StructureKind::Inserter | StructureKind::LongInserter => INSERTER_TRANSFER_RATE,
+
+              StructureKind::Belt
+            | StructureKind::FastBelt
+            | StructureKind::ExpressBelt => BELT_TRANSFER_RATE,
+
+              StructureKind::Chest
+            | StructureKind::Silo
+            | StructureKind::ProviderChest
+            | StructureKind::RequesterChest => CHEST_TRANSFER_RATE,
+
+            _ => TransferRate::UNLIMITED,
+        }
+    }
+
+
+    ///
+    /// The width of the square a quarry of this kind digs out,
+    /// the frame around it is one block wider on every side
+    ///
+    pub fn quarry_size(self) -> u32 {
+        match self {
+            StructureKind::Quarry => 3,
+            StructureKind::LargeQuarry => 5,
+            _ => unreachable!(),
+        }
+    }
+
+
+    pub fn create_mesh(self, device: &wgpu::Device) -> Result<Mesh, String> {
         match self {
             StructureKind::Quarry => Mesh::from_vmf(device, "assets/models/quarry.vmf"),
+            StructureKind::LargeQuarry => Mesh::from_vmf(device, "assets/models/large_quarry.vmf"),
             StructureKind::Inserter => Mesh::from_vmf(device, "assets/models/inserter.vmf"),
+            StructureKind::LongInserter => Mesh::from_vmf(device, "assets/models/inserter.vmf"),
             StructureKind::Chest => Mesh::from_vmf(device, "assets/models/chest.vmf"),
             StructureKind::Silo => Mesh::from_vmf(device, "assets/models/silo.vmf"),
+            StructureKind::ProviderChest => Mesh::from_vmf(device, "assets/models/provider_chest.vmf"),
+            StructureKind::RequesterChest => Mesh::from_vmf(device, "assets/models/requester_chest.vmf"),
             StructureKind::Belt => Mesh::from_vmf(device, "assets/models/belt.vmf"),
+            StructureKind::FastBelt => Mesh::from_vmf(device, "assets/models/fast_belt.vmf"),
+            StructureKind::ExpressBelt => Mesh::from_vmf(device, "assets/models/express_belt.vmf"),
             StructureKind::Splitter => Mesh::from_vmf(device, "assets/models/splitter.vmf"),
             StructureKind::Assembler => Mesh::from_vmf(device, "assets/models/assembler.vmf"),
             StructureKind::Furnace => Mesh::from_vmf(device, "assets/models/furnace.vmf"),
             StructureKind::SteelFurnace => Mesh::from_vmf(device, "assets/models/steel_furnace.vmf"),
+            StructureKind::Pump => Mesh::from_vmf(device, "assets/models/pump.vmf"),
+            StructureKind::Pipe => Mesh::from_vmf(device, "assets/models/pipe.vmf"),
         }
     }
 }
@@ -566,17 +1035,66 @@ pub const fn rotate_block_vector(dir: CardinalDirection, v: IVec3) -> IVec3 {
 }
 
 
+///
+/// `rotate_block_vector` for structures that can also face up or
+/// down, the front (-x) gets tipped onto the y axis before the yaw
+///
+pub const fn rotate_oriented_vector(dir: CardinalDirection, orientation: Orientation, v: IVec3) -> IVec3 {
+    let v = match orientation {
+        Orientation::Flat => v,
+        Orientation::Up => IVec3::new(v.y, -v.x, v.z),
+        Orientation::Down => IVec3::new(-v.y, v.x, v.z),
+    };
+
+    rotate_block_vector(dir, v)
+}
+
+
+///
+/// The direction a structure has to face for its front, the
+/// -x side in `rotate_block_vector`, to point along `v`
+///
+pub fn facing_towards(v: IVec3) -> Option<CardinalDirection> {
+    [CardinalDirection::North, CardinalDirection::East, CardinalDirection::South, CardinalDirection::West]
+        .into_iter()
+        .find(|&dir| rotate_block_vector(dir, IVec3::new(-1, 0, 0)) == v)
+}
+
+
+///
+/// The rotation that tips a structure's model over
+/// the same way `rotate_oriented_vector` tips its blocks
+///
+pub fn orientation_tilt(dir: CardinalDirection, orientation: Orientation) -> Quat {
+    let axis = rotate_block_vector(dir, IVec3::new(0, 0, 1)).as_vec3();
+    match orientation {
+        Orientation::Flat => Quat::IDENTITY,
+        Orientation::Up => Quat::from_axis_angle(axis, -90f32.to_radians()),
+        Orientation::Down => Quat::from_axis_angle(axis, 90f32.to_radians()),
+    }
+}
+
+
 
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct Furnace {
-    pub multiplier: u32,
+    /// either `StructureKind::Furnace` or `StructureKind::SteelFurnace`
+    kind: StructureKind,
+    /// pull fuel from adjacent structures when running low
+    pub auto_fuel: bool,
+    /// set while the furnace is polling for fuel instead of smelting,
+    /// the next update shouldn't complete a smelt
+    pub waiting_for_fuel: bool,
 }
 
 
 impl Furnace {
-    pub fn new(multiplier: u32) -> Self {
+    pub fn new(kind: StructureKind) -> Self {
+        assert!(matches!(kind, StructureKind::Furnace | StructureKind::SteelFurnace), "{kind:?} isn't a furnace");
         Self {
-            multiplier,
+            kind,
+            auto_fuel: false,
+            waiting_for_fuel: false,
         }
     }
 
@@ -596,16 +1114,16 @@ impl Furnace {
 
             if let Some(output) = output {
                 if output.kind != recipe.result.kind 
-                    || output.amount + recipe.result.amount > output.kind.max_stack_size() {
+                    || recipe.result.amount > output.room_left() {
                     return None;
                 }
             }
 
-            if !energy.consume_energy(inv, FURNACE_COST_PER_SMELT) {
+            if !energy.consume_energy(inv, self.cost_per_smelt()) {
                 return None;
             }
 
-            return Some(recipe.time * self.multiplier);
+            return Some(self.smelt_time(recipe));
         }  
 
         None
@@ -613,6 +1131,52 @@ impl Furnace {
 
 
 
+    ///
+    /// Finishes the smelt that was in progress, unless the last
+    /// update was only polling for fuel, and starts the next one.
+    /// Returns in how many ticks that one is done
+    ///
+    pub fn step(&mut self, inv: &mut StructureInventory, energy: &mut StructureEnergy) -> Option<u32> {
+        if !self.waiting_for_fuel {
+            self.process(inv);
+        }
+
+        self.waiting_for_fuel = false;
+        self.attempt(inv, energy)
+    }
+
+
+    ///
+    /// How many ticks smelting `recipe` takes in this furnace
+    ///
+    pub fn smelt_time(&self, recipe: &Recipe) -> u32 {
+        recipe.time * self.multiplier()
+    }
+
+
+    ///
+    /// Scales every recipe's time, steel furnaces
+    /// smelt twice as fast as regular ones
+    ///
+    pub fn multiplier(&self) -> u32 {
+        match self.kind {
+            StructureKind::SteelFurnace => 1,
+            _ => 2,
+        }
+    }
+
+
+    ///
+    /// How much energy every smelt takes out of the furnace
+    ///
+    pub fn cost_per_smelt(&self) -> u32 {
+        match self.kind {
+            StructureKind::SteelFurnace => STEEL_FURNACE_COST_PER_SMELT,
+            _ => FURNACE_COST_PER_SMELT,
+        }
+    }
+
+
     pub fn process(&self, inv: &mut StructureInventory) {
         let input = inv.input(0).0;
         let output = inv.output_mut(0);
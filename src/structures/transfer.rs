@@ -0,0 +1,15 @@
+///
+/// How many items a structure takes in and hands out per
+/// tick, inserters and logistics networks check this before
+/// moving anything so throughput is balanced in one place
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferRate {
+    pub accept: u32,
+    pub provide: u32,
+}
+
+
+impl TransferRate {
+    pub const UNLIMITED : TransferRate = TransferRate { accept: u32::MAX, provide: u32::MAX };
+}
@@ -1,10 +1,11 @@
-use glam::{DVec3, Vec2, Vec4};
+use glam::{DVec3, IVec3, Vec2, Vec3, Vec4};
 use kira::{sound::static_sound::{StaticSoundData, StaticSoundHandle}, Tween};
 use sti::hash::hash_map::SlotIdx;
 use winit::{event::MouseButton, keyboard::KeyCode};
-use std::{fmt::Write, ops::Bound};
+use wgpu::PresentMode;
+use std::{fmt::Write, hash::{DefaultHasher, Hash, Hasher}, ops::Bound, time::{SystemTime, UNIX_EPOCH}};
 
-use crate::{commands::Command, constants::{COAL_ENERGY_PER_UNIT, COLOUR_ADDITIVE_HIGHLIGHT, COLOUR_DARK_GREY, COLOUR_DENY, COLOUR_GREY, COLOUR_PASS, COLOUR_PLAYER_ACTIVE_HOTBAR, COLOUR_SCREEN_DIM, COLOUR_WARN, COLOUR_WHITE, PLAYER_HOTBAR_SIZE, PLAYER_INVENTORY_SIZE, PLAYER_REACH, PLAYER_ROW_SIZE, TICKS_PER_SECOND, UI_HOVER_ACTION_OFFSET, UI_Z_MAX, UI_Z_MIN}, crafting::{self, Recipe, FURNACE_RECIPES, RECIPES}, entities::{EntityKind, EntityMap}, input::InputManager, items::{self, Item, ItemKind}, renderer::{point_in_rect, Renderer}, structures::{self, inventory::{Filter, SlotKind, SlotMeta, StructureInventory}, strct::{InserterState, StructureData}, StructureId}, voxel_world::{chunker::MeshEntry, split_world_pos, VoxelWorld}, Game, Player};
+use crate::{commands::Command, constants::{ASSEMBLER_MODULE_SLOTS, COAL_ENERGY_PER_UNIT, COLOUR_ADDITIVE_HIGHLIGHT, COLOUR_DARK_GREY, COLOUR_DENY, COLOUR_GREY, COLOUR_PASS, COLOUR_PLAYER_ACTIVE_HOTBAR, COLOUR_SCREEN_DIM, COLOUR_WARN, COLOUR_WHITE, DROPPED_ITEM_THROW_SPEED, DROPPED_ITEM_TOSS_SPEED, MAX_RENDER_DISTANCE, PLAYER_HOTBAR_SIZE, PLAYER_INVENTORY_SIZE, PLAYER_ROW_SIZE, TERRAIN_SEED, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH, TICKS_PER_SECOND, UI_CROSSAIR_SIZE, UI_HOVER_ACTION_OFFSET, UI_THUMBNAIL_SCALE, UI_Z_MAX, UI_Z_MIN}, crafting::{self, Recipe, RecipeCategory, FURNACE_RECIPES, RECIPES}, entities::{EntityKind, EntityMap}, game::save_system::{delete_world, list_worlds, load_thumbnail}, input::InputManager, items::{self, Item, ItemKind}, renderer::{point_in_rect, textures::TextureId, Renderer, TextAlign, View}, structures::{self, inventory::{sort_slots, Filter, SlotKind, SlotMeta, StructureInventory}, pipes::Fluid, strct::{self, InserterState, Structure, StructureData}, StructureId}, voxel_world::{chunker::MeshEntry, split_world_pos, VoxelWorld}, Game, Player};
 
 pub enum UILayer {
     Inventory {
@@ -19,28 +20,84 @@ pub enum UILayer {
         cursor: u32,
         just_opened: bool,
         offset: u32,
+        completion: Option<Completion>,
     },
     Gameplay { smoothed_dt: f32 },
 
+    MainMenu {
+        worlds: Vec<String>,
+        new_world: Option<NewWorld>,
+        /// the world whose delete button has been pressed once
+        confirm_delete: Option<usize>,
+        /// each world's save thumbnail, they're uploaded to
+        /// the renderer the first time the list is drawn
+        thumbnails: Option<Vec<Option<TextureId>>>,
+    },
+
     Credits {
         time: f32,
         audio: StaticSoundHandle,
     },
 
+    /// opened with escape, the simulation stops while it's up
+    PauseMenu,
+
     None,
 }
 
 
+///
+/// Tab completion state of the console, kept around
+/// so pressing tab again cycles through the matches
+///
+pub struct Completion {
+    /// what the user typed before the first tab
+    prefix: String,
+    /// the index of the next match
+    index: usize,
+}
+
+
+///
+/// The world creation prompt of the main menu
+///
+#[derive(Default)]
+pub struct NewWorld {
+    name: String,
+    seed: String,
+    editing_seed: bool,
+    error: Option<&'static str>,
+}
+
+
 pub enum InventoryMode {
     Chest(StructureId),
     Furnace(StructureId),
     Silo(StructureId),
-    Assembler(StructureId),
+    /// `editing` swaps the chest's contents out for its request list
+    Requester { id: StructureId, editing: bool },
+    /// `category` and `search` narrow down the recipe list,
+    /// `typing` is whether keystrokes go into `search`
+    Assembler {
+        id: StructureId,
+        category: Option<RecipeCategory>,
+        search: String,
+        typing: bool,
+    },
     Inserter(StructureId),
+    /// `side` is the output whose filter is being edited
+    Splitter { id: StructureId, side: usize },
     Recipes,
 }
 
 
+impl InventoryMode {
+    pub fn assembler(id: StructureId) -> Self {
+        Self::Assembler { id, category: None, search: String::new(), typing: false }
+    }
+}
+
+
 pub const HOTBAR_KEYS : &[KeyCode] = &[KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
                                KeyCode::Digit4, KeyCode::Digit5];
 const SLOT_SIZE : f32 = 64.0;
@@ -52,12 +109,19 @@ impl UILayer {
     }
 
 
+    pub fn main_menu() -> Self {
+        Self::MainMenu { worlds: list_worlds(), new_world: None, confirm_delete: None, thumbnails: None }
+    }
+
+
     pub fn is_mouse_locked(&self) -> bool {
         match self {
             UILayer::Gameplay { .. } => true,
+            UILayer::MainMenu { .. } => false,
             UILayer::Inventory { .. } => false,
             UILayer::Console { .. } => false,
             UILayer::Credits { .. } => false,
+            UILayer::PauseMenu => false,
             UILayer::None => false,
         }
     }
@@ -66,9 +130,11 @@ impl UILayer {
     pub fn is_escapable(&self) -> bool {
         match self {
             UILayer::Gameplay { .. } => false,
+            UILayer::MainMenu { .. } => false,
             UILayer::Inventory { .. } => true,
             UILayer::Console { .. } => true,
             UILayer::Credits { .. } => true,
+            UILayer::PauseMenu => true,
             UILayer::None => false,
         }
     }
@@ -93,9 +159,18 @@ impl UILayer {
             },
 
 
+            UILayer::PauseMenu => {
+                *self = UILayer::Gameplay { smoothed_dt: dt };
+            },
+
+
             UILayer::Gameplay { .. } => (),
 
 
+            // there's nothing to go back to
+            UILayer::MainMenu { .. } => (),
+
+
             UILayer::None => (),
 
 
@@ -110,7 +185,7 @@ impl UILayer {
 
     pub fn render(&mut self, game: &mut Game, input: &InputManager, renderer: &mut Renderer, dt: f32) {
         match self {
-            UILayer::Console { text, backspace_cooldown, timer, cursor, just_opened, offset } => {
+            UILayer::Console { text, backspace_cooldown, timer, cursor, just_opened, offset, completion } => {
                 const TEXT_SIZE : f32 = 0.5;
                 let window = renderer.window_size();
                 let text_box = Vec2::new(window.x * 0.6, renderer.line_size * 0.6);
@@ -120,6 +195,19 @@ impl UILayer {
                 let text_pos = Vec2::new(box_pos.x, box_pos.y);
                 renderer.draw_text(&text, text_pos, TEXT_SIZE, Vec4::ONE);
 
+                if !input.current_chars().is_empty()
+                    || input.is_key_just_pressed(KeyCode::Backspace)
+                    || input.is_key_just_pressed(KeyCode::ArrowLeft)
+                    || input.is_key_just_pressed(KeyCode::ArrowRight)
+                    || input.is_key_just_pressed(KeyCode::ArrowUp)
+                    || input.should_paste_now() {
+                    *completion = None;
+                }
+
+                if input.is_key_just_pressed(KeyCode::Tab) {
+                    complete_command(game, text, cursor, completion);
+                }
+
                 for key in input.current_chars() {
                     if !key.is_ascii() {
                         text.insert(*cursor as usize, '?');
@@ -274,7 +362,8 @@ impl UILayer {
 
             UILayer::Inventory { just_opened, holding_item, inventory_mode } => {
                 let window = renderer.window_size();
-                if input.is_key_just_pressed(KeyCode::KeyE) && !*just_opened {
+                let typing = matches!(inventory_mode, InventoryMode::Assembler { typing: true, .. });
+                if input.is_key_just_pressed(KeyCode::KeyE) && !*just_opened && !typing {
                     self.close(game, dt);
                     return;
                 } else {
@@ -295,7 +384,7 @@ impl UILayer {
                 let padding = 16.0;
 
                 let player_inv_size = Vec2::new(cols as f32, rows as f32) * (slot_size + padding) as f32;
-                let mut other_inv = None;
+                let mut other_inv : Option<(&mut [Option<Item>], &[Filter])> = None;
 
                 'mode: {
                 match inventory_mode {
@@ -310,12 +399,18 @@ impl UILayer {
 
 
                         let structure = game.structures.get_mut(*structure);
-                        let inventory = &mut structure.inventory.as_mut().unwrap().slots;
 
                         renderer.draw_rect(corner, external_view_size, Vec4::ONE);
-                        draw_inventory(renderer, &mut *inventory, game.player.body.position, &mut game.world, &mut game.entities, Some(&mut game.player.inventory), input, holding_item, corner, cols, rows);
+                        if draw_button(renderer, input, corner - Vec2::new(0.0, padding * 3.0), "Sort") {
+                            structure.inventory.as_mut().unwrap().sort();
+                        }
+
+                        renderer.draw_text("§8Ctrl + right click a slot to filter it", corner + Vec2::new(0.0, external_view_size.y + padding * 0.5), 0.4, Vec4::ONE);
+
+                        let inventory = structure.inventory.as_mut().unwrap();
+                        draw_inventory(renderer, &mut inventory.slots, Some(&mut inventory.filters), game.player.body.position, game.camera.front, &mut game.world, &mut game.entities, Some((&mut game.player.inventory, &[])), input, holding_item, corner, cols, rows);
 
-                        other_inv = Some(inventory.as_mut_slice());
+                        other_inv = Some((inventory.slots.as_mut_slice(), inventory.filters.as_slice()));
                     },
 
 
@@ -330,15 +425,138 @@ impl UILayer {
 
 
                         let structure = game.structures.get_mut(*structure);
+
+                        renderer.draw_rect(corner, external_view_size, Vec4::ONE);
+                        if draw_button(renderer, input, corner - Vec2::new(0.0, padding * 3.0), "Sort") {
+                            structure.inventory.as_mut().unwrap().sort();
+                        }
+
                         let inventory = &mut structure.inventory.as_mut().unwrap().slots;
+                        draw_inventory(renderer, inventory, None, game.player.body.position, game.camera.front, &mut game.world, &mut game.entities, Some((&mut game.player.inventory, &[])), input, holding_item, corner, cols, rows);
+
+                        other_inv = Some((inventory.as_mut_slice(), &[]));
+                    },
+
+
+                    InventoryMode::Requester { id, editing: false } => {
+                        let rows = 3;
+                        let cols = 3;
+                        let external_view_size = Vec2::new(rows as f32, cols as f32) * (slot_size + padding) as f32;
+
+                        let mut corner = window * 0.5 - external_view_size * 0.5;
+                        corner.x += external_view_size.x * 0.5;
+                        corner.x += padding * 0.5;
+
+                        let id = *id;
+                        if draw_button(renderer, input, corner + Vec2::new(0.0, external_view_size.y + padding), "Requests") {
+                            *inventory_mode = InventoryMode::Requester { id, editing: true };
+                            break 'mode;
+                        }
+
+
+                        let structure = game.structures.get_mut(id);
 
                         renderer.draw_rect(corner, external_view_size, Vec4::ONE);
-                        draw_inventory(renderer, inventory, game.player.body.position, &mut game.world, &mut game.entities, Some(&mut game.player.inventory), input, holding_item, corner, cols, rows);
+                        if draw_button(renderer, input, corner - Vec2::new(0.0, padding * 3.0), "Sort") {
+                            structure.inventory.as_mut().unwrap().sort();
+                        }
 
-                        other_inv = Some(inventory.as_mut_slice());
+                        let inventory = &mut structure.inventory.as_mut().unwrap().slots;
+                        draw_inventory(renderer, inventory, None, game.player.body.position, game.camera.front, &mut game.world, &mut game.entities, Some((&mut game.player.inventory, &[])), input, holding_item, corner, cols, rows);
+
+                        other_inv = Some((inventory.as_mut_slice(), &[]));
                     },
 
 
+                    InventoryMode::Requester { id, editing: true } => {
+                        let mut corner = window * 0.5 - player_inv_size * 0.5;
+                        corner.x += player_inv_size.x * 0.5;
+                        corner.x += padding * 0.5;
+
+                        let rows = PLAYER_HOTBAR_SIZE;
+                        let cols = PLAYER_ROW_SIZE;
+
+                        let size = Vec2::new(rows as f32, cols as f32) * (slot_size + padding) as f32;
+
+                        let text = "\n  \
+                              §8Left Click §8to §2request §8another stack  \n  \
+                              §8Right Click §8to §crequest §8one stack less  \n\n\
+                        ";
+
+                        let text_size = renderer.text_size(text, 0.6);
+
+                        let text_pos = window * 0.5 - Vec2::new(text_size.x * 0.5, text_size.y + player_inv_size.y * 0.5 + padding);
+                        renderer.draw_rect(text_pos, text_size, Vec4::ONE);
+                        renderer.draw_text(text, text_pos, 0.6, Vec4::new(0.2, 0.2, 0.2, 1.0));
+                        renderer.draw_rect(corner, size, Vec4::ONE);
+
+                        let id = *id;
+                        if draw_button(renderer, input, corner - Vec2::new(0.0, padding * 3.0), "Contents") {
+                            *inventory_mode = InventoryMode::Requester { id, editing: false };
+                            break 'mode;
+                        }
+
+                        let mouse_pos = renderer.to_point(input.mouse_position());
+
+                        let mut base = corner + padding * 0.5;
+                        for col in 0..cols {
+                            let mut pos = base;
+                            for row in 0..rows {
+                                let index = col*rows+row;
+                                let Some(&curr) = ItemKind::ALL.get(index)
+                                else { break 'mode };
+
+                                let StructureData::RequesterChest { requests } = &game.structures.get(id).data
+                                else { unreachable!() };
+
+                                let requested = requests.iter().find(|x| x.kind == curr).map_or(0, |x| x.amount);
+                                let colour = if requested > 0 { COLOUR_PASS } else { COLOUR_GREY };
+
+                                draw_inventory_slot(
+                                    game.structures.get_mut(id),
+                                    renderer,
+                                    input,
+                                    pos,
+                                    Some(Item::new(curr, requested.max(1))),
+                                    colour,
+                                    |renderer, _| {
+                                        default_hover_action(renderer, mouse_pos, curr);
+                                    },
+
+                                    |_, s| {
+                                        let StructureData::RequesterChest { requests } = &mut s.data
+                                        else { unreachable!() };
+
+                                        if let Some(request) = requests.iter_mut().find(|x| x.kind == curr) {
+                                            request.amount += curr.max_stack_size();
+                                        } else {
+                                            requests.push(Item::new(curr, curr.max_stack_size()));
+                                        }
+                                    },
+                                    |_, s| {
+                                        let StructureData::RequesterChest { requests } = &mut s.data
+                                        else { unreachable!() };
+
+                                        let Some(index) = requests.iter().position(|x| x.kind == curr)
+                                        else { return };
+
+                                        let request = &mut requests[index];
+                                        request.amount = request.amount.saturating_sub(curr.max_stack_size());
+                                        if request.amount == 0 {
+                                            requests.remove(index);
+                                        }
+                                    },
+                                    |_, _| {},
+                                );
+
+                                pos += Vec2::new(slot_size+padding, 0.0);
+                            }
+
+                            base += Vec2::new(0.0, slot_size+padding);
+                        }
+                    }
+
+
 
                     InventoryMode::Inserter(structure_id) => {
                         let mut corner = window * 0.5 - player_inv_size * 0.5;
@@ -419,6 +637,113 @@ impl UILayer {
 
 
 
+                    InventoryMode::Splitter { id, side } => {
+                        let mut corner = window * 0.5 - player_inv_size * 0.5;
+                        corner.x += player_inv_size.x * 0.5;
+                        corner.x += padding * 0.5;
+
+                        let rows = PLAYER_HOTBAR_SIZE;
+                        let cols = PLAYER_ROW_SIZE;
+
+                        let size = Vec2::new(rows as f32, cols as f32) * (slot_size + padding) as f32;
+
+                        let text = "\n  \
+                              §8Left Click §8to §2set §8the output's filter  \n  \
+                              §8Right Click §8any slot to §cremove §8it  \n\n\
+                        ";
+
+                        let text_size = renderer.text_size(text, 0.6);
+
+                        let text_pos = window * 0.5 - Vec2::new(text_size.x * 0.5, text_size.y + player_inv_size.y * 0.5 + padding);
+                        renderer.draw_rect(text_pos, text_size, Vec4::ONE);
+                        renderer.draw_text(text, text_pos, 0.6, Vec4::new(0.2, 0.2, 0.2, 1.0));
+                        renderer.draw_rect(corner, size, Vec4::ONE);
+
+                        let StructureData::Splitter { filters, preferred, .. } = game.structures.get(*id).data
+                        else { unreachable!() };
+
+                        let filter = filters[*side].map_or("Anything", |x| x.name());
+                        let button = format!("Output {}: {filter}", *side + 1);
+                        let button_pos = corner - Vec2::new(0.0, padding * 3.0);
+                        if draw_button(renderer, input, button_pos, &button) {
+                            *side = 1 - *side;
+                        }
+
+                        // goes from alternating to always trying
+                        // the first output, then the second one
+                        let priority = match preferred {
+                            Some(side) => format!("Priority: Output {}", side + 1),
+                            None => "Priority: None".to_string(),
+                        };
+
+                        let priority_pos = button_pos + Vec2::new(renderer.text_size(&button, 0.6).x + padding * 3.0, 0.0);
+                        if draw_button(renderer, input, priority_pos, &priority) {
+                            let StructureData::Splitter { preferred, .. } = &mut game.structures.get_mut(*id).data
+                            else { unreachable!() };
+
+                            *preferred = match *preferred {
+                                None => Some(0),
+                                Some(0) => Some(1),
+                                _ => None,
+                            };
+                        }
+
+                        let mouse_pos = renderer.to_point(input.mouse_position());
+                        let side = *side;
+
+                        let mut base = corner + padding * 0.5;
+                        for col in 0..cols {
+                            let mut pos = base;
+                            for row in 0..rows {
+                                let index = col*rows+row;
+                                let Some(&curr) = ItemKind::ALL.get(index)
+                                else { break 'mode };
+
+
+                                let mut close = false;
+                                draw_inventory_slot(
+                                    &mut (game.structures.get_mut(*id), &mut close),
+                                    renderer,
+                                    input,
+                                    pos,
+                                    Some(Item::new(curr, 1)),
+                                    COLOUR_GREY,
+                                    |renderer, _| {
+                                        default_hover_action(renderer, mouse_pos, curr);
+                                    },
+
+                                    |_, (s, close)| {
+                                        let StructureData::Splitter { filters, .. } = &mut s.data
+                                        else { unreachable!() };
+
+                                        filters[side] = Some(curr);
+                                        **close = true;
+                                    },
+                                    |_, (s, close)| {
+                                        let StructureData::Splitter { filters, .. } = &mut s.data
+                                        else { unreachable!() };
+
+                                        filters[side] = None;
+                                        **close = true;
+                                    },
+                                    |_, _| {},
+                                );
+
+                                if close {
+                                    self.close(game, dt);
+                                    return;
+                                }
+
+                                pos += Vec2::new(slot_size+padding, 0.0);
+                            }
+
+                            base += Vec2::new(0.0, slot_size+padding);
+                        }
+                    }
+
+
+
+
                     InventoryMode::Furnace(structure_id) => {
                         let mut corner = window * 0.5 - player_inv_size * 0.5;
                         corner.x += player_inv_size.x * 0.5;
@@ -431,6 +756,14 @@ impl UILayer {
 
                         renderer.draw_rect(corner, size, Vec4::ONE);
 
+                        let structure = game.structures.get_mut(*structure_id);
+                        if let StructureData::Furnace(furnace) = &mut structure.data {
+                            let text = if furnace.auto_fuel { "Auto-fuel: On" } else { "Auto-fuel: Off" };
+                            if draw_button(renderer, input, corner - Vec2::new(0.0, padding * 3.0), text) {
+                                furnace.auto_fuel = !furnace.auto_fuel;
+                            }
+                        }
+
                         corner += padding;
                         renderer.draw_rect(corner, Vec2::splat(slot_size), Vec4::ZERO.with_w(1.0));
 
@@ -451,7 +784,7 @@ impl UILayer {
                             &mut inventory.slots,
                             game.player.body.position,
                             &mut game.entities,
-                            &mut Some(&mut game.player.inventory),
+                            &mut Some((&mut game.player.inventory, &[])),
                             input,
                             holding_item,
                             Vec2::new(corner.x, corner.y),
@@ -468,7 +801,7 @@ impl UILayer {
                             &mut inventory.slots,
                             game.player.body.position,
                             &mut game.entities,
-                            &mut Some(&mut game.player.inventory),
+                            &mut Some((&mut game.player.inventory, &[])),
                             input,
                             holding_item,
                             Vec2::new(corner.x, corner.y+padding+slot_size),
@@ -508,7 +841,7 @@ impl UILayer {
                             else { unreachable!() };
 
 
-                            let time = recipe.time * furnace.multiplier;
+                            let time = furnace.smelt_time(recipe);
                             let start_time = tick.u32() - time;
                             let diff = game.current_tick.u32() - start_time;
 
@@ -555,7 +888,7 @@ impl UILayer {
                             &mut inventory.slots,
                             game.player.body.position,
                             &mut game.entities,
-                            &mut Some(&mut game.player.inventory),
+                            &mut Some((&mut game.player.inventory, &[])),
                             input,
                             holding_item,
                             Vec2::new(corner.x, corner.y),
@@ -588,7 +921,7 @@ impl UILayer {
                                        recipe.requirements[0].amount > input.amount
                                     || (if let Some(ik) = input_kind { ik != input.kind } else { false })
                                     || recipe.result.kind != output.kind
-                                    || recipe.result.amount + output.amount > output.kind.max_stack_size()
+                                    || recipe.result.amount > output.room_left()
                                 },
                             };
 
@@ -605,7 +938,7 @@ impl UILayer {
 
 
 
-                    InventoryMode::Assembler(structure) => {
+                    InventoryMode::Assembler { id: structure, category, search, typing } => {
                         let mut corner = window * 0.5 - player_inv_size * 0.5;
                         corner.x += player_inv_size.x * 0.5;
                         corner.x += padding * 0.5;
@@ -617,9 +950,71 @@ impl UILayer {
 
                         renderer.draw_rect(corner, size, Vec4::ONE);
 
+                        let strct = game.structures.get_mut(*structure);
+                        let StructureData::Assembler { auto_output, modules, .. } = &mut strct.data
+                        else { unreachable!() };
+
+                        let text = if *auto_output { "Auto-output: On" } else { "Auto-output: Off" };
+                        if draw_button(renderer, input, corner - Vec2::new(0.0, padding * 3.0), text) {
+                            *auto_output = !*auto_output;
+                        }
+
+
+                        // module slots sit above the right edge of the recipe grid
+                        let modules = *modules;
+                        let point = renderer.to_point(input.mouse_position());
+                        let mut pos = corner + Vec2::new(size.x, -slot_size - padding);
+                        pos.x -= (slot_size + padding) * ASSEMBLER_MODULE_SLOTS as f32;
+                        for (slot, module) in modules.into_iter().enumerate() {
+                            let mut clicked = false;
+                            draw_inventory_slot(
+                                &mut clicked,
+                                renderer,
+                                input,
+                                pos,
+                                module.map(|x| Item::new(x, 1)),
+                                COLOUR_DARK_GREY,
+                                |renderer, _| {
+                                    if let Some(module) = module {
+                                        default_hover_action(renderer, point, module);
+                                    }
+                                },
+                                |_, clicked| *clicked = true,
+                                |_, _| {},
+                                |_, _| {},
+                            );
+
+                            pos.x += slot_size + padding;
+
+                            if !clicked { continue }
+
+                            match (module, *holding_item) {
+                                (None, Some(held)) if held.kind.is_module() => {
+                                    Structure::swap_module(*structure, &mut game.structures, slot, Some(held.kind));
+                                    *holding_item = if held.amount > 1 { Some(held.with_amount(held.amount - 1)) }
+                                                    else { None };
+                                },
+
+                                (Some(module), None) => {
+                                    Structure::swap_module(*structure, &mut game.structures, slot, None);
+                                    *holding_item = Some(Item::new(module, 1));
+                                },
+
+                                (Some(module), Some(held)) if held.kind == module
+                                    && held.amount < module.max_stack_size() => {
+                                    Structure::swap_module(*structure, &mut game.structures, slot, None);
+                                    *holding_item = Some(held.with_amount(held.amount + 1));
+                                },
+
+                                _ => (),
+                            }
+                        }
+
 
                         let text = "\n  \
-                              §8Left Click §8to §2set §8recipe  \n\n\
+                              §8Left Click §8to §2set §8recipe  \n  \
+                              §8Drop §2modules §8into the slots above  \n  \
+                              §8Click §2Search §8below to filter by name  \n\n\
                         ";
 
                         let text_size = renderer.text_size(text, 0.6);
@@ -630,18 +1025,61 @@ impl UILayer {
                         renderer.draw_rect(corner, size, Vec4::ONE);
 
 
+                        // category tabs and the search field sit below the recipe grid
+                        let mut pos = corner + Vec2::new(0.0, size.y + padding);
+                        let tabs = [None].into_iter().chain(RecipeCategory::ALL.iter().copied().map(Some));
+                        for tab in tabs {
+                            let name = tab.map_or("All", |x| x.name());
+                            let text = if tab == *category { format!("§e{name}") }
+                                       else { name.to_string() };
+
+                            if draw_button(renderer, input, pos, &text) {
+                                *category = tab;
+                            }
+
+                            pos.x += renderer.text_size(&text, 0.6).x + padding;
+                        }
+
+
+                        if *typing {
+                            for &ch in input.current_chars() {
+                                if !ch.is_ascii_alphanumeric() && ch != ' ' { continue }
+                                search.push(ch);
+                            }
+
+                            if input.is_key_just_pressed(KeyCode::Backspace) {
+                                search.pop();
+                            }
+
+                            if input.is_key_just_pressed(KeyCode::Enter) {
+                                *typing = false;
+                            }
+                        }
+
+                        let cursor = if *typing { "_" } else { "" };
+                        let text = format!("Search: {search}{cursor}");
+                        let pos = Vec2::new(corner.x, pos.y + padding * 3.0);
+                        if draw_button(renderer, input, pos, &text) {
+                            *typing = !*typing;
+                        }
+
+
+                        let recipes : Vec<usize> = (0..RECIPES.len())
+                            .filter(|&x| RECIPES[x].result.kind != ItemKind::Radar)
+                            .filter(|&x| category.is_none_or(|c| RECIPES[x].category() == c))
+                            .filter(|&x| matches_search(RECIPES[x].result.kind, search))
+                            .collect();
+
 
                         let mut base = corner + padding * 0.5;
                         let point = renderer.to_point(input.mouse_position());
                         for col in 0..cols {
                             let mut pos = base;
                             for row in 0..rows {
-                                let recipe_index = col*rows+row;
-                                let Some(&curr_recipe) = RECIPES.get(recipe_index)
+                                let Some(&recipe_index) = recipes.get(col*rows+row)
                                 else { break 'mode };
 
-
-                                if curr_recipe.result.kind == ItemKind::Radar { continue }
+                                let curr_recipe = RECIPES[recipe_index];
 
                                 let mut close = false;
                                 draw_inventory_slot(
@@ -657,7 +1095,7 @@ impl UILayer {
 
                                     |_, _| {
                                         let structure = game.structures.get_mut(*structure);
-                                        let StructureData::Assembler { recipe } = &mut structure.data
+                                        let StructureData::Assembler { recipe, .. } = &mut structure.data
                                         else { unreachable!() };
 
                                         let prev_inv = if recipe.is_some() {
@@ -722,7 +1160,11 @@ impl UILayer {
                 corner.x -= player_inv_size.x * 0.5;
                 corner.x -= padding * 0.5;
 
-                draw_player_inventory(renderer, &mut game.player, &mut game.world, &mut game.entities, &mut other_inv, input, holding_item, corner);
+                if input.is_key_just_pressed(KeyCode::KeyR) && !typing {
+                    sort_slots(&mut game.player.inventory, u32::MAX);
+                }
+
+                draw_player_inventory(renderer, &mut game.player, game.camera.front, &mut game.world, &mut game.entities, &mut other_inv, input, holding_item, corner);
             }
 
             UILayer::Gameplay { smoothed_dt } => {
@@ -736,13 +1178,17 @@ impl UILayer {
                     let colour_code = if fps > 55.0 { 'a' } else if fps > 25.0 { '6' } else { '4' };
 
                     let _ = writeln!(text, "§eFPS: §{colour_code}{fps}§r");
+                    if game.player.creative {
+                        let _ = writeln!(text, "§eMODE: §bCreative§r");
+                    }
                     let _ = writeln!(text, "§eSAVE TIME ELAPSED: §a{:.1}§r", game.current_tick.u32() as f64 / TICKS_PER_SECOND as f64);
-                    let _ = writeln!(text, "§eRENDER DISTANCE: §a{}§r", game.settings.render_distance);
+                    let _ = writeln!(text, "§eRENDER DISTANCE: §a{}/{}§r", game.render_distance, game.settings.render_distance);
+                    let _ = writeln!(text, "§eLOD DISTANCE: §a{}§r", game.settings.lod_distance);
                     let _ = writeln!(text, "§eTRIANGLE COUNT: §a{}§r", renderer.triangle_count.get());
                     renderer.triangle_count.set(0);
                     renderer.draw_count.set(0);
 
-                    let _ = writeln!(text, "§eRENDER WORLD TIME: §a{}ms§r", game.render_world_time);
+                    let _ = writeln!(text, "§eRENDER WORLD TIME: §a{:.2}ms§r", game.render_world_time as f32 / 1000.0);
                     let _ = writeln!(text, "§eRENDERED CHUNKS: §a{}§r", game.total_rendered_chunks);
                     let _ = writeln!(text, "§eCHUNK LOAD QUEUE: §a{}§r", game.world.chunker.chunk_load_queue_len());
                     let _ = writeln!(text, "§eCHUNK ACTIVE JOBS: §a{}§r", game.world.chunker.chunk_active_jobs_len());
@@ -768,7 +1214,7 @@ impl UILayer {
 
                     let _ = writeln!(text, "§eDIRECTION: §b{:?}§r", game.camera.compass_direction());
 
-                    let target_block = game.world.raycast_voxel(game.camera.position, game.camera.front, PLAYER_REACH);
+                    let target_block = game.world.raycast_voxel(game.camera.position, game.camera.front, game.reach());
                     if let Some(target_block) = target_block {
                         let target_voxel = game.world.get_voxel(target_block.0);
                         let target_voxel_kind = target_voxel;
@@ -781,12 +1227,13 @@ impl UILayer {
 
 
                         if target_voxel.is_structure() {
-                            let structure = game.world.structure_blocks.get(&target_block.0).unwrap();
-                            let structure = game.structures.get(*structure);
+                            let structure_id = *game.world.structure_blocks.get(&target_block.0).unwrap();
+                            let structure = game.structures.get(structure_id);
 
                             let _ = writeln!(text, "Structure");
                             let _ = writeln!(text, "§e- POSITION: §a{}, {}, {}", structure.position.x, structure.position.y, structure.position.z);
                             let _ = writeln!(text, "§e- DIRECTION: §b{:?}", structure.direction);
+                            let _ = writeln!(text, "§e- DURABILITY: §a{}/{}", structure.durability, structure.data.as_kind().durability());
                             let _ = writeln!(text, "§e- IS ASLEEP: §b{}", structure.is_asleep);
                             let _ = writeln!(text, "§e- ENERGY: §b{}", structure.energy.energy);
 
@@ -830,17 +1277,20 @@ impl UILayer {
                             let _ = write!(text, "§e- KIND: §b");
 
                             match &structure.data {
-                                StructureData::Quarry { current_progress } => {
+                                StructureData::Quarry { current_progress, size, max_depth } => {
                                     let _ = writeln!(text, "Quarry:");
                                     let _ = writeln!(text, "§e    - CURRENT PROGRESS: §a{}", current_progress);
-                                    let y = *current_progress / 9;
+                                    let _ = writeln!(text, "§e    - SIZE: §a{size}x{size}");
+                                    let _ = writeln!(text, "§e    - MAX DEPTH: §a{max_depth}");
+                                    let y = *current_progress / (size * size);
                                     let y = structure.zero_zero().y + -(y as i32) - 1;
                                     let eff = structures::quarry_efficiency(y as _);
                                     let _ = writeln!(text, "§e    - EFFICIENCY: §a{:.1}%", (1.0 / eff) * 100.0);
                                 },
 
-                                StructureData::Inserter { state, filter } => {
+                                StructureData::Inserter { state, filter, reach } => {
                                     let _ = writeln!(text, "Inserter:");
+                                    let _ = writeln!(text, "§e  - REACH: §a{reach}");
                                     if let Some(filter) = filter {
                                         let _ = writeln!(text, "§e  - FILTER: §a{filter:?}");
                                     } else {
@@ -870,32 +1320,81 @@ impl UILayer {
                                 }
 
 
-                                StructureData::Belt { } => {
+                                StructureData::ProviderChest => {
+                                    let _ = writeln!(text, "Provider Chest");
+                                    if let Some((network, _)) = game.structures.logistics().network_of(structure_id) {
+                                        let _ = writeln!(text, "§e  - NETWORK: §a#{}", network.usize());
+                                    }
+                                }
+
+
+                                StructureData::RequesterChest { requests } => {
+                                    let _ = writeln!(text, "Requester Chest");
+                                    if let Some((network, _)) = game.structures.logistics().network_of(structure_id) {
+                                        let _ = writeln!(text, "§e  - NETWORK: §a#{}", network.usize());
+                                    }
+
+                                    for request in requests {
+                                        let _ = writeln!(text, "§e  - REQUEST: §b{:?} §a{}x", request.kind, request.amount);
+                                    }
+                                }
+
+
+                                StructureData::Belt { tier } => {
                                     let _ = writeln!(text, "Belt");
+                                    let _ = writeln!(text, "§e  - TIER: §a{tier:?}");
+                                    write_lanes(&mut text, &structure.inventory.as_ref().unwrap().slots);
                                 }
 
 
-                                StructureData::Splitter { priority } => {
+                                StructureData::Splitter { priority, filters, preferred } => {
                                     let _ = writeln!(text, "Splitter");
                                     let _ = writeln!(text, "§e  - PRIORITY: §a{priority:?}");
+                                    let _ = writeln!(text, "§e  - PREFERRED: §a{preferred:?}");
+                                    let _ = writeln!(text, "§e  - FILTERS: §a{filters:?}");
+                                    write_lanes(&mut text, &structure.inventory.as_ref().unwrap().slots);
                                 }
 
 
-                                StructureData::Assembler { recipe: crafter } => {
+                                StructureData::Assembler { recipe: crafter, auto_output, modules, productivity } => {
                                     let _ = writeln!(text, "Assembler");
                                     let _ = writeln!(text, "§e  - RECIPE: §a{crafter:?}");
+                                    let _ = writeln!(text, "§e  - AUTO OUTPUT: §a{auto_output}");
+                                    let _ = writeln!(text, "§e  - MODULES: §a{modules:?}");
+                                    let _ = writeln!(text, "§e  - PRODUCTIVITY: §a{productivity}/1000");
                                 }
 
                                 StructureData::Furnace(_) => {
                                     let _ = writeln!(text, "Furnace");
                                 }
+
+
+                                StructureData::Pump => {
+                                    let _ = writeln!(text, "Pump");
+                                    let below = game.world.get_voxel(structure.position - IVec3::Y);
+                                    let _ = writeln!(text, "§e  - FLUID: {}", Fluid::pumped_from(below).name());
+                                }
+
+
+                                StructureData::Pipe { fluid, amount } => {
+                                    let _ = writeln!(text, "Pipe");
+                                    let fluid = fluid.map(|x| x.name()).unwrap_or("§7None");
+                                    let _ = writeln!(text, "§e  - FLUID: {fluid}§e, §a{amount}");
+
+                                    let pipes = game.structures.pipes(&game.world);
+                                    if let Some((id, network)) = pipes.network_of(structure_id) {
+                                        let _ = writeln!(text, "§e  - NETWORK: §a#{}", id.usize());
+                                        let _ = writeln!(text, "§e  - FILL: §a{}/{}", network.amount, network.capacity());
+                                    }
+                                }
                             }
                         } else {
                            let _ = writeln!(text, "{:?}", target_voxel);
                         }
 
 
-                        if let Some(mining_progress) = game.player.mining_progress {
+                        if let Some(mining_progress) = game.player.mining_progress
+                            && !target_voxel.is_structure() {
                             let _ = writeln!(text, "§eMINING PROGRESS: §a{}/{}",
                                              mining_progress, target_voxel_kind.base_hardness());
                         }
@@ -976,7 +1475,30 @@ impl UILayer {
 
                     }
 
-                    renderer.draw_text(&text, Vec2::ZERO, 0.4, Vec4::ONE);
+                    let mut view = View::default();
+                    view.vstack(|view| {
+                        view.text_ex(&text, 0.4);
+                        view.spacer();
+                    });
+
+                    let window = renderer.window_size();
+                    view.render(renderer, window, Vec2::ZERO, game.settings.debug_layout);
+                }
+
+
+                // a short summary of the structure under the crosshair
+                if let Some((pos, _)) = game.world.raycast_voxel(game.camera.position, game.camera.front, game.reach())
+                    && let Some(&id) = game.world.structure_blocks.get(&pos) {
+
+                    let text = structure_tooltip(game, id);
+
+                    let scale = 0.5;
+                    let padding = 8.0;
+                    let size = renderer.text_size(&text, scale) + padding * 2.0;
+                    let pos = renderer.window_size() * 0.5 + Vec2::splat(UI_CROSSAIR_SIZE * 2.0);
+
+                    renderer.draw_rect(pos, size, COLOUR_DARK_GREY.with_w(0.8));
+                    renderer.draw_text(&text, pos + padding, scale, COLOUR_WHITE);
                 }
             },
 
@@ -1110,11 +1632,13 @@ impl UILayer {
                     let scale = if i == 1 { 3.0 } else { 1.0 };
                     let line = line.trim();
                     let text_size = renderer.text_size(line, scale);
-                    renderer.draw_text(
+                    renderer.draw_text_ex(
                         line,
-                        Vec2::new((window.x - text_size.x) * 0.5, y),
+                        Vec2::new(window.x * 0.5, y),
                         scale,
                         Vec4::ONE,
+                        false,
+                        TextAlign::Center,
                     );
 
                     if line.is_empty() {
@@ -1133,6 +1657,209 @@ impl UILayer {
             }
 
 
+            UILayer::MainMenu { worlds, new_world, confirm_delete, thumbnails } => {
+                let window = renderer.window_size();
+                renderer.draw_rect(Vec2::ZERO, window, COLOUR_SCREEN_DIM);
+
+                let scale = 0.6;
+                let line = renderer.line_size * scale * 1.5;
+                let mut pos = Vec2::new(window.x * 0.5 - 200.0, window.y * 0.25);
+
+                renderer.draw_text("§eWORLDS", pos, 0.8, Vec4::ONE);
+                pos.y += line * 1.5;
+
+
+                if let Some(prompt) = new_world {
+                    for &ch in input.current_chars() {
+                        if !ch.is_ascii_alphanumeric() && !matches!(ch, ' ' | '-' | '_') { continue }
+
+                        if prompt.editing_seed { prompt.seed.push(ch) }
+                        else { prompt.name.push(ch) }
+                    }
+
+                    if input.is_key_just_pressed(KeyCode::Backspace) {
+                        if prompt.editing_seed { prompt.seed.pop(); }
+                        else { prompt.name.pop(); }
+                    }
+
+                    if input.is_key_just_pressed(KeyCode::Tab) {
+                        prompt.editing_seed = !prompt.editing_seed;
+                    }
+
+
+                    let name_cursor = if prompt.editing_seed { "" } else { "_" };
+                    let seed_cursor = if prompt.editing_seed { "_" } else { "" };
+                    renderer.draw_text(&format!("§7Name: §f{}{name_cursor}", prompt.name), pos, scale, Vec4::ONE);
+                    pos.y += line;
+                    renderer.draw_text(&format!("§7Seed: §f{}{seed_cursor}", prompt.seed), pos, scale, Vec4::ONE);
+                    pos.y += line;
+                    renderer.draw_text("§8Tab to switch fields, leave the seed empty for a random one", pos, 0.4, Vec4::ONE);
+                    pos.y += line;
+
+                    if let Some(error) = prompt.error {
+                        renderer.draw_text(&format!("§c{error}"), pos, 0.4, Vec4::ONE);
+                        pos.y += line;
+                    }
+
+
+                    let create = draw_button(renderer, input, pos, "Create")
+                                 || input.is_key_just_pressed(KeyCode::Enter);
+
+                    if draw_button(renderer, input, pos + Vec2::new(120.0, 0.0), "Cancel")
+                        || input.is_key_just_pressed(KeyCode::Escape) {
+                        *new_world = None;
+                        return;
+                    }
+
+                    if create {
+                        let name = prompt.name.trim();
+                        if name.is_empty() {
+                            prompt.error = Some("the world needs a name");
+                        } else if worlds.iter().any(|x| x == name) {
+                            prompt.error = Some("a world with that name already exists");
+                        } else {
+                            let seed = parse_seed(&prompt.seed);
+                            game.open_world(name, seed);
+                        }
+                    }
+
+                    return;
+                }
+
+
+                if worlds.is_empty() {
+                    renderer.draw_text("§7No worlds yet", pos, scale, Vec4::ONE);
+                    pos.y += line;
+                }
+
+                let thumbnail_textures = thumbnails.get_or_insert_with(|| {
+                    worlds.iter().enumerate()
+                        .map(|(i, world)| renderer.upload_thumbnail(i, &load_thumbnail(world)?))
+                        .collect()
+                });
+
+                let thumbnail_size = Vec2::new(THUMBNAIL_WIDTH as f32, THUMBNAIL_HEIGHT as f32) * UI_THUMBNAIL_SCALE;
+                let row = line.max(thumbnail_size.y + 8.0);
+                // keeps the text centered on the thumbnail
+                let text_offset = Vec2::new(thumbnail_size.x + 12.0, (thumbnail_size.y - line) * 0.5);
+
+                let mut play = None;
+                let mut delete = None;
+                for (i, world) in worlds.iter().enumerate() {
+                    match thumbnail_textures[i] {
+                        Some(texture) => renderer.draw_tex_rect(pos, thumbnail_size, texture, Vec4::ONE),
+                        None => {
+                            renderer.draw_rect(pos, thumbnail_size, COLOUR_DARK_GREY);
+                            renderer.draw_text_ex("?", pos + Vec2::new(thumbnail_size.x * 0.5, text_offset.y), scale, COLOUR_WHITE, false, TextAlign::Center);
+                        },
+                    }
+
+                    let text_pos = pos + text_offset;
+                    renderer.draw_text(world, text_pos, scale, Vec4::ONE);
+
+                    if draw_button(renderer, input, text_pos + Vec2::new(250.0, 0.0), "Play") {
+                        play = Some(i);
+                    }
+
+                    let text = if *confirm_delete == Some(i) { "§cSure?" } else { "Delete" };
+                    if draw_button(renderer, input, text_pos + Vec2::new(330.0, 0.0), text) {
+                        delete = Some(i);
+                    }
+
+                    pos.y += row;
+                }
+
+                pos.y += line * 0.5;
+                if draw_button(renderer, input, pos, "New World") {
+                    *new_world = Some(NewWorld::default());
+                }
+
+
+                if let Some(i) = play {
+                    // the seed is only used when creating a world
+                    game.open_world(&worlds[i], TERRAIN_SEED);
+                    return;
+                }
+
+                if let Some(i) = delete {
+                    if *confirm_delete == Some(i) {
+                        delete_world(&worlds[i]);
+                        *worlds = list_worlds();
+                        *confirm_delete = None;
+                        // the slots shift down along with the list
+                        *thumbnails = None;
+                    } else {
+                        *confirm_delete = Some(i);
+                    }
+                }
+            },
+
+
+            UILayer::PauseMenu => {
+                const SLIDER_WIDTH : f32 = 200.0;
+
+                let window = renderer.window_size();
+                renderer.draw_rect(Vec2::ZERO, window, COLOUR_SCREEN_DIM);
+
+                let scale = 0.6;
+                let line = renderer.line_size * scale * 2.0;
+                let mut pos = Vec2::new(window.x * 0.5 - 200.0, window.y * 0.25);
+                let slider = Vec2::new(250.0, 0.0);
+
+                renderer.draw_text("§ePAUSED", pos, 0.8, Vec4::ONE);
+                pos.y += line * 1.5;
+
+
+                let settings = &mut game.settings;
+
+                renderer.draw_text(&format!("Render distance: {}", settings.render_distance), pos, scale, Vec4::ONE);
+                let mut render_distance = settings.render_distance as f32;
+                if draw_slider(renderer, input, pos + slider, SLIDER_WIDTH, &mut render_distance, 1.0, MAX_RENDER_DISTANCE as f32) {
+                    settings.render_distance = render_distance.round() as i32;
+                }
+                pos.y += line;
+
+
+                renderer.draw_text(&format!("UI scale: {:.2}", settings.ui_scale), pos, scale, Vec4::ONE);
+                let mut ui_scale = settings.ui_scale;
+                if draw_slider(renderer, input, pos + slider, SLIDER_WIDTH, &mut ui_scale, 0.5, 2.0) {
+                    settings.ui_scale = (ui_scale * 20.0).round() / 20.0;
+                }
+                pos.y += line;
+
+
+                let sensitivity = &mut settings.sensitivity;
+                renderer.draw_text(&format!("Horizontal sensitivity: {:.1}", sensitivity.x), pos, scale, Vec4::ONE);
+                draw_slider(renderer, input, pos + slider, SLIDER_WIDTH, &mut sensitivity.x, 0.1, 5.0);
+                pos.y += line;
+
+                renderer.draw_text(&format!("Vertical sensitivity: {:.1}", sensitivity.y), pos, scale, Vec4::ONE);
+                draw_slider(renderer, input, pos + slider, SLIDER_WIDTH, &mut sensitivity.y, 0.1, 5.0);
+                pos.y += line;
+
+
+                let vsync = settings.present_mode != PresentMode::Immediate;
+                renderer.draw_text("VSync", pos, scale, Vec4::ONE);
+                if draw_button(renderer, input, pos + slider, if vsync { "On" } else { "Off" }) {
+                    settings.present_mode = if vsync { PresentMode::Immediate } else { PresentMode::Fifo };
+                }
+                pos.y += line;
+
+
+                renderer.draw_text(&format!("Volume: {:.0}%", settings.volume * 100.0), pos, scale, Vec4::ONE);
+                let mut volume = settings.volume;
+                if draw_slider(renderer, input, pos + slider, SLIDER_WIDTH, &mut volume, 0.0, 1.0) {
+                    game.set_volume(volume);
+                }
+                pos.y += line * 1.5;
+
+
+                if draw_button(renderer, input, pos, "Resume") {
+                    *self = UILayer::Gameplay { smoothed_dt: dt };
+                }
+            },
+
+
             UILayer::None => unreachable!(),
         }
     }
@@ -1140,6 +1867,27 @@ impl UILayer {
 
 
 
+///
+/// Numbers are used as a seed as is, anything else
+/// is hashed and an empty seed picks a random one
+///
+fn parse_seed(seed: &str) -> u64 {
+    let seed = seed.trim();
+    if seed.is_empty() {
+        return SystemTime::now().duration_since(UNIX_EPOCH).map_or(TERRAIN_SEED, |x| x.as_nanos() as u64);
+    }
+
+    if let Ok(seed) = seed.parse() {
+        return seed;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+
 fn draw_recipes(game: &mut Game, input: &InputManager, renderer: &mut Renderer, _: &mut Option<Item>, corner: Vec2) {
     let rows = PLAYER_HOTBAR_SIZE;
     let cols = PLAYER_ROW_SIZE;
@@ -1151,18 +1899,35 @@ fn draw_recipes(game: &mut Game, input: &InputManager, renderer: &mut Renderer,
 
     renderer.draw_rect(corner, size, COLOUR_WHITE);
 
+    // pinned recipes go first, in the order they were pinned
+    let pinned = &game.player.pinned_recipes;
+    let order : Vec<usize> = pinned.iter().copied()
+        .chain((0..RECIPES.len()).filter(|x| !pinned.contains(x)))
+        .collect();
+
     let mut base = corner + padding * 0.5;
     let point = renderer.to_point(input.mouse_position());
     for col in 0..cols {
         let mut pos = base;
         for row in 0..rows {
             // render
-            let Some(&recipe) = RECIPES.get(col*rows+row)
+            let Some(&recipe_index) = order.get(col*rows+row)
             else { return };
 
+            let recipe = RECIPES[recipe_index];
+            let is_pinned = game.player.pinned_recipes.contains(&recipe_index);
+
             let (can_craft, mut rc) = RecipeCraft::try_craft(game.player.inventory, recipe);
             let is_mouse_intersecting = point_in_rect(point, pos, Vec2::splat(slot_size));
 
+            if is_mouse_intersecting && input.is_button_just_pressed(MouseButton::Right) {
+                if is_pinned {
+                    game.player.pinned_recipes.retain(|&x| x != recipe_index);
+                } else {
+                    game.player.pinned_recipes.push(recipe_index);
+                }
+            }
+
             if is_mouse_intersecting && can_craft && input.is_button_just_pressed(MouseButton::Left) {
                 game.player.inventory = rc.inv;
                 assert!(can_craft);
@@ -1196,7 +1961,12 @@ fn draw_recipes(game: &mut Game, input: &InputManager, renderer: &mut Renderer,
            
             renderer.draw_rect(pos, Vec2::splat(slot_size), colour);
             renderer.draw_item_icon(recipe.result.kind, pos+slot_size*0.05, Vec2::splat(slot_size*0.9), Vec4::ONE);
-            renderer.draw_text(format!("{}", recipe.result.amount).as_str(), pos+slot_size*0.05, 0.5, Vec4::ONE);
+            renderer.draw_text_shadowed(format!("{}", recipe.result.amount).as_str(), pos+slot_size*0.05, 0.5, Vec4::ONE);
+
+            if is_pinned {
+                let marker = slot_size * 0.2;
+                renderer.draw_rect(pos + Vec2::new(slot_size - marker, 0.0), Vec2::splat(marker), COLOUR_WARN);
+            }
 
 
             if is_mouse_intersecting {
@@ -1258,7 +2028,7 @@ fn draw_recipes(game: &mut Game, input: &InputManager, renderer: &mut Renderer,
                     write!(str, "{}x {}", item.amount, item.kind.name()).unwrap();
                     let text_size = renderer.text_size(&str, scale);
 
-                    renderer.draw_text_ex(&str, base + Vec2::new(padding + slot_size, (slot_size - text_size.y) * 0.5), scale, colour, true);
+                    renderer.draw_text_ex(&str, base + Vec2::new(padding + slot_size, (slot_size - text_size.y) * 0.5), scale, colour, true, TextAlign::Left);
                     base += Vec2::new(0.0, slot_size+padding*0.5);
                 }
 
@@ -1453,7 +2223,7 @@ impl RecipeCraft {
 }
 
 
-fn draw_player_inventory(renderer: &mut Renderer, player: &mut Player, world: &mut VoxelWorld, entities: &mut EntityMap, other_inv: &mut Option<&mut [Option<Item>]>, input: &InputManager, holding_item: &mut Option<Item>, corner: Vec2) {
+fn draw_player_inventory(renderer: &mut Renderer, player: &mut Player, throw_dir: Vec3, world: &mut VoxelWorld, entities: &mut EntityMap, other_inv: &mut Option<(&mut [Option<Item>], &[Filter])>, input: &InputManager, holding_item: &mut Option<Item>, corner: Vec2) {
     let rows = PLAYER_ROW_SIZE;
     let cols = PLAYER_HOTBAR_SIZE;
 
@@ -1475,7 +2245,7 @@ fn draw_player_inventory(renderer: &mut Renderer, player: &mut Player, world: &m
                          else { COLOUR_GREY }; 
 
 
-            draw_inventory_item(renderer, &mut player.inventory, player.body.position, entities, other_inv, input, holding_item,
+            draw_inventory_item(renderer, &mut player.inventory, player.body.position, throw_dir, entities, other_inv, input, holding_item,
                                 pos, slot_index, colour, |_| true);
 
             pos += Vec2::new(slot_size+padding, 0.0);
@@ -1508,16 +2278,20 @@ fn draw_player_inventory(renderer: &mut Renderer, player: &mut Player, world: &m
 
     if let Some(item) = *holding_item {
         renderer.draw_item_icon(item.kind, point, Vec2::splat(slot_size), Vec4::ONE);
-        renderer.draw_text(format!("{}", item.amount).as_str(), point+slot_size*0.05, 0.5, Vec4::ONE);
+        renderer.draw_text_shadowed(format!("{}", item.amount).as_str(), point+slot_size*0.05, 0.5, Vec4::ONE);
     }
 
 
 }
 
 
-fn draw_inventory(renderer: &mut Renderer, inventory: &mut [Option<Item>],
-                  player_pos: DVec3, world: &mut VoxelWorld, entities: &mut EntityMap,
-                  mut other_inv: Option<&mut [Option<Item>]>,
+///
+/// Draws `inventory` as a grid of slots, if `filters` is given
+/// ctrl + right clicking a slot cycles its filter, see `cycle_filter`
+///
+fn draw_inventory(renderer: &mut Renderer, inventory: &mut [Option<Item>], mut filters: Option<&mut [Filter]>,
+                  player_pos: DVec3, throw_dir: Vec3, world: &mut VoxelWorld, entities: &mut EntityMap,
+                  mut other_inv: Option<(&mut [Option<Item>], &[Filter])>,
                   input: &InputManager, holding_item: &mut Option<Item>,
                   corner: Vec2, cols: usize, rows: usize) {
     let slot_size = 64.0;
@@ -1530,10 +2304,28 @@ fn draw_inventory(renderer: &mut Renderer, inventory: &mut [Option<Item>],
         for col in 0..cols {
             let slot_index = row*cols+col;
             let is_mouse_intersecting = point_in_rect(point, pos, Vec2::splat(SLOT_SIZE));
-            let colour = COLOUR_GREY; 
+            let filter = filters.as_deref().map_or(Filter::None, |filters| filters[slot_index]);
+            let colour = if filter == Filter::None { COLOUR_GREY }
+                         else { COLOUR_GREY.lerp(COLOUR_WARN, 0.35) };
+
+            if let Some(filters) = filters.as_deref_mut()
+                && is_mouse_intersecting
+                && input.is_key_pressed(KeyCode::ControlLeft)
+                && input.is_button_just_pressed(MouseButton::Right) {
+                let item = holding_item.or(inventory[slot_index]).map(|x| x.kind);
+                filters[slot_index] = cycle_filter(filter, item);
+
+                // the click went to the filter, don't split the stack too
+                draw_inventory_slot(&mut (), renderer, input, pos, inventory[slot_index], colour, |_, _| (), |_, _| (), |_, _| (), |_, _| ());
+            } else {
+                draw_inventory_item(renderer, inventory, player_pos, throw_dir, entities, &mut other_inv, input, holding_item,
+                                    pos, slot_index, colour, |kind| filter.is_valid(kind));
+            }
 
-            draw_inventory_item(renderer, inventory, player_pos, entities, &mut other_inv, input, holding_item,
-                                pos, slot_index, colour, |_| true);
+            // show what an empty filtered slot is waiting for
+            if let Filter::ItemKind(kind) = filter && inventory[slot_index].is_none() {
+                renderer.draw_item_icon(kind, pos+SLOT_SIZE*0.05, Vec2::splat(SLOT_SIZE*0.9), Vec4::new(1.0, 1.0, 1.0, 0.3));
+            }
 
             pos += Vec2::new(slot_size+padding, 0.0);
             
@@ -1549,7 +2341,7 @@ fn draw_inventory(renderer: &mut Renderer, inventory: &mut [Option<Item>],
 
     if let Some(item) = *holding_item {
         renderer.draw_item_icon(item.kind, point, Vec2::splat(slot_size), Vec4::ONE);
-        renderer.draw_text(format!("{}", item.amount).as_str(), point+slot_size*0.05, 0.5, Vec4::ONE);
+        renderer.draw_text_shadowed(format!("{}", item.amount).as_str(), point+slot_size*0.05, 0.5, Vec4::ONE);
     }
 }
 
@@ -1577,7 +2369,7 @@ fn draw_inventory_slot<T>(
     if let Some(item) = item {
         renderer.draw_item_icon(item.kind, at+SLOT_SIZE*0.05, Vec2::splat(SLOT_SIZE*0.9), Vec4::ONE);
         if item.amount > 1 {
-            renderer.draw_text(format!("{}", item.amount).as_str(), at+SLOT_SIZE*0.05, 0.5, Vec4::ONE);
+            renderer.draw_text_shadowed(format!("{}", item.amount).as_str(), at+SLOT_SIZE*0.05, 0.5, Vec4::ONE);
         }
     }
 
@@ -1604,6 +2396,239 @@ fn draw_inventory_slot<T>(
 }
 
 
+///
+/// The name of a structure and what it's up to in a line
+/// or two, energy is only shown for things that burn fuel
+///
+fn structure_tooltip(game: &mut Game, id: StructureId) -> String {
+    let structure = game.structures.get(id);
+    let kind = structure.data.as_kind();
+
+    // how far along the work the structure is waiting on is, `total` is how long it takes
+    let progress = |total: u32| {
+        let Some(tick) = game.structures.work_queue.find(id)
+        else { return 0.0 };
+
+        let remaining = tick.u32().saturating_sub(game.current_tick.u32());
+        (1.0 - remaining as f32 / total.max(1) as f32).clamp(0.0, 1.0) * 100.0
+    };
+
+    let stored = || structure.inventory.as_ref()
+        .map(|x| x.slots.iter().flatten().map(|x| x.amount).sum::<u32>())
+        .unwrap_or(0);
+
+
+    let mut text = String::new();
+    let _ = write!(text, "{}§f: ", kind.item_kind().name());
+
+    match &structure.data {
+        StructureData::Quarry { current_progress, size, max_depth } => {
+            let depth = current_progress / (size * size) + 1;
+            if depth > *max_depth { let _ = write!(text, "§7done"); }
+            else if structure.is_asleep { let _ = write!(text, "§coutput full"); }
+            else { let _ = write!(text, "digging at depth §a{depth}"); }
+        },
+
+
+        StructureData::Inserter { state, .. } => {
+            let zz = structure.zero_zero();
+            let has_input = kind.inserter_inputs().iter()
+                .map(|&offset| zz + strct::rotate_oriented_vector(structure.direction, structure.orientation, offset))
+                .any(|pos| game.world.structure_blocks.contains_key(&pos));
+            let output = zz + strct::rotate_oriented_vector(structure.direction, structure.orientation, kind.inserter_output());
+
+            match state {
+                InserterState::Placing(item) => { let _ = write!(text, "carrying {}", item.kind.name()); },
+                InserterState::Searching if !has_input
+                    || !game.world.structure_blocks.contains_key(&output) => { let _ = write!(text, "§cno target"); },
+                InserterState::Searching => { let _ = write!(text, "§7waiting for items"); },
+            }
+        },
+
+
+          StructureData::Chest
+        | StructureData::Silo
+        | StructureData::ProviderChest
+        | StructureData::RequesterChest { .. } => {
+            let _ = write!(text, "§a{}§f items stored", stored());
+            if kind.is_logistics_chest()
+                && let Some((network, _)) = game.structures.logistics().network_of(id) {
+                let _ = write!(text, "\n§7network #{}", network.usize());
+            }
+        },
+
+
+          StructureData::Belt { .. }
+        | StructureData::Splitter { .. } => {
+            let _ = write!(text, "§a{}§f items carried", stored());
+        },
+
+
+        StructureData::Assembler { recipe, modules, .. } => {
+            match recipe {
+                None => { let _ = write!(text, "§cno recipe"); },
+                Some(recipe) if structure.is_asleep => {
+                    let _ = write!(text, "§7idle, §fmakes {}", recipe.result.kind.name());
+                },
+                Some(recipe) => {
+                    let progress = progress(structures::assembler_time(*recipe, modules));
+                    let _ = write!(text, "making {} §a{progress:.0}%", recipe.result.kind.name());
+                },
+            }
+        },
+
+
+        StructureData::Furnace(furnace) => {
+            let input = structure.inventory.as_ref().unwrap().input(0).0;
+            let recipe = input.and_then(|input| FURNACE_RECIPES.iter().find(|x| x.requirements[0].kind == input.kind));
+
+            match recipe {
+                _ if furnace.waiting_for_fuel => { let _ = write!(text, "§cno fuel"); },
+                Some(recipe) if !structure.is_asleep => {
+                    let progress = progress(furnace.smelt_time(recipe));
+                    let _ = write!(text, "smelting §a{progress:.0}%");
+                },
+                _ => { let _ = write!(text, "§7idle"); },
+            }
+
+            let _ = write!(text, "\n§fEnergy: §a{}", structure.energy.energy);
+        },
+
+
+        StructureData::Pump => {
+            let position = structure.position;
+            let below = game.world.get_voxel(position - IVec3::Y);
+            let _ = write!(text, "pumping {}", Fluid::pumped_from(below).name());
+        },
+
+
+        StructureData::Pipe { fluid, amount } => {
+            match fluid {
+                Some(fluid) => { let _ = write!(text, "{} §a{amount}", fluid.name()); },
+                None => { let _ = write!(text, "§7empty"); },
+            }
+        },
+    }
+
+    text
+}
+
+
+fn write_lanes(text: &mut String, slots: &[Option<Item>]) {
+    // every lane holds 2 items, a splitter is two belts side by side
+    for (lane, items) in slots.chunks(2).enumerate() {
+        let _ = write!(text, "§e  - LANE {lane}:");
+        for item in items {
+            match item {
+                Some(item) => { let _ = write!(text, " §b{:?} §a{}x", item.kind, item.amount); },
+                None => { let _ = write!(text, " §bEmpty"); },
+            }
+        }
+
+        let _ = writeln!(text, "§r");
+    }
+}
+
+
+///
+/// Completes the token under the cursor, the first token is
+/// completed against the registered commands and the item
+/// argument of `give` and `remove` against the item names
+///
+fn complete_command(game: &Game, text: &mut String, cursor: &mut u32, completion: &mut Option<Completion>) {
+    let cursor_index = *cursor as usize;
+    let token_start = text[..cursor_index].rfind(' ').map(|x| x + 1).unwrap_or(0);
+    let token_index = text[..token_start].split_whitespace().count();
+
+    let mut candidates : Vec<&str> = match (token_index, text.split_whitespace().next()) {
+        (0, _) => game.command_registry.names().collect(),
+        (1, Some("give" | "remove")) => ItemKind::ALL.iter().map(|x| x.to_string()).collect(),
+        (2, Some("give")) => vec!["@max", "@stack"],
+        _ => return,
+    };
+
+    candidates.sort();
+
+    let completion = completion.get_or_insert_with(|| Completion {
+        prefix: text[token_start..cursor_index].to_string(),
+        index: 0,
+    });
+
+    let matches = candidates.iter()
+        .filter(|x| x.starts_with(completion.prefix.as_str()))
+        .collect::<Vec<_>>();
+
+    if matches.is_empty() { return }
+
+    let word = matches[completion.index % matches.len()];
+    completion.index += 1;
+
+    text.replace_range(token_start..cursor_index, word);
+    *cursor = (token_start + word.len()) as u32;
+}
+
+
+///
+/// A slider for `value` between `min` and `max` that follows the
+/// mouse while it's held down on it, returns whether it moved
+///
+fn draw_slider(renderer: &mut Renderer, input: &InputManager, pos: Vec2, width: f32, value: &mut f32, min: f32, max: f32) -> bool {
+    let size = Vec2::new(width, renderer.line_size * 0.6);
+    let handle = Vec2::new(12.0, size.y);
+
+    let mouse_pos = renderer.to_point(input.mouse_position());
+    let is_hovered = point_in_rect(mouse_pos, pos, size);
+
+    let t = ((*value - min) / (max - min)).clamp(0.0, 1.0);
+    let colour = if is_hovered { COLOUR_GREY + COLOUR_ADDITIVE_HIGHLIGHT } else { COLOUR_GREY };
+    renderer.draw_rect(pos, size, COLOUR_DARK_GREY.with_w(1.0));
+    renderer.draw_rect(pos + Vec2::new(t * (width - handle.x), 0.0), handle, colour.with_w(1.0));
+
+    if !is_hovered || !input.is_button_pressed(MouseButton::Left) { return false }
+
+    let t = ((mouse_pos.x - pos.x - handle.x * 0.5) / (width - handle.x)).clamp(0.0, 1.0);
+    let new = min + t * (max - min);
+    let moved = new != *value;
+    *value = new;
+    moved
+}
+
+
+fn draw_button(renderer: &mut Renderer, input: &InputManager, pos: Vec2, text: &str) -> bool {
+    let scale = 0.6;
+    let padding = 8.0;
+
+    let size = renderer.text_size(text, scale) + padding * 2.0;
+    let mouse_pos = renderer.to_point(input.mouse_position());
+    let is_hovered = point_in_rect(mouse_pos, pos, size);
+
+    let colour = if is_hovered { COLOUR_GREY + COLOUR_ADDITIVE_HIGHLIGHT } else { COLOUR_GREY };
+    renderer.draw_rect(pos, size, colour.with_w(1.0));
+    renderer.draw_text(text, pos + padding, scale, COLOUR_WHITE);
+
+    is_hovered && input.is_button_just_pressed(MouseButton::Left)
+}
+
+
+///
+/// Whether `search` shows up anywhere in the name of `kind`,
+/// ignoring case and colour codes
+///
+fn matches_search(kind: ItemKind, search: &str) -> bool {
+    let search = search.trim().to_lowercase();
+    if search.is_empty() { return true }
+
+    let mut name = String::new();
+    let mut chars = kind.name().chars();
+    while let Some(ch) = chars.next() {
+        if ch == '§' { chars.next(); continue }
+        name.extend(ch.to_lowercase());
+    }
+
+    name.contains(&search)
+}
+
+
 fn default_hover_action(renderer: &mut Renderer, mouse_pos: Vec2, item: ItemKind) {
     let item_name = item.name();
     let scale = 0.5;
@@ -1620,8 +2645,8 @@ fn default_hover_action(renderer: &mut Renderer, mouse_pos: Vec2, item: ItemKind
 
 
 fn draw_inventory_item(renderer: &mut Renderer, inventory: &mut [Option<Item>],
-                       player_pos: DVec3, entities: &mut EntityMap,
-                       other_inv: &mut Option<&mut [Option<Item>]>,
+                       player_pos: DVec3, throw_dir: Vec3, entities: &mut EntityMap,
+                       other_inv: &mut Option<(&mut [Option<Item>], &[Filter])>,
                        input: &InputManager, holding_item: &mut Option<Item>,
                        pos: Vec2, index: usize, colour: Vec4, filter: impl FnOnce(ItemKind) -> bool) {
 
@@ -1640,38 +2665,9 @@ fn draw_inventory_item(renderer: &mut Renderer, inventory: &mut [Option<Item>],
 
         |_, (item_slot, holding_item)| {
             if input.is_key_pressed(KeyCode::ShiftLeft)
-                && let Some(other_inv) = other_inv 
+                && let Some((other_inv, other_filters)) = other_inv 
                 && let Some(inv_item) = item_slot {
-                for slot in other_inv.iter_mut() {
-                    let Some(item) = slot
-                    else { continue };
-
-                    if item.kind != inv_item.kind {
-                        continue;
-                    }
-
-                    let addition = inv_item.amount.min(item.kind.max_stack_size() - item.amount);
-                    inv_item.amount -= addition;
-                    item.amount += addition;
-                    if inv_item.amount != 0 {
-                        continue;
-                    }
-
-                    **item_slot = None;
-                    return;
-                }
-
-
-                for slot in other_inv.iter_mut() {
-                    if slot.is_some() { continue }
-
-                    if inv_item.amount != 0 {
-                        *slot = Some(*inv_item);
-                    }
-
-                    **item_slot = None;
-                    return ;
-                }
+                **item_slot = quick_transfer(*inv_item, other_inv, other_filters);
             } else {
                 if let Some(item) = holding_item && !filter(item.kind) {
                     return;
@@ -1680,7 +2676,7 @@ fn draw_inventory_item(renderer: &mut Renderer, inventory: &mut [Option<Item>],
                    && let Some(item) = holding_item
                    && inv_item.kind == item.kind {
 
-                    let addition = item.amount.min(inv_item.kind.max_stack_size().max(inv_item.amount) - inv_item.amount);
+                    let addition = item.amount.min(inv_item.room_left());
 
                     inv_item.amount += addition;
 
@@ -1724,20 +2720,122 @@ fn draw_inventory_item(renderer: &mut Renderer, inventory: &mut [Option<Item>],
                 && let Some(item) = item_slot {
 
                 let mut drop_item = *item;
+                let mut speed = DROPPED_ITEM_THROW_SPEED;
                 if input.is_alt_pressed() {
                     drop_item.amount = 1;
+                    speed = DROPPED_ITEM_TOSS_SPEED;
                 }
 
                 item.amount -= drop_item.amount;
-                let item = *item;
                 if item.amount == 0 {
                     **item_slot = None;
                 }
 
-                entities.spawn(EntityKind::dropped_item(item), player_pos);
+                entities.throw(EntityKind::dropped_item(drop_item), player_pos, throw_dir, speed);
             }
 
         }
 
     );
 }
+
+
+///
+/// The filter a slot gets when it's ctrl + right clicked with `item`
+/// held or in it. Clicking with the kind it's already filtered to
+/// or with nothing at all clears it
+///
+fn cycle_filter(filter: Filter, item: Option<ItemKind>) -> Filter {
+    match item {
+        Some(kind) if filter != Filter::ItemKind(kind) => Filter::ItemKind(kind),
+        _ => Filter::None,
+    }
+}
+
+
+///
+/// Moves as much of `item` as fits into `slots`, topping up stacks
+/// of the same kind before starting new ones in empty slots.
+/// Slots whose entry in `filters` doesn't allow the item are
+/// skipped, `filters` can be shorter than `slots` or empty.
+/// Returns whatever didn't fit
+///
+fn quick_transfer(mut item: Item, slots: &mut [Option<Item>], filters: &[Filter]) -> Option<Item> {
+    let accepts = |index: usize| filters.get(index).is_none_or(|filter| filter.is_valid(item.kind));
+
+    for (index, slot) in slots.iter_mut().enumerate() {
+        let Some(other) = slot
+        else { continue };
+
+        if other.kind != item.kind || !accepts(index) { continue }
+
+        let addition = item.amount.min(other.room_left());
+        other.amount += addition;
+        item.amount -= addition;
+
+        if item.amount == 0 { return None }
+    }
+
+
+    for (index, slot) in slots.iter_mut().enumerate() {
+        if slot.is_some() || !accepts(index) { continue }
+
+        let addition = item.amount.min(item.kind.max_stack_size());
+        *slot = Some(Item::new(item.kind, addition));
+        item.amount -= addition;
+
+        if item.amount == 0 { return None }
+    }
+
+    Some(item)
+}
+
+
+#[test]
+fn quick_transfer_fills_partial_stacks_first() {
+    let max = ItemKind::IronPlate.max_stack_size();
+    let mut slots = [
+        Some(Item::new(ItemKind::IronPlate, max - 5)),
+        None,
+        Some(Item::new(ItemKind::IronPlate, max - 10)),
+        Some(Item::new(ItemKind::Coal, 10)),
+        None,
+    ];
+
+    assert_eq!(quick_transfer(Item::new(ItemKind::IronPlate, 30), &mut slots, &[]), None);
+    assert_eq!(slots, [
+        Some(Item::new(ItemKind::IronPlate, max)),
+        Some(Item::new(ItemKind::IronPlate, 15)),
+        Some(Item::new(ItemKind::IronPlate, max)),
+        Some(Item::new(ItemKind::Coal, 10)),
+        None,
+    ]);
+}
+
+
+#[test]
+fn quick_transfer_leaves_the_remainder() {
+    let max = ItemKind::IronPlate.max_stack_size();
+    let mut slots = [
+        Some(Item::new(ItemKind::IronPlate, max - 1)),
+        Some(Item::new(ItemKind::Coal, 10)),
+    ];
+
+    assert_eq!(quick_transfer(Item::new(ItemKind::IronPlate, 30), &mut slots, &[]), Some(Item::new(ItemKind::IronPlate, 29)));
+    assert_eq!(slots[0], Some(Item::new(ItemKind::IronPlate, max)));
+    assert_eq!(slots[1], Some(Item::new(ItemKind::Coal, 10)));
+}
+
+
+#[test]
+fn quick_transfer_honors_filters() {
+    let mut slots = [None, None, None];
+    let filters = [Filter::ItemKind(ItemKind::Coal), Filter::None, Filter::ItemKind(ItemKind::IronPlate)];
+
+    assert_eq!(quick_transfer(Item::new(ItemKind::IronPlate, 10), &mut slots, &filters), None);
+    assert_eq!(slots, [None, Some(Item::new(ItemKind::IronPlate, 10)), None]);
+
+    assert_eq!(cycle_filter(Filter::None, Some(ItemKind::Coal)), Filter::ItemKind(ItemKind::Coal));
+    assert_eq!(cycle_filter(Filter::ItemKind(ItemKind::Coal), Some(ItemKind::Coal)), Filter::None);
+    assert_eq!(cycle_filter(Filter::ItemKind(ItemKind::Coal), None), Filter::None);
+}
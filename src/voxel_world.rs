@@ -3,7 +3,7 @@ pub mod voxel;
 pub mod mesh;
 pub mod chunker;
 
-use std::{fs::{self}, hint::spin_loop, ops::Bound, sync::Arc, time::Instant};
+use std::{fs::{self}, hint::spin_loop, ops::Bound, sync::Arc, time::{Duration, Instant}};
 
 use chunk::{ChunkData, Noise};
 use chunker::{Chunker, WorldChunkPos};
@@ -14,7 +14,7 @@ use tracing::{error, info, warn};
 use voxel::Voxel;
 use wgpu::util::StagingBelt;
 
-use crate::{constants::{CHUNK_SIZE, CHUNK_SIZE_I32, REGION_SIZE}, entities::{EntityKind, EntityMap}, free_list::FreeKVec, items::{Item}, renderer::{gpu_allocator::GPUAllocator, ssbo::SSBO}, structures::{strct::{InserterState, StructureData}, StructureId, Structures}, voxel_world::chunk::Chunk, PhysicsBody};
+use crate::{constants::{CHUNK_SIZE, CHUNK_SIZE_I32, MESH_BUDGET_MAX, MESH_BUDGET_MIN, REGION_SIZE, TERRAIN_SEED, WATER_DRAG, WATER_GRAVITY_MULTIPLIER, WORLD_MAX_Y, WORLD_MIN_Y}, entities::{EntityKind, EntityMap}, free_list::FreeKVec, items::{Item}, renderer::{gpu_allocator::GPUAllocator, ssbo::SSBO}, structures::{strct::{InserterState, StructureData}, StructureId, Structures}, voxel_world::chunk::Chunk, PhysicsBody};
 
 
 pub struct VoxelWorld {
@@ -33,22 +33,58 @@ pub const SURROUNDING_OFFSETS : &[IVec3] = &[
 ];
 
 
+///
+/// The vertical extent of the world.
+/// `min` is inclusive and `max` is exclusive
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightLimits {
+    pub min: i32,
+    pub max: i32,
+}
+
+
+impl HeightLimits {
+    pub fn contains(self, y: i32) -> bool {
+        y >= self.min && y < self.max
+    }
+
+
+    pub fn contains_chunk(self, chunk_y: i32) -> bool {
+        let bottom = chunk_y * CHUNK_SIZE_I32;
+        let top = bottom + CHUNK_SIZE_I32;
+        top > self.min && bottom < self.max
+    }
+}
+
+
+impl Default for HeightLimits {
+    fn default() -> Self {
+        Self { min: WORLD_MIN_Y, max: WORLD_MAX_Y }
+    }
+}
+
+
 
 impl VoxelWorld {
     pub fn new() -> Self {
         Self {
-            chunker: Chunker::new(),
+            chunker: Chunker::new(TERRAIN_SEED),
             structure_blocks: sti::hash::HashMap::new(),
         }
 
     }
 
 
-    pub fn process(&mut self, free_list: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>, instance_allocator: &mut GPUAllocator<ChunkQuadInstance>) {
-        self.chunker.process_mesh_queue(3, free_list);
-        self.chunker.process_chunk_queue(3);
-        self.chunker.process_chunk_jobs(3);
-        self.chunker.process_mesh_unload_queue(3, free_list, instance_allocator);
+    ///
+    /// Works through the chunk and mesh queues, each
+    /// of them gets its share of what's left of `budget`
+    ///
+    pub fn process(&mut self, budget: &mut MeshBudget, free_list: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>, instance_allocator: &mut GPUAllocator<ChunkQuadInstance>) {
+        self.chunker.process_mesh_queue(budget.next_share(), free_list);
+        self.chunker.process_chunk_queue(budget.next_share());
+        self.chunker.process_chunk_jobs(budget.next_share());
+        self.chunker.process_mesh_unload_queue(budget.next_share(), free_list, instance_allocator);
     }
 
 
@@ -73,23 +109,31 @@ impl VoxelWorld {
     }
 
 
-    pub fn chunk_creation_job(pos: IVec3, noise: &Noise) -> Chunk {
-        let path = format!("saves/chunks/{pos}.chunk");
-        let chunk = match fs::read(&path) {
-            Ok(ref v) if let Some(mut byte_reader) = ByteReader::new(&v) => {
+    pub fn chunk_creation_job(pos: IVec3, noise: &Noise, limits: HeightLimits, save_dir: &str) -> Chunk {
+        if !limits.contains_chunk(pos.y) {
+            return Chunk::empty_chunk();
+        }
+
+        let path = format!("{save_dir}/chunks/{pos}.chunk");
+        let file = fs::read(&path).ok().and_then(|v| chunker::decompress_chunk_file(&v));
+        let chunk = match file {
+            Some((ref v, is_legacy)) if let Some(mut byte_reader) = ByteReader::new(&v) => {
                 let mut chunk = Chunk::empty_chunk();
                 let data = ChunkData::from_bytes(byte_reader.read().unwrap());
                 if !data.is_empty() {
                     chunk.set_chunk_data(data);
                 }
 
-                chunk.is_dirty = false;
+                chunk.is_dirty = is_legacy;
+                if !is_legacy {
+                    chunk.saved_version = Some(chunk.version);
+                }
                 chunk
             },
 
 
             _ => {
-                Chunk::generate(pos, noise)
+                Chunk::generate(pos, noise, limits)
             }
         };
 
@@ -116,6 +160,50 @@ impl VoxelWorld {
     }
 
 
+    ///
+    /// Sets every voxel in the inclusive box between `min` and `max`
+    /// to `voxel` and returns how many of them changed.
+    /// Structure blocks are left alone so structures stay intact
+    ///
+    pub fn fill(&mut self, min: IVec3, max: IVec3, voxel: Voxel) -> u32 {
+        let (min_chunk, _) = split_world_pos(min);
+        let (max_chunk, _) = split_world_pos(max);
+
+        let mut changed = 0;
+        for z in min_chunk.0.z..=max_chunk.0.z {
+            for y in min_chunk.0.y..=max_chunk.0.y {
+                for x in min_chunk.0.x..=max_chunk.0.x {
+                    let chunk_pos = WorldChunkPos(IVec3::new(x, y, z));
+                    let base = chunk_pos.0 * CHUNK_SIZE_I32;
+                    let lo = (min - base).max(IVec3::ZERO);
+                    let hi = (max - base).min(IVec3::splat(CHUNK_SIZE_I32 - 1));
+
+                    let positions = (lo.z..=hi.z)
+                        .flat_map(|z| (lo.y..=hi.y).map(move |y| (y, z)))
+                        .flat_map(|(y, z)| (lo.x..=hi.x).map(move |x| IVec3::new(x, y, z)));
+
+                    // getting the chunk mutably remeshes it and its
+                    // neighbours so chunks that don't change are skipped
+                    let chunk = self.get_chunk(chunk_pos);
+                    let needs_change = |current: Voxel| current != voxel && !current.is_structure();
+                    if !positions.clone().any(|pos| needs_change(chunk.get(pos))) { continue }
+
+                    let chunk = self.get_chunk_mut(chunk_pos);
+                    for pos in positions {
+                        let current = chunk.get_mut(pos);
+                        if !needs_change(*current) { continue }
+
+                        *current = voxel;
+                        changed += 1;
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+
+
     pub fn block_item(&mut self, structures: &Structures, pos: IVec3) -> Item {
         let voxel = self.get_voxel(pos);
 
@@ -139,12 +227,10 @@ impl VoxelWorld {
         let item = if voxel.is_structure() {
             let structure_id = *self.structure_blocks.get(&pos).unwrap();
             let structure = structures.remove(structure_id);
-            let placement_origin = structure.position - structure.data.as_kind().origin(structure.direction);
-            
-            let blocks = structure.data.as_kind().blocks(structure.direction);
+            let placement_origin = structure.zero_zero();
             let kind = structure.data.as_kind().item_kind();
 
-            for offset in blocks {
+            for offset in structure.blocks() {
                 let pos = placement_origin + offset;
 
                 *self.get_voxel_mut(pos) = Voxel::Air;
@@ -180,6 +266,15 @@ impl VoxelWorld {
                     );
                 }
 
+                StructureData::Assembler { modules, .. } => {
+                    for module in modules.into_iter().flatten() {
+                        entities.spawn(
+                            EntityKind::dropped_item(Item::new(module, 1)),
+                            pos.as_dvec3() + DVec3::new(0.5, 0.5, 0.5)
+                        );
+                    }
+                }
+
                 _ => (),
             }
 
@@ -251,38 +346,57 @@ impl VoxelWorld {
     }
 
 
-    pub fn move_physics_body(&mut self, delta_time: f32, physics_body: &mut PhysicsBody) {
-        physics_body.velocity.y -= 9.8 * delta_time;
+    pub fn move_physics_body(&mut self, structures: &Structures, delta_time: f32, physics_body: &mut PhysicsBody) {
+        // a body swims once its middle is under water,
+        // which turns falling into a slow sink
+        let in_water = self.get_voxel(physics_body.position.floor().as_ivec3()).is_water();
+        physics_body.in_water = in_water;
+
+        if physics_body.has_gravity {
+            let gravity = if in_water { 9.8 * WATER_GRAVITY_MULTIPLIER } else { 9.8 };
+            physics_body.velocity.y -= gravity * delta_time;
+        }
+
+        if in_water {
+            physics_body.velocity.y *= 1.0 - WATER_DRAG * delta_time;
+        }
 
         let mut position = physics_body.position;
 
         physics_body.velocity.x *= 1.0 - 10.0 * delta_time;
         physics_body.velocity.z *= 1.0 - 10.0 * delta_time;
 
+        let pass_through_walkable = physics_body.pass_through_walkable;
+        physics_body.on_ground = false;
+
         for axis in 0..3 {
             let mut new_position = position;
             new_position[axis] += (physics_body.velocity[axis] * delta_time) as f64;
 
-            let min = (new_position - (physics_body.aabb_dims * 0.5).as_dvec3()).floor().as_ivec3();
+            let body_min = new_position - (physics_body.aabb_dims * 0.5).as_dvec3();
+            let min = body_min.floor().as_ivec3();
             let max = (new_position + (physics_body.aabb_dims * 0.5).as_dvec3()).ceil().as_ivec3();
 
             let mut collided = false;
 
-            for x in min.x..max.x {
+            'outer: for x in min.x..max.x {
                 for y in min.y..max.y {
                     for z in min.z..max.z {
                         let voxel_pos = IVec3::new(x, y, z);
-                        if !self.get_voxel(voxel_pos).is_air() {
+                        let height = self.collision_height(structures, voxel_pos, pass_through_walkable);
+                        if height > 0.0 && body_min.y < y as f64 + height as f64 {
                             collided = true;
-                            break;
+                            break 'outer;
                         }
                     }
-                    if collided { break; }
                 }
-                if collided { break; }
             }
 
             if collided {
+                if axis == 1 && physics_body.velocity.y < 0.0 {
+                    physics_body.on_ground = true;
+                }
+
                 physics_body.velocity[axis] = 0.0;
             } else {
                 position[axis] = new_position[axis];
@@ -290,40 +404,77 @@ impl VoxelWorld {
         }
 
 
-        while !self.get_voxel(position.floor().as_ivec3()).is_air() {
-            position.y += 1.0;
+        loop {
+            let pos = position.floor().as_ivec3();
+            let height = self.collision_height(structures, pos, pass_through_walkable);
+            let top = pos.y as f64 + height as f64;
+
+            if height == 0.0 || position.y >= top { break }
+            position.y = top;
         }
 
         physics_body.position = position;
     }
 
 
+    ///
+    /// Returns how tall the solid part of the block at `pos` is,
+    /// 0.0 if a body can move through it
+    ///
+    pub fn collision_height(&mut self, structures: &Structures, pos: IVec3, pass_through_walkable: bool) -> f32 {
+        let voxel = self.get_voxel(pos);
+        if voxel.is_air() || voxel.is_water() { return 0.0 }
+        if !voxel.is_structure() { return 1.0 }
+
+        let Some(&id) = self.structure_blocks.get(&pos)
+        else { return 1.0 };
 
-    pub fn save(&mut self) {
-        warn!("voxel-save-system: saving the world..");
+        let kind = structures.get(id).data.as_kind();
+        if pass_through_walkable && kind.is_walkable() { return 0.0 }
+
+        kind.collision_height()
+    }
+
+
+
+    pub fn save(&mut self, full: bool) {
+        warn!("voxel-save-system: saving the world.. (full: {full})");
         let time = Instant::now();
-        while self.chunker.chunk_load_queue_len() > 0 { self.chunker.process_chunk_queue(128); }
-        while self.chunker.chunk_active_jobs_len() > 0 { self.chunker.process_chunk_jobs(512); }
+        while self.chunker.chunk_load_queue_len() > 0 { self.chunker.process_chunk_queue(Duration::from_millis(128)); }
+        while self.chunker.chunk_active_jobs_len() > 0 { self.chunker.process_chunk_jobs(Duration::from_millis(512)); }
 
         let chunks = self.chunker.iter_chunks().filter(|x| {
             if let chunker::ChunkEntry::Loaded(c) = x.1 {
-                c.is_dirty
+                full || c.is_dirty
             } else { false }
         }).map(|x| x.0).collect::<Vec<_>>();
 
-        for pos in chunks { self.chunker.save_chunk(pos); }
+        let count = chunks.len();
+        for pos in chunks { self.chunker.save_chunk(pos, full); }
         //while self.chunker.chunk_save_jobs.fetch_add(0, std::sync::atomic::Ordering::SeqCst) > 0 { spin_loop(); }
 
-        info!("voxel-save-system: saved the world in {:?}", time.elapsed());
+        info!("voxel-save-system: saved {count} chunks in {:?}", time.elapsed());
     }
 
 
 
-    pub fn greedy_mesh(c: [VoxelMeshIndex; 6], pos: IVec3, chunks: ChunkDataRef) -> [Vec<ChunkQuadInstance>; 6]{
-        let [west, east] = Self::greedy_mesh_dir(c[0], c[3], &chunks, pos, 0);
-        let [up, down] = Self::greedy_mesh_dir(c[1], c[4], &chunks, pos, 1);
-        let [north, south] = Self::greedy_mesh_dir(c[2], c[5], &chunks, pos, 2);
-        [west, up, north, east, down, south]
+    ///
+    /// Meshes every face of the chunk, the first 6 lists are
+    /// the opaque faces and the last 6 are the water faces
+    ///
+    pub fn greedy_mesh(c: [VoxelMeshIndex; 12], pos: IVec3, chunks: ChunkDataRef) -> [Vec<ChunkQuadInstance>; 12]{
+        let [west, east] = Self::greedy_mesh_dir(c[0], c[3], &chunks, pos, 0, false);
+        let [up, down] = Self::greedy_mesh_dir(c[1], c[4], &chunks, pos, 1, false);
+        let [north, south] = Self::greedy_mesh_dir(c[2], c[5], &chunks, pos, 2, false);
+
+        let [water_west, water_east] = Self::greedy_mesh_dir(c[6], c[9], &chunks, pos, 0, true);
+        let [water_up, water_down] = Self::greedy_mesh_dir(c[7], c[10], &chunks, pos, 1, true);
+        let [water_north, water_south] = Self::greedy_mesh_dir(c[8], c[11], &chunks, pos, 2, true);
+
+        [
+            west, up, north, east, down, south,
+            water_west, water_up, water_north, water_east, water_down, water_south,
+        ]
     }
 
 
@@ -332,7 +483,8 @@ impl VoxelWorld {
         back_chunk_index: VoxelMeshIndex,
         chunks: &ChunkDataRef,
         pos: IVec3,
-        d: usize
+        d: usize,
+        water: bool,
     ) -> [Vec<ChunkQuadInstance>; 2] {
         // offsets of corners per vertex per direction
         const AO_OFFSETS: &[[[IVec3; 3]; 4]; 6] = &[
@@ -411,12 +563,19 @@ impl VoxelWorld {
 
                     // the mask is set to true if there is a visible face
                     // between two blocks, i.e. both aren't empty and both aren't blocks
-                    let (voxel, neg_d) = match (block_current.is_transparent(), block_compare.is_transparent()) {
+                    //
+                    // in the water pass water counts as a block so faces
+                    // between two water voxels or water and a solid block
+                    // are hidden, only the ones facing air are left
+                    let see_through = |voxel: Voxel| voxel.is_transparent() && !(water && voxel.is_water());
+                    let (voxel, neg_d) = match (see_through(block_current), see_through(block_compare)) {
                         (true, false) if !neigh => (block_compare, true),
                         (false, true) => (block_current, false),
                         (_, _) => (Voxel::Air, false),
                     };
 
+                    let voxel = if voxel.is_water() == water { voxel } else { Voxel::Air };
+
                     fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u32 {
                         if side1 && side2 {
                             return 0
@@ -558,6 +717,54 @@ impl VoxelWorld {
 }
 
 
+///
+/// How many milliseconds the chunk and mesh queues get this frame
+/// between them. Half of what `busy_time` leaves over of
+/// `target_frame_time` so loading speeds up while frames have room
+/// to spare and backs off to `MESH_BUDGET_MIN` once they run late.
+/// `busy_time` shouldn't include waiting on vsync or the frame
+/// limiter, otherwise every frame looks like it ran late
+///
+pub fn mesh_job_budget(busy_time: f32, target_frame_time: f32) -> u32 {
+    let headroom = (target_frame_time - busy_time) * 1000.0;
+    // negative headroom saturates to 0
+    ((headroom * 0.5) as u32).clamp(MESH_BUDGET_MIN, MESH_BUDGET_MAX)
+}
+
+
+///
+/// One frame's `mesh_job_budget` split between the chunk and mesh
+/// queues, each one gets an even share of whatever the ones before
+/// it left over
+///
+pub struct MeshBudget {
+    deadline: Instant,
+    queues_left: u32,
+}
+
+
+impl MeshBudget {
+    /// the four queues of `VoxelWorld::process` and `Chunker::process_mesh_jobs`
+    pub const QUEUES : u32 = 5;
+
+
+    pub fn new(millis: u32) -> Self {
+        Self {
+            deadline: Instant::now() + Duration::from_millis(millis as u64),
+            queues_left: Self::QUEUES,
+        }
+    }
+
+
+    pub fn next_share(&mut self) -> Duration {
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        let share = left / self.queues_left.max(1);
+        self.queues_left = self.queues_left.saturating_sub(1);
+        share
+    }
+}
+
+
 /// takes in a world position and returns a chunk position, chunk local position pair
 pub fn split_world_pos(pos: IVec3) -> (WorldChunkPos, IVec3) {
     let chunk_pos = pos.div_euclid(IVec3::splat(CHUNK_SIZE as i32));
@@ -573,3 +780,173 @@ pub fn split_chunk_pos(pos: IVec3) -> (IVec3, UVec3) {
     let region_local = pos.rem_euclid(IVec3::splat(REGION_SIZE as i32));
     (region, region_local.as_uvec3())
 }
+
+
+#[test]
+fn test_item_lands_on_chest() {
+    use crate::{constants::{DELTA_TICK, DROPPED_ITEM_SCALE, TEST_SITE}, directions::CardinalDirection, structures::strct::StructureKind};
+
+    let mut world = VoxelWorld::new();
+    let mut structures = Structures::new();
+
+    let chest_pos = TEST_SITE;
+    structures.place_at_test_site(&mut world, StructureKind::Chest, IVec3::ZERO, CardinalDirection::North);
+
+    let mut body = PhysicsBody {
+        position: chest_pos.as_dvec3() + DVec3::new(0.5, 3.0, 0.5),
+        velocity: Vec3::ZERO,
+        aabb_dims: Vec3::splat(DROPPED_ITEM_SCALE),
+        pass_through_walkable: false,
+        has_gravity: true,
+        on_ground: false,
+        in_water: false,
+    };
+
+    for _ in 0..240 {
+        world.move_physics_body(&structures, DELTA_TICK, &mut body);
+    }
+
+    let bottom = body.position.y - (DROPPED_ITEM_SCALE * 0.5) as f64;
+    let chest_top = (chest_pos.y + 1) as f64;
+    assert!(bottom >= chest_top, "item clipped into the chest: {bottom} < {chest_top}");
+    assert!(bottom - chest_top < 0.1, "item is floating above the chest: {bottom} > {chest_top}");
+}
+
+
+#[test]
+fn test_body_sinks_slowly_in_water() {
+    use crate::constants::{DELTA_TICK, TEST_SITE};
+
+    let mut world = VoxelWorld::new();
+    let structures = Structures::new();
+
+    let surface = TEST_SITE;
+    for y in 0..8 {
+        *world.get_voxel_mut(surface - IVec3::new(0, y, 0)) = Voxel::Water;
+    }
+
+    let mut body = PhysicsBody {
+        position: surface.as_dvec3() + DVec3::new(0.5, 0.5, 0.5),
+        velocity: Vec3::ZERO,
+        aabb_dims: Vec3::splat(0.5),
+        pass_through_walkable: false,
+        has_gravity: true,
+        on_ground: false,
+        in_water: false,
+    };
+
+    for _ in 0..60 {
+        world.move_physics_body(&structures, DELTA_TICK, &mut body);
+    }
+
+    assert!(body.in_water);
+    let sunk = surface.y as f64 + 0.5 - body.position.y;
+    assert!(sunk > 0.0 && sunk < 1.0, "sank {sunk} blocks in a second");
+}
+
+
+#[test]
+fn test_water_hides_inner_faces() {
+    use bytemuck::Zeroable;
+
+    let mut data = ChunkData::empty();
+    *data.get_mut(IVec3::new(4, 4, 4)) = Voxel::Water;
+    *data.get_mut(IVec3::new(5, 4, 4)) = Voxel::Water;
+
+    let mut chunks = [const { None }; 27];
+    chunks[13] = Some(Arc::new(data));
+
+    let mut free_list = FreeKVec::new();
+    let offsets = core::array::from_fn(|_| free_list.push(ChunkMeshFramedata::zeroed()));
+    let meshes = VoxelWorld::greedy_mesh(offsets, IVec3::ZERO, ChunkDataRef::new(chunks));
+
+    assert!(meshes[..6].iter().all(|x| x.is_empty()), "water ended up in the opaque faces");
+    // the face between the two voxels would be a second quad
+    assert!(meshes[6..].iter().all(|x| x.len() == 1));
+}
+
+
+#[test]
+fn test_fill_counts_changed_voxels() {
+    use crate::constants::TEST_SITE;
+
+    let mut world = VoxelWorld::new();
+
+    // straddles a chunk border
+    let min = TEST_SITE + IVec3::new(-1, 0, 30);
+    let max = TEST_SITE + IVec3::new(1, 2, 33);
+
+    assert_eq!(world.fill(min, max, Voxel::Stone), 3 * 3 * 4);
+    assert_eq!(world.get_voxel(TEST_SITE + IVec3::new(0, 1, 32)), Voxel::Stone);
+    assert_eq!(world.fill(min, max, Voxel::Stone), 0);
+    assert_eq!(world.fill(min, min, Voxel::Air), 1);
+}
+
+
+#[test]
+fn test_break_block_clears_every_chunk_a_structure_spans() {
+    use crate::{constants::TEST_SITE, directions::CardinalDirection, structures::strct::StructureKind};
+
+    let mut world = VoxelWorld::new();
+    let mut structures = Structures::new();
+    let mut entities = EntityMap::new();
+
+    // straddles a chunk border. there's no per chunk flag keeping
+    // chunks with structures loaded so all that's left to check is
+    // that none of the chunks keep any of it around
+    let pos = TEST_SITE + IVec3::new(-1, 0, -1);
+    let id = structures.place_at_test_site(&mut world, StructureKind::Assembler, IVec3::new(-1, 0, -1), CardinalDirection::North);
+
+    let blocks : Vec<IVec3> = {
+        let structure = structures.get(id);
+        structure.blocks().map(|x| structure.zero_zero() + x).collect()
+    };
+    let mut chunks : Vec<_> = blocks.iter().map(|x| split_world_pos(*x).0.0).collect();
+    chunks.sort_by_key(|x| x.to_array());
+    chunks.dedup();
+    assert!(chunks.len() > 1);
+
+    world.break_block(&mut structures, &mut entities, pos);
+
+    assert!(blocks.iter().all(|x| world.structure_blocks.get(x).is_none()));
+    assert!(blocks.iter().all(|x| world.get_voxel(*x) == Voxel::Air));
+}
+
+
+#[test]
+fn test_mesh_job_budget() {
+    let target = 1.0 / 60.0;
+
+    // fast frames get more time, slow ones back off
+    assert!(mesh_job_budget(0.004, target) > mesh_job_budget(0.012, target));
+    assert_eq!(mesh_job_budget(0.1, target), MESH_BUDGET_MIN);
+    assert_eq!(mesh_job_budget(0.0, 1.0), MESH_BUDGET_MAX);
+}
+
+
+#[test]
+fn test_site_is_empty() {
+    use crate::constants::TEST_SITE;
+
+    let mut world = VoxelWorld::new();
+
+    // covers everything the tests build around it
+    for x in -72..=132 {
+        for z in -2..=34 {
+            for y in -1..=4 {
+                let pos = TEST_SITE + IVec3::new(x, y, z);
+                assert!(world.get_voxel(pos).is_air(), "{pos} isn't empty");
+            }
+        }
+    }
+}
+
+
+#[test]
+fn test_mesh_budget_is_shared() {
+    let mut budget = MeshBudget::new(10);
+    let shares : Vec<Duration> = (0..MeshBudget::QUEUES).map(|_| budget.next_share()).collect();
+
+    assert!(shares[0] <= Duration::from_millis(2));
+    assert!(shares.iter().sum::<Duration>() <= Duration::from_millis(10));
+}
@@ -1,11 +1,11 @@
 use std::{cell::Cell, hash::Hash, i32, num::{NonZero, NonZeroI16, NonZeroU32}, rc::Rc, simd::{cmp::SimdPartialEq, u8x64}, sync::Arc};
 
-use glam::{DVec2, IVec2, IVec3, Vec3Swizzles};
-use libnoise::{Generator, ImprovedPerlin, Simplex, Source};
+use glam::{DVec2, DVec3, IVec2, IVec3, Vec3Swizzles};
+use libnoise::{Generator, Simplex, Source};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use sti::{hash::fxhash::FxHasher64, key::Key};
 
-use crate::{constants::{CHUNK_SIZE, CHUNK_SIZE_P3}, octree::NodeId, voxel_world::voxel::Voxel};
+use crate::{perlin::{ImprovedPerlin, LaneF64, LANES}, constants::{BIOME_BLEND_RADIUS, BIOME_SCALE, CAVE_DENSITY, CAVE_SCALE, CAVE_SURFACE_FADE, CHUNK_SIZE, CHUNK_SIZE_P3}, octree::NodeId, voxel_world::{voxel::Voxel, HeightLimits}};
 
 use super::mesh::ChunkFaceMesh;
 
@@ -14,6 +14,8 @@ pub struct Chunk {
     pub data: Option<Arc<ChunkData>>,
     pub is_dirty: bool,
     pub version: NonZeroU32,
+    /// the version of the chunk that was last written to disk
+    pub saved_version: Option<NonZeroU32>,
 }
 
 
@@ -32,55 +34,243 @@ pub enum MeshState {
 
 
 pub struct Noise {
-    perlin: ImprovedPerlin<2>,
+    perlin: ImprovedPerlin,
     simplex: Simplex<2>,
-    biomes: ImprovedPerlin<2>,
+    biomes: ImprovedPerlin,
+    biome_map: ImprovedPerlin,
+    caves: Simplex<3>,
+    /// mixed into the rng that places ore veins
+    seed: u64,
 }
 
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Biome {
+    Desert,
+    Plains,
+    Forest,
+    Rocky,
+}
+
+
+///
+/// The biome parameters at a position,
+/// blended with the surrounding biomes
+///
+#[derive(Debug, Clone, Copy)]
+pub struct BiomeSample {
+    /// the biome right at the position
+    pub biome: Biome,
+    pub height_amplitude: f64,
+    pub ore_density: f64,
+}
+
+
+impl Biome {
+    pub const ALL : [Biome; 4] = [Biome::Desert, Biome::Plains, Biome::Forest, Biome::Rocky];
+
+
+    pub fn height_amplitude(self) -> f64 {
+        match self {
+            Biome::Desert => 0.4,
+            Biome::Plains => 0.6,
+            Biome::Forest => 1.0,
+            Biome::Rocky => 1.6,
+        }
+    }
+
+
+    pub fn ore_density(self) -> f64 {
+        match self {
+            Biome::Desert => 0.6,
+            Biome::Plains => 1.0,
+            Biome::Forest => 0.8,
+            Biome::Rocky => 1.6,
+        }
+    }
+
+
+    ///
+    /// The voxel the top of the terrain is made of
+    /// and how many blocks deep it goes
+    ///
+    pub fn surface(self) -> (Voxel, i32) {
+        match self {
+            // there's no sand voxel yet so deserts are just deep dirt
+            Biome::Desert => (Voxel::Dirt, 6),
+            Biome::Plains => (Voxel::Dirt, 1),
+            Biome::Forest => (Voxel::Dirt, 4),
+            Biome::Rocky => (Voxel::Stone, 1),
+        }
+    }
+}
+
+
 impl Noise {
-    pub fn new(seed: u64) -> Self {
+    pub fn new(seed: u64, biome_seed: u64) -> Self {
         Self {
-            perlin: Source::improved_perlin(seed),
+            perlin: ImprovedPerlin::new(seed),
             simplex: Source::simplex(seed),
-            biomes: Source::improved_perlin(seed),
+            biomes: ImprovedPerlin::new(seed),
+            biome_map: ImprovedPerlin::new(biome_seed),
+            caves: Source::simplex(seed),
+            seed,
         }
     }
 
 
+    pub fn biome(&self, pos: DVec2) -> Biome {
+        let value = self.biome_map.sample([pos.x * BIOME_SCALE, pos.y * BIOME_SCALE]);
+        Self::biome_from_value(value)
+    }
+
+
+    ///
+    /// `biome` for `LANES` positions at once
+    ///
+    pub fn biome_lanes(&self, x: LaneF64, z: LaneF64) -> [Biome; LANES] {
+        let scale = LaneF64::splat(BIOME_SCALE);
+        let values = self.biome_map.sample_lanes(x * scale, z * scale);
+        values.to_array().map(Self::biome_from_value)
+    }
+
+
+    fn biome_from_value(value: f64) -> Biome {
+        let value = ((value + 1.0) * 0.5).clamp(0.0, 0.999);
+        Biome::ALL[(value * Biome::ALL.len() as f64) as usize]
+    }
+
+
+    ///
+    /// Averages the biome parameters over the surrounding
+    /// `BIOME_BLEND_RADIUS` blocks so there are no hard seams
+    ///
+    pub fn biome_sample(&self, pos: DVec2) -> BiomeSample {
+        let mut height_amplitude = 0.0;
+        let mut ore_density = 0.0;
+        let mut count = 0.0;
+
+        for x in -1..=1 {
+            for z in -1..=1 {
+                let offset = DVec2::new(x as f64, z as f64) * BIOME_BLEND_RADIUS as f64;
+                let biome = self.biome(pos + offset);
+                height_amplitude += biome.height_amplitude();
+                ore_density += biome.ore_density();
+                count += 1.0;
+            }
+        }
+
+        BiomeSample {
+            biome: self.biome(pos),
+            height_amplitude: height_amplitude / count,
+            ore_density: ore_density / count,
+        }
+    }
+
+
+    ///
+    /// `biome_sample` for `LANES` positions at once, adds up the
+    /// surrounding biomes in the same order so it comes out the same
+    ///
+    pub fn biome_sample_lanes(&self, x: LaneF64, z: LaneF64) -> [BiomeSample; LANES] {
+        let mut height_amplitude = [0.0; LANES];
+        let mut ore_density = [0.0; LANES];
+        let mut count = 0.0;
+
+        for ox in -1..=1 {
+            for oz in -1..=1 {
+                let offset = DVec2::new(ox as f64, oz as f64) * BIOME_BLEND_RADIUS as f64;
+                let biomes = self.biome_lanes(x + LaneF64::splat(offset.x), z + LaneF64::splat(offset.y));
+                for lane in 0..LANES {
+                    height_amplitude[lane] += biomes[lane].height_amplitude();
+                    ore_density[lane] += biomes[lane].ore_density();
+                }
+                count += 1.0;
+            }
+        }
+
+        let biomes = self.biome_lanes(x, z);
+        std::array::from_fn(|lane| BiomeSample {
+            biome: biomes[lane],
+            height_amplitude: height_amplitude[lane] / count,
+            ore_density: ore_density[lane] / count,
+        })
+    }
+
+
+    ///
+    /// Whether the voxel at `pos` gets carved out into a cave,
+    /// `depth` is how many blocks below the surface it is
+    ///
+    pub fn is_cave(&self, pos: IVec3, depth: i32) -> bool {
+        // squashed vertically so tunnels run mostly sideways
+        let pos = pos.as_dvec3() * DVec3::new(CAVE_SCALE, CAVE_SCALE * 2.0, CAVE_SCALE);
+
+        // tunnels follow the lines where two noise fields both cross zero
+        let a = self.caves.sample([pos.x, pos.y, pos.z]);
+        let b = self.caves.sample([pos.x + 512.0, pos.y + 512.0, pos.z + 512.0]);
+
+        // and get thinner towards the surface so it doesn't end up full of holes
+        let fade = (depth as f64 / CAVE_SURFACE_FADE as f64).clamp(0.0, 1.0);
+        a * a + b * b < CAVE_DENSITY * fade
+    }
+
+
     pub fn sample(&self, pos: DVec2) -> f64 {
         let x = pos.x + 10_000.0;
         let z = pos.y + 10_000.0;
+
         let biome = self.biomes.sample([x * 0.0055, z * 0.0055]);
-        let biome = (biome + 1.0) * 0.5;
-        let giant_mountain_height = {
-            let base_scale = 0.0003;
-            let detail_scale = 0.02;
+        let giant_mountain = self.perlin.sample([x * GIANT_MOUNTAIN_SCALE, z * GIANT_MOUNTAIN_SCALE]);
+        let mountain = self.perlin.sample([x * MOUNTAIN_SCALE, z * MOUNTAIN_SCALE]);
+        let plateau = self.perlin.sample([x * PLATEAU_SCALE, z * PLATEAU_SCALE]);
 
-            let base = self.perlin.sample([x * base_scale, z * base_scale]) * 180.0 + 40.0;
+        self.height(x, z, biome, giant_mountain, mountain, plateau)
+    }
 
-            let detail = self.simplex.sample([x * detail_scale + 1337.0, z * detail_scale + 420.0]) * 8.0;
-            base + detail
-        };
 
+    ///
+    /// `sample` for `LANES` positions at once, the perlin noise
+    /// is what dominates so that's what gets sampled together
+    ///
+    pub fn sample_lanes(&self, x: LaneF64, z: LaneF64) -> [f64; LANES] {
+        let x = x + LaneF64::splat(10_000.0);
+        let z = z + LaneF64::splat(10_000.0);
+
+        let scaled = |scale: f64| (x * LaneF64::splat(scale), z * LaneF64::splat(scale));
+
+        let biome = { let (x, z) = scaled(0.0055); self.biomes.sample_lanes(x, z) };
+        let giant_mountain = { let (x, z) = scaled(GIANT_MOUNTAIN_SCALE); self.perlin.sample_lanes(x, z) };
+        let mountain = { let (x, z) = scaled(MOUNTAIN_SCALE); self.perlin.sample_lanes(x, z) };
+        let plateau = { let (x, z) = scaled(PLATEAU_SCALE); self.perlin.sample_lanes(x, z) };
+
+        std::array::from_fn(|lane| self.height(x[lane], z[lane], biome[lane], giant_mountain[lane], mountain[lane], plateau[lane]))
+    }
 
-        let mountain_height = {
-            let base_scale = 0.003;
-            let detail_scale = 0.02;
 
-            let base = self.perlin.sample([x * base_scale, z * base_scale]) * 10.0;
+    ///
+    /// Mixes the already sampled perlin noise for `sample` with
+    /// the finer detail on top
+    ///
+    fn height(&self, x: f64, z: f64, biome: f64, giant_mountain: f64, mountain: f64, plateau: f64) -> f64 {
+        let detail_scale = 0.02;
+        let detail = self.simplex.sample([x * detail_scale + 1337.0, z * detail_scale + 420.0]);
 
-            let detail = self.simplex.sample([x * detail_scale + 1337.0, z * detail_scale + 420.0]) * 4.0;
-            base + detail
+        let biome = (biome + 1.0) * 0.5;
+        let giant_mountain_height = {
+            let base = giant_mountain * 180.0 + 40.0;
+            base + detail * 8.0
         };
 
-        let plateau_height = {
-            let base_scale = 0.005;
-            let detail_scale = 0.02;
 
-            let base = self.perlin.sample([x * base_scale, z * base_scale]) * 1.5;
+        let mountain_height = {
+            let base = mountain * 10.0;
+            base + detail * 4.0
+        };
 
-            let detail = self.simplex.sample([x * detail_scale + 1337.0, z * detail_scale + 420.0]) * 0.5;
-            base + detail
+        let plateau_height = {
+            let base = plateau * 1.5;
+            base + detail * 0.5
         };
 
         let height = if biome < 0.5 {
@@ -97,6 +287,11 @@ impl Noise {
 }
 
 
+const GIANT_MOUNTAIN_SCALE : f64 = 0.0003;
+const MOUNTAIN_SCALE : f64 = 0.003;
+const PLATEAU_SCALE : f64 = 0.005;
+
+
 fn lerp(a: f64, b: f64, t: f64) -> f64 {
     a * (1.0 - t) + b * t
 }
@@ -113,26 +308,37 @@ impl Chunk {
             data: None,
             is_dirty: false,
             version: NonZero::new(1).unwrap(),
+            saved_version: None,
         }
     }
 
 
-    pub fn generate(pos: IVec3, noise: &Noise) -> Chunk {
+    pub fn generate(pos: IVec3, noise: &Noise, limits: HeightLimits) -> Chunk {
         let mut data = ChunkData::empty();
 
         let mut height_map = [[0; CHUNK_SIZE]; CHUNK_SIZE];
+        let mut surface_map = [[(Voxel::Dirt, 1); CHUNK_SIZE]; CHUNK_SIZE];
         let mut max_height = i32::MIN;
+        // a few columns at a time, see `Noise::sample_lanes`
         for x in 0..CHUNK_SIZE {
-            for z in 0..CHUNK_SIZE {
+            for z in (0..CHUNK_SIZE).step_by(LANES) {
                 let global_pos = (pos * CHUNK_SIZE as i32).xz() + IVec2::new(x as i32, z as i32);
+                let xs = LaneF64::splat(global_pos.x as f64);
+                let zs = LaneF64::from_array(std::array::from_fn(|lane| (global_pos.y + lane as i32) as f64));
 
-                let height = noise.sample(global_pos.as_dvec2());
-                let height = height as i32;
+                let biomes = noise.biome_sample_lanes(xs, zs);
+                let heights = noise.sample_lanes(xs, zs);
 
-                max_height = max_height.max(height);
+                for lane in 0..LANES {
+                    let height = heights[lane] * biomes[lane].height_amplitude;
+                    let height = height as i32;
 
+                    max_height = max_height.max(height);
 
-                height_map[x][z] = height;
+
+                    height_map[x][z + lane] = height;
+                    surface_map[x][z + lane] = biomes[lane].biome.surface();
+                }
             }
         }
 
@@ -144,14 +350,18 @@ impl Chunk {
                     for y in 0..CHUNK_SIZE {
                         let chunk_local_position = IVec3::new(x as i32, y as i32, z as i32);
                         let global_position = pos * CHUNK_SIZE as i32 + chunk_local_position;
+                        if !limits.contains(global_position.y) { continue }
 
                         let height = height_map[x][z];
+                        let (surface, surface_depth) = surface_map[x][z];
 
                         let kind;
-                        if global_position.y == height {
-                            kind = Voxel::Dirt;
-                        } else if global_position.y > height {
+                        if global_position.y > height {
                             continue
+                        } else if noise.is_cave(global_position, height - global_position.y) {
+                            continue
+                        } else if global_position.y > height - surface_depth {
+                            kind = surface;
                         } else {
                             kind = Voxel::Stone;
                         }
@@ -162,11 +372,15 @@ impl Chunk {
             }
 
             let mut hasher = FxHasher64::new();
+            noise.seed.hash(&mut hasher);
             pos.hash(&mut hasher);
             let mut rng = rand::rngs::SmallRng::seed_from_u64(hasher.hash);
 
 
-            let vein_count = rng.random_range(64..128);
+            let centre = (pos * CHUNK_SIZE as i32).xz() + IVec2::splat(CHUNK_SIZE as i32 / 2);
+            let ore_density = noise.biome_sample(centre.as_dvec2()).ore_density;
+
+            let vein_count = (rng.random_range(64..128) as f64 * ore_density) as usize;
             let mut buff : Vec<IVec3> = vec![];
             let mut i = 0;
             while i < vein_count {
@@ -218,6 +432,7 @@ impl Chunk {
             data: if skip || data.is_empty() { None } else { Some(Arc::new(data)) },
             is_dirty: true,
             version: NonZero::new(1).unwrap(),
+            saved_version: None,
         };
         chunk
     }
@@ -298,6 +513,43 @@ impl ChunkData {
     }
 
 
+    ///
+    /// Merges every 2x2x2 cell into a single voxel, a cell
+    /// that's at least half solid becomes its most common
+    /// solid voxel and anything less becomes air
+    ///
+    pub fn coarsened(&self) -> ChunkData {
+        let mut data = ChunkData::empty();
+
+        for z in (0..CHUNK_SIZE).step_by(2) {
+            for y in (0..CHUNK_SIZE).step_by(2) {
+                for x in (0..CHUNK_SIZE).step_by(2) {
+                    let mut counts : [(Voxel, u32); 8] = [(Voxel::Air, 0); 8];
+                    let mut solid = 0;
+
+                    for i in 0..8 {
+                        let voxel = self.get_usize(x + (i & 1), y + ((i >> 1) & 1), z + ((i >> 2) & 1));
+                        if voxel.is_transparent() { continue }
+
+                        solid += 1;
+                        let slot = counts.iter().position(|x| x.0 == voxel || x.1 == 0).unwrap();
+                        counts[slot] = (voxel, counts[slot].1 + 1);
+                    }
+
+                    if solid < 4 { continue }
+
+                    let voxel = counts.iter().max_by_key(|x| x.1).unwrap().0;
+                    for i in 0..8 {
+                        *data.get_mut_usize(x + (i & 1), y + ((i >> 1) & 1), z + ((i >> 2) & 1)) = voxel;
+                    }
+                }
+            }
+        }
+
+        data
+    }
+
+
     pub fn as_bytes(&self) -> &[u8; CHUNK_SIZE_P3] {
         unsafe {
             core::mem::transmute::<_, &[u8; CHUNK_SIZE_P3]>
@@ -342,3 +594,55 @@ impl core::fmt::Debug for ChunkData {
 unsafe impl Send for Chunk {}
 unsafe impl Sync for Chunk {}
 
+
+
+#[test]
+fn test_generated_chunk_is_stable() {
+    use crate::constants::{BIOME_SEED, TERRAIN_SEED};
+
+    let noise = Noise::new(TERRAIN_SEED, TERRAIN_SEED ^ BIOME_SEED);
+    let chunk = Chunk::generate(IVec3::new(1, -2, 3), &noise, HeightLimits::default());
+    let data = chunk.data.unwrap();
+
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut air = 0;
+    for &voxel in data.data.iter() {
+        // ore veins only ever replace stone and are placed with a
+        // hashed rng so they're left out to only check the terrain
+        let voxel = match voxel {
+            Voxel::Copper | Voxel::Iron | Voxel::Coal => Voxel::Stone,
+            _ => voxel,
+        };
+
+        if voxel == Voxel::Air { air += 1 }
+        hash = (hash ^ voxel as u64).wrapping_mul(0x100000001b3);
+    }
+
+    assert_eq!(air, 2320, "the chunk is well below the surface so all the air is caves");
+    assert_eq!(hash, 0x8b6594311cb3e41d);
+}
+
+
+#[test]
+fn test_noise_lanes_match_scalar() {
+    use crate::constants::{BIOME_SEED, TERRAIN_SEED};
+
+    let noise = Noise::new(TERRAIN_SEED, TERRAIN_SEED ^ BIOME_SEED);
+
+    for i in -64..64 {
+        let x = LaneF64::splat(i as f64 * 37.0);
+        let z = LaneF64::from_array(std::array::from_fn(|lane| (i * 53 + lane as i32) as f64));
+
+        let heights = noise.sample_lanes(x, z);
+        let biomes = noise.biome_sample_lanes(x, z);
+        for lane in 0..LANES {
+            let pos = DVec2::new(x[lane], z[lane]);
+            let biome = noise.biome_sample(pos);
+
+            assert_eq!(heights[lane].to_bits(), noise.sample(pos).to_bits());
+            assert_eq!(biomes[lane].biome, biome.biome);
+            assert_eq!(biomes[lane].height_amplitude.to_bits(), biome.height_amplitude.to_bits());
+            assert_eq!(biomes[lane].ore_density.to_bits(), biome.ore_density.to_bits());
+        }
+    }
+}
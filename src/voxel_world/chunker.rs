@@ -1,16 +1,17 @@
-use std::{cell::Cell, collections::{HashMap, HashSet}, num::NonZeroU32, rc::Rc, sync::{atomic::AtomicU32, mpsc::{Receiver, Sender}, Arc}, time::Instant};
+use std::{cell::Cell, cmp::Reverse, collections::{HashMap, HashSet}, num::NonZeroU32, rc::Rc, sync::{atomic::AtomicU32, mpsc::{Receiver, Sender}, Arc}, time::{Duration, Instant}};
 
 use bytemuck::Zeroable;
 use glam::{IVec3, UVec3};
 use rand::seq::IndexedRandom;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use save_format::byte::{ByteReader, ByteWriter};
 use sti::key::Key;
 use tracing::{error, info, trace, warn};
 use wgpu::util::StagingBelt;
 
-use crate::{constants::{CHUNK_SIZE, CHUNK_SIZE_I32, CHUNK_SIZE_P3, REGION_SIZE, REGION_SIZE_P3}, free_list::FreeKVec, octree::{Leaf, MeshOctree}, renderer::{gpu_allocator::GPUAllocator, ssbo::SSBO}, voxel_world::voxel::Voxel};
+use crate::{constants::{BIOME_SEED, CHUNK_COMPRESSION_LEVEL, CHUNK_FILE_MAGIC, CHUNK_FILE_VERSION, CHUNK_SIZE, CHUNK_SIZE_I32, CHUNK_SIZE_P3, REGION_SIZE, REGION_SIZE_P3, SAVES_DIR, DEFAULT_WORLD_NAME}, free_list::FreeKVec, octree::{Leaf, MeshOctree}, renderer::{gpu_allocator::GPUAllocator, ssbo::SSBO}, voxel_world::voxel::Voxel};
 
-use super::{chunk::{Chunk, ChunkData, Noise}, mesh::{ChunkDataRef, ChunkFaceMesh, ChunkMeshFramedata, ChunkMeshes, ChunkQuadInstance, VoxelMeshIndex}, VoxelWorld, SURROUNDING_OFFSETS};
+use super::{chunk::{Chunk, ChunkData, Noise}, mesh::{ChunkDataRef, ChunkFaceMesh, ChunkMeshFramedata, ChunkMeshes, ChunkQuadInstance, VoxelMeshIndex}, HeightLimits, VoxelWorld, SURROUNDING_OFFSETS};
 
 pub struct Chunker {
     regions: sti::hash::HashMap<RegionPos, Region>,
@@ -20,18 +21,36 @@ pub struct Chunker {
     chunk_reciever: Receiver<ChunkMPSC>,
     chunk_active_jobs: u32,
     pub chunk_save_jobs: Arc<AtomicU32>,
+    /// the voxel data of chunks that got unloaded while saves were
+    /// still being written, loading one of them again uses this as
+    /// the file on disk might not have caught up yet
+    unloaded_while_saving: HashMap<WorldChunkPos, Option<Arc<ChunkData>>>,
 
     mesh_load_queue: HashSet<WorldChunkPos>,
     mesh_active_jobs: HashSet<WorldChunkPos>,
     mesh_unload_queue: HashSet<WorldChunkPos>,
     mesh_sender: Sender<MeshMPSC>,
     mesh_reciever: Receiver<MeshMPSC>,
+    /// meshed on the main thread the next time the mesh
+    /// queue is processed, see `mesh_immediately`
+    immediate_mesh: Option<WorldChunkPos>,
+    /// chunks that need a coarse mesh, see `queue_lod_mesh`
+    lod_load_queue: HashSet<WorldChunkPos>,
+    lod_active_jobs: HashSet<WorldChunkPos>,
 
     noise: Arc<Noise>,
+    seed: u64,
+    /// the directory of the world chunks are loaded from and saved to
+    save_dir: Arc<str>,
+    /// chunks entirely outside of these are never generated
+    pub limits: HeightLimits,
+    /// the chunk the player is in, queued chunks closest
+    /// to it get loaded and meshed first
+    pub focus: WorldChunkPos,
 }
 
 type ChunkMPSC = (WorldChunkPos, Chunk);
-type MeshMPSC = (WorldChunkPos, [VoxelMeshIndex; 6], [Vec<ChunkQuadInstance>; 6], NonZeroU32);
+type MeshMPSC = (WorldChunkPos, [VoxelMeshIndex; 12], [Vec<ChunkQuadInstance>; 12], NonZeroU32, bool);
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub struct RegionPos(pub IVec3);
@@ -51,9 +70,11 @@ pub struct Region {
 
 pub struct MeshTaskData {
     version: NonZeroU32,
-    offsets: [VoxelMeshIndex; 6],
+    offsets: [VoxelMeshIndex; 12],
     chunks: ChunkDataRef,
     pos: WorldChunkPos,
+    /// whether this builds the coarse mesh instead of the full one
+    lod: bool,
 }
 
 
@@ -76,6 +97,36 @@ pub enum MeshEntry {
 }
 
 
+///
+/// A loaded chunk whose mesh doesn't match its voxel data,
+/// see `Chunker::mesh_desyncs`
+///
+#[derive(Debug)]
+pub struct MeshDesync {
+    pub pos: WorldChunkPos,
+    pub chunk_version: NonZeroU32,
+    /// `None` if the chunk was never meshed
+    pub mesh_version: Option<NonZeroU32>,
+    pub is_queued: bool,
+    pub is_meshing: bool,
+}
+
+
+///
+/// Totals over every uploaded chunk mesh, see `Chunker::mesh_stats`
+///
+#[derive(Debug, Default)]
+pub struct MeshStats {
+    /// chunks with at least one non-empty face
+    pub chunks: usize,
+    pub quads: usize,
+    pub lod_quads: usize,
+    /// what the meshes take up in the instance allocator
+    /// which rounds every allocation up to a power of two
+    pub allocated_bytes: usize,
+}
+
+
 pub enum GetChunk<'a> {
     Chunk(&'a mut Chunk),
 
@@ -86,7 +137,7 @@ pub enum GetChunk<'a> {
 
 
 impl Chunker {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
         let (cs, cr) = std::sync::mpsc::channel();
         let (ms, mr) = std::sync::mpsc::channel();
 
@@ -98,34 +149,111 @@ impl Chunker {
             chunk_reciever: cr,
             chunk_active_jobs: 0,
             chunk_save_jobs: Arc::new(AtomicU32::new(0)),
+            unloaded_while_saving: HashMap::new(),
 
             mesh_load_queue: HashSet::new(),
             mesh_unload_queue: HashSet::new(),
             mesh_sender: ms,
             mesh_reciever: mr,
             mesh_active_jobs: HashSet::new(),
+            immediate_mesh: None,
+            lod_load_queue: HashSet::new(),
+            lod_active_jobs: HashSet::new(),
+
+            noise: Arc::new(Noise::new(seed, seed ^ BIOME_SEED)),
+            seed,
+            save_dir: Arc::from(format!("{SAVES_DIR}/{DEFAULT_WORLD_NAME}")),
+            limits: HeightLimits::default(),
+            focus: WorldChunkPos(IVec3::ZERO),
+        }
+    }
+
+
+    ///
+    /// Points the chunker at the world saved in `save_dir`
+    ///
+    /// This should happen before any chunks are loaded as
+    /// already loaded ones are kept as they are
+    ///
+    pub fn set_world(&mut self, save_dir: &str, seed: u64) {
+        self.noise = Arc::new(Noise::new(seed, seed ^ BIOME_SEED));
+        self.seed = seed;
+        self.save_dir = Arc::from(save_dir);
+    }
+
 
-            noise: Arc::new(Noise::new(69696969)),
+    ///
+    /// Changes the seed chunks are generated from. Loaded chunks that
+    /// were only ever generated get regenerated with it, edited ones
+    /// and anything that's been written to disk keep their terrain.
+    /// Returns how many chunks were regenerated
+    ///
+    pub fn set_seed(&mut self, seed: u64) -> usize {
+        self.noise = Arc::new(Noise::new(seed, seed ^ BIOME_SEED));
+        self.seed = seed;
+
+        // chunks already being generated still use the old seed
+        while self.chunk_active_jobs_len() > 0 { self.process_chunk_jobs(Duration::from_millis(512)); }
+
+        let stale = self.iter_chunks()
+            .filter(|(pos, chunk, _)| self.limits.contains_chunk(pos.0.y) && matches!(chunk,
+                    ChunkEntry::Loaded(chunk) if !chunk.is_dirty && chunk.saved_version.is_none()))
+            .map(|(pos, ..)| pos)
+            .collect::<Vec<_>>();
+
+        let noise = &*self.noise;
+        let limits = self.limits;
+        let regenerated = stale.into_par_iter()
+            .map(|pos| (pos, Chunk::generate(pos.0, noise, limits)))
+            .collect::<Vec<_>>();
+
+        let count = regenerated.len();
+        for (pos, regenerated) in regenerated {
+            let ChunkEntry::Loaded(chunk) = self.get_chunk_entry(pos)
+            else { unreachable!() };
+
+            chunk.data = regenerated.data;
         }
+
+        // the neighbours of a regenerated chunk need new meshes too
+        self.remesh_all();
+        count
     }
 
     pub fn process_mesh_queue(
         &mut self,
-        timeout: u32,
+        timeout: Duration,
         framedata: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>,
     ) {
-        let timeout = timeout as u128;
         let start = Instant::now();
 
+        if let Some(pos) = self.immediate_mesh.take() {
+            let mut batch = vec![];
+            if self.try_prepare_mesh_task(framedata, &mut batch, pos) {
+                self.mesh_load_queue.remove(&pos);
+            }
+
+            // the result goes through the same channel as the async
+            // jobs so `process_mesh_jobs` picks it up this frame
+            for item in batch {
+                let mesh = VoxelWorld::greedy_mesh(item.offsets, item.pos.0, item.chunks);
+                if let Err(e) = self.mesh_sender.send((item.pos, item.offsets, mesh, item.version, false)) {
+                    error!("immediate-mesh: {e}");
+                }
+            }
+        }
+
         let mut batch = vec![];
 
         let mut load_queue = core::mem::take(&mut self.mesh_load_queue);
-        let mut iter = load_queue.iter();
+        let mut by_distance = load_queue.iter().copied().collect::<Vec<_>>();
+        by_distance.sort_unstable_by_key(|&x| self.distance_to_focus(x));
+        let mut iter = by_distance.into_iter();
 
         loop {
-            if start.elapsed().as_millis() > timeout { break; }
+            if start.elapsed() > timeout { break; }
 
-            let Some(&chunk_pos) = iter.next()
+            let Some(chunk_pos) = iter.next()
             else { break };
 
             let did_succeed = self.try_prepare_mesh_task(
@@ -138,7 +266,6 @@ impl Chunker {
             if !did_succeed { warn!("failed to spawn mesh task for chunk at '{}'", chunk_pos.0); continue }
 
             load_queue.remove(&chunk_pos);
-            iter = load_queue.iter();
 
             if batch.len() == 32 {
                 self.spawn_mesh_task(batch);
@@ -150,15 +277,71 @@ impl Chunker {
         self.spawn_mesh_task(batch);
 
         self.mesh_load_queue = load_queue;
+
+
+        // coarse meshes only get whatever time is left over
+        let mut batch = vec![];
+        let mut lod_queue = core::mem::take(&mut self.lod_load_queue);
+        let mut remove_list = vec![];
+
+        for &chunk_pos in lod_queue.iter() {
+            if start.elapsed() > timeout { break; }
+
+            // if it fails it'll get queued again the next
+            // time the chunk is drawn past the lod distance
+            self.try_prepare_lod_task(framedata, &mut batch, chunk_pos);
+            remove_list.push(chunk_pos);
+
+            if batch.len() == 32 {
+                self.spawn_mesh_task(batch);
+                batch = vec![];
+            }
+        }
+
+        remove_list.iter().for_each(|x| { lod_queue.remove(x); });
+        self.spawn_mesh_task(batch);
+
+        self.lod_load_queue = lod_queue;
+    }
+
+
+    ///
+    /// How far `pos` is from `focus`, squared
+    ///
+    fn distance_to_focus(&self, pos: WorldChunkPos) -> i64 {
+        (pos.0.as_i64vec3() - self.focus.0.as_i64vec3()).length_squared()
+    }
+
+
+    ///
+    /// Queues a coarse mesh to be built for the chunk at `pos`
+    ///
+    /// The full mesh keeps getting drawn until it's done
+    ///
+    pub fn queue_lod_mesh(&mut self, pos: WorldChunkPos) {
+        if self.lod_active_jobs.contains(&pos) { return }
+        self.lod_load_queue.insert(pos);
+    }
+
+
+    ///
+    /// Meshes the chunk at `pos` synchronously on the next
+    /// `process_mesh_queue` instead of on a worker thread so
+    /// edits show up on the same frame.
+    ///
+    /// Only one chunk is kept, calling this again replaces it
+    ///
+    pub fn mesh_immediately(&mut self, pos: WorldChunkPos) {
+        self.immediate_mesh = Some(pos);
     }
 
+
     pub fn process_mesh_unload_queue(
         &mut self,
-        timeout: u32,
+        timeout: Duration,
         framedata: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>,
         instance_allocator: &mut GPUAllocator<ChunkQuadInstance>,
     ) {
-        let timeout = timeout as u128;
         let start = Instant::now();
 
         let mut unload_queue = core::mem::take(&mut self.mesh_unload_queue);
@@ -166,7 +349,7 @@ impl Chunker {
         let mut iter = unload_queue.iter();
 
         loop {
-            if start.elapsed().as_millis() > timeout { break; }
+            if start.elapsed() > timeout { break; }
 
             let Some(&chunk_pos) = iter.next()
             else { break };
@@ -187,14 +370,10 @@ impl Chunker {
                     if let Some(meshes) = chunk_meshes.meshes.take() {
                         *mesh = MeshEntry::None;
                         let leaf = region.octree.get_mut(meshes);
-                        let prev_meshes = &mut leaf.mesh;
+                        free_meshes(&mut leaf.mesh, framedata, instance_allocator);
 
-                        for mesh in prev_meshes {
-                            let Some(mesh) = mesh.take()
-                            else { continue };
-
-                            framedata.remove(mesh.chunk_mesh_data_index);
-                            instance_allocator.free(mesh.quads);
+                        if let Some(mut lod) = leaf.lod.take() {
+                            free_meshes(&mut lod, framedata, instance_allocator);
                         }
                     } else {
                         *mesh = MeshEntry::None;
@@ -213,22 +392,33 @@ impl Chunker {
     }
 
 
-    pub fn process_chunk_queue(&mut self, timeout: u32) {
-        let timeout = timeout as u128;
+    pub fn process_chunk_queue(&mut self, timeout: Duration) {
         let start = Instant::now();
 
+        // popped from the back so the closest go last
+        let mut queue = core::mem::take(&mut self.chunk_load_queue);
+        queue.sort_unstable_by_key(|&x| Reverse(self.distance_to_focus(x)));
+        self.chunk_load_queue = queue;
+
         loop {
-            if start.elapsed().as_millis() > timeout { break; }
+            if start.elapsed() > timeout { break; }
 
             let Some(chunk_pos) = self.chunk_load_queue.pop()
             else { break };
 
+            if let Some(chunk) = self.take_unloaded_while_saving(chunk_pos) {
+                self.register_chunk(chunk_pos, chunk);
+                continue;
+            }
+
             let noise = self.noise.clone();
+            let save_dir = self.save_dir.clone();
+            let limits = self.limits;
             let sender = self.chunk_sender.clone();
             self.chunk_active_jobs += 1;
 
             rayon::spawn(move || {
-                let result = generate_chunk(chunk_pos, &noise);
+                let result = generate_chunk(chunk_pos, &noise, limits, &save_dir);
 
                 if let Err(e) = sender.send((chunk_pos, result)) {
                     error!("chunk-generation: {e}");
@@ -238,11 +428,11 @@ impl Chunker {
     }
 
 
-    pub fn process_chunk_jobs(&mut self, timeout: u32) {
+    pub fn process_chunk_jobs(&mut self, timeout: Duration) {
         let start = Instant::now();
 
         loop {
-            if start.elapsed().as_millis() as u32 > timeout { break; }
+            if start.elapsed() > timeout { break; }
 
             let Ok((chunk_pos, chunk)) = self.chunk_reciever.try_recv()
             else { break; };
@@ -255,7 +445,7 @@ impl Chunker {
 
     pub fn process_mesh_jobs(
         &mut self,
-        timeout: u32,
+        timeout: Duration,
 
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
@@ -267,12 +457,16 @@ impl Chunker {
 
         let start = Instant::now();
         loop {
-            if start.elapsed().as_millis() as u32 > timeout { break; }
+            if start.elapsed() > timeout { break; }
 
-            let Ok((chunk_pos, offsets, result, version)) = self.mesh_reciever.try_recv()
+            let Ok((chunk_pos, offsets, result, version, lod)) = self.mesh_reciever.try_recv()
             else { break; };
 
-            assert!(self.mesh_active_jobs.remove(&chunk_pos));
+            if lod {
+                assert!(self.lod_active_jobs.remove(&chunk_pos));
+            } else {
+                assert!(self.mesh_active_jobs.remove(&chunk_pos));
+            }
 
             let region = self.get_region_or_insert(chunk_pos.region());
 
@@ -284,8 +478,9 @@ impl Chunker {
 
             let chunk_version = match chunk_entry {
                 ChunkEntry::Loaded(chunk) => {
-                    if version.get() < chunk.version.get() {
+                    if !lod && version.get() < chunk.version.get() {
                         warn!("outdated mesh '{chunk_pos:?}'");
+                        offsets.iter().for_each(|&x| { free_list.remove(x); });
                         self.mesh_load_queue.insert(chunk_pos);
                         continue;
                     }
@@ -296,43 +491,48 @@ impl Chunker {
                 _ => None,
             };
 
-            let mut data = [const { None }; 6];
-            for i in 0..6 {
-                if result[i].is_empty() { continue };
+            if lod {
+                // the coarse mesh is only kept if it was built from
+                // the same voxel data as the full mesh it's replacing
+                let leaf = match mesh_entry {
+                    MeshEntry::Loaded(ChunkMeshes { meshes: Some(meshes), version: mesh_version })
+                        if *mesh_version == version && chunk_version == Some(version)
+                        => Some(*meshes),
+                    _ => None,
+                };
+
+                let Some(leaf) = leaf
+                else {
+                    trace!("discarded an outdated lod mesh at '{}'", chunk_pos.0);
+                    offsets.iter().for_each(|&x| { free_list.remove(x); });
+                    continue;
+                };
 
-                let mesh = ChunkFaceMesh::new(
-                    belt, encoder, device, instance_allocator,
-                    &result[i], offsets[i]
+                let data = upload_meshes(
+                    chunk_pos, offsets, &result,
+                    device, encoder, belt, instance_allocator, free_list, gpu_mesh_data,
                 );
 
-
-                let index = mesh.chunk_mesh_data_index.usize();
-                if index >= gpu_mesh_data.len() {
-                    warn!("resizing ssbo");
-                    gpu_mesh_data.resize(device, encoder, (gpu_mesh_data.len() * 2).max(index+1));
+                let leaf = region.octree.get_mut(leaf);
+                if let Some(mut prev) = leaf.lod.replace(data) {
+                    free_meshes(&mut prev, free_list, instance_allocator);
                 }
 
-                gpu_mesh_data.write(
-                    belt,
-                    encoder,
-                    device,
-                    index,
-                    &[ChunkMeshFramedata { offset: chunk_pos.0, normal: i as u32 }]
-                );
-
-
-
-                data[i] = Some(mesh);
+                continue;
             }
 
-            let data = data;
+            let mut data = upload_meshes(
+                chunk_pos, offsets, &result,
+                device, encoder, belt, instance_allocator, free_list, gpu_mesh_data,
+            );
+
             let is_data_some = data.iter().any(|x| x.is_some());
 
             match mesh_entry {
                 MeshEntry::None => {
                     let meshes = 
                         if !is_data_some { None }
-                        else { Some(region.octree.insert(chunk_pos.chunk(), Leaf { mesh: data })) };
+                        else { Some(region.octree.insert(chunk_pos.chunk(), Leaf { mesh: data, lod: None })) };
 
                     let value = ChunkMeshes { meshes, version };
                     *mesh_entry = MeshEntry::Loaded(value);
@@ -342,6 +542,7 @@ impl Chunker {
                 MeshEntry::Loaded(chunk_meshes) => {
                     if chunk_meshes.version > version {
                         warn!("mesh discarded because it was outdated");
+                        free_meshes(&mut data, free_list, instance_allocator);
                         continue;
                     }
 
@@ -349,13 +550,11 @@ impl Chunker {
 
                     if let Some(meshes) = chunk_meshes.meshes {
                         let prev_meshes = region.octree.get_mut(meshes);
+                        free_meshes(&mut prev_meshes.mesh, free_list, instance_allocator);
 
-                        for mesh in prev_meshes.mesh.iter_mut() {
-                            let Some(mesh) = mesh.take()
-                            else { continue };
-
-                            free_list.remove(mesh.chunk_mesh_data_index);
-                            instance_allocator.free(mesh.quads);
+                        // the coarse mesh was built from the old data
+                        if let Some(mut lod) = prev_meshes.lod.take() {
+                            free_meshes(&mut lod, free_list, instance_allocator);
                         }
 
                         if is_data_some {
@@ -366,7 +565,7 @@ impl Chunker {
                     } else {
                         let meshes = 
                             if !is_data_some { None }
-                            else { Some(region.octree.insert(chunk_pos.chunk(), Leaf { mesh: data })) };
+                            else { Some(region.octree.insert(chunk_pos.chunk(), Leaf { mesh: data, lod: None })) };
 
                         chunk_meshes.meshes = meshes;
                     }
@@ -386,8 +585,9 @@ impl Chunker {
         let sender = self.mesh_sender.clone();
         rayon::spawn(move || {
             for item in batch {
-                let mesh = VoxelWorld::greedy_mesh(item.offsets, item.pos.0, item.chunks);
-                if let Err(e) = sender.send((item.pos, item.offsets, mesh, item.version)) {
+                let chunks = if item.lod { item.chunks.coarsened() } else { item.chunks };
+                let mesh = VoxelWorld::greedy_mesh(item.offsets, item.pos.0, chunks);
+                if let Err(e) = sender.send((item.pos, item.offsets, mesh, item.version, item.lod)) {
                     error!("mesh-task: {e}");
                     break;
                 }
@@ -472,15 +672,81 @@ impl Chunker {
 
         self.mesh_active_jobs.insert(pos);
 
-        let offsets = [
-            free_list.push(ChunkMeshFramedata::zeroed()),
-            free_list.push(ChunkMeshFramedata::zeroed()),
-            free_list.push(ChunkMeshFramedata::zeroed()),
-            free_list.push(ChunkMeshFramedata::zeroed()),
-            free_list.push(ChunkMeshFramedata::zeroed()),
-            free_list.push(ChunkMeshFramedata::zeroed()),
-        ];
+        // one per face, then one per water face
+        let offsets = core::array::from_fn(|_| free_list.push(ChunkMeshFramedata::zeroed()));
+
+
+        task_queue.push(MeshTaskData {
+            version,
+            offsets,
+            chunks: self.surrounding_chunk_data(pos),
+            pos,
+            lod: false,
+        });
+
+        true
+    }
+
+
+    ///
+    /// Like `try_prepare_mesh_task` but for the coarse mesh,
+    /// only chunks that already have an up-to-date full mesh
+    /// get one
+    ///
+    fn try_prepare_lod_task(
+        &mut self,
+        free_list: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>,
+        task_queue: &mut Vec<MeshTaskData>,
+        pos: WorldChunkPos,
+    ) {
+        if self.lod_active_jobs.contains(&pos) { return };
+        if self.mesh_active_jobs.contains(&pos) { return };
+
+        // unlike full meshes this never queues neighbours to be loaded
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let offset = IVec3::new(x, y, z);
+                    if self.get_chunk(WorldChunkPos(pos.0 + offset)).is_none() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        let region = self.get_region_or_insert(pos.region());
+
+        let (meshes, version) = match (region.get(pos.chunk()), region.get_mesh(pos.chunk())) {
+            (ChunkEntry::Loaded(chunk), MeshEntry::Loaded(ChunkMeshes { meshes: Some(meshes), version }))
+                if chunk.version == *version => (*meshes, *version),
+
+            _ => return,
+        };
+
+        if region.octree.get(meshes).lod.is_some() { return };
+
+
+        self.lod_active_jobs.insert(pos);
+
+        // one per face, then one per water face
+        let offsets = core::array::from_fn(|_| free_list.push(ChunkMeshFramedata::zeroed()));
+
+
+        task_queue.push(MeshTaskData {
+            version,
+            offsets,
+            chunks: self.surrounding_chunk_data(pos),
+            pos,
+            lod: true,
+        });
+    }
+
 
+    ///
+    /// Collects the voxel data of the chunk at `pos` and
+    /// its 26 neighbours, all of which have to be loaded
+    ///
+    fn surrounding_chunk_data(&mut self, pos: WorldChunkPos) -> ChunkDataRef {
         let mut chunks : [Option<Arc<ChunkData>>; 27] = [const { None }; 27];
         let base = pos.0 - IVec3::ONE;
         for x in 0..3 {
@@ -499,15 +765,7 @@ impl Chunker {
             }
         }
 
-
-        task_queue.push(MeshTaskData {
-            version,
-            offsets,
-            chunks: ChunkDataRef::new(chunks),
-            pos,
-        });
-
-        true
+        ChunkDataRef::new(chunks)
     }
 
 
@@ -541,11 +799,17 @@ impl Chunker {
 
         match entry {
             ChunkEntry::Loaded(_) => {
-                self.save_chunk(pos);
-                
+                // edited chunks get written out before their data is gone
+                self.save_chunk(pos, false);
+
                 let region = self.get_region_or_insert(pos.region());
                 let entry = region.get_mut(pos.chunk());
-                *entry = ChunkEntry::None;
+                let ChunkEntry::Loaded(chunk) = std::mem::replace(entry, ChunkEntry::None)
+                else { unreachable!() };
+
+                if self.chunk_save_jobs.load(std::sync::atomic::Ordering::SeqCst) > 0 {
+                    self.unloaded_while_saving.insert(pos, chunk.data);
+                }
             },
 
 
@@ -559,7 +823,12 @@ impl Chunker {
     }
 
 
-    pub fn save_chunk(&mut self, pos: WorldChunkPos) {
+    ///
+    /// Writes the chunk to disk if it has changed since it was last saved.
+    ///
+    /// If `force` is set the chunk is written regardless of its dirty flag
+    ///
+    pub fn save_chunk(&mut self, pos: WorldChunkPos, force: bool) {
         let entry = self.get_chunk_entry(pos);
 
         let ChunkEntry::Loaded(chunk) = entry
@@ -569,14 +838,18 @@ impl Chunker {
         };
 
 
-        if !chunk.is_dirty {
-            warn!("save-chunk: chunk at '{}' is not dirty", pos.0);
-            *entry = ChunkEntry::None;
+        let is_current = chunk.saved_version == Some(chunk.version);
+        if !force && (!chunk.is_dirty || is_current) {
+            trace!("save-chunk: chunk at '{}' is not dirty", pos.0);
             return;
         }
 
 
+        chunk.is_dirty = false;
+        chunk.saved_version = Some(chunk.version);
+
         let data = chunk.data.clone();
+        let save_dir = self.save_dir.clone();
         self.chunk_save_jobs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         let counter = self.chunk_save_jobs.clone();
 
@@ -584,12 +857,15 @@ impl Chunker {
             let time = Instant::now();
             let mut byte_writer = ByteWriter::new();
 
-            let path = format!("saves/chunks/{}.chunk", pos.0);
+            let path = format!("{save_dir}/chunks/{}.chunk", pos.0);
             let Some(data) = data
             else {
                 byte_writer.write([Voxel::Air as u8; CHUNK_SIZE_P3]);
-                std::fs::write(path, byte_writer.finish()).unwrap();
-                info!("save-chunk: saved empty chunk at '{}' in {:?}", pos.0, time.elapsed());
+                let raw = byte_writer.finish();
+                let file = compress_chunk_file(&raw);
+                std::fs::write(path, &file).unwrap();
+                counter.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                info!("save-chunk: saved empty chunk at '{}' in {:?} ({} bytes -> {} bytes)", pos.0, time.elapsed(), raw.len(), file.len());
                 return;
             };
 
@@ -602,13 +878,34 @@ impl Chunker {
 
             byte_writer.write(bytes);
 
-            std::fs::write(path, byte_writer.finish()).unwrap();
+            let raw = byte_writer.finish();
+            let file = compress_chunk_file(&raw);
+            std::fs::write(path, &file).unwrap();
             counter.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
-            info!("save-chunk: saved chunk at '{}' in {:?}", pos.0, time.elapsed());
+            info!("save-chunk: saved chunk at '{}' in {:?} ({} bytes -> {} bytes)", pos.0, time.elapsed(), raw.len(), file.len());
         });
     }
 
 
+    ///
+    /// The chunk at `pos` as it was when it got unloaded, if that
+    /// happened while saves were still in flight. Once every save
+    /// has landed the files are up to date and nothing is kept
+    ///
+    fn take_unloaded_while_saving(&mut self, pos: WorldChunkPos) -> Option<Chunk> {
+        if self.chunk_save_jobs.load(std::sync::atomic::Ordering::SeqCst) == 0 {
+            self.unloaded_while_saving.clear();
+            return None;
+        }
+
+        let data = self.unloaded_while_saving.remove(&pos)?;
+        let mut chunk = Chunk::empty_chunk();
+        chunk.data = data;
+        chunk.saved_version = Some(chunk.version);
+        Some(chunk)
+    }
+
+
     pub fn unload_mesh(&mut self, pos: WorldChunkPos) {
         self.mesh_unload_queue.insert(pos);
     }
@@ -684,6 +981,15 @@ impl Chunker {
 
 
             ChunkEntry::None => {
+                // there's nothing out there, don't bother with a job
+                if !self.limits.contains_chunk(pos.0.y) {
+                    *chunk = ChunkEntry::Loaded(Chunk::empty_chunk());
+                    let ChunkEntry::Loaded(chunk) = chunk
+                    else { unreachable!() };
+
+                    return Some(chunk);
+                }
+
                 *chunk = ChunkEntry::Loading;
 
                 self.chunk_load_queue.push(pos);
@@ -710,7 +1016,10 @@ impl Chunker {
 
               ChunkEntry::None 
             | ChunkEntry::Loading => {
-                let result = generate_chunk(pos, &self.noise);
+                let result = match self.take_unloaded_while_saving(pos) {
+                    Some(chunk) => chunk,
+                    None => generate_chunk(pos, &self.noise, self.limits, &self.save_dir),
+                };
 
                 self.register_chunk(pos, result);
                 self.get_chunk_or_generate(pos)
@@ -811,6 +1120,12 @@ impl Chunker {
         self.regions.iter().map(|x| (*x.0, x.1))
     }
 
+    pub fn noise(&self) -> &Noise { &self.noise }
+
+    pub fn seed(&self) -> u64 { self.seed }
+
+    pub fn save_dir(&self) -> &str { &self.save_dir }
+
 
     pub fn mesh_load_queue_len(&self) -> usize { self.mesh_load_queue.len() }
     pub fn mesh_active_jobs_len(&self) -> usize { self.mesh_active_jobs.len() }
@@ -842,6 +1157,93 @@ impl Chunker {
                       .map(move |c| (WorldChunkPos::new(x.0, c.0), c.1, c.2)
            ))
     }
+
+
+    ///
+    /// Every loaded chunk whose mesh version differs from the chunk's version,
+    /// chunks without any voxel data don't need a mesh so they're skipped
+    ///
+    pub fn mesh_desyncs(&self) -> Vec<MeshDesync> {
+        let mut desyncs = vec![];
+        for (pos, chunk, mesh) in self.iter_chunks() {
+            let ChunkEntry::Loaded(chunk) = chunk
+            else { continue };
+
+            let mesh_version = match mesh {
+                MeshEntry::Loaded(mesh) => Some(mesh.version),
+                MeshEntry::None => None,
+            };
+
+            if mesh_version == Some(chunk.version) { continue }
+            if mesh_version.is_none() && chunk.data.is_none() { continue }
+
+            desyncs.push(MeshDesync {
+                pos,
+                chunk_version: chunk.version,
+                mesh_version,
+                is_queued: self.is_queued_for_meshing(pos),
+                is_meshing: self.is_chunk_meshing(pos),
+            });
+        }
+
+        desyncs
+    }
+
+
+    ///
+    /// Queues every chunk that has a mesh to be meshed again
+    ///
+    /// The chunk versions get bumped without marking them dirty so
+    /// nothing gets saved, the old meshes are freed once the new
+    /// ones come in. Returns how many chunks were queued
+    ///
+    pub fn remesh_all(&mut self) -> usize {
+        let queued = self.iter_chunks()
+            .filter(|(_, chunk, mesh)| matches!((chunk, mesh), (ChunkEntry::Loaded(_), MeshEntry::Loaded(_))))
+            .map(|(pos, ..)| pos)
+            .collect::<Vec<_>>();
+
+        for &pos in &queued {
+            let ChunkEntry::Loaded(chunk) = self.get_chunk_entry(pos)
+            else { unreachable!() };
+
+            chunk.version = chunk.version.checked_add(1).unwrap();
+            self.mesh_load_queue.insert(pos);
+        }
+
+        queued.len()
+    }
+
+
+    pub fn mesh_stats(&self) -> MeshStats {
+        let mut stats = MeshStats::default();
+        let count = |faces: &[Option<ChunkFaceMesh>; 12]| {
+            let quads = faces.iter().flatten().map(|face| face.quads.size).sum::<usize>();
+            let bytes = faces.iter().flatten().map(|face| face.quads.size.next_power_of_two() * size_of::<ChunkQuadInstance>()).sum::<usize>();
+            (quads, bytes)
+        };
+
+        for (_, region) in self.regions() {
+            for mesh in region.meshes.iter() {
+                let MeshEntry::Loaded(ChunkMeshes { meshes: Some(node), .. }) = mesh
+                else { continue };
+
+                let leaf = region.octree.get(*node);
+                let (quads, bytes) = count(&leaf.mesh);
+                stats.chunks += 1;
+                stats.quads += quads;
+                stats.allocated_bytes += bytes;
+
+                if let Some(lod) = &leaf.lod {
+                    let (quads, bytes) = count(lod);
+                    stats.lod_quads += quads;
+                    stats.allocated_bytes += bytes;
+                }
+            }
+        }
+
+        stats
+    }
 }
 
 
@@ -890,6 +1292,11 @@ impl Region {
     }
 
 
+    pub fn octree_mut(&mut self) -> &mut MeshOctree {
+        &mut self.octree
+    }
+
+
     pub fn chunks(&self) -> &[ChunkEntry] {
         &*self.chunks
     }
@@ -946,27 +1353,236 @@ impl ChunkPos {
 }
 
 
-fn generate_chunk(pos: WorldChunkPos, noise: &Noise) -> Chunk {
+///
+/// Uploads the quads of a finished mesh job, faces
+/// without any quads are left as `None`
+///
+fn upload_meshes(
+    chunk_pos: WorldChunkPos,
+    offsets: [VoxelMeshIndex; 12],
+    result: &[Vec<ChunkQuadInstance>; 12],
+
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    belt: &mut StagingBelt,
+    instance_allocator: &mut GPUAllocator<ChunkQuadInstance>,
+    free_list: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>,
+    gpu_mesh_data: &mut SSBO<ChunkMeshFramedata>,
+) -> [Option<ChunkFaceMesh>; 12] {
+    let mut data = [const { None }; 12];
+    for i in 0..12 {
+        if result[i].is_empty() {
+            free_list.remove(offsets[i]);
+            continue
+        };
+
+        let mesh = ChunkFaceMesh::new(
+            belt, encoder, device, instance_allocator,
+            &result[i], offsets[i]
+        );
+
+
+        let index = mesh.chunk_mesh_data_index.usize();
+        if index >= gpu_mesh_data.len() {
+            warn!("resizing ssbo");
+            gpu_mesh_data.resize(device, encoder, (gpu_mesh_data.len() * 2).max(index+1));
+        }
+
+        gpu_mesh_data.write(
+            belt,
+            encoder,
+            device,
+            index,
+            &[ChunkMeshFramedata { offset: chunk_pos.0, normal: (i % 6) as u32 }]
+        );
+
+
+
+        data[i] = Some(mesh);
+    }
+
+    data
+}
+
+
+fn free_meshes(
+    meshes: &mut [Option<ChunkFaceMesh>; 12],
+    free_list: &mut FreeKVec<VoxelMeshIndex, ChunkMeshFramedata>,
+    instance_allocator: &mut GPUAllocator<ChunkQuadInstance>,
+) {
+    for mesh in meshes.iter_mut() {
+        let Some(mesh) = mesh.take()
+        else { continue };
+
+        free_list.remove(mesh.chunk_mesh_data_index);
+        instance_allocator.free(mesh.quads);
+    }
+}
+
+
+fn generate_chunk(pos: WorldChunkPos, noise: &Noise, limits: HeightLimits, save_dir: &str) -> Chunk {
     let pos = pos.0;
-    let path = format!("saves/chunks/{pos}.chunk");
-    let chunk = match std::fs::read(&path) {
-        Ok(ref v) if let Some(mut byte_reader) = ByteReader::new(&v) => {
+    if !limits.contains_chunk(pos.y) {
+        return Chunk::empty_chunk();
+    }
+
+    let path = format!("{save_dir}/chunks/{pos}.chunk");
+    let file = std::fs::read(&path).ok().and_then(|v| decompress_chunk_file(&v));
+    let chunk = match file {
+        Some((ref v, is_legacy)) if let Some(mut byte_reader) = ByteReader::new(&v) => {
             let mut chunk = Chunk::empty_chunk();
             let data = ChunkData::from_bytes(byte_reader.read().unwrap());
             if !data.is_empty() {
                 chunk.data = Some(Arc::new(data));
             }
 
-            chunk.is_dirty = false;
+            // legacy chunks get rewritten in the new format on the next save
+            chunk.is_dirty = is_legacy;
+            if !is_legacy {
+                chunk.saved_version = Some(chunk.version);
+            }
             chunk
         },
 
 
         _ => {
-            Chunk::generate(pos, noise)
+            Chunk::generate(pos, noise, limits)
         }
     };
 
     chunk
 }
 
+
+
+///
+/// Wraps the raw chunk bytes in a zstd stream prefixed with
+/// `CHUNK_FILE_MAGIC` and `CHUNK_FILE_VERSION`
+///
+pub fn compress_chunk_file(raw: &[u8]) -> Vec<u8> {
+    let mut file = Vec::with_capacity(raw.len() / 4);
+    file.extend_from_slice(&CHUNK_FILE_MAGIC);
+    file.push(CHUNK_FILE_VERSION);
+
+    let mut encoder = zstd::Encoder::new(file, CHUNK_COMPRESSION_LEVEL).unwrap();
+    std::io::Write::write_all(&mut encoder, raw).unwrap();
+    encoder.finish().unwrap()
+}
+
+
+///
+/// Returns the raw chunk bytes of a chunk file and whether
+/// the file was saved in the legacy, uncompressed format
+///
+pub fn decompress_chunk_file(file: &[u8]) -> Option<(Vec<u8>, bool)> {
+    let Some(rest) = file.strip_prefix(&CHUNK_FILE_MAGIC)
+    else {
+        warn!("chunk-file: no magic found, reading as a legacy uncompressed chunk");
+        return Some((file.to_vec(), true));
+    };
+
+    let (&version, body) = rest.split_first()?;
+    if version != CHUNK_FILE_VERSION {
+        error!("chunk-file: unsupported chunk file version '{version}'");
+        return None;
+    }
+
+    match zstd::decode_all(body) {
+        Ok(raw) => Some((raw, false)),
+        Err(e) => {
+            error!("chunk-file: failed to decompress chunk: {e}");
+            None
+        },
+    }
+}
+
+
+
+#[test]
+fn test_same_seed_generates_same_chunks() {
+    let a = Chunker::new(1234);
+    let b = Chunker::new(1234);
+    let c = Chunker::new(4321);
+
+    let limits = HeightLimits::default();
+    for pos in [IVec3::new(0, 0, 0), IVec3::new(1, -2, 3), IVec3::new(-7, 1, 12)] {
+        let chunk_a = Chunk::generate(pos, &a.noise, limits);
+        let chunk_b = Chunk::generate(pos, &b.noise, limits);
+        assert_eq!(chunk_a.data.map(|x| *x.as_bytes()), chunk_b.data.map(|x| *x.as_bytes()));
+    }
+
+    let pos = IVec3::new(1, -2, 3);
+    let chunk_a = Chunk::generate(pos, &a.noise, limits);
+    let chunk_c = Chunk::generate(pos, &c.noise, limits);
+    assert_ne!(chunk_a.data.map(|x| *x.as_bytes()), chunk_c.data.map(|x| *x.as_bytes()));
+}
+
+
+#[test]
+fn test_new_seed_regenerates_untouched_chunks() {
+    let mut chunker = Chunker::new(1234);
+
+    let untouched = WorldChunkPos(IVec3::new(1, -2, 3));
+    let edited = WorldChunkPos(IVec3::new(3, -2, 3));
+    chunker.get_chunk_or_generate(untouched);
+    chunker.get_chunk_or_generate(edited);
+    *chunker.get_mut_chunk(edited).unwrap().get_mut(IVec3::ZERO) = Voxel::Copper;
+
+    assert_eq!(chunker.set_seed(4321), 1);
+
+    let expected = Chunk::generate(untouched.0, &Noise::new(4321, 4321 ^ BIOME_SEED), HeightLimits::default());
+    let chunk = chunker.get_chunk(untouched).unwrap();
+    assert_eq!(chunk.data.as_ref().map(|x| *x.as_bytes()), expected.data.map(|x| *x.as_bytes()));
+    assert_eq!(chunker.get_chunk(edited).unwrap().get(IVec3::ZERO), Voxel::Copper);
+}
+
+
+#[test]
+fn test_unloaded_edits_are_kept() {
+    let save_dir = std::env::temp_dir().join(format!("factory-game-unload-{}", std::process::id()));
+    std::fs::create_dir_all(save_dir.join("chunks")).unwrap();
+
+    let mut chunker = Chunker::new(1234);
+    chunker.set_world(save_dir.to_str().unwrap(), 1234);
+
+    let pos = WorldChunkPos(IVec3::ZERO);
+    let local = IVec3::new(1, 2, 3);
+    chunker.get_chunk_or_generate(pos);
+    *chunker.get_mut_chunk(pos).unwrap().get_mut(local) = Voxel::Copper;
+
+    // straight back in, the save might not have landed yet
+    chunker.unload_voxel_data_of_chunk(pos);
+    assert_eq!(chunker.get_chunk_or_generate(pos).get(local), Voxel::Copper);
+
+    // and read back from disk once it has
+    *chunker.get_mut_chunk(pos).unwrap().get_mut(local) = Voxel::Iron;
+    chunker.unload_voxel_data_of_chunk(pos);
+    while chunker.chunk_save_jobs.load(std::sync::atomic::Ordering::SeqCst) > 0 { std::hint::spin_loop() }
+    assert_eq!(chunker.get_chunk_or_generate(pos).get(local), Voxel::Iron);
+
+    let _ = std::fs::remove_dir_all(save_dir);
+}
+
+
+#[test]
+fn test_remesh_all_skips_unmeshed_chunks() {
+    let mut chunker = Chunker::new(1234);
+
+    let meshed = WorldChunkPos(IVec3::ZERO);
+    let unmeshed = WorldChunkPos(IVec3::X);
+    chunker.get_chunk_or_generate(meshed);
+    chunker.get_chunk_or_generate(unmeshed);
+
+    let chunk = chunker.get_chunk(meshed).unwrap();
+    let (version, was_dirty) = (chunk.version, chunk.is_dirty);
+    *chunker.get_mesh_entry(meshed) = MeshEntry::Loaded(ChunkMeshes { meshes: None, version });
+
+    assert_eq!(chunker.remesh_all(), 1);
+    assert!(chunker.is_queued_for_meshing(meshed));
+    assert!(!chunker.is_queued_for_meshing(unmeshed));
+
+    // a remesh doesn't change what's on disk
+    let chunk = chunker.get_chunk(meshed).unwrap();
+    assert!(chunk.version > version);
+    assert_eq!(chunk.is_dirty, was_dirty);
+}
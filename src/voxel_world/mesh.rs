@@ -70,6 +70,17 @@ impl<'a> ChunkDataRef {
     }
 
 
+    ///
+    /// Returns a copy with every chunk merged down into 2x2x2
+    /// cells, see `ChunkData::coarsened`
+    ///
+    pub fn coarsened(&self) -> Self {
+        Self {
+            chunks: self.chunks.each_ref().map(|x| x.as_ref().map(|x| Arc::new(x.coarsened()))),
+        }
+    }
+
+
     pub fn get(&self, mut pos: IVec3) -> Voxel {
         pos += CHUNK_SIZE_I32;
 
@@ -13,11 +13,38 @@ pub enum Voxel {
     Iron = 4,
     Coal = 5,
 
+    Water = 6,
+
     StructureBlock = 255,
 }
 
 
 impl Voxel {
+    pub const ALL : &[Voxel] = &[
+        Voxel::Air,
+        Voxel::Dirt,
+        Voxel::Stone,
+        Voxel::Copper,
+        Voxel::Iron,
+        Voxel::Coal,
+        Voxel::Water,
+    ];
+
+
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Voxel::Air => "air",
+            Voxel::Dirt => "dirt",
+            Voxel::Stone => "stone",
+            Voxel::Copper => "copper",
+            Voxel::Iron => "iron",
+            Voxel::Coal => "coal",
+            Voxel::Water => "water",
+            Voxel::StructureBlock => "structure_block",
+        }
+    }
+
+
     pub fn is_air(self) -> bool {
         matches!(self, Voxel::Air)
     }
@@ -28,13 +55,22 @@ impl Voxel {
     }
 
 
+    ///
+    /// Water is see-through like air but still gets meshed,
+    /// into its own transparent draw list
+    ///
+    pub fn is_water(self) -> bool {
+        matches!(self, Voxel::Water)
+    }
+
+
     pub fn is_solid(self) -> bool { 
         !self.is_transparent()
     }
 
 
     pub fn is_transparent(self) -> bool { 
-        matches!(self, Voxel::Air | Voxel::StructureBlock)
+        matches!(self, Voxel::Air | Voxel::StructureBlock | Voxel::Water)
     }
 
 
@@ -47,6 +83,8 @@ impl Voxel {
             Voxel::Iron => Vec4::new(0.8, 0.8, 0.8, 1.0),
             Voxel::Coal => Vec4::new(0.2, 0.2, 0.2, 1.0),
 
+            Voxel::Water => Vec4::new(0.2, 0.4, 0.85, 1.0),
+
             Voxel::StructureBlock => Vec4::ZERO.with_w(1.0),
             Voxel::Air => unreachable!(),
         }
@@ -61,6 +99,7 @@ impl Voxel {
             Voxel::Copper => TICKS_PER_SECOND * 2 / 3,
             Voxel::Iron => TICKS_PER_SECOND * 2 / 3,
             Voxel::Coal => TICKS_PER_SECOND * 2 / 3,
+            Voxel::Water => TICKS_PER_SECOND / 3,
             Voxel::StructureBlock => TICKS_PER_SECOND * 1 / 3,
 
             Voxel::Air => unreachable!(),
@@ -72,6 +111,7 @@ impl Voxel {
         match self {
             Voxel::Dirt => ItemKind::Voxel(self),
             Voxel::Stone => ItemKind::Voxel(self),
+            Voxel::Water => ItemKind::Voxel(self),
 
             Voxel::Copper => ItemKind::CopperOre,
             Voxel::Iron => ItemKind::IronOre,
@@ -97,6 +137,8 @@ impl Voxel {
             Voxel::Copper => 4,
             Voxel::Iron => 5,
             Voxel::Coal => 6,
+            // the voxel shader animates this tile, see `WATER_TEXTURE`
+            Voxel::Water => 7,
 
             Voxel::Air => unreachable!(),
             Voxel::StructureBlock => unreachable!(),